@@ -1,8 +0,0 @@
-use anchor_lang::prelude::*;
-
-pub const ORACLE_AUTHORITY: Pubkey = pubkey!("oracLExRKSH9PQq5Yw2YDrXZWJ5b1yKqN3z8vXwZ7Yz");
-pub const VERIFIER_AUTHORITY: Pubkey = pubkey!("veriF1ErX9WZ8kLFH7YqDxyzREGcKyJkqmE6N3zRvXy");
-pub const TAP_AUTHORITY: Pubkey = pubkey!("tapAUT4orX9YnRPQsWZ8kLFH7YqDxyzREGcKyJkqmE6");
-pub const DAO_AUTHORITY: Pubkey = pubkey!("daoAUT4orX9YnRPQsWZ8kLFH7YqDxyzREGcKyJkqmE6");
-pub const DAO_TREASURY: Pubkey = pubkey!("treasURY4orX9YnRPQsWZ8kLFH7YqDxyzREGcKyJk");
-pub const STAKE_TOKEN_MINT: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+
+use crate::ErrorCode;
+
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_SIZE: usize = 14;
+const PUBKEY_SIZE: usize = 32;
+
+pub fn verify_previous_ed25519_instruction(
+    instructions_sysvar: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    verify_ed25519_instruction_before(instructions_sysvar, 1, signer, message)
+}
+
+/// Checks that the instruction `distance` places before the current one verifies `signer`'s signature over `message`.
+pub fn verify_ed25519_instruction_before(
+    instructions_sysvar: &AccountInfo,
+    distance: u16,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    require!(distance > 0 && current_index >= distance, ErrorCode::MissingSignatureVerification);
+
+    let instruction = load_instruction_at_checked((current_index - distance) as usize, instructions_sysvar)?;
+    require!(
+        instruction.program_id == ed25519_program::ID && instruction.accounts.is_empty(),
+        ErrorCode::MissingSignatureVerification
+    );
+
+    let data = &instruction.data;
+    require!(
+        data.len() >= SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_SIZE && data[0] == 1,
+        ErrorCode::InvalidSignatureVerification
+    );
+
+    let read_u16 = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+    let offsets = SIGNATURE_OFFSETS_START;
+    let signature_instruction_index = read_u16(offsets + 2);
+    let public_key_offset = read_u16(offsets + 4) as usize;
+    let public_key_instruction_index = read_u16(offsets + 6);
+    let message_data_offset = read_u16(offsets + 8) as usize;
+    let message_data_size = read_u16(offsets + 10) as usize;
+    let message_instruction_index = read_u16(offsets + 12);
+
+    require!(
+        signature_instruction_index == u16::MAX
+            && public_key_instruction_index == u16::MAX
+            && message_instruction_index == u16::MAX,
+        ErrorCode::InvalidSignatureVerification
+    );
+
+    let public_key = data
+        .get(public_key_offset..public_key_offset + PUBKEY_SIZE)
+        .ok_or(ErrorCode::InvalidSignatureVerification)?;
+    let signed_message = data
+        .get(message_data_offset..message_data_offset + message_data_size)
+        .ok_or(ErrorCode::InvalidSignatureVerification)?;
+
+    require!(public_key == signer.as_ref(), ErrorCode::InvalidSignatureVerification);
+    require!(signed_message == message, ErrorCode::InvalidSignatureVerification);
+
+    Ok(())
+}
@@ -1,17 +1,198 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{self, TokenInterface, TransferChecked};
 
 declare_id!("85GHuKTjE4RXR2d4tCMKLXSbdwr2wkELVvUhNeyrwEfj");
 
-mod constants;
+mod ed25519;
+mod reputation;
 mod state;
-use constants::*;
+#[cfg(test)]
+mod tests;
+use reputation::{
+    apply_penalty, calculate_dispute_reputation, calculate_new_reputation, calculate_rating_weight, decay_reputation,
+    ReputationParams,
+};
 use state::*;
 
 #[program]
 pub mod x402_registry {
     use super::*;
 
+    pub fn initialize_config(ctx: Context<InitializeConfig>, params: ConfigParams) -> Result<()> {
+        validate_config_params(&params)?;
+
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key();
+        config.pending_authority = None;
+        config.oracle_authority = params.oracle_authority;
+        config.verifier_authority = params.verifier_authority;
+        config.tap_authority = params.tap_authority;
+        config.dao_authority = params.dao_authority;
+        config.arbiter_authority = params.arbiter_authority;
+        config.dao_treasury = params.dao_treasury;
+        config.stake_token_mint = params.stake_token_mint;
+        config.min_stake_amount = params.min_stake_amount;
+        config.min_slash_amount = params.min_slash_amount;
+        config.unstake_lock_seconds = params.unstake_lock_seconds;
+        config.restake_cooldown_seconds = params.restake_cooldown_seconds;
+        config.transaction_rate_limit_seconds = params.transaction_rate_limit_seconds;
+        config.rating_update_cooldown_seconds = params.rating_update_cooldown_seconds;
+        config.slash_cooldown_seconds = params.slash_cooldown_seconds;
+        config.credit_reputation_threshold = params.credit_reputation_threshold;
+        config.dispute_bond_lamports = params.dispute_bond_lamports;
+        config.dispute_response_seconds = params.dispute_response_seconds;
+        config.credit_fee_bps = params.credit_fee_bps;
+        config.credit_repayment_seconds = params.credit_repayment_seconds;
+        config.channel_challenge_seconds = params.channel_challenge_seconds;
+        config.health_report_interval_seconds = params.health_report_interval_seconds;
+        config.uptime_alpha_bps = params.uptime_alpha_bps;
+        config.reputation_half_life_seconds = params.reputation_half_life_seconds;
+        config.reputation_value_unit = params.reputation_value_unit;
+        config.reputation_alpha_bps = params.reputation_alpha_bps;
+        config.reputation_max_alpha_bps = params.reputation_max_alpha_bps;
+        config.reputation_slash_penalty = params.reputation_slash_penalty;
+        config.rating_window_seconds = params.rating_window_seconds;
+        config.council = params.council;
+        config.council_threshold = params.council_threshold;
+        config.governance_timelock_seconds = params.governance_timelock_seconds;
+        config.proposal_count = 0;
+        config.bump = ctx.bumps.config;
+
+        emit!(ConfigUpdatedEvent {
+            authority: config.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_config(ctx: Context<UpdateConfig>, update: ConfigUpdate) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        if let Some(oracle_authority) = update.oracle_authority {
+            config.oracle_authority = oracle_authority;
+        }
+        if let Some(verifier_authority) = update.verifier_authority {
+            config.verifier_authority = verifier_authority;
+        }
+        if let Some(tap_authority) = update.tap_authority {
+            config.tap_authority = tap_authority;
+        }
+        if let Some(dao_treasury) = update.dao_treasury {
+            config.dao_treasury = dao_treasury;
+        }
+        if let Some(min_stake_amount) = update.min_stake_amount {
+            config.min_stake_amount = min_stake_amount;
+        }
+        if let Some(min_slash_amount) = update.min_slash_amount {
+            config.min_slash_amount = min_slash_amount;
+        }
+        if let Some(unstake_lock_seconds) = update.unstake_lock_seconds {
+            config.unstake_lock_seconds = unstake_lock_seconds;
+        }
+        if let Some(restake_cooldown_seconds) = update.restake_cooldown_seconds {
+            config.restake_cooldown_seconds = restake_cooldown_seconds;
+        }
+        if let Some(transaction_rate_limit_seconds) = update.transaction_rate_limit_seconds {
+            config.transaction_rate_limit_seconds = transaction_rate_limit_seconds;
+        }
+        if let Some(rating_update_cooldown_seconds) = update.rating_update_cooldown_seconds {
+            config.rating_update_cooldown_seconds = rating_update_cooldown_seconds;
+        }
+        if let Some(slash_cooldown_seconds) = update.slash_cooldown_seconds {
+            config.slash_cooldown_seconds = slash_cooldown_seconds;
+        }
+        if let Some(credit_reputation_threshold) = update.credit_reputation_threshold {
+            config.credit_reputation_threshold = credit_reputation_threshold;
+        }
+        if let Some(dispute_bond_lamports) = update.dispute_bond_lamports {
+            config.dispute_bond_lamports = dispute_bond_lamports;
+        }
+        if let Some(dispute_response_seconds) = update.dispute_response_seconds {
+            config.dispute_response_seconds = dispute_response_seconds;
+        }
+        if let Some(credit_fee_bps) = update.credit_fee_bps {
+            config.credit_fee_bps = credit_fee_bps;
+        }
+        if let Some(credit_repayment_seconds) = update.credit_repayment_seconds {
+            config.credit_repayment_seconds = credit_repayment_seconds;
+        }
+        if let Some(channel_challenge_seconds) = update.channel_challenge_seconds {
+            config.channel_challenge_seconds = channel_challenge_seconds;
+        }
+        if let Some(health_report_interval_seconds) = update.health_report_interval_seconds {
+            config.health_report_interval_seconds = health_report_interval_seconds;
+        }
+        if let Some(uptime_alpha_bps) = update.uptime_alpha_bps {
+            config.uptime_alpha_bps = uptime_alpha_bps;
+        }
+        if let Some(reputation_half_life_seconds) = update.reputation_half_life_seconds {
+            config.reputation_half_life_seconds = reputation_half_life_seconds;
+        }
+        if let Some(reputation_value_unit) = update.reputation_value_unit {
+            config.reputation_value_unit = reputation_value_unit;
+        }
+        if let Some(reputation_alpha_bps) = update.reputation_alpha_bps {
+            config.reputation_alpha_bps = reputation_alpha_bps;
+        }
+        if let Some(reputation_max_alpha_bps) = update.reputation_max_alpha_bps {
+            config.reputation_max_alpha_bps = reputation_max_alpha_bps;
+        }
+        if let Some(reputation_slash_penalty) = update.reputation_slash_penalty {
+            config.reputation_slash_penalty = reputation_slash_penalty;
+        }
+        if let Some(rating_window_seconds) = update.rating_window_seconds {
+            config.rating_window_seconds = rating_window_seconds;
+        }
+
+        validate_config(config)?;
+
+        emit!(ConfigUpdatedEvent {
+            authority: config.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn transfer_config_authority(
+        ctx: Context<UpdateConfig>,
+        new_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.pending_authority = new_authority;
+
+        emit!(AuthorityTransferStartedEvent {
+            authority: config.authority,
+            pending_authority: new_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_config_authority(ctx: Context<AcceptConfigAuthority>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let new_authority = ctx.accounts.new_authority.key();
+
+        require!(
+            config.pending_authority == Some(new_authority),
+            ErrorCode::UnauthorizedAccess
+        );
+
+        let previous_authority = config.authority;
+        config.authority = new_authority;
+        config.pending_authority = None;
+
+        emit!(AuthorityTransferredEvent {
+            previous_authority,
+            new_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
     pub fn register_agent(
         ctx: Context<RegisterAgent>,
         did: String,
@@ -19,10 +200,9 @@ pub mod x402_registry {
         stake_amount: u64,
         metadata_uri: String,
     ) -> Result<()> {
-        let agent = &mut ctx.accounts.agent;
+        let agent_key = ctx.accounts.agent.key();
         let clock = Clock::get()?;
 
-        require!(stake_amount >= 1_000_000_000, ErrorCode::InvalidStakeAmount);
         require!(did.len() <= 256, ErrorCode::StringTooLong);
         require!(visa_tap_cert.len() <= 512, ErrorCode::StringTooLong);
         require!(metadata_uri.len() <= 256, ErrorCode::StringTooLong);
@@ -33,20 +213,32 @@ pub mod x402_registry {
             ctx.program_id
         );
         require!(
-            ctx.accounts.agent.key() == derived_pda,
+            agent_key == derived_pda,
             ErrorCode::InvalidPDA
         );
 
+        let staked = deposit_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.staker_token_account,
+            &ctx.accounts.mint,
+            &mut ctx.accounts.escrow_account,
+            &ctx.accounts.signer,
+            stake_amount,
+        )?;
+        let stake_value = calculate_stake_value(staked, ctx.accounts.stake_mint.reputation_weight_bps)?;
+        require!(stake_value >= ctx.accounts.config.min_stake_amount, ErrorCode::InvalidStakeAmount);
+
+        let agent = &mut ctx.accounts.agent;
         agent.wallet = expected_wallet;
         agent.did = did;
         agent.visa_tap_cert = visa_tap_cert;
-        agent.reputation_score = calculate_initial_reputation(stake_amount);
+        agent.reputation_score = calculate_initial_reputation(stake_value);
         agent.total_spent = 0;
         agent.total_transactions = 0;
         agent.successful_transactions = 0;
         agent.disputes_won = 0;
         agent.disputes_lost = 0;
-        agent.staked_amount = stake_amount;
+        agent.staked_amount = staked;
         agent.slashed_amount = 0;
         agent.credit_limit = 0;
         agent.credit_used = 0;
@@ -59,23 +251,12 @@ pub mod x402_registry {
         agent.status = AgentStatus::Active;
         agent.historical_min_reputation = agent.reputation_score;
         agent.service_count = 0;
-
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.staker_token_account.to_account_info(),
-                    to: ctx.accounts.escrow_account.to_account_info(),
-                    authority: ctx.accounts.signer.to_account_info(),
-                },
-            ),
-            stake_amount,
-        )?;
+        agent.stake_mint = ctx.accounts.mint.key();
 
         emit!(AgentRegisteredEvent {
             wallet: agent.wallet,
             reputation_score: agent.reputation_score,
-            stake_amount,
+            stake_amount: staked,
             timestamp: clock.unix_timestamp,
         });
 
@@ -91,6 +272,7 @@ pub mod x402_registry {
         price_per_call: u64,
         accepted_tokens: Vec<Pubkey>,
     ) -> Result<()> {
+        let service_key = ctx.accounts.service.key();
         let service = &mut ctx.accounts.service;
         let agent = &ctx.accounts.agent;
         let clock = Clock::get()?;
@@ -104,7 +286,7 @@ pub mod x402_registry {
             ErrorCode::AgentSuspended
         );
         require!(
-            agent.staked_amount >= 1_000_000_000,
+            agent.staked_amount >= ctx.accounts.config.min_stake_amount,
             ErrorCode::InsufficientStake
         );
 
@@ -113,7 +295,7 @@ pub mod x402_registry {
         require!(description.len() <= 1024, ErrorCode::StringTooLong);
         require!(category.len() <= 64, ErrorCode::StringTooLong);
         require!(price_per_call > 0, ErrorCode::InvalidPrice);
-        require!(accepted_tokens.len() > 0 && accepted_tokens.len() <= 5, ErrorCode::InvalidTokenList);
+        require!(!accepted_tokens.is_empty() && accepted_tokens.len() <= 5, ErrorCode::InvalidTokenList);
 
         service.url = url;
         service.name = name;
@@ -132,18 +314,28 @@ pub mod x402_registry {
         service.total_ratings = 0;
         service.total_rating_sum = 0;
         service.average_rating = 0;
+        service.total_rating_weight = 0;
+        service.weighted_rating_sum = 0;
         service.verified = false;
         service.visa_tap_verified = false;
         service.created_at = clock.unix_timestamp;
         service.last_updated = clock.unix_timestamp;
         service.status = ServiceStatus::Active;
+        service.directory_index = Some(list_service(
+            &mut ctx.accounts.directory,
+            &mut ctx.accounts.page,
+            service_key,
+            ctx.accounts.owner.key(),
+            ctx.bumps.directory,
+            ctx.bumps.page,
+        )?);
 
         let agent_mut = &mut ctx.accounts.agent;
         agent_mut.service_count = agent_mut.service_count.checked_add(1)
             .ok_or(ErrorCode::MathOverflow)?;
 
         emit!(ServiceRegisteredEvent {
-            service_key: ctx.accounts.service.key(),
+            service_key,
             owner: service.owner,
             price_per_call,
             timestamp: clock.unix_timestamp,
@@ -159,10 +351,12 @@ pub mod x402_registry {
         response_time_ms: u32,
     ) -> Result<()> {
         require!(
-            ctx.accounts.authority.key() == ORACLE_AUTHORITY,
+            ctx.accounts.authority.key() == ctx.accounts.config.oracle_authority,
             ErrorCode::UnauthorizedAccess
         );
 
+        let agent_key = ctx.accounts.agent.key();
+        let config = &ctx.accounts.config;
         let agent = &mut ctx.accounts.agent;
         let service = &mut ctx.accounts.service;
         let clock = Clock::get()?;
@@ -172,79 +366,73 @@ pub mod x402_registry {
             ctx.program_id
         );
         require!(
-            ctx.accounts.agent.key() == expected_agent_pda,
+            agent_key == expected_agent_pda,
             ErrorCode::InvalidPDA
         );
 
-        require!(
-            service.status == ServiceStatus::Active,
-            ErrorCode::ServiceNotActive
-        );
-        require!(
-            agent.status == AgentStatus::Active,
-            ErrorCode::AgentSuspended
-        );
-
-        require!(
-            clock.unix_timestamp >= agent.last_transaction_time + 10,
-            ErrorCode::RateLimitExceeded
-        );
-
-        agent.total_transactions = agent.total_transactions.checked_add(1)
-            .ok_or(ErrorCode::MathOverflow)?;
-        agent.last_active = clock.unix_timestamp;
-        agent.last_transaction_time = clock.unix_timestamp;
-
-        service.total_calls = service.total_calls.checked_add(1)
-            .ok_or(ErrorCode::MathOverflow)?;
-
-        if success {
-            agent.successful_transactions = agent.successful_transactions.checked_add(1)
-                .ok_or(ErrorCode::MathOverflow)?;
-            agent.total_spent = agent.total_spent.checked_add(amount)
-                .ok_or(ErrorCode::MathOverflow)?;
-
-            let new_reputation = calculate_new_reputation(
-                agent.reputation_score,
-                agent.total_transactions,
-                agent.successful_transactions,
-            )?;
-
-            agent.reputation_score = new_reputation;
-
-            if new_reputation > 9000 {
-                agent.credit_limit = agent.total_spent.checked_div(10)
-                    .ok_or(ErrorCode::MathOverflow)?;
-            }
-
-            service.successful_calls = service.successful_calls.checked_add(1)
-                .ok_or(ErrorCode::MathOverflow)?;
-            service.total_revenue = service.total_revenue.checked_add(amount)
-                .ok_or(ErrorCode::MathOverflow)?;
-
-            service.total_response_time_sum = service.total_response_time_sum
-                .checked_add(response_time_ms as u64)
-                .ok_or(ErrorCode::MathOverflow)?;
-
-            service.average_response_time_ms = (service.total_response_time_sum
-                .checked_div(service.total_calls as u64)
-                .ok_or(ErrorCode::MathOverflow)?) as u32;
-        } else {
-            let penalty = if agent.reputation_score > 100 { 100 } else { agent.reputation_score };
-            agent.reputation_score = agent.reputation_score.saturating_sub(penalty);
-        }
+        apply_transaction(
+            config,
+            agent,
+            service,
+            &mut ctx.accounts.interaction,
+            ctx.bumps.interaction,
+            amount,
+            Some(amount),
+            success,
+            response_time_ms,
+            clock.unix_timestamp,
+        )
+    }
 
-        service.last_updated = clock.unix_timestamp;
+    /// Records a call from a receipt co-signed by the payer and the service owner. The two can collude, so the amount
+    /// only counts towards reputation, spend, revenue and rating weight up to a released escrow for the same payment;
+    /// without one, reputation only decays.
+    pub fn record_cosigned_transaction(
+        ctx: Context<RecordCosignedTransaction>,
+        amount: u64,
+        success: bool,
+        response_time_ms: u32,
+        nonce: u64,
+    ) -> Result<()> {
+        let service_key = ctx.accounts.service.key();
+        let payer = ctx.accounts.agent.wallet;
+        require!(ctx.accounts.service.owner != payer, ErrorCode::SelfPaymentNotAllowed);
+        let message = receipt_message(&service_key, &payer, amount, success, response_time_ms, nonce);
+
+        ed25519::verify_ed25519_instruction_before(&ctx.accounts.instructions_sysvar, 2, &payer, &message)?;
+        ed25519::verify_ed25519_instruction_before(
+            &ctx.accounts.instructions_sysvar,
+            1,
+            &ctx.accounts.service.owner,
+            &message,
+        )?;
 
-        emit!(TransactionRecordedEvent {
-            agent: agent.wallet,
-            service: ctx.accounts.service.key(),
+        let clock = Clock::get()?;
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.service = service_key;
+        receipt.agent = payer;
+        receipt.nonce = nonce;
+        receipt.amount = amount;
+        receipt.success = success;
+        receipt.recorded_at = clock.unix_timestamp;
+        receipt.bump = ctx.bumps.receipt;
+
+        let settled_amount = ctx.accounts.payment_escrow
+            .as_ref()
+            .map(|escrow| escrow.amount.min(amount));
+
+        apply_transaction(
+            &ctx.accounts.config,
+            &mut ctx.accounts.agent,
+            &mut ctx.accounts.service,
+            &mut ctx.accounts.interaction,
+            ctx.bumps.interaction,
             amount,
+            settled_amount,
             success,
-            timestamp: clock.unix_timestamp,
-        });
-
-        Ok(())
+            response_time_ms,
+            clock.unix_timestamp,
+        )
     }
 
     pub fn rate_service(
@@ -256,7 +444,7 @@ pub mod x402_registry {
             ErrorCode::UnauthorizedAccess
         );
         require!(
-            ctx.accounts.agent.total_transactions > 0,
+            ctx.accounts.interaction.transactions > 0,
             ErrorCode::NoTransactionHistory
         );
 
@@ -270,11 +458,13 @@ pub mod x402_registry {
             ErrorCode::InvalidPDA
         );
 
+        let weight = rater_weight(&ctx.accounts.config, agent, &ctx.accounts.stake_mint)?;
+        let service_key = ctx.accounts.service.key();
         let service = &mut ctx.accounts.service;
         let service_rating = &mut ctx.accounts.service_rating;
         let clock = Clock::get()?;
 
-        require!(rating >= 1 && rating <= 500, ErrorCode::InvalidRating);
+        require!((1..=500).contains(&rating), ErrorCode::InvalidRating);
         require!(
             service.status == ServiceStatus::Active,
             ErrorCode::ServiceNotActive
@@ -284,30 +474,23 @@ pub mod x402_registry {
             ErrorCode::SelfRatingNotAllowed
         );
 
-        service_rating.service = ctx.accounts.service.key();
+        service_rating.service = service_key;
         service_rating.agent = ctx.accounts.agent.wallet;
         service_rating.rating = rating;
         service_rating.timestamp = clock.unix_timestamp;
         service_rating.last_update_time = clock.unix_timestamp;
+        service_rating.weight = weight;
 
+        seed_legacy_rating_weights(service)?;
         service.total_ratings = service.total_ratings.checked_add(1)
             .ok_or(ErrorCode::MathOverflow)?;
         service.total_rating_sum = service.total_rating_sum.checked_add(rating as u64)
             .ok_or(ErrorCode::MathOverflow)?;
-
-        let avg_rating = service.total_rating_sum
-            .checked_div(service.total_ratings as u64)
-            .ok_or(ErrorCode::MathOverflow)?;
-        require!(avg_rating <= 500, ErrorCode::InvalidRating);
-
-        service.average_rating = avg_rating as u16;
-        service.reputation_score = (service.average_rating as u64)
-            .checked_mul(2)
-            .ok_or(ErrorCode::MathOverflow)?;
+        add_weighted_rating(service, rating, weight)?;
         service.last_updated = clock.unix_timestamp;
 
         emit!(ServiceRatedEvent {
-            service: ctx.accounts.service.key(),
+            service: service_key,
             rating,
             new_average: service.average_rating,
             timestamp: clock.unix_timestamp,
@@ -316,21 +499,27 @@ pub mod x402_registry {
         Ok(())
     }
 
-    pub fn slash_for_fraud(
-        ctx: Context<SlashAgent>,
-        fraud_amount: u64,
-        evidence_uri: String,
-    ) -> Result<()> {
-        require!(
-            ctx.accounts.authority.key() == DAO_AUTHORITY,
-            ErrorCode::UnauthorizedAccess
-        );
+    pub fn slash_for_fraud(ctx: Context<SlashAgent>) -> Result<()> {
+        let (fraud_amount, evidence_uri) = match &ctx.accounts.proposal.action {
+            GovernanceAction::SlashForFraud { agent, fraud_amount, evidence_uri }
+                if *agent == ctx.accounts.agent.key() =>
+            {
+                (*fraud_amount, evidence_uri.clone())
+            }
+            _ => return err!(ErrorCode::ProposalActionMismatch),
+        };
 
+        let config = &ctx.accounts.config;
         let agent = &mut ctx.accounts.agent;
         let clock = Clock::get()?;
 
+        execute_proposal(config, &mut ctx.accounts.proposal, ctx.accounts.executor.key(), clock.unix_timestamp)?;
+
         require!(evidence_uri.len() <= 256, ErrorCode::StringTooLong);
-        require!(fraud_amount >= 1_000_000, ErrorCode::SlashAmountTooLow);
+        require!(
+            calculate_stake_value(fraud_amount, ctx.accounts.stake_mint.reputation_weight_bps)? >= config.min_slash_amount,
+            ErrorCode::SlashAmountTooLow
+        );
         require!(
             fraud_amount <= agent.staked_amount,
             ErrorCode::FraudAmountTooHigh
@@ -341,7 +530,7 @@ pub mod x402_registry {
         );
 
         let slash_cooldown = agent.last_slashed_time
-            .checked_add(24 * 60 * 60)
+            .checked_add(config.slash_cooldown_seconds)
             .ok_or(ErrorCode::TimestampOverflow)?;
         require!(
             clock.unix_timestamp >= slash_cooldown,
@@ -352,20 +541,23 @@ pub mod x402_registry {
 
         require!(slash_amount <= agent.staked_amount, ErrorCode::InsufficientStake);
 
-        let seeds = &[b"escrow".as_ref(), &[ctx.bumps.escrow_account]];
+        let mint_key = ctx.accounts.mint.key();
+        let seeds = &[b"escrow".as_ref(), mint_key.as_ref(), &[ctx.accounts.stake_mint.escrow_bump]];
         let signer_seeds = &[&seeds[..]];
 
-        token::transfer(
+        token_interface::transfer_checked(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.escrow_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
                     to: ctx.accounts.dao_treasury.to_account_info(),
                     authority: ctx.accounts.escrow_account.to_account_info(),
                 },
                 signer_seeds,
             ),
             slash_amount,
+            ctx.accounts.mint.decimals,
         )?;
 
         agent.staked_amount = agent.staked_amount.checked_sub(slash_amount)
@@ -377,8 +569,7 @@ pub mod x402_registry {
         agent.last_slashed_time = clock.unix_timestamp;
         agent.last_slash_evidence = evidence_uri;
 
-        let reputation_penalty = if agent.reputation_score > 1000 { 1000 } else { agent.reputation_score };
-        agent.reputation_score = agent.reputation_score.saturating_sub(reputation_penalty);
+        agent.reputation_score = apply_penalty(agent.reputation_score, ctx.accounts.config.reputation_slash_penalty);
 
         emit!(AgentSlashedEvent {
             wallet: agent.wallet,
@@ -389,9 +580,265 @@ pub mod x402_registry {
         Ok(())
     }
 
+    pub fn open_dispute(
+        ctx: Context<OpenDispute>,
+        transaction_signature: [u8; 64],
+        evidence_uri: String,
+    ) -> Result<()> {
+        require!(evidence_uri.len() <= 256, ErrorCode::StringTooLong);
+        require!(
+            ctx.accounts.payer_agent.status != AgentStatus::Banned,
+            ErrorCode::AgentSuspended
+        );
+        require!(
+            ctx.accounts.service.owner != ctx.accounts.payer.key(),
+            ErrorCode::SelfDisputeNotAllowed
+        );
+
+        let config = &ctx.accounts.config;
+        let clock = Clock::get()?;
+
+        let response_deadline = clock.unix_timestamp
+            .checked_add(config.dispute_response_seconds)
+            .ok_or(ErrorCode::TimestampOverflow)?;
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.dispute.to_account_info(),
+                },
+            ),
+            config.dispute_bond_lamports,
+        )?;
+
+        let dispute_key = ctx.accounts.dispute.key();
+        let dispute = &mut ctx.accounts.dispute;
+        dispute.payer = ctx.accounts.payer.key();
+        dispute.provider = ctx.accounts.service.owner;
+        dispute.service = ctx.accounts.service.key();
+        dispute.transaction_signature = transaction_signature;
+        dispute.evidence_uri = evidence_uri;
+        dispute.response_uri = String::new();
+        dispute.bond_lamports = config.dispute_bond_lamports;
+        dispute.provider_bonded = false;
+        dispute.opened_at = clock.unix_timestamp;
+        dispute.response_deadline = response_deadline;
+        dispute.resolved_at = 0;
+        dispute.status = DisputeStatus::Open;
+        dispute.refund_amount = 0;
+        dispute.slash_amount = 0;
+        dispute.bump = ctx.bumps.dispute;
+
+        emit!(DisputeOpenedEvent {
+            dispute: dispute_key,
+            payer: dispute.payer,
+            provider: dispute.provider,
+            service: dispute.service,
+            bond_lamports: dispute.bond_lamports,
+            response_deadline,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn respond_to_dispute(ctx: Context<RespondToDispute>, response_uri: String) -> Result<()> {
+        require!(response_uri.len() <= 256, ErrorCode::StringTooLong);
+
+        let clock = Clock::get()?;
+        let dispute = &ctx.accounts.dispute;
+
+        require!(dispute.status == DisputeStatus::Open, ErrorCode::DisputeNotOpen);
+        require!(
+            clock.unix_timestamp <= dispute.response_deadline,
+            ErrorCode::DisputeResponseWindowClosed
+        );
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.provider.to_account_info(),
+                    to: ctx.accounts.dispute.to_account_info(),
+                },
+            ),
+            dispute.bond_lamports,
+        )?;
+
+        let dispute_key = ctx.accounts.dispute.key();
+        let dispute = &mut ctx.accounts.dispute;
+        dispute.response_uri = response_uri;
+        dispute.provider_bonded = true;
+        dispute.status = DisputeStatus::Responded;
+
+        emit!(DisputeRespondedEvent {
+            dispute: dispute_key,
+            provider: dispute.provider,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, ruling: DisputeRuling) -> Result<()> {
+        require!(
+            ctx.accounts.arbiter.key() == ctx.accounts.config.arbiter_authority,
+            ErrorCode::UnauthorizedAccess
+        );
+
+        let dispute = &ctx.accounts.dispute;
+        require!(
+            dispute.status == DisputeStatus::Open || dispute.status == DisputeStatus::Responded,
+            ErrorCode::DisputeNotOpen
+        );
+        require!(
+            ruling.payer_wins || (ruling.refund_amount == 0 && ruling.slash_amount == 0),
+            ErrorCode::InvalidRuling
+        );
+
+        let penalty = ruling.refund_amount
+            .checked_add(ruling.slash_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            penalty <= ctx.accounts.provider_agent.staked_amount,
+            ErrorCode::InsufficientStake
+        );
+
+        let mint_key = ctx.accounts.mint.key();
+        let seeds = &[b"escrow".as_ref(), mint_key.as_ref(), &[ctx.accounts.stake_mint.escrow_bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        if ruling.refund_amount > 0 {
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.escrow_account.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: ctx.accounts.payer_token_account.to_account_info(),
+                        authority: ctx.accounts.escrow_account.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                ruling.refund_amount,
+                ctx.accounts.mint.decimals,
+            )?;
+        }
+
+        if ruling.slash_amount > 0 {
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.escrow_account.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: ctx.accounts.dao_treasury.to_account_info(),
+                        authority: ctx.accounts.escrow_account.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                ruling.slash_amount,
+                ctx.accounts.mint.decimals,
+            )?;
+        }
+
+        let clock = Clock::get()?;
+        let provider_agent = &mut ctx.accounts.provider_agent;
+
+        provider_agent.staked_amount = provider_agent.staked_amount.checked_sub(penalty)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        if ruling.slash_amount > 0 {
+            provider_agent.slashed_amount = provider_agent.slashed_amount.checked_add(ruling.slash_amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+            provider_agent.last_slashed_time = clock.unix_timestamp;
+            provider_agent.last_slash_evidence = ctx.accounts.dispute.evidence_uri.clone();
+
+            provider_agent.reputation_score = apply_penalty(
+                provider_agent.reputation_score,
+                ctx.accounts.config.reputation_slash_penalty,
+            );
+
+            emit!(AgentSlashedEvent {
+                wallet: provider_agent.wallet,
+                slash_amount: ruling.slash_amount,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        let winner = if ruling.payer_wins {
+            ctx.accounts.payer.to_account_info()
+        } else {
+            ctx.accounts.provider.to_account_info()
+        };
+
+        settle_dispute(
+            &mut ctx.accounts.dispute,
+            &mut ctx.accounts.payer_agent,
+            &mut ctx.accounts.provider_agent,
+            &winner,
+            ruling.payer_wins,
+            &ReputationParams::from_config(&ctx.accounts.config),
+            clock.unix_timestamp,
+        )?;
+
+        let dispute = &mut ctx.accounts.dispute;
+        dispute.refund_amount = ruling.refund_amount;
+        dispute.slash_amount = ruling.slash_amount;
+
+        emit!(DisputeResolvedEvent {
+            dispute: dispute.key(),
+            payer: dispute.payer,
+            provider: dispute.provider,
+            payer_wins: ruling.payer_wins,
+            refund_amount: ruling.refund_amount,
+            slash_amount: ruling.slash_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn resolve_expired_dispute(ctx: Context<ResolveExpiredDispute>) -> Result<()> {
+        let clock = Clock::get()?;
+        let dispute = &ctx.accounts.dispute;
+
+        require!(dispute.status == DisputeStatus::Open, ErrorCode::DisputeNotOpen);
+        require!(
+            clock.unix_timestamp > dispute.response_deadline,
+            ErrorCode::DisputeResponseWindowOpen
+        );
+
+        let payer = ctx.accounts.payer.to_account_info();
+        settle_dispute(
+            &mut ctx.accounts.dispute,
+            &mut ctx.accounts.payer_agent,
+            &mut ctx.accounts.provider_agent,
+            &payer,
+            true,
+            &ReputationParams::from_config(&ctx.accounts.config),
+            clock.unix_timestamp,
+        )?;
+
+        let dispute = &ctx.accounts.dispute;
+        emit!(DisputeResolvedEvent {
+            dispute: dispute.key(),
+            payer: dispute.payer,
+            provider: dispute.provider,
+            payer_wins: true,
+            refund_amount: 0,
+            slash_amount: 0,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
     pub fn verify_service(ctx: Context<VerifyService>) -> Result<()> {
         require!(
-            ctx.accounts.verifier.key() == VERIFIER_AUTHORITY,
+            ctx.accounts.verifier.key() == ctx.accounts.config.verifier_authority,
             ErrorCode::UnauthorizedAccess
         );
 
@@ -414,7 +861,7 @@ pub mod x402_registry {
         verified: bool,
     ) -> Result<()> {
         require!(
-            ctx.accounts.authority.key() == TAP_AUTHORITY,
+            ctx.accounts.authority.key() == ctx.accounts.config.tap_authority,
             ErrorCode::UnauthorizedAccess
         );
 
@@ -435,20 +882,22 @@ pub mod x402_registry {
             ctx.accounts.agent.wallet == ctx.accounts.rater.key(),
             ErrorCode::UnauthorizedAccess
         );
-        require!(new_rating >= 1 && new_rating <= 500, ErrorCode::InvalidRating);
+        require!((1..=500).contains(&new_rating), ErrorCode::InvalidRating);
 
+        let weight = rater_weight(&ctx.accounts.config, &ctx.accounts.agent, &ctx.accounts.stake_mint)?;
         let service = &mut ctx.accounts.service;
         let service_rating = &mut ctx.accounts.service_rating;
         let clock = Clock::get()?;
 
         require!(
-            clock.unix_timestamp >= service_rating.last_update_time + (7 * 24 * 60 * 60),
+            clock.unix_timestamp >= service_rating.last_update_time + ctx.accounts.config.rating_update_cooldown_seconds,
             ErrorCode::RatingUpdateCooldown
         );
 
         let old_rating = service_rating.rating;
 
         require!(service.total_ratings > 0, ErrorCode::InvalidState);
+        seed_legacy_rating_weights(service)?;
 
         service.total_rating_sum = service.total_rating_sum
             .checked_sub(old_rating as u64)
@@ -456,18 +905,11 @@ pub mod x402_registry {
         service.total_rating_sum = service.total_rating_sum
             .checked_add(new_rating as u64)
             .ok_or(ErrorCode::MathOverflow)?;
-
-        let avg_rating = service.total_rating_sum
-            .checked_div(service.total_ratings as u64)
-            .ok_or(ErrorCode::MathOverflow)?;
-        require!(avg_rating <= 500, ErrorCode::InvalidRating);
-
-        service.average_rating = avg_rating as u16;
-        service.reputation_score = (service.average_rating as u64)
-            .checked_mul(2)
-            .ok_or(ErrorCode::MathOverflow)?;
+        remove_weighted_rating(service, old_rating, service_rating.weight)?;
+        add_weighted_rating(service, new_rating, weight)?;
 
         service_rating.rating = new_rating;
+        service_rating.weight = weight;
         service_rating.timestamp = clock.unix_timestamp;
         service_rating.last_update_time = clock.unix_timestamp;
         service.last_updated = clock.unix_timestamp;
@@ -475,28 +917,85 @@ pub mod x402_registry {
         Ok(())
     }
 
-    pub fn update_service(
-        ctx: Context<UpdateService>,
-        new_price_per_call: Option<u64>,
-        new_url: Option<String>,
-        new_description: Option<String>,
-    ) -> Result<()> {
-        let service = &mut ctx.accounts.service;
+    pub fn prune_rating(ctx: Context<PruneRating>) -> Result<()> {
         let clock = Clock::get()?;
+        let service_rating = &ctx.accounts.service_rating;
 
-        require!(
-            service.status != ServiceStatus::Suspended,
-            ErrorCode::ServiceSuspended
-        );
+        let expires_at = service_rating.timestamp
+            .checked_add(ctx.accounts.config.rating_window_seconds)
+            .ok_or(ErrorCode::TimestampOverflow)?;
+        require!(clock.unix_timestamp >= expires_at, ErrorCode::RatingNotExpired);
 
-        if let Some(price) = new_price_per_call {
-            require!(price > 0, ErrorCode::InvalidPrice);
-            service.price_per_call = price;
-        }
+        let service = &mut ctx.accounts.service;
+        seed_legacy_rating_weights(service)?;
+        service.total_ratings = service.total_ratings.checked_sub(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        service.total_rating_sum = service.total_rating_sum
+            .checked_sub(service_rating.rating as u64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        remove_weighted_rating(service, service_rating.rating, service_rating.weight)?;
+        service.last_updated = clock.unix_timestamp;
 
-        if let Some(url) = new_url {
-            require!(url.len() <= 512, ErrorCode::StringTooLong);
-            service.url = url;
+        emit!(RatingPrunedEvent {
+            service: service.key(),
+            agent: service_rating.agent,
+            rating: service_rating.rating,
+            weight: service_rating.weight,
+            new_average: service.average_rating,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Grows a rating written before ratings were weighted and gives it `ServiceRating::LEGACY_WEIGHT`, seeding the
+    /// service's weighted totals from its legacy ones first so the two stay consistent. Anyone may pay for it.
+    pub fn migrate_rating(ctx: Context<MigrateRating>) -> Result<()> {
+        let rating_info = ctx.accounts.service_rating.to_account_info();
+        let mut service_rating: ServiceRating = load_legacy_account(
+            &rating_info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            ServiceRating::SPACE,
+        )?;
+        require!(service_rating.weight == 0, ErrorCode::RatingAlreadyMigrated);
+
+        let service = &mut ctx.accounts.service;
+        let (expected_rating, _) = Pubkey::find_program_address(
+            &[b"rating", service.key().as_ref(), service_rating.agent.as_ref()],
+            ctx.program_id,
+        );
+        require_keys_eq!(rating_info.key(), expected_rating, ErrorCode::InvalidPDA);
+
+        seed_legacy_rating_weights(service)?;
+        service_rating.weight = ServiceRating::LEGACY_WEIGHT;
+        service_rating.try_serialize(&mut &mut rating_info.try_borrow_mut_data()?[..])?;
+
+        Ok(())
+    }
+
+    pub fn update_service(
+        ctx: Context<UpdateService>,
+        new_price_per_call: Option<u64>,
+        new_url: Option<String>,
+        new_description: Option<String>,
+    ) -> Result<()> {
+        let service = &mut ctx.accounts.service;
+        let clock = Clock::get()?;
+
+        require!(
+            service.status != ServiceStatus::Suspended,
+            ErrorCode::ServiceSuspended
+        );
+
+        if let Some(price) = new_price_per_call {
+            require!(price > 0, ErrorCode::InvalidPrice);
+            service.price_per_call = price;
+        }
+
+        if let Some(url) = new_url {
+            require!(url.len() <= 512, ErrorCode::StringTooLong);
+            service.url = url;
         }
 
         if let Some(description) = new_description {
@@ -521,30 +1020,35 @@ pub mod x402_registry {
         );
 
         let unlock_time = agent.created_at
-            .checked_add(30 * 24 * 60 * 60)
+            .checked_add(ctx.accounts.config.unstake_lock_seconds)
             .ok_or(ErrorCode::TimestampOverflow)?;
         require!(
             clock.unix_timestamp >= unlock_time,
             ErrorCode::UnstakeLockPeriod
         );
 
+        require!(agent.credit_used == 0, ErrorCode::CreditOutstanding);
+
         let unstake_amount = agent.staked_amount;
         require!(unstake_amount > 0, ErrorCode::NoStakeToWithdraw);
 
-        let seeds = &[b"escrow".as_ref(), &[ctx.bumps.escrow_account]];
+        let mint_key = ctx.accounts.mint.key();
+        let seeds = &[b"escrow".as_ref(), mint_key.as_ref(), &[ctx.accounts.stake_mint.escrow_bump]];
         let signer_seeds = &[&seeds[..]];
 
-        token::transfer(
+        token_interface::transfer_checked(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.escrow_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
                     to: ctx.accounts.recipient_token_account.to_account_info(),
                     authority: ctx.accounts.escrow_account.to_account_info(),
                 },
                 signer_seeds,
             ),
             unstake_amount,
+            ctx.accounts.mint.decimals,
         )?;
 
         agent.staked_amount = 0;
@@ -560,37 +1064,37 @@ pub mod x402_registry {
         ctx: Context<RestakeAgent>,
         stake_amount: u64,
     ) -> Result<()> {
-        let agent = &mut ctx.accounts.agent;
         let clock = Clock::get()?;
 
-        require!(stake_amount >= 1_000_000_000, ErrorCode::InvalidStakeAmount);
+        let agent = &ctx.accounts.agent;
         require!(agent.staked_amount == 0, ErrorCode::AlreadyStaked);
         require!(agent.status == AgentStatus::Paused, ErrorCode::InvalidAgentStatus);
 
         let cooldown_end = agent.last_active
-            .checked_add(7 * 24 * 60 * 60)
+            .checked_add(ctx.accounts.config.restake_cooldown_seconds)
             .ok_or(ErrorCode::TimestampOverflow)?;
         require!(
             clock.unix_timestamp >= cooldown_end,
             ErrorCode::RestakeCooldownNotElapsed
         );
 
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.staker_token_account.to_account_info(),
-                    to: ctx.accounts.escrow_account.to_account_info(),
-                    authority: ctx.accounts.signer.to_account_info(),
-                },
-            ),
+        let staked = deposit_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.staker_token_account,
+            &ctx.accounts.mint,
+            &mut ctx.accounts.escrow_account,
+            &ctx.accounts.signer,
             stake_amount,
         )?;
+        let stake_value = calculate_stake_value(staked, ctx.accounts.stake_mint.reputation_weight_bps)?;
+        require!(stake_value >= ctx.accounts.config.min_stake_amount, ErrorCode::InvalidStakeAmount);
 
-        let calculated_reputation = calculate_initial_reputation(stake_amount);
+        let agent = &mut ctx.accounts.agent;
+        let calculated_reputation = calculate_initial_reputation(stake_value);
         let historical_min = agent.historical_min_reputation;
 
-        agent.staked_amount = stake_amount;
+        agent.staked_amount = staked;
+        agent.stake_mint = ctx.accounts.mint.key();
         agent.reputation_score = if calculated_reputation > historical_min {
             historical_min
         } else {
@@ -605,16 +1109,22 @@ pub mod x402_registry {
         Ok(())
     }
 
-    pub fn suspend_agent(ctx: Context<AdminAgentAction>) -> Result<()> {
+    pub fn suspend_agent(ctx: Context<GovernedAgentAction>) -> Result<()> {
         require!(
-            ctx.accounts.authority.key() == DAO_AUTHORITY,
-            ErrorCode::UnauthorizedAccess
+            ctx.accounts.proposal.action == GovernanceAction::SuspendAgent { agent: ctx.accounts.agent.key() },
+            ErrorCode::ProposalActionMismatch
         );
 
         let agent = &mut ctx.accounts.agent;
         require!(agent.status == AgentStatus::Active, ErrorCode::InvalidAgentStatus);
 
         let clock = Clock::get()?;
+        execute_proposal(
+            &ctx.accounts.config,
+            &mut ctx.accounts.proposal,
+            ctx.accounts.executor.key(),
+            clock.unix_timestamp,
+        )?;
         agent.status = AgentStatus::Suspended;
 
         emit!(AgentStatusChangedEvent {
@@ -626,16 +1136,22 @@ pub mod x402_registry {
         Ok(())
     }
 
-    pub fn unsuspend_agent(ctx: Context<AdminAgentAction>) -> Result<()> {
+    pub fn unsuspend_agent(ctx: Context<GovernedAgentAction>) -> Result<()> {
         require!(
-            ctx.accounts.authority.key() == DAO_AUTHORITY,
-            ErrorCode::UnauthorizedAccess
+            ctx.accounts.proposal.action == GovernanceAction::UnsuspendAgent { agent: ctx.accounts.agent.key() },
+            ErrorCode::ProposalActionMismatch
         );
 
         let agent = &mut ctx.accounts.agent;
         require!(agent.status == AgentStatus::Suspended, ErrorCode::InvalidAgentStatus);
 
         let clock = Clock::get()?;
+        execute_proposal(
+            &ctx.accounts.config,
+            &mut ctx.accounts.proposal,
+            ctx.accounts.executor.key(),
+            clock.unix_timestamp,
+        )?;
         agent.status = AgentStatus::Active;
 
         emit!(AgentStatusChangedEvent {
@@ -647,14 +1163,20 @@ pub mod x402_registry {
         Ok(())
     }
 
-    pub fn ban_agent(ctx: Context<AdminAgentAction>) -> Result<()> {
+    pub fn ban_agent(ctx: Context<GovernedAgentAction>) -> Result<()> {
         require!(
-            ctx.accounts.authority.key() == DAO_AUTHORITY,
-            ErrorCode::UnauthorizedAccess
+            ctx.accounts.proposal.action == GovernanceAction::BanAgent { agent: ctx.accounts.agent.key() },
+            ErrorCode::ProposalActionMismatch
         );
 
         let agent = &mut ctx.accounts.agent;
         let clock = Clock::get()?;
+        execute_proposal(
+            &ctx.accounts.config,
+            &mut ctx.accounts.proposal,
+            ctx.accounts.executor.key(),
+            clock.unix_timestamp,
+        )?;
         agent.status = AgentStatus::Banned;
 
         emit!(AgentStatusChangedEvent {
@@ -705,14 +1227,20 @@ pub mod x402_registry {
         Ok(())
     }
 
-    pub fn suspend_service(ctx: Context<AdminServiceAction>) -> Result<()> {
+    pub fn suspend_service(ctx: Context<GovernedServiceAction>) -> Result<()> {
         require!(
-            ctx.accounts.authority.key() == DAO_AUTHORITY,
-            ErrorCode::UnauthorizedAccess
+            ctx.accounts.proposal.action == GovernanceAction::SuspendService { service: ctx.accounts.service.key() },
+            ErrorCode::ProposalActionMismatch
         );
 
         let service = &mut ctx.accounts.service;
         let clock = Clock::get()?;
+        execute_proposal(
+            &ctx.accounts.config,
+            &mut ctx.accounts.proposal,
+            ctx.accounts.executor.key(),
+            clock.unix_timestamp,
+        )?;
 
         service.status = ServiceStatus::Suspended;
         service.last_updated = clock.unix_timestamp;
@@ -726,10 +1254,10 @@ pub mod x402_registry {
         Ok(())
     }
 
-    pub fn unsuspend_service(ctx: Context<AdminServiceAction>) -> Result<()> {
+    pub fn unsuspend_service(ctx: Context<GovernedServiceAction>) -> Result<()> {
         require!(
-            ctx.accounts.authority.key() == DAO_AUTHORITY,
-            ErrorCode::UnauthorizedAccess
+            ctx.accounts.proposal.action == GovernanceAction::UnsuspendService { service: ctx.accounts.service.key() },
+            ErrorCode::ProposalActionMismatch
         );
 
         let service = &mut ctx.accounts.service;
@@ -737,6 +1265,12 @@ pub mod x402_registry {
 
         require!(service.status == ServiceStatus::Suspended, ErrorCode::InvalidServiceStatus);
 
+        execute_proposal(
+            &ctx.accounts.config,
+            &mut ctx.accounts.proposal,
+            ctx.accounts.executor.key(),
+            clock.unix_timestamp,
+        )?;
         service.status = ServiceStatus::Active;
         service.last_updated = clock.unix_timestamp;
 
@@ -774,6 +1308,8 @@ pub mod x402_registry {
             ErrorCode::InvalidServiceStatus
         );
 
+        unlist_service(&mut ctx.accounts.directory, &mut ctx.accounts.page, &service.key())?;
+
         agent.service_count = agent.service_count.checked_sub(1)
             .ok_or(ErrorCode::MathOverflow)?;
 
@@ -782,9 +1318,16 @@ pub mod x402_registry {
 
     pub fn close_suspended_service(ctx: Context<CloseSuspendedService>) -> Result<()> {
         require!(
-            ctx.accounts.authority.key() == DAO_AUTHORITY,
-            ErrorCode::UnauthorizedAccess
+            ctx.accounts.proposal.action
+                == GovernanceAction::CloseSuspendedService { service: ctx.accounts.service.key() },
+            ErrorCode::ProposalActionMismatch
         );
+        execute_proposal(
+            &ctx.accounts.config,
+            &mut ctx.accounts.proposal,
+            ctx.accounts.executor.key(),
+            Clock::get()?.unix_timestamp,
+        )?;
 
         let service = &ctx.accounts.service;
         let agent = &mut ctx.accounts.agent;
@@ -794,333 +1337,2739 @@ pub mod x402_registry {
             ErrorCode::InvalidServiceStatus
         );
 
+        unlist_service(&mut ctx.accounts.directory, &mut ctx.accounts.page, &service.key())?;
+
         agent.service_count = agent.service_count.checked_sub(1)
             .ok_or(ErrorCode::MathOverflow)?;
 
         Ok(())
     }
 
+    /// `owner` derives the directory accounts and must match the service's owner.
+    pub fn migrate_service(ctx: Context<MigrateService>, owner: Pubkey) -> Result<()> {
+        let service_info = ctx.accounts.service.to_account_info();
+        let mut service: Service = load_legacy_account(
+            &service_info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + Service::SPACE,
+        )?;
+        require!(service.directory_index.is_none(), ErrorCode::ServiceAlreadyListed);
+        require_keys_eq!(service.owner, owner, ErrorCode::InvalidPDA);
+
+        let service_key = service_info.key();
+        let index = list_service(
+            &mut ctx.accounts.directory,
+            &mut ctx.accounts.page,
+            service_key,
+            owner,
+            ctx.bumps.directory,
+            ctx.bumps.page,
+        )?;
+        service.directory_index = Some(index);
+        service.try_serialize(&mut &mut service_info.try_borrow_mut_data()?[..])?;
+
+        emit!(ServiceMigratedEvent {
+            service: service_key,
+            owner,
+            index,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-    pub fn initialize_escrow(ctx: Context<InitializeEscrow>) -> Result<()> {
-        require!(
-            ctx.accounts.authority.key() == DAO_AUTHORITY,
-            ErrorCode::UnauthorizedAccess
-        );
         Ok(())
     }
-}
 
-fn calculate_initial_reputation(stake_amount: u64) -> u64 {
-    if stake_amount >= 10_000_000_000 {
-        7000
-    } else if stake_amount >= 5_000_000_000 {
-        6000
-    } else if stake_amount >= 1_000_000_000 {
-        5000
-    } else {
-        4000
-    }
-}
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: GovernanceAction, emergency: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let proposer = ctx.accounts.proposer.key();
 
-fn calculate_new_reputation(
-    current_reputation: u64,
-    total_transactions: u64,
-    successful_transactions: u64,
-) -> Result<u64> {
-    let success_rate_numerator = (successful_transactions as u128)
-        .checked_mul(10000)
-        .ok_or(ErrorCode::MathOverflow)?;
-    let success_rate = success_rate_numerator
-        .checked_div(total_transactions as u128)
-        .ok_or(ErrorCode::MathOverflow)? as u64;
+        require!(config.is_council_member(&proposer), ErrorCode::NotCouncilMember);
+        require!(!emergency || action.is_suspension(), ErrorCode::EmergencyActionNotAllowed);
+        match &action {
+            GovernanceAction::SlashForFraud { evidence_uri, .. } => {
+                require!(evidence_uri.len() <= 256, ErrorCode::StringTooLong);
+            }
+            GovernanceAction::UpdateCouncil { council, .. } => {
+                require!(council.len() <= MAX_COUNCIL_MEMBERS, ErrorCode::InvalidConfig);
+            }
+            _ => {}
+        }
 
-    let weighted_current = (current_reputation as u128)
-        .checked_mul(95)
-        .ok_or(ErrorCode::MathOverflow)?;
-    let weighted_success = (success_rate as u128)
-        .checked_mul(5)
-        .ok_or(ErrorCode::MathOverflow)?;
-    let weighted_sum = weighted_current
-        .checked_add(weighted_success)
-        .ok_or(ErrorCode::MathOverflow)?;
-    let weighted = weighted_sum
-        .checked_div(100)
-        .ok_or(ErrorCode::MathOverflow)? as u64;
+        let clock = Clock::get()?;
+        let proposal_key = ctx.accounts.proposal.key();
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.id = config.proposal_count;
+        proposal.proposer = proposer;
+        proposal.action = action;
+        proposal.emergency = emergency;
+        proposal.approvals = vec![proposer];
+        proposal.status = ProposalStatus::Pending;
+        proposal.created_at = clock.unix_timestamp;
+        proposal.executable_at = 0;
+        proposal.executed_at = 0;
+        proposal.bump = ctx.bumps.proposal;
+
+        config.proposal_count = config.proposal_count.checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
 
-    Ok(if weighted > 10000 { 10000 } else { weighted })
-}
+        emit!(ProposalCreatedEvent {
+            proposal: proposal_key,
+            id: proposal.id,
+            proposer,
+            action: proposal.action.clone(),
+            emergency,
+            timestamp: clock.unix_timestamp,
+        });
 
-fn calculate_slash_amount(fraud_amount: u64, staked_amount: u64) -> Result<u64> {
-    let min_slash = fraud_amount.checked_mul(2)
-        .ok_or(ErrorCode::MathOverflow)?;
-    let slash_amount = if min_slash > staked_amount {
-        staked_amount
-    } else {
-        min_slash
-    };
-    Ok(slash_amount)
-}
+        record_approval(config, proposal, proposal_key, proposer, clock.unix_timestamp)
+    }
 
-#[derive(Accounts)]
-pub struct RegisterAgent<'info> {
-    #[account(
-        init,
-        payer = signer,
-        space = 8 + Agent::SPACE,
-        seeds = [b"agent", signer.key().as_ref()],
-        bump
-    )]
-    pub agent: Account<'info, Agent>,
-    #[account(mut)]
-    pub signer: Signer<'info>,
-    #[account(
-        mut,
-        constraint = staker_token_account.owner == signer.key() @ ErrorCode::UnauthorizedAccess,
-        constraint = staker_token_account.mint == crate::constants::STAKE_TOKEN_MINT @ ErrorCode::InvalidMint
-    )]
-    pub staker_token_account: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        seeds = [b"escrow"],
-        bump,
-        constraint = escrow_account.mint == crate::constants::STAKE_TOKEN_MINT @ ErrorCode::InvalidMint
-    )]
-    pub escrow_account: Account<'info, TokenAccount>,
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
-}
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let approver = ctx.accounts.approver.key();
+        let proposal_key = ctx.accounts.proposal.key();
+        let proposal = &mut ctx.accounts.proposal;
 
-#[derive(Accounts)]
-pub struct RegisterService<'info> {
-    #[account(
-        init,
-        payer = owner,
-        space = 8 + Service::SPACE
-    )]
-    pub service: Account<'info, Service>,
-    #[account(
-        mut,
-        constraint = agent.wallet == owner.key() @ ErrorCode::UnauthorizedAccess
-    )]
-    pub agent: Account<'info, Agent>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        require!(config.is_council_member(&approver), ErrorCode::NotCouncilMember);
+        require!(proposal.status == ProposalStatus::Pending, ErrorCode::InvalidProposalStatus);
+        require!(!proposal.approvals.contains(&approver), ErrorCode::AlreadyApproved);
 
-#[derive(Accounts)]
-pub struct RecordTransaction<'info> {
-    #[account(mut)]
-    pub agent: Account<'info, Agent>,
-    #[account(mut)]
-    pub service: Account<'info, Service>,
-    pub authority: Signer<'info>,
-}
+        // Approvals from members removed since they approved no longer count and would crowd out the new council.
+        proposal.approvals.retain(|member| config.is_council_member(member));
+        proposal.approvals.push(approver);
 
-#[derive(Accounts)]
-pub struct RateService<'info> {
-    #[account(mut)]
-    pub service: Account<'info, Service>,
-    #[account(
-        constraint = agent.wallet == rater.key()
-    )]
-    pub agent: Account<'info, Agent>,
-    #[account(
-        init,
-        payer = rater,
-        space = ServiceRating::SPACE,
-        seeds = [b"rating", service.key().as_ref(), agent.wallet.as_ref()],
-        bump
-    )]
-    pub service_rating: Account<'info, ServiceRating>,
-    #[account(mut)]
-    pub rater: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        record_approval(config, proposal, proposal_key, approver, Clock::get()?.unix_timestamp)
+    }
 
-#[derive(Accounts)]
-pub struct SlashAgent<'info> {
-    #[account(mut)]
-    pub agent: Account<'info, Agent>,
-    #[account(
-        mut,
-        seeds = [b"escrow"],
-        bump
-    )]
-    pub escrow_account: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        address = DAO_TREASURY
-    )]
-    pub dao_treasury: Account<'info, TokenAccount>,
-    pub authority: Signer<'info>,
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
-}
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
 
-#[derive(Accounts)]
-pub struct UpdateRating<'info> {
-    #[account(mut)]
-    pub service: Account<'info, Service>,
-    #[account(constraint = agent.wallet == rater.key())]
-    pub agent: Account<'info, Agent>,
-    #[account(
-        mut,
-        seeds = [b"rating", service.key().as_ref(), agent.wallet.as_ref()],
-        bump
-    )]
-    pub service_rating: Account<'info, ServiceRating>,
-    pub rater: Signer<'info>,
-}
+        require!(
+            proposal.status == ProposalStatus::Pending || proposal.status == ProposalStatus::Approved,
+            ErrorCode::InvalidProposalStatus
+        );
 
-#[derive(Accounts)]
-pub struct UpdateService<'info> {
-    #[account(
-        mut,
-        constraint = service.owner == owner.key() @ ErrorCode::UnauthorizedAccess
-    )]
-    pub service: Account<'info, Service>,
-    pub owner: Signer<'info>,
-}
+        proposal.status = ProposalStatus::Cancelled;
 
-#[derive(Accounts)]
-pub struct UnstakeAgent<'info> {
-    #[account(
-        mut,
-        constraint = agent.wallet == signer.key() @ ErrorCode::UnauthorizedAccess
-    )]
-    pub agent: Account<'info, Agent>,
-    #[account(
-        mut,
-        seeds = [b"escrow"],
-        bump,
-        constraint = escrow_account.mint == crate::constants::STAKE_TOKEN_MINT @ ErrorCode::InvalidMint
-    )]
-    pub escrow_account: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        constraint = recipient_token_account.owner == signer.key() @ ErrorCode::UnauthorizedAccess,
-        constraint = recipient_token_account.mint == crate::constants::STAKE_TOKEN_MINT @ ErrorCode::InvalidMint
-    )]
-    pub recipient_token_account: Account<'info, TokenAccount>,
-    pub signer: Signer<'info>,
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
-}
+        emit!(ProposalCancelledEvent {
+            proposal: ctx.accounts.proposal.key(),
+            cancelled_by: ctx.accounts.proposer.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-#[derive(Accounts)]
-pub struct RestakeAgent<'info> {
-    #[account(
-        mut,
-        constraint = agent.wallet == signer.key() @ ErrorCode::UnauthorizedAccess
-    )]
-    pub agent: Account<'info, Agent>,
-    #[account(mut)]
-    pub signer: Signer<'info>,
-    #[account(
-        mut,
-        constraint = staker_token_account.owner == signer.key() @ ErrorCode::UnauthorizedAccess,
-        constraint = staker_token_account.mint == crate::constants::STAKE_TOKEN_MINT @ ErrorCode::InvalidMint
-    )]
-    pub staker_token_account: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        seeds = [b"escrow"],
-        bump,
-        constraint = escrow_account.mint == crate::constants::STAKE_TOKEN_MINT @ ErrorCode::InvalidMint
-    )]
-    pub escrow_account: Account<'info, TokenAccount>,
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct AdminAgentAction<'info> {
-    #[account(mut)]
-    pub agent: Account<'info, Agent>,
-    pub authority: Signer<'info>,
-}
+    /// Replaces the council, its threshold and the timelock. Approvals are counted against the outgoing council.
+    pub fn update_council(ctx: Context<GovernedConfigAction>) -> Result<()> {
+        let GovernanceAction::UpdateCouncil {
+            council,
+            threshold,
+            timelock_seconds,
+        } = ctx.accounts.proposal.action.clone()
+        else {
+            return err!(ErrorCode::ProposalActionMismatch);
+        };
 
-#[derive(Accounts)]
-pub struct AdminServiceAction<'info> {
+        let clock = Clock::get()?;
+        execute_proposal(
+            &ctx.accounts.config,
+            &mut ctx.accounts.proposal,
+            ctx.accounts.executor.key(),
+            clock.unix_timestamp,
+        )?;
+
+        let config = &mut ctx.accounts.config;
+        config.council = council;
+        config.council_threshold = threshold;
+        config.governance_timelock_seconds = timelock_seconds;
+        validate_config(config)?;
+
+        emit!(CouncilUpdatedEvent {
+            council: config.council.clone(),
+            threshold: config.council_threshold,
+            timelock_seconds: config.governance_timelock_seconds,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_authorities(ctx: Context<GovernedConfigAction>) -> Result<()> {
+        let GovernanceAction::UpdateAuthorities {
+            dao_authority,
+            arbiter_authority,
+        } = ctx.accounts.proposal.action
+        else {
+            return err!(ErrorCode::ProposalActionMismatch);
+        };
+
+        let clock = Clock::get()?;
+        execute_proposal(
+            &ctx.accounts.config,
+            &mut ctx.accounts.proposal,
+            ctx.accounts.executor.key(),
+            clock.unix_timestamp,
+        )?;
+
+        let config = &mut ctx.accounts.config;
+        config.dao_authority = dao_authority;
+        config.arbiter_authority = arbiter_authority;
+
+        emit!(ConfigUpdatedEvent {
+            authority: config.authority,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_credit_vault(ctx: Context<InitializeCreditVault>) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.config.dao_authority,
+            ErrorCode::UnauthorizedAccess
+        );
+        Ok(())
+    }
+
+    pub fn open_credit_line(ctx: Context<OpenCreditLine>) -> Result<()> {
+        let credit_line = &mut ctx.accounts.credit_line;
+        credit_line.agent = ctx.accounts.signer.key();
+        credit_line.due_at = 0;
+        credit_line.total_drawn = 0;
+        credit_line.total_fees = 0;
+        credit_line.total_repaid = 0;
+        credit_line.total_seized = 0;
+        credit_line.last_draw_at = 0;
+        credit_line.bump = ctx.bumps.credit_line;
+        Ok(())
+    }
+
+    pub fn draw_credit(ctx: Context<DrawCredit>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let config = &ctx.accounts.config;
+        let agent = &mut ctx.accounts.agent;
+        let service = &ctx.accounts.service;
+        let credit_line = &mut ctx.accounts.credit_line;
+        let clock = Clock::get()?;
+
+        require!(agent.status == AgentStatus::Active, ErrorCode::AgentSuspended);
+        require!(service.status == ServiceStatus::Active, ErrorCode::ServiceNotActive);
+        require!(service.owner != agent.wallet, ErrorCode::SelfPaymentNotAllowed);
+        require!(agent.stake_mint == config.stake_token_mint, ErrorCode::InvalidMint);
+        require!(
+            service.accepted_tokens.contains(&config.stake_token_mint),
+            ErrorCode::InvalidMint
+        );
+
+        let fee = calculate_credit_fee(amount, config.credit_fee_bps)?;
+        let owed = amount.checked_add(fee).ok_or(ErrorCode::MathOverflow)?;
+        let credit_used = agent.credit_used.checked_add(owed).ok_or(ErrorCode::MathOverflow)?;
+        require!(credit_used <= agent.credit_limit, ErrorCode::CreditLimitExceeded);
+
+        let seeds = &[b"credit_vault".as_ref(), &[ctx.bumps.credit_vault]];
+        let signer_seeds = &[&seeds[..]];
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.credit_vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.service_token_account.to_account_info(),
+                    authority: ctx.accounts.credit_vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        if agent.credit_used == 0 {
+            credit_line.due_at = clock.unix_timestamp
+                .checked_add(config.credit_repayment_seconds)
+                .ok_or(ErrorCode::TimestampOverflow)?;
+        }
+
+        agent.credit_used = credit_used;
+        agent.last_active = clock.unix_timestamp;
+        credit_line.total_drawn = credit_line.total_drawn.checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        credit_line.total_fees = credit_line.total_fees.checked_add(fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+        credit_line.last_draw_at = clock.unix_timestamp;
+
+        emit!(CreditDrawnEvent {
+            wallet: agent.wallet,
+            service: service.key(),
+            amount,
+            fee,
+            credit_used,
+            due_at: credit_line.due_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn repay_credit(ctx: Context<RepayCredit>, amount: u64) -> Result<()> {
+        let agent = &mut ctx.accounts.agent;
+        let credit_line = &mut ctx.accounts.credit_line;
+        let clock = Clock::get()?;
+
+        require!(agent.credit_used > 0, ErrorCode::NoCreditOutstanding);
+
+        let repay_amount = amount.min(agent.credit_used);
+        require!(repay_amount > 0, ErrorCode::InvalidAmount);
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.payer_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.credit_vault.to_account_info(),
+                    authority: ctx.accounts.signer.to_account_info(),
+                },
+            ),
+            repay_amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        agent.credit_used = agent.credit_used.checked_sub(repay_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        credit_line.total_repaid = credit_line.total_repaid.checked_add(repay_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        if agent.credit_used == 0 {
+            credit_line.due_at = 0;
+        }
+
+        emit!(CreditRepaidEvent {
+            wallet: agent.wallet,
+            amount: repay_amount,
+            credit_used: agent.credit_used,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn settle_credit_default(ctx: Context<SettleCreditDefault>) -> Result<()> {
+        let agent = &mut ctx.accounts.agent;
+        let credit_line = &mut ctx.accounts.credit_line;
+        let clock = Clock::get()?;
+
+        require!(agent.credit_used > 0, ErrorCode::NoCreditOutstanding);
+        require!(clock.unix_timestamp > credit_line.due_at, ErrorCode::CreditNotDue);
+
+        let seized = agent.credit_used.min(agent.staked_amount);
+
+        if seized > 0 {
+            let mint_key = ctx.accounts.mint.key();
+            let seeds = &[b"escrow".as_ref(), mint_key.as_ref(), &[ctx.accounts.stake_mint.escrow_bump]];
+            let signer_seeds = &[&seeds[..]];
+
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.escrow_account.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: ctx.accounts.credit_vault.to_account_info(),
+                        authority: ctx.accounts.escrow_account.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                seized,
+                ctx.accounts.mint.decimals,
+            )?;
+        }
+
+        agent.staked_amount = agent.staked_amount.checked_sub(seized)
+            .ok_or(ErrorCode::MathOverflow)?;
+        // Whatever the stake cannot cover is written off, so a default settles, and is penalised, exactly once.
+        let written_off = agent.credit_used.checked_sub(seized)
+            .ok_or(ErrorCode::MathOverflow)?;
+        agent.credit_used = 0;
+        agent.credit_limit = 0;
+        credit_line.total_seized = credit_line.total_seized.checked_add(seized)
+            .ok_or(ErrorCode::MathOverflow)?;
+        credit_line.due_at = 0;
+
+        agent.reputation_score = apply_penalty(agent.reputation_score, ctx.accounts.config.reputation_slash_penalty);
+
+        if agent.status == AgentStatus::Active || agent.status == AgentStatus::Paused {
+            agent.status = AgentStatus::Suspended;
+
+            emit!(AgentStatusChangedEvent {
+                wallet: agent.wallet,
+                new_status: AgentStatus::Suspended,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        emit!(CreditDefaultedEvent {
+            wallet: agent.wallet,
+            seized,
+            written_off,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn create_payment_escrow(
+        ctx: Context<CreatePaymentEscrow>,
+        nonce: u64,
+        amount: u64,
+        deadline: i64,
+    ) -> Result<()> {
+        let service = &ctx.accounts.service;
+        let clock = Clock::get()?;
+
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(deadline > clock.unix_timestamp, ErrorCode::InvalidDeadline);
+        require!(service.status == ServiceStatus::Active, ErrorCode::ServiceNotActive);
+        require!(service.owner != ctx.accounts.payer.key(), ErrorCode::SelfPaymentNotAllowed);
+        require!(
+            service.accepted_tokens.contains(&ctx.accounts.mint.key()),
+            ErrorCode::InvalidMint
+        );
+
+        let amount = deposit_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.payer_token_account,
+            &ctx.accounts.mint,
+            &mut ctx.accounts.vault,
+            &ctx.accounts.payer,
+            amount,
+        )?;
+
+        let escrow_key = ctx.accounts.payment_escrow.key();
+        let escrow = &mut ctx.accounts.payment_escrow;
+        escrow.payer = ctx.accounts.payer.key();
+        escrow.payee = service.owner;
+        escrow.service = service.key();
+        escrow.mint = ctx.accounts.mint.key();
+        escrow.nonce = nonce;
+        escrow.amount = amount;
+        escrow.deadline = deadline;
+        escrow.created_at = clock.unix_timestamp;
+        escrow.settled_at = 0;
+        escrow.status = PaymentEscrowStatus::Funded;
+        escrow.bump = ctx.bumps.payment_escrow;
+        escrow.vault_bump = ctx.bumps.vault;
+
+        emit!(PaymentEscrowFundedEvent {
+            escrow: escrow_key,
+            payer: escrow.payer,
+            payee: escrow.payee,
+            service: escrow.service,
+            mint: escrow.mint,
+            nonce,
+            amount,
+            deadline,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn release_payment_escrow(ctx: Context<ReleasePaymentEscrow>) -> Result<()> {
+        let escrow = &ctx.accounts.payment_escrow;
+        let authority = ctx.accounts.authority.key();
+
+        require!(
+            authority == escrow.payer || authority == ctx.accounts.config.oracle_authority,
+            ErrorCode::UnauthorizedAccess
+        );
+        require!(
+            escrow.status == PaymentEscrowStatus::Funded,
+            ErrorCode::PaymentEscrowNotFunded
+        );
+
+        settle_payment_escrow(
+            escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.payee_token_account.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+
+        let clock = Clock::get()?;
+        let escrow_key = ctx.accounts.payment_escrow.key();
+        let escrow = &mut ctx.accounts.payment_escrow;
+        escrow.status = PaymentEscrowStatus::Released;
+        escrow.settled_at = clock.unix_timestamp;
+
+        emit!(PaymentEscrowReleasedEvent {
+            escrow: escrow_key,
+            payee: escrow.payee,
+            amount: escrow.amount,
+            released_by: authority,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn refund_payment_escrow(ctx: Context<RefundPaymentEscrow>) -> Result<()> {
+        let escrow = &ctx.accounts.payment_escrow;
+        let clock = Clock::get()?;
+
+        require!(
+            escrow.status == PaymentEscrowStatus::Funded,
+            ErrorCode::PaymentEscrowNotFunded
+        );
+        require!(
+            clock.unix_timestamp > escrow.deadline,
+            ErrorCode::PaymentEscrowNotExpired
+        );
+
+        settle_payment_escrow(
+            escrow,
+            &ctx.accounts.vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.payer_token_account.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+
+        let escrow_key = ctx.accounts.payment_escrow.key();
+        let escrow = &mut ctx.accounts.payment_escrow;
+        escrow.status = PaymentEscrowStatus::Refunded;
+        escrow.settled_at = clock.unix_timestamp;
+
+        emit!(PaymentEscrowRefundedEvent {
+            escrow: escrow_key,
+            payer: escrow.payer,
+            amount: escrow.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn open_channel(ctx: Context<OpenChannel>, channel_id: u64, deposit: u64) -> Result<()> {
+        let service = &ctx.accounts.service;
+        let clock = Clock::get()?;
+
+        require!(deposit > 0, ErrorCode::InvalidAmount);
+        require!(service.status == ServiceStatus::Active, ErrorCode::ServiceNotActive);
+        require!(service.owner != ctx.accounts.payer.key(), ErrorCode::SelfPaymentNotAllowed);
+        require!(
+            service.accepted_tokens.contains(&ctx.accounts.mint.key()),
+            ErrorCode::InvalidMint
+        );
+
+        let deposit = deposit_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.payer_token_account,
+            &ctx.accounts.mint,
+            &mut ctx.accounts.vault,
+            &ctx.accounts.payer,
+            deposit,
+        )?;
+
+        let channel_key = ctx.accounts.channel.key();
+        let channel = &mut ctx.accounts.channel;
+        channel.payer = ctx.accounts.payer.key();
+        channel.payee = service.owner;
+        channel.service = service.key();
+        channel.mint = ctx.accounts.mint.key();
+        channel.channel_id = channel_id;
+        channel.deposit = deposit;
+        channel.redeemed = 0;
+        channel.last_nonce = 0;
+        channel.challenge_seconds = ctx.accounts.config.channel_challenge_seconds;
+        channel.opened_at = clock.unix_timestamp;
+        channel.close_requested_at = 0;
+        channel.status = ChannelStatus::Open;
+        channel.bump = ctx.bumps.channel;
+        channel.vault_bump = ctx.bumps.vault;
+
+        emit!(ChannelOpenedEvent {
+            channel: channel_key,
+            payer: channel.payer,
+            payee: channel.payee,
+            service: channel.service,
+            mint: channel.mint,
+            deposit,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn top_up_channel(ctx: Context<TopUpChannel>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            ctx.accounts.channel.status == ChannelStatus::Open,
+            ErrorCode::ChannelNotOpen
+        );
+
+        let amount = deposit_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.payer_token_account,
+            &ctx.accounts.mint,
+            &mut ctx.accounts.vault,
+            &ctx.accounts.payer,
+            amount,
+        )?;
+
+        let channel = &mut ctx.accounts.channel;
+        channel.deposit = channel.deposit.checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(ChannelToppedUpEvent {
+            channel: channel.key(),
+            amount,
+            deposit: channel.deposit,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn redeem_voucher(ctx: Context<RedeemVoucher>, amount: u64, nonce: u64) -> Result<()> {
+        let channel = &ctx.accounts.channel;
+        let channel_key = channel.key();
+
+        require!(channel.status != ChannelStatus::Closed, ErrorCode::ChannelNotOpen);
+        require!(nonce > channel.last_nonce, ErrorCode::StaleVoucher);
+        require!(amount > channel.redeemed, ErrorCode::StaleVoucher);
+        require!(amount <= channel.deposit, ErrorCode::VoucherExceedsDeposit);
+
+        ed25519::verify_previous_ed25519_instruction(
+            &ctx.accounts.instructions_sysvar,
+            &channel.payer,
+            &voucher_message(&channel_key, amount, nonce),
+        )?;
+
+        let payout = amount.checked_sub(channel.redeemed)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let channel_id = channel.channel_id.to_le_bytes();
+        let seeds = &[
+            b"channel".as_ref(),
+            channel.payer.as_ref(),
+            channel.service.as_ref(),
+            channel_id.as_ref(),
+            &[channel.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.payee_token_account.to_account_info(),
+                    authority: ctx.accounts.channel.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            payout,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        let channel = &mut ctx.accounts.channel;
+        channel.redeemed = amount;
+        channel.last_nonce = nonce;
+
+        emit!(VoucherRedeemedEvent {
+            channel: channel_key,
+            payee: channel.payee,
+            amount,
+            nonce,
+            payout,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn request_channel_close(ctx: Context<RequestChannelClose>) -> Result<()> {
+        let channel = &mut ctx.accounts.channel;
+        let clock = Clock::get()?;
+
+        require!(channel.status == ChannelStatus::Open, ErrorCode::ChannelNotOpen);
+
+        channel.status = ChannelStatus::Closing;
+        channel.close_requested_at = clock.unix_timestamp;
+
+        let closes_at = clock.unix_timestamp
+            .checked_add(channel.challenge_seconds)
+            .ok_or(ErrorCode::TimestampOverflow)?;
+
+        emit!(ChannelCloseRequestedEvent {
+            channel: channel.key(),
+            payer: channel.payer,
+            closes_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn finalize_channel_close(ctx: Context<FinalizeChannelClose>) -> Result<()> {
+        let channel = &ctx.accounts.channel;
+        let channel_key = channel.key();
+        let clock = Clock::get()?;
+
+        require!(channel.status != ChannelStatus::Closed, ErrorCode::ChannelNotOpen);
+
+        if ctx.accounts.authority.key() != channel.payee {
+            require!(
+                ctx.accounts.authority.key() == channel.payer,
+                ErrorCode::UnauthorizedAccess
+            );
+            require!(channel.status == ChannelStatus::Closing, ErrorCode::ChannelNotOpen);
+
+            let closes_at = channel.close_requested_at
+                .checked_add(channel.challenge_seconds)
+                .ok_or(ErrorCode::TimestampOverflow)?;
+            require!(
+                clock.unix_timestamp >= closes_at,
+                ErrorCode::ChallengePeriodActive
+            );
+        }
+
+        let refund = channel.deposit.checked_sub(channel.redeemed)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let channel_id = channel.channel_id.to_le_bytes();
+        let seeds = &[
+            b"channel".as_ref(),
+            channel.payer.as_ref(),
+            channel.service.as_ref(),
+            channel_id.as_ref(),
+            &[channel.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        if refund > 0 {
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.vault.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: ctx.accounts.payer_token_account.to_account_info(),
+                        authority: ctx.accounts.channel.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                refund,
+                ctx.accounts.mint.decimals,
+            )?;
+        }
+
+        token_interface::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token_interface::CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.payer.to_account_info(),
+                authority: ctx.accounts.channel.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        let channel = &mut ctx.accounts.channel;
+        channel.status = ChannelStatus::Closed;
+
+        emit!(ChannelClosedEvent {
+            channel: channel_key,
+            redeemed: channel.redeemed,
+            refunded: refund,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn add_stake_mint(ctx: Context<AddStakeMint>, reputation_weight_bps: u32) -> Result<()> {
+        require!(reputation_weight_bps > 0, ErrorCode::InvalidConfig);
+
+        let stake_mint = &mut ctx.accounts.stake_mint;
+        stake_mint.mint = ctx.accounts.mint.key();
+        stake_mint.token_program = ctx.accounts.token_program.key();
+        stake_mint.decimals = ctx.accounts.mint.decimals;
+        stake_mint.reputation_weight_bps = reputation_weight_bps;
+        stake_mint.enabled = true;
+        stake_mint.bump = ctx.bumps.stake_mint;
+        stake_mint.escrow_bump = ctx.bumps.escrow_account;
+
+        emit!(StakeMintUpdatedEvent {
+            mint: stake_mint.mint,
+            token_program: stake_mint.token_program,
+            reputation_weight_bps,
+            enabled: true,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_stake_mint(ctx: Context<UpdateStakeMint>, update: StakeMintUpdate) -> Result<()> {
+        let stake_mint = &mut ctx.accounts.stake_mint;
+
+        if let Some(reputation_weight_bps) = update.reputation_weight_bps {
+            require!(reputation_weight_bps > 0, ErrorCode::InvalidConfig);
+            stake_mint.reputation_weight_bps = reputation_weight_bps;
+        }
+        if let Some(enabled) = update.enabled {
+            stake_mint.enabled = enabled;
+        }
+
+        emit!(StakeMintUpdatedEvent {
+            mint: stake_mint.mint,
+            token_program: stake_mint.token_program,
+            reputation_weight_bps: stake_mint.reputation_weight_bps,
+            enabled: stake_mint.enabled,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Moves a pre-multi-mint agent's stake out of the global `["escrow"]` account into the escrow of the config's
+    /// stake mint, which must already be listed, and records that mint on the agent. Anyone may pay for it.
+    pub fn migrate_legacy_stake(ctx: Context<MigrateLegacyStake>) -> Result<()> {
+        let agent_info = ctx.accounts.agent.to_account_info();
+        let mut agent: Agent = load_legacy_account(
+            &agent_info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            8 + Agent::SPACE,
+        )?;
+        require!(agent.stake_mint == Pubkey::default(), ErrorCode::StakeAlreadyMigrated);
+
+        let amount = agent.staked_amount;
+        if amount > 0 {
+            let seeds = &[b"escrow".as_ref(), &[ctx.bumps.legacy_escrow]];
+            let signer_seeds = &[&seeds[..]];
+
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.legacy_escrow.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: ctx.accounts.escrow_account.to_account_info(),
+                        authority: ctx.accounts.legacy_escrow.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+                ctx.accounts.mint.decimals,
+            )?;
+        }
+
+        agent.stake_mint = ctx.accounts.mint.key();
+        agent.try_serialize(&mut &mut agent_info.try_borrow_mut_data()?[..])?;
+
+        emit!(LegacyStakeMigratedEvent {
+            agent: agent_info.key(),
+            wallet: agent.wallet,
+            mint: agent.stake_mint,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn add_probe_operator(ctx: Context<AddProbeOperator>, operator: Pubkey) -> Result<()> {
+        let clock = Clock::get()?;
+        let probe_operator = &mut ctx.accounts.probe_operator;
+        probe_operator.operator = operator;
+        probe_operator.added_at = clock.unix_timestamp;
+        probe_operator.reports = 0;
+        probe_operator.bump = ctx.bumps.probe_operator;
+
+        emit!(ProbeOperatorUpdatedEvent {
+            operator,
+            enabled: true,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_probe_operator(ctx: Context<RemoveProbeOperator>) -> Result<()> {
+        emit!(ProbeOperatorUpdatedEvent {
+            operator: ctx.accounts.probe_operator.operator,
+            enabled: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn open_service_health(ctx: Context<OpenServiceHealth>) -> Result<()> {
+        let health = &mut ctx.accounts.service_health;
+        health.service = ctx.accounts.service.key();
+        health.uptime_bps = 0;
+        health.latency_ms = 0;
+        health.total_probes = 0;
+        health.successful_probes = 0;
+        health.reports = 0;
+        health.last_operator = Pubkey::default();
+        health.last_window_end = 0;
+        health.last_reported_at = 0;
+        health.bump = ctx.bumps.service_health;
+        Ok(())
+    }
+
+    pub fn report_health(ctx: Context<ReportHealth>, report: HealthReport) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let health = &mut ctx.accounts.service_health;
+        let clock = Clock::get()?;
+
+        require!(report.probes > 0, ErrorCode::InvalidHealthReport);
+        require!(report.successes <= report.probes, ErrorCode::InvalidHealthReport);
+        require!(
+            report.window_start < report.window_end && report.window_end <= clock.unix_timestamp,
+            ErrorCode::InvalidHealthReport
+        );
+        require!(report.window_start >= health.last_window_end, ErrorCode::InvalidHealthReport);
+
+        let next_report = health.last_reported_at
+            .checked_add(config.health_report_interval_seconds)
+            .ok_or(ErrorCode::TimestampOverflow)?;
+        require!(
+            health.reports == 0 || clock.unix_timestamp >= next_report,
+            ErrorCode::HealthReportRateLimited
+        );
+
+        let window_uptime_bps = (report.successes as u64 * 10_000 / report.probes as u64) as u16;
+
+        if health.reports == 0 {
+            health.uptime_bps = window_uptime_bps;
+            health.latency_ms = report.average_latency_ms;
+        } else {
+            health.uptime_bps = calculate_ewma(health.uptime_bps as u64, window_uptime_bps as u64, config.uptime_alpha_bps) as u16;
+            if report.successes > 0 {
+                health.latency_ms = calculate_ewma(
+                    health.latency_ms as u64,
+                    report.average_latency_ms as u64,
+                    config.uptime_alpha_bps,
+                ) as u32;
+            }
+        }
+
+        health.total_probes = health.total_probes.checked_add(report.probes as u64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        health.successful_probes = health.successful_probes.checked_add(report.successes as u64)
+            .ok_or(ErrorCode::MathOverflow)?;
+        health.reports = health.reports.checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        health.last_operator = ctx.accounts.operator.key();
+        health.last_window_end = report.window_end;
+        health.last_reported_at = clock.unix_timestamp;
+
+        let probe_operator = &mut ctx.accounts.probe_operator;
+        probe_operator.reports = probe_operator.reports.checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let service = &mut ctx.accounts.service;
+        service.uptime_percent = (health.uptime_bps / 100) as u8;
+        service.last_updated = clock.unix_timestamp;
+
+        emit!(ServiceHealthReportedEvent {
+            service: service.key(),
+            operator: ctx.accounts.operator.key(),
+            window_start: report.window_start,
+            window_end: report.window_end,
+            probes: report.probes,
+            successes: report.successes,
+            window_uptime_bps,
+            uptime_bps: health.uptime_bps,
+            latency_ms: health.latency_ms,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+fn validate_config_params(params: &ConfigParams) -> Result<()> {
+    require!(params.min_stake_amount > 0, ErrorCode::InvalidConfig);
+    require!(params.min_slash_amount > 0, ErrorCode::InvalidConfig);
+    require!(
+        params.unstake_lock_seconds >= 0
+            && params.restake_cooldown_seconds >= 0
+            && params.transaction_rate_limit_seconds >= 0
+            && params.rating_update_cooldown_seconds >= 0
+            && params.slash_cooldown_seconds >= 0
+            && params.dispute_response_seconds > 0
+            && params.credit_repayment_seconds > 0
+            && params.channel_challenge_seconds > 0
+            && params.health_report_interval_seconds >= 0,
+        ErrorCode::InvalidConfig
+    );
+    require!(params.credit_reputation_threshold <= 10000, ErrorCode::InvalidConfig);
+    require!(params.credit_fee_bps <= 10000, ErrorCode::InvalidConfig);
+    require!(
+        params.uptime_alpha_bps > 0 && params.uptime_alpha_bps <= 10000,
+        ErrorCode::InvalidConfig
+    );
+    require!(
+        params.reputation_half_life_seconds >= 0 && params.reputation_value_unit > 0,
+        ErrorCode::InvalidConfig
+    );
+    require!(params.rating_window_seconds > 0, ErrorCode::InvalidConfig);
+    require!(
+        params.reputation_alpha_bps > 0
+            && params.reputation_alpha_bps <= params.reputation_max_alpha_bps
+            && params.reputation_max_alpha_bps <= 10000,
+        ErrorCode::InvalidConfig
+    );
+    require!(
+        !params.council.is_empty() && params.council.len() <= MAX_COUNCIL_MEMBERS,
+        ErrorCode::InvalidConfig
+    );
+    require!(
+        params.council.iter().enumerate().all(|(i, member)| !params.council[..i].contains(member)),
+        ErrorCode::InvalidConfig
+    );
+    require!(
+        params.council_threshold > 0 && params.council_threshold as usize <= params.council.len(),
+        ErrorCode::InvalidConfig
+    );
+    require!(params.governance_timelock_seconds >= 0, ErrorCode::InvalidConfig);
+    Ok(())
+}
+
+fn validate_config(config: &RegistryConfig) -> Result<()> {
+    validate_config_params(&ConfigParams {
+        oracle_authority: config.oracle_authority,
+        verifier_authority: config.verifier_authority,
+        tap_authority: config.tap_authority,
+        dao_authority: config.dao_authority,
+        arbiter_authority: config.arbiter_authority,
+        dao_treasury: config.dao_treasury,
+        stake_token_mint: config.stake_token_mint,
+        min_stake_amount: config.min_stake_amount,
+        min_slash_amount: config.min_slash_amount,
+        unstake_lock_seconds: config.unstake_lock_seconds,
+        restake_cooldown_seconds: config.restake_cooldown_seconds,
+        transaction_rate_limit_seconds: config.transaction_rate_limit_seconds,
+        rating_update_cooldown_seconds: config.rating_update_cooldown_seconds,
+        slash_cooldown_seconds: config.slash_cooldown_seconds,
+        credit_reputation_threshold: config.credit_reputation_threshold,
+        dispute_bond_lamports: config.dispute_bond_lamports,
+        dispute_response_seconds: config.dispute_response_seconds,
+        credit_fee_bps: config.credit_fee_bps,
+        credit_repayment_seconds: config.credit_repayment_seconds,
+        channel_challenge_seconds: config.channel_challenge_seconds,
+        health_report_interval_seconds: config.health_report_interval_seconds,
+        uptime_alpha_bps: config.uptime_alpha_bps,
+        reputation_half_life_seconds: config.reputation_half_life_seconds,
+        reputation_value_unit: config.reputation_value_unit,
+        reputation_alpha_bps: config.reputation_alpha_bps,
+        reputation_max_alpha_bps: config.reputation_max_alpha_bps,
+        reputation_slash_penalty: config.reputation_slash_penalty,
+        rating_window_seconds: config.rating_window_seconds,
+        council: config.council.clone(),
+        council_threshold: config.council_threshold,
+        governance_timelock_seconds: config.governance_timelock_seconds,
+    })
+}
+
+fn settle_dispute<'info>(
+    dispute: &mut Account<'info, Dispute>,
+    payer_agent: &mut Account<'info, Agent>,
+    provider_agent: &mut Account<'info, Agent>,
+    winner: &AccountInfo<'info>,
+    payer_wins: bool,
+    params: &ReputationParams,
+    timestamp: i64,
+) -> Result<()> {
+    let bonds = if dispute.provider_bonded {
+        dispute.bond_lamports.checked_mul(2).ok_or(ErrorCode::MathOverflow)?
+    } else {
+        dispute.bond_lamports
+    };
+
+    let dispute_info = dispute.to_account_info();
+    **dispute_info.try_borrow_mut_lamports()? = dispute_info
+        .lamports()
+        .checked_sub(bonds)
+        .ok_or(ErrorCode::MathOverflow)?;
+    **winner.try_borrow_mut_lamports()? = winner
+        .lamports()
+        .checked_add(bonds)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let (won, lost) = if payer_wins {
+        (payer_agent, provider_agent)
+    } else {
+        (provider_agent, payer_agent)
+    };
+    won.disputes_won = won.disputes_won.checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    lost.disputes_lost = lost.disputes_lost.checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    won.reputation_score = calculate_dispute_reputation(won.reputation_score, true, params)?;
+    lost.reputation_score = calculate_dispute_reputation(lost.reputation_score, false, params)?;
+
+    dispute.status = if payer_wins {
+        DisputeStatus::ResolvedForPayer
+    } else {
+        DisputeStatus::ResolvedForProvider
+    };
+    dispute.resolved_at = timestamp;
+
+    Ok(())
+}
+
+fn settle_payment_escrow<'info>(
+    escrow: &Account<'info, PaymentEscrow>,
+    vault: &InterfaceAccount<'info, token_interface::TokenAccount>,
+    mint: &InterfaceAccount<'info, token_interface::Mint>,
+    destination: &AccountInfo<'info>,
+    rent_recipient: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    let nonce = escrow.nonce.to_le_bytes();
+    let seeds = &[
+        b"payment_escrow".as_ref(),
+        escrow.payer.as_ref(),
+        escrow.service.as_ref(),
+        nonce.as_ref(),
+        &[escrow.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: vault.to_account_info(),
+                mint: mint.to_account_info(),
+                to: destination.clone(),
+                authority: escrow.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        escrow.amount,
+        mint.decimals,
+    )?;
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token_interface::CloseAccount {
+            account: vault.to_account_info(),
+            destination: rent_recipient.clone(),
+            authority: escrow.to_account_info(),
+        },
+        signer_seeds,
+    ))
+}
+
+/// `amount` is what the caller reported; only `settled_amount`, the value known to have moved, feeds reputation,
+/// spend, revenue and the interaction totals that weight ratings. With nothing settled, reputation only decays.
+#[allow(clippy::too_many_arguments)]
+fn apply_transaction<'info>(
+    config: &RegistryConfig,
+    agent: &mut Account<'info, Agent>,
+    service: &mut Account<'info, Service>,
+    interaction: &mut Account<'info, ServiceInteraction>,
+    interaction_bump: u8,
+    amount: u64,
+    settled_amount: Option<u64>,
+    success: bool,
+    response_time_ms: u32,
+    now: i64,
+) -> Result<()> {
+    require!(
+        service.status == ServiceStatus::Active,
+        ErrorCode::ServiceNotActive
+    );
+    require!(
+        agent.status == AgentStatus::Active,
+        ErrorCode::AgentSuspended
+    );
+
+    require!(
+        now >= agent.last_transaction_time + config.transaction_rate_limit_seconds,
+        ErrorCode::RateLimitExceeded
+    );
+
+    let inactive_seconds = now.saturating_sub(agent.last_active);
+    let params = ReputationParams::from_config(config);
+    agent.reputation_score = match settled_amount {
+        Some(settled_amount) => {
+            calculate_new_reputation(agent.reputation_score, success, settled_amount, inactive_seconds, &params)?
+        }
+        None => decay_reputation(agent.reputation_score, inactive_seconds, params.half_life_seconds)?,
+    };
+    let settled_amount = settled_amount.unwrap_or(0);
+
+    agent.total_transactions = agent.total_transactions.checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    agent.last_active = now;
+    agent.last_transaction_time = now;
+
+    service.total_calls = service.total_calls.checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    if success {
+        agent.successful_transactions = agent.successful_transactions.checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        agent.total_spent = agent.total_spent.checked_add(settled_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        if agent.reputation_score > config.credit_reputation_threshold {
+            agent.credit_limit = agent.total_spent.checked_div(10)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+
+        service.successful_calls = service.successful_calls.checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        service.total_revenue = service.total_revenue.checked_add(settled_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        service.total_response_time_sum = service.total_response_time_sum
+            .checked_add(response_time_ms as u64)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        service.average_response_time_ms = (service.total_response_time_sum
+            .checked_div(service.total_calls)
+            .ok_or(ErrorCode::MathOverflow)?) as u32;
+    }
+
+    service.last_updated = now;
+
+    if interaction.transactions == 0 {
+        interaction.service = service.key();
+        interaction.agent = agent.key();
+        interaction.first_at = now;
+        interaction.bump = interaction_bump;
+    }
+    interaction.transactions = interaction.transactions.checked_add(1)
+        .ok_or(ErrorCode::MathOverflow)?;
+    if success {
+        interaction.successful_transactions = interaction.successful_transactions.checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+        interaction.total_amount = interaction.total_amount.checked_add(settled_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+    interaction.last_at = now;
+
+    emit!(TransactionRecordedEvent {
+        agent: agent.wallet,
+        service: service.key(),
+        amount,
+        success,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+const RECEIPT_DOMAIN: &[u8] = b"x402-registry:receipt";
+const VOUCHER_DOMAIN: &[u8] = b"x402-registry:voucher";
+
+fn receipt_message(
+    service: &Pubkey,
+    payer: &Pubkey,
+    amount: u64,
+    success: bool,
+    response_time_ms: u32,
+    nonce: u64,
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(RECEIPT_DOMAIN.len() + 32 + 85);
+    message.extend_from_slice(RECEIPT_DOMAIN);
+    message.extend_from_slice(crate::ID.as_ref());
+    message.extend_from_slice(service.as_ref());
+    message.extend_from_slice(payer.as_ref());
+    message.extend_from_slice(&amount.to_le_bytes());
+    message.push(success as u8);
+    message.extend_from_slice(&response_time_ms.to_le_bytes());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message
+}
+
+fn voucher_message(channel: &Pubkey, amount: u64, nonce: u64) -> Vec<u8> {
+    let mut message = Vec::with_capacity(VOUCHER_DOMAIN.len() + 32 + 48);
+    message.extend_from_slice(VOUCHER_DOMAIN);
+    message.extend_from_slice(crate::ID.as_ref());
+    message.extend_from_slice(channel.as_ref());
+    message.extend_from_slice(&amount.to_le_bytes());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message
+}
+
+fn deposit_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: &InterfaceAccount<'info, token_interface::TokenAccount>,
+    mint: &InterfaceAccount<'info, token_interface::Mint>,
+    to: &mut InterfaceAccount<'info, token_interface::TokenAccount>,
+    authority: &Signer<'info>,
+    amount: u64,
+) -> Result<u64> {
+    let balance_before = to.amount;
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: authority.to_account_info(),
+            },
+        ),
+        amount,
+        mint.decimals,
+    )?;
+
+    // Token-2022 transfer fees are withheld from the destination, so count what actually arrived.
+    to.reload()?;
+    to.amount.checked_sub(balance_before).ok_or(ErrorCode::MathOverflow.into())
+}
+
+/// Loads a program account written under an older layout: checks its owner and discriminator, grows it to `space`
+/// with zeroed tail bytes, and only then deserializes it, so fields added since then read as zero.
+fn load_legacy_account<'info, T: AccountDeserialize + anchor_lang::Discriminator>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<T> {
+    require_keys_eq!(*account.owner, crate::ID, ErrorCode::InvalidAccountLayout);
+    require!(
+        account.try_borrow_data()?.get(..8) == Some(T::DISCRIMINATOR.as_ref()),
+        ErrorCode::InvalidAccountLayout
+    );
+
+    if account.data_len() < space {
+        let shortfall = Rent::get()?.minimum_balance(space).saturating_sub(account.lamports());
+        if shortfall > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: account.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        account.realloc(space, true)?;
+    }
+
+    T::try_deserialize(&mut &account.try_borrow_data()?[..])
+}
+
+fn council_approvals(config: &RegistryConfig, proposal: &Proposal) -> usize {
+    proposal.approvals.iter().filter(|approver| config.is_council_member(approver)).count()
+}
+
+/// Emits the approval and starts the timelock once the proposal reaches the council threshold. Emergency
+/// proposals are executable as soon as they are approved.
+fn record_approval(
+    config: &RegistryConfig,
+    proposal: &mut Proposal,
+    proposal_key: Pubkey,
+    approver: Pubkey,
+    now: i64,
+) -> Result<()> {
+    let approvals = council_approvals(config, proposal);
+    if proposal.status == ProposalStatus::Pending && approvals >= config.council_threshold as usize {
+        let delay = if proposal.emergency { 0 } else { config.governance_timelock_seconds };
+        proposal.status = ProposalStatus::Approved;
+        proposal.executable_at = now.checked_add(delay).ok_or(ErrorCode::TimestampOverflow)?;
+    }
+
+    emit!(ProposalApprovedEvent {
+        proposal: proposal_key,
+        approver,
+        approvals: approvals as u8,
+        executable_at: proposal.executable_at,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Approvals are recounted against the current council, so removing members can stall an approved proposal.
+fn execute_proposal(
+    config: &RegistryConfig,
+    proposal: &mut Account<Proposal>,
+    executor: Pubkey,
+    now: i64,
+) -> Result<()> {
+    require!(proposal.status == ProposalStatus::Approved, ErrorCode::ProposalNotApproved);
+    require!(
+        council_approvals(config, proposal) >= config.council_threshold as usize,
+        ErrorCode::ProposalNotApproved
+    );
+    require!(now >= proposal.executable_at, ErrorCode::TimelockActive);
+
+    proposal.status = ProposalStatus::Executed;
+    proposal.executed_at = now;
+
+    emit!(ProposalExecutedEvent {
+        proposal: proposal.key(),
+        id: proposal.id,
+        executor,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+fn list_service(
+    directory: &mut ServiceDirectory,
+    page: &mut ServiceDirectoryPage,
+    service: Pubkey,
+    owner: Pubkey,
+    directory_bump: u8,
+    page_bump: u8,
+) -> Result<u32> {
+    if directory.owner == Pubkey::default() {
+        directory.owner = owner;
+        directory.bump = directory_bump;
+    }
+    if page.owner == Pubkey::default() {
+        page.owner = owner;
+        page.page = directory.next_page();
+        page.bump = page_bump;
+    }
+
+    let index = directory.next_index;
+    page.services.push(service);
+    directory.next_index = index.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+    directory.service_count = directory.service_count.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+
+    Ok(index)
+}
+
+fn unlist_service(directory: &mut ServiceDirectory, page: &mut ServiceDirectoryPage, service: &Pubkey) -> Result<()> {
+    page.services.retain(|listed| listed != service);
+    directory.service_count = directory.service_count.checked_sub(1).ok_or(ErrorCode::MathOverflow)?;
+    Ok(())
+}
+
+fn rater_weight(config: &RegistryConfig, agent: &Agent, stake_mint: &StakeMint) -> Result<u64> {
+    let stake_value = calculate_stake_value(agent.staked_amount, stake_mint.reputation_weight_bps)?;
+    calculate_rating_weight(agent.reputation_score, stake_value, config.reputation_value_unit)
+}
+
+/// Services rated before ratings were weighted have ratings but no rating weight; count each of those ratings at
+/// `ServiceRating::LEGACY_WEIGHT` so later weighted updates and removals line up with them.
+fn seed_legacy_rating_weights(service: &mut Service) -> Result<()> {
+    if service.total_rating_weight > 0 || service.total_ratings == 0 {
+        return Ok(());
+    }
+
+    service.total_rating_weight = (service.total_ratings as u64)
+        .checked_mul(ServiceRating::LEGACY_WEIGHT)
+        .ok_or(ErrorCode::MathOverflow)?;
+    service.weighted_rating_sum = service.total_rating_sum
+        .checked_mul(ServiceRating::LEGACY_WEIGHT)
+        .ok_or(ErrorCode::MathOverflow)?;
+    refresh_service_rating(service)
+}
+
+fn add_weighted_rating(service: &mut Service, rating: u16, weight: u64) -> Result<()> {
+    let weighted = (rating as u64).checked_mul(weight).ok_or(ErrorCode::MathOverflow)?;
+    service.weighted_rating_sum = service.weighted_rating_sum.checked_add(weighted)
+        .ok_or(ErrorCode::MathOverflow)?;
+    service.total_rating_weight = service.total_rating_weight.checked_add(weight)
+        .ok_or(ErrorCode::MathOverflow)?;
+    refresh_service_rating(service)
+}
+
+fn remove_weighted_rating(service: &mut Service, rating: u16, weight: u64) -> Result<()> {
+    let weighted = (rating as u64).checked_mul(weight).ok_or(ErrorCode::MathOverflow)?;
+    service.weighted_rating_sum = service.weighted_rating_sum.checked_sub(weighted)
+        .ok_or(ErrorCode::MathOverflow)?;
+    service.total_rating_weight = service.total_rating_weight.checked_sub(weight)
+        .ok_or(ErrorCode::MathOverflow)?;
+    refresh_service_rating(service)
+}
+
+fn refresh_service_rating(service: &mut Service) -> Result<()> {
+    let avg_rating = service.weighted_rating_sum
+        .checked_div(service.total_rating_weight)
+        .unwrap_or(0);
+    require!(avg_rating <= 500, ErrorCode::InvalidRating);
+
+    service.average_rating = avg_rating as u16;
+    service.reputation_score = (service.average_rating as u64)
+        .checked_mul(2)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(())
+}
+
+fn calculate_stake_value(amount: u64, reputation_weight_bps: u32) -> Result<u64> {
+    let value = (amount as u128)
+        .checked_mul(reputation_weight_bps as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        / 10_000;
+    u64::try_from(value).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+fn calculate_ewma(current: u64, sample: u64, alpha_bps: u16) -> u64 {
+    let alpha = alpha_bps as u128;
+    ((sample as u128 * alpha + current as u128 * (10_000 - alpha)) / 10_000) as u64
+}
+
+fn calculate_initial_reputation(stake_amount: u64) -> u64 {
+    if stake_amount >= 10_000_000_000 {
+        7000
+    } else if stake_amount >= 5_000_000_000 {
+        6000
+    } else if stake_amount >= 1_000_000_000 {
+        5000
+    } else {
+        4000
+    }
+}
+
+fn calculate_credit_fee(amount: u64, fee_bps: u16) -> Result<u64> {
+    let fee = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(10000)
+        .ok_or(ErrorCode::MathOverflow)?;
+    Ok(fee as u64)
+}
+
+fn calculate_slash_amount(fraud_amount: u64, staked_amount: u64) -> Result<u64> {
+    let min_slash = fraud_amount.checked_mul(2)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let slash_amount = if min_slash > staked_amount {
+        staked_amount
+    } else {
+        min_slash
+    };
+    Ok(slash_amount)
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = RegistryConfig::SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ ErrorCode::UnauthorizedAccess
+    )]
+    pub program: Program<'info, crate::program::X402Registry>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::UnauthorizedAccess
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAccess
+    )]
+    pub config: Account<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptConfigAuthority<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterAgent<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + Agent::SPACE,
+        seeds = [b"agent", signer.key().as_ref()],
+        bump
+    )]
+    pub agent: Account<'info, Agent>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        seeds = [b"stake_mint", mint.key().as_ref()],
+        bump = stake_mint.bump,
+        constraint = stake_mint.enabled @ ErrorCode::StakeMintDisabled
+    )]
+    pub stake_mint: Account<'info, StakeMint>,
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        constraint = staker_token_account.owner == signer.key() @ ErrorCode::UnauthorizedAccess,
+        constraint = staker_token_account.mint == mint.key() @ ErrorCode::InvalidMint
+    )]
+    pub staker_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"escrow", mint.key().as_ref()],
+        bump = stake_mint.escrow_bump
+    )]
+    pub escrow_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(address = stake_mint.token_program)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterService<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = ServiceDirectory::SPACE,
+        seeds = [b"service_directory", owner.key().as_ref()],
+        bump
+    )]
+    pub directory: Account<'info, ServiceDirectory>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = ServiceDirectoryPage::SPACE,
+        seeds = [b"service_page", owner.key().as_ref(), &directory.next_page().to_le_bytes()],
+        bump
+    )]
+    pub page: Account<'info, ServiceDirectoryPage>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Service::SPACE,
+        seeds = [b"service", owner.key().as_ref(), &directory.next_index.to_le_bytes()],
+        bump
+    )]
+    pub service: Account<'info, Service>,
+    #[account(
+        mut,
+        constraint = agent.wallet == owner.key() @ ErrorCode::UnauthorizedAccess
+    )]
+    pub agent: Account<'info, Agent>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordTransaction<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(mut)]
+    pub agent: Account<'info, Agent>,
+    #[account(mut)]
+    pub service: Account<'info, Service>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ServiceInteraction::SPACE,
+        seeds = [b"interaction", service.key().as_ref(), agent.key().as_ref()],
+        bump
+    )]
+    pub interaction: Account<'info, ServiceInteraction>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, success: bool, response_time_ms: u32, nonce: u64)]
+pub struct RecordCosignedTransaction<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [b"agent", agent.wallet.as_ref()],
+        bump
+    )]
+    pub agent: Account<'info, Agent>,
+    #[account(mut)]
+    pub service: Account<'info, Service>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ServiceInteraction::SPACE,
+        seeds = [b"interaction", service.key().as_ref(), agent.key().as_ref()],
+        bump
+    )]
+    pub interaction: Account<'info, ServiceInteraction>,
+    #[account(
+        init,
+        payer = payer,
+        space = TransactionReceipt::SPACE,
+        seeds = [b"receipt", service.key().as_ref(), agent.wallet.as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub receipt: Account<'info, TransactionReceipt>,
+    /// The released escrow for this payment, if it was escrowed; it shares the receipt's payer, service and nonce.
+    #[account(
+        seeds = [b"payment_escrow", agent.wallet.as_ref(), service.key().as_ref(), &nonce.to_le_bytes()],
+        bump = payment_escrow.bump,
+        constraint = payment_escrow.status == PaymentEscrowStatus::Released @ ErrorCode::PaymentEscrowNotReleased
+    )]
+    pub payment_escrow: Option<Account<'info, PaymentEscrow>>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: the instructions sysvar, checked by address
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RateService<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(mut)]
+    pub service: Account<'info, Service>,
+    #[account(
+        constraint = agent.wallet == rater.key()
+    )]
+    pub agent: Account<'info, Agent>,
+    #[account(
+        seeds = [b"stake_mint", agent.stake_mint.as_ref()],
+        bump = stake_mint.bump
+    )]
+    pub stake_mint: Account<'info, StakeMint>,
+    #[account(
+        seeds = [b"interaction", service.key().as_ref(), agent.key().as_ref()],
+        bump = interaction.bump
+    )]
+    pub interaction: Account<'info, ServiceInteraction>,
+    #[account(
+        init,
+        payer = rater,
+        space = ServiceRating::SPACE,
+        seeds = [b"rating", service.key().as_ref(), agent.wallet.as_ref()],
+        bump
+    )]
+    pub service_rating: Account<'info, ServiceRating>,
+    #[account(mut)]
+    pub rater: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SlashAgent<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(mut)]
+    pub agent: Account<'info, Agent>,
+    #[account(
+        seeds = [b"stake_mint", agent.stake_mint.as_ref()],
+        bump = stake_mint.bump
+    )]
+    pub stake_mint: Account<'info, StakeMint>,
+    #[account(address = agent.stake_mint @ ErrorCode::InvalidMint)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        seeds = [b"escrow", mint.key().as_ref()],
+        bump = stake_mint.escrow_bump
+    )]
+    pub escrow_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = config.dao_treasury
+    )]
+    pub dao_treasury: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(mut, seeds = [b"proposal", &proposal.id.to_le_bytes()], bump = proposal.bump)]
+    pub proposal: Account<'info, Proposal>,
+    pub executor: Signer<'info>,
+    #[account(address = stake_mint.token_program)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRating<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(mut)]
+    pub service: Account<'info, Service>,
+    #[account(constraint = agent.wallet == rater.key())]
+    pub agent: Account<'info, Agent>,
+    #[account(
+        seeds = [b"stake_mint", agent.stake_mint.as_ref()],
+        bump = stake_mint.bump
+    )]
+    pub stake_mint: Account<'info, StakeMint>,
+    #[account(
+        mut,
+        seeds = [b"rating", service.key().as_ref(), agent.wallet.as_ref()],
+        bump
+    )]
+    pub service_rating: Account<'info, ServiceRating>,
+    pub rater: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PruneRating<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(mut)]
+    pub service: Account<'info, Service>,
+    #[account(
+        mut,
+        close = rater,
+        seeds = [b"rating", service.key().as_ref(), service_rating.agent.as_ref()],
+        bump
+    )]
+    pub service_rating: Account<'info, ServiceRating>,
+    /// CHECK: receives the rating account's rent; address checked against the rating
+    #[account(mut, address = service_rating.agent @ ErrorCode::UnauthorizedAccess)]
+    pub rater: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateRating<'info> {
+    #[account(mut)]
+    pub service: Account<'info, Service>,
+    /// CHECK: a rating written before ratings were weighted; the handler checks owner, discriminator and address and
+    /// grows it before deserializing
+    #[account(mut)]
+    pub service_rating: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateService<'info> {
+    #[account(
+        mut,
+        constraint = service.owner == owner.key() @ ErrorCode::UnauthorizedAccess
+    )]
+    pub service: Account<'info, Service>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeAgent<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        constraint = agent.wallet == signer.key() @ ErrorCode::UnauthorizedAccess
+    )]
+    pub agent: Account<'info, Agent>,
+    #[account(
+        seeds = [b"stake_mint", agent.stake_mint.as_ref()],
+        bump = stake_mint.bump
+    )]
+    pub stake_mint: Account<'info, StakeMint>,
+    #[account(address = agent.stake_mint @ ErrorCode::InvalidMint)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        seeds = [b"escrow", mint.key().as_ref()],
+        bump = stake_mint.escrow_bump
+    )]
+    pub escrow_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        mut,
+        constraint = recipient_token_account.owner == signer.key() @ ErrorCode::UnauthorizedAccess,
+        constraint = recipient_token_account.mint == mint.key() @ ErrorCode::InvalidMint
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    pub signer: Signer<'info>,
+    #[account(address = stake_mint.token_program)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RestakeAgent<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        constraint = agent.wallet == signer.key() @ ErrorCode::UnauthorizedAccess
+    )]
+    pub agent: Account<'info, Agent>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        seeds = [b"stake_mint", mint.key().as_ref()],
+        bump = stake_mint.bump,
+        constraint = stake_mint.enabled @ ErrorCode::StakeMintDisabled
+    )]
+    pub stake_mint: Account<'info, StakeMint>,
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        constraint = staker_token_account.owner == signer.key() @ ErrorCode::UnauthorizedAccess,
+        constraint = staker_token_account.mint == mint.key() @ ErrorCode::InvalidMint
+    )]
+    pub staker_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"escrow", mint.key().as_ref()],
+        bump = stake_mint.escrow_bump
+    )]
+    pub escrow_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(address = stake_mint.token_program)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct GovernedAgentAction<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(mut, seeds = [b"proposal", &proposal.id.to_le_bytes()], bump = proposal.bump)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub agent: Account<'info, Agent>,
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GovernedServiceAction<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(mut, seeds = [b"proposal", &proposal.id.to_le_bytes()], bump = proposal.bump)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub service: Account<'info, Service>,
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GovernedConfigAction<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(mut, seeds = [b"proposal", &proposal.id.to_le_bytes()], bump = proposal.bump)]
+    pub proposal: Account<'info, Proposal>,
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAgent<'info> {
+    #[account(
+        mut,
+        close = recipient,
+        constraint = agent.wallet == recipient.key() @ ErrorCode::UnauthorizedAccess
+    )]
+    pub agent: Account<'info, Agent>,
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseService<'info> {
+    #[account(
+        mut,
+        close = recipient,
+        constraint = service.owner == recipient.key() @ ErrorCode::UnauthorizedAccess,
+        constraint = service.directory_index.is_some() @ ErrorCode::ServiceNotListed
+    )]
+    pub service: Account<'info, Service>,
+    #[account(
+        mut,
+        constraint = agent.wallet == service.owner @ ErrorCode::UnauthorizedAccess
+    )]
+    pub agent: Account<'info, Agent>,
+    #[account(mut, seeds = [b"service_directory", service.owner.as_ref()], bump = directory.bump)]
+    pub directory: Account<'info, ServiceDirectory>,
+    #[account(
+        mut,
+        seeds = [b"service_page", service.owner.as_ref(), &service.directory_page().to_le_bytes()],
+        bump = page.bump
+    )]
+    pub page: Account<'info, ServiceDirectoryPage>,
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSuspendedService<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        close = recipient,
+        constraint = service.status == ServiceStatus::Suspended @ ErrorCode::InvalidServiceStatus,
+        constraint = service.directory_index.is_some() @ ErrorCode::ServiceNotListed
+    )]
+    pub service: Account<'info, Service>,
+    #[account(
+        mut,
+        constraint = agent.wallet == service.owner @ ErrorCode::UnauthorizedAccess
+    )]
+    pub agent: Account<'info, Agent>,
+    #[account(mut, seeds = [b"service_directory", service.owner.as_ref()], bump = directory.bump)]
+    pub directory: Account<'info, ServiceDirectory>,
+    #[account(
+        mut,
+        seeds = [b"service_page", service.owner.as_ref(), &service.directory_page().to_le_bytes()],
+        bump = page.bump
+    )]
+    pub page: Account<'info, ServiceDirectoryPage>,
+    #[account(mut, seeds = [b"proposal", &proposal.id.to_le_bytes()], bump = proposal.bump)]
+    pub proposal: Account<'info, Proposal>,
+    /// CHECK: receives the service's rent; address checked against the config
+    #[account(mut, address = config.dao_treasury @ ErrorCode::UnauthorizedAccess)]
+    pub recipient: UncheckedAccount<'info>,
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        init,
+        payer = proposer,
+        space = Proposal::SPACE,
+        seeds = [b"proposal", &config.proposal_count.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(mut, seeds = [b"proposal", &proposal.id.to_le_bytes()], bump = proposal.bump)]
+    pub proposal: Account<'info, Proposal>,
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", &proposal.id.to_le_bytes()],
+        bump = proposal.bump,
+        constraint = proposal.proposer == proposer.key() @ ErrorCode::UnauthorizedAccess
+    )]
+    pub proposal: Account<'info, Proposal>,
+    pub proposer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct MigrateService<'info> {
+    /// CHECK: a service written before directories existed; the handler checks owner and discriminator and grows it
+    /// before deserializing
+    #[account(mut)]
+    pub service: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ServiceDirectory::SPACE,
+        seeds = [b"service_directory", owner.as_ref()],
+        bump
+    )]
+    pub directory: Account<'info, ServiceDirectory>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = ServiceDirectoryPage::SPACE,
+        seeds = [b"service_page", owner.as_ref(), &directory.next_page().to_le_bytes()],
+        bump
+    )]
+    pub page: Account<'info, ServiceDirectoryPage>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCreditVault<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"credit_vault"],
+        bump,
+        token::mint = stake_token_mint,
+        token::authority = credit_vault,
+        token::token_program = token_program
+    )]
+    pub credit_vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        constraint = stake_token_mint.key() == config.stake_token_mint @ ErrorCode::InvalidMint
+    )]
+    pub stake_token_mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct OpenCreditLine<'info> {
+    #[account(
+        init,
+        payer = signer,
+        space = CreditLine::SPACE,
+        seeds = [b"credit", signer.key().as_ref()],
+        bump
+    )]
+    pub credit_line: Account<'info, CreditLine>,
+    #[account(
+        seeds = [b"agent", signer.key().as_ref()],
+        bump
+    )]
+    pub agent: Account<'info, Agent>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DrawCredit<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [b"agent", signer.key().as_ref()],
+        bump
+    )]
+    pub agent: Account<'info, Agent>,
+    #[account(
+        mut,
+        seeds = [b"credit", signer.key().as_ref()],
+        bump = credit_line.bump
+    )]
+    pub credit_line: Account<'info, CreditLine>,
+    pub service: Account<'info, Service>,
+    #[account(address = config.stake_token_mint @ ErrorCode::InvalidMint)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        seeds = [b"credit_vault"],
+        bump
+    )]
+    pub credit_vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        mut,
+        constraint = service_token_account.owner == service.owner @ ErrorCode::UnauthorizedAccess,
+        constraint = service_token_account.mint == config.stake_token_mint @ ErrorCode::InvalidMint
+    )]
+    pub service_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    pub signer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RepayCredit<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [b"agent", signer.key().as_ref()],
+        bump
+    )]
+    pub agent: Account<'info, Agent>,
+    #[account(
+        mut,
+        seeds = [b"credit", signer.key().as_ref()],
+        bump = credit_line.bump
+    )]
+    pub credit_line: Account<'info, CreditLine>,
+    #[account(address = config.stake_token_mint @ ErrorCode::InvalidMint)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        seeds = [b"credit_vault"],
+        bump
+    )]
+    pub credit_vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == signer.key() @ ErrorCode::UnauthorizedAccess,
+        constraint = payer_token_account.mint == config.stake_token_mint @ ErrorCode::InvalidMint
+    )]
+    pub payer_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    pub signer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SettleCreditDefault<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [b"agent", credit_line.agent.as_ref()],
+        bump,
+        constraint = agent.stake_mint == config.stake_token_mint @ ErrorCode::InvalidMint
+    )]
+    pub agent: Account<'info, Agent>,
+    #[account(
+        mut,
+        seeds = [b"credit", credit_line.agent.as_ref()],
+        bump = credit_line.bump
+    )]
+    pub credit_line: Account<'info, CreditLine>,
+    #[account(
+        seeds = [b"stake_mint", mint.key().as_ref()],
+        bump = stake_mint.bump
+    )]
+    pub stake_mint: Account<'info, StakeMint>,
+    #[account(address = config.stake_token_mint @ ErrorCode::InvalidMint)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        seeds = [b"escrow", mint.key().as_ref()],
+        bump = stake_mint.escrow_bump
+    )]
+    pub escrow_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"credit_vault"],
+        bump
+    )]
+    pub credit_vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(address = stake_mint.token_program)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct CreatePaymentEscrow<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = PaymentEscrow::SPACE,
+        seeds = [b"payment_escrow", payer.key().as_ref(), service.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub payment_escrow: Account<'info, PaymentEscrow>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"payment_vault", payment_escrow.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = payment_escrow,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    pub service: Account<'info, Service>,
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == payer.key() @ ErrorCode::UnauthorizedAccess,
+        constraint = payer_token_account.mint == mint.key() @ ErrorCode::InvalidMint
+    )]
+    pub payer_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ReleasePaymentEscrow<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        has_one = payer @ ErrorCode::InvalidPDA
+    )]
+    pub payment_escrow: Account<'info, PaymentEscrow>,
+    #[account(
+        mut,
+        seeds = [b"payment_vault", payment_escrow.key().as_ref()],
+        bump = payment_escrow.vault_bump
+    )]
+    pub vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(address = payment_escrow.mint @ ErrorCode::InvalidMint)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        constraint = payee_token_account.owner == payment_escrow.payee @ ErrorCode::UnauthorizedAccess,
+        constraint = payee_token_account.mint == payment_escrow.mint @ ErrorCode::InvalidMint
+    )]
+    pub payee_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    /// CHECK: receives the vault's rent; address checked against the escrow
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RefundPaymentEscrow<'info> {
+    #[account(
+        mut,
+        has_one = payer @ ErrorCode::UnauthorizedAccess
+    )]
+    pub payment_escrow: Account<'info, PaymentEscrow>,
+    #[account(
+        mut,
+        seeds = [b"payment_vault", payment_escrow.key().as_ref()],
+        bump = payment_escrow.vault_bump
+    )]
+    pub vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(address = payment_escrow.mint @ ErrorCode::InvalidMint)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == payer.key() @ ErrorCode::UnauthorizedAccess,
+        constraint = payer_token_account.mint == payment_escrow.mint @ ErrorCode::InvalidMint
+    )]
+    pub payer_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(channel_id: u64)]
+pub struct OpenChannel<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        init,
+        payer = payer,
+        space = PaymentChannel::SPACE,
+        seeds = [b"channel", payer.key().as_ref(), service.key().as_ref(), &channel_id.to_le_bytes()],
+        bump
+    )]
+    pub channel: Account<'info, PaymentChannel>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"channel_vault", channel.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = channel,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    pub service: Account<'info, Service>,
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == payer.key() @ ErrorCode::UnauthorizedAccess,
+        constraint = payer_token_account.mint == mint.key() @ ErrorCode::InvalidMint
+    )]
+    pub payer_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct TopUpChannel<'info> {
+    #[account(
+        mut,
+        has_one = payer @ ErrorCode::UnauthorizedAccess
+    )]
+    pub channel: Account<'info, PaymentChannel>,
+    #[account(
+        mut,
+        seeds = [b"channel_vault", channel.key().as_ref()],
+        bump = channel.vault_bump
+    )]
+    pub vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(address = channel.mint @ ErrorCode::InvalidMint)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == payer.key() @ ErrorCode::UnauthorizedAccess,
+        constraint = payer_token_account.mint == channel.mint @ ErrorCode::InvalidMint
+    )]
+    pub payer_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemVoucher<'info> {
+    #[account(
+        mut,
+        has_one = payee @ ErrorCode::UnauthorizedAccess
+    )]
+    pub channel: Account<'info, PaymentChannel>,
+    #[account(
+        mut,
+        seeds = [b"channel_vault", channel.key().as_ref()],
+        bump = channel.vault_bump
+    )]
+    pub vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(address = channel.mint @ ErrorCode::InvalidMint)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        constraint = payee_token_account.owner == payee.key() @ ErrorCode::UnauthorizedAccess,
+        constraint = payee_token_account.mint == channel.mint @ ErrorCode::InvalidMint
+    )]
+    pub payee_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    pub payee: Signer<'info>,
+    /// CHECK: the instructions sysvar, checked by address
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RequestChannelClose<'info> {
+    #[account(
+        mut,
+        has_one = payer @ ErrorCode::UnauthorizedAccess
+    )]
+    pub channel: Account<'info, PaymentChannel>,
+    pub payer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeChannelClose<'info> {
+    #[account(
+        mut,
+        has_one = payer @ ErrorCode::InvalidPDA
+    )]
+    pub channel: Account<'info, PaymentChannel>,
+    #[account(
+        mut,
+        seeds = [b"channel_vault", channel.key().as_ref()],
+        bump = channel.vault_bump
+    )]
+    pub vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(address = channel.mint @ ErrorCode::InvalidMint)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == channel.payer @ ErrorCode::UnauthorizedAccess,
+        constraint = payer_token_account.mint == channel.mint @ ErrorCode::InvalidMint
+    )]
+    pub payer_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    /// CHECK: receives the vault's rent; address checked against the channel
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct AddStakeMint<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAccess
+    )]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        init,
+        payer = payer,
+        space = StakeMint::SPACE,
+        seeds = [b"stake_mint", mint.key().as_ref()],
+        bump
+    )]
+    pub stake_mint: Account<'info, StakeMint>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"escrow", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow_account,
+        token::token_program = token_program
+    )]
+    pub escrow_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateLegacyStake<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    /// CHECK: a pre-multi-mint agent; the handler checks owner and discriminator and grows it before deserializing
+    #[account(mut)]
+    pub agent: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"escrow"],
+        bump,
+        constraint = legacy_escrow.mint == mint.key() @ ErrorCode::InvalidMint
+    )]
+    pub legacy_escrow: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        seeds = [b"stake_mint", mint.key().as_ref()],
+        bump = stake_mint.bump
+    )]
+    pub stake_mint: Account<'info, StakeMint>,
+    #[account(address = config.stake_token_mint @ ErrorCode::InvalidMint)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        seeds = [b"escrow", mint.key().as_ref()],
+        bump = stake_mint.escrow_bump
+    )]
+    pub escrow_account: InterfaceAccount<'info, token_interface::TokenAccount>,
     #[account(mut)]
-    pub service: Account<'info, Service>,
-    pub authority: Signer<'info>,
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(address = stake_mint.token_program)]
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct CloseAgent<'info> {
+pub struct UpdateStakeMint<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAccess
+    )]
+    pub config: Account<'info, RegistryConfig>,
     #[account(
         mut,
-        close = recipient,
-        constraint = agent.wallet == recipient.key() @ ErrorCode::UnauthorizedAccess
+        seeds = [b"stake_mint", stake_mint.mint.as_ref()],
+        bump = stake_mint.bump
     )]
-    pub agent: Account<'info, Agent>,
+    pub stake_mint: Account<'info, StakeMint>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(operator: Pubkey)]
+pub struct AddProbeOperator<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAccess
+    )]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = ProbeOperator::SPACE,
+        seeds = [b"probe_operator", operator.as_ref()],
+        bump
+    )]
+    pub probe_operator: Account<'info, ProbeOperator>,
     #[account(mut)]
-    pub recipient: Signer<'info>,
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CloseService<'info> {
+pub struct RemoveProbeOperator<'info> {
     #[account(
-        mut,
-        close = recipient,
-        constraint = service.owner == recipient.key() @ ErrorCode::UnauthorizedAccess
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::UnauthorizedAccess
     )]
-    pub service: Account<'info, Service>,
+    pub config: Account<'info, RegistryConfig>,
     #[account(
         mut,
-        constraint = agent.wallet == service.owner @ ErrorCode::UnauthorizedAccess
+        close = authority,
+        seeds = [b"probe_operator", probe_operator.operator.as_ref()],
+        bump = probe_operator.bump
     )]
-    pub agent: Account<'info, Agent>,
+    pub probe_operator: Account<'info, ProbeOperator>,
     #[account(mut)]
-    pub recipient: Signer<'info>,
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct CloseSuspendedService<'info> {
+pub struct OpenServiceHealth<'info> {
     #[account(
-        mut,
-        close = recipient,
-        constraint = service.status == ServiceStatus::Suspended @ ErrorCode::InvalidServiceStatus
+        init,
+        payer = payer,
+        space = ServiceHealth::SPACE,
+        seeds = [b"service_health", service.key().as_ref()],
+        bump
     )]
+    pub service_health: Account<'info, ServiceHealth>,
     pub service: Account<'info, Service>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReportHealth<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
     #[account(
         mut,
-        constraint = agent.wallet == service.owner @ ErrorCode::UnauthorizedAccess
+        seeds = [b"probe_operator", operator.key().as_ref()],
+        bump = probe_operator.bump
     )]
-    pub agent: Account<'info, Agent>,
+    pub probe_operator: Account<'info, ProbeOperator>,
+    #[account(
+        mut,
+        seeds = [b"service_health", service.key().as_ref()],
+        bump = service_health.bump
+    )]
+    pub service_health: Account<'info, ServiceHealth>,
     #[account(mut)]
-    pub recipient: Signer<'info>,
-    pub authority: Signer<'info>,
+    pub service: Account<'info, Service>,
+    pub operator: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct InitializeEscrow<'info> {
+#[instruction(transaction_signature: [u8; 64])]
+pub struct OpenDispute<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
     #[account(
         init,
         payer = payer,
-        seeds = [b"escrow"],
-        bump,
-        token::mint = stake_token_mint,
-        token::authority = escrow_account
+        space = Dispute::SPACE,
+        seeds = [
+            b"dispute",
+            service.key().as_ref(),
+            &transaction_signature[..32],
+            &transaction_signature[32..]
+        ],
+        bump
     )]
-    pub escrow_account: Account<'info, TokenAccount>,
+    pub dispute: Account<'info, Dispute>,
+    pub service: Account<'info, Service>,
     #[account(
-        constraint = stake_token_mint.key() == crate::constants::STAKE_TOKEN_MINT @ ErrorCode::InvalidMint
+        seeds = [b"agent", payer.key().as_ref()],
+        bump
     )]
-    pub stake_token_mint: Account<'info, token::Mint>,
+    pub payer_agent: Account<'info, Agent>,
     #[account(mut)]
     pub payer: Signer<'info>,
-    pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RespondToDispute<'info> {
+    #[account(
+        mut,
+        has_one = provider @ ErrorCode::UnauthorizedAccess
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(mut)]
+    pub provider: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        has_one = payer @ ErrorCode::InvalidPDA,
+        has_one = provider @ ErrorCode::InvalidPDA
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(
+        mut,
+        seeds = [b"agent", dispute.payer.as_ref()],
+        bump
+    )]
+    pub payer_agent: Account<'info, Agent>,
+    #[account(
+        mut,
+        seeds = [b"agent", dispute.provider.as_ref()],
+        bump
+    )]
+    pub provider_agent: Account<'info, Agent>,
+    /// CHECK: receives the bonds if the payer wins; address checked against the dispute
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    /// CHECK: receives the bonds if the provider wins; address checked against the dispute
+    #[account(mut)]
+    pub provider: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"stake_mint", provider_agent.stake_mint.as_ref()],
+        bump = stake_mint.bump
+    )]
+    pub stake_mint: Account<'info, StakeMint>,
+    #[account(address = provider_agent.stake_mint @ ErrorCode::InvalidMint)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    #[account(
+        mut,
+        seeds = [b"escrow", mint.key().as_ref()],
+        bump = stake_mint.escrow_bump
+    )]
+    pub escrow_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == dispute.payer @ ErrorCode::UnauthorizedAccess,
+        constraint = payer_token_account.mint == mint.key() @ ErrorCode::InvalidMint
+    )]
+    pub payer_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = config.dao_treasury
+    )]
+    pub dao_treasury: InterfaceAccount<'info, token_interface::TokenAccount>,
+    pub arbiter: Signer<'info>,
+    #[account(address = stake_mint.token_program)]
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveExpiredDispute<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        has_one = payer @ ErrorCode::InvalidPDA
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(
+        mut,
+        seeds = [b"agent", dispute.payer.as_ref()],
+        bump
+    )]
+    pub payer_agent: Account<'info, Agent>,
+    #[account(
+        mut,
+        seeds = [b"agent", dispute.provider.as_ref()],
+        bump
+    )]
+    pub provider_agent: Account<'info, Agent>,
+    /// CHECK: receives the payer's bond back; address checked against the dispute
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
 pub struct VerifyService<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
     #[account(mut)]
     pub service: Account<'info, Service>,
     pub verifier: Signer<'info>,
@@ -1128,6 +4077,8 @@ pub struct VerifyService<'info> {
 
 #[derive(Accounts)]
 pub struct SetVisaTapVerified<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
     #[account(mut)]
     pub service: Account<'info, Service>,
     pub authority: Signer<'info>,
@@ -1157,10 +4108,11 @@ pub struct Agent {
     pub status: AgentStatus,
     pub historical_min_reputation: u64,
     pub service_count: u32,
+    pub stake_mint: Pubkey,
 }
 
 impl Agent {
-    pub const SPACE: usize = 32 + (4 + 256) + (4 + 512) + 8 + 8 + 8 + 8 + 4 + 4 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + (4 + 256) + (4 + 256) + 1 + 8 + 4;
+    pub const SPACE: usize = 32 + (4 + 256) + (4 + 512) + 8 + 8 + 8 + 8 + 4 + 4 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + (4 + 256) + (4 + 256) + 1 + 8 + 4 + 32;
 }
 
 #[account]
@@ -1187,10 +4139,17 @@ pub struct Service {
     pub created_at: i64,
     pub last_updated: i64,
     pub status: ServiceStatus,
+    pub total_rating_weight: u64,
+    pub weighted_rating_sum: u64,
+    pub directory_index: Option<u32>,
 }
 
 impl Service {
-    pub const SPACE: usize = (4 + 512) + (4 + 128) + (4 + 1024) + (4 + 64) + 32 + 8 + (4 + 32 * 5) + 8 + 8 + 8 + 8 + 4 + 1 + 8 + 4 + 8 + 2 + 1 + 1 + 8 + 8 + 1;
+    pub const SPACE: usize = (4 + 512) + (4 + 128) + (4 + 1024) + (4 + 64) + 32 + 8 + (4 + 32 * 5) + 8 + 8 + 8 + 8 + 4 + 1 + 8 + 4 + 8 + 2 + 1 + 1 + 8 + 8 + 1 + 8 + 8 + (1 + 4);
+
+    pub fn directory_page(&self) -> u32 {
+        self.directory_index.unwrap_or_default() / SERVICE_PAGE_SIZE
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
@@ -1225,6 +4184,56 @@ pub struct ServiceRegisteredEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct ServiceMigratedEvent {
+    pub service: Pubkey,
+    pub owner: Pubkey,
+    pub index: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CouncilUpdatedEvent {
+    pub council: Vec<Pubkey>,
+    pub threshold: u8,
+    pub timelock_seconds: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalCreatedEvent {
+    pub proposal: Pubkey,
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: GovernanceAction,
+    pub emergency: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalApprovedEvent {
+    pub proposal: Pubkey,
+    pub approver: Pubkey,
+    pub approvals: u8,
+    pub executable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalExecutedEvent {
+    pub proposal: Pubkey,
+    pub id: u64,
+    pub executor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalCancelledEvent {
+    pub proposal: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct TransactionRecordedEvent {
     pub agent: Pubkey,
@@ -1242,6 +4251,16 @@ pub struct ServiceRatedEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct RatingPrunedEvent {
+    pub service: Pubkey,
+    pub agent: Pubkey,
+    pub rating: u16,
+    pub weight: u64,
+    pub new_average: u16,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct AgentSlashedEvent {
     pub wallet: Pubkey,
@@ -1269,6 +4288,196 @@ pub struct ServiceStatusChangedEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct ConfigUpdatedEvent {
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferStartedEvent {
+    pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferredEvent {
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeOpenedEvent {
+    pub dispute: Pubkey,
+    pub payer: Pubkey,
+    pub provider: Pubkey,
+    pub service: Pubkey,
+    pub bond_lamports: u64,
+    pub response_deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeRespondedEvent {
+    pub dispute: Pubkey,
+    pub provider: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeResolvedEvent {
+    pub dispute: Pubkey,
+    pub payer: Pubkey,
+    pub provider: Pubkey,
+    pub payer_wins: bool,
+    pub refund_amount: u64,
+    pub slash_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CreditDrawnEvent {
+    pub wallet: Pubkey,
+    pub service: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub credit_used: u64,
+    pub due_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CreditRepaidEvent {
+    pub wallet: Pubkey,
+    pub amount: u64,
+    pub credit_used: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CreditDefaultedEvent {
+    pub wallet: Pubkey,
+    pub seized: u64,
+    pub written_off: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PaymentEscrowFundedEvent {
+    pub escrow: Pubkey,
+    pub payer: Pubkey,
+    pub payee: Pubkey,
+    pub service: Pubkey,
+    pub mint: Pubkey,
+    pub nonce: u64,
+    pub amount: u64,
+    pub deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PaymentEscrowReleasedEvent {
+    pub escrow: Pubkey,
+    pub payee: Pubkey,
+    pub amount: u64,
+    pub released_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PaymentEscrowRefundedEvent {
+    pub escrow: Pubkey,
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChannelOpenedEvent {
+    pub channel: Pubkey,
+    pub payer: Pubkey,
+    pub payee: Pubkey,
+    pub service: Pubkey,
+    pub mint: Pubkey,
+    pub deposit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChannelToppedUpEvent {
+    pub channel: Pubkey,
+    pub amount: u64,
+    pub deposit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VoucherRedeemedEvent {
+    pub channel: Pubkey,
+    pub payee: Pubkey,
+    pub amount: u64,
+    pub nonce: u64,
+    pub payout: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChannelCloseRequestedEvent {
+    pub channel: Pubkey,
+    pub payer: Pubkey,
+    pub closes_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChannelClosedEvent {
+    pub channel: Pubkey,
+    pub redeemed: u64,
+    pub refunded: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProbeOperatorUpdatedEvent {
+    pub operator: Pubkey,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ServiceHealthReportedEvent {
+    pub service: Pubkey,
+    pub operator: Pubkey,
+    pub window_start: i64,
+    pub window_end: i64,
+    pub probes: u32,
+    pub successes: u32,
+    pub window_uptime_bps: u16,
+    pub uptime_bps: u16,
+    pub latency_ms: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeMintUpdatedEvent {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub reputation_weight_bps: u32,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LegacyStakeMigratedEvent {
+    pub agent: Pubkey,
+    pub wallet: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
 #[error_code]
 pub enum ErrorCode {
     #[msg("Invalid stake amount")]
@@ -1333,4 +4542,80 @@ pub enum ErrorCode {
     RatingUpdateCooldown,
     #[msg("Fraud amount too high")]
     FraudAmountTooHigh,
+    #[msg("Invalid config")]
+    InvalidConfig,
+    #[msg("Cannot dispute own service")]
+    SelfDisputeNotAllowed,
+    #[msg("Dispute is not open")]
+    DisputeNotOpen,
+    #[msg("Dispute response window closed")]
+    DisputeResponseWindowClosed,
+    #[msg("Dispute response window still open")]
+    DisputeResponseWindowOpen,
+    #[msg("Invalid dispute ruling")]
+    InvalidRuling,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Cannot pay own service")]
+    SelfPaymentNotAllowed,
+    #[msg("Credit limit exceeded")]
+    CreditLimitExceeded,
+    #[msg("No credit outstanding")]
+    NoCreditOutstanding,
+    #[msg("Credit repayment deadline not reached")]
+    CreditNotDue,
+    #[msg("Credit must be repaid first")]
+    CreditOutstanding,
+    #[msg("Invalid deadline")]
+    InvalidDeadline,
+    #[msg("Payment escrow is not funded")]
+    PaymentEscrowNotFunded,
+    #[msg("Payment escrow deadline not reached")]
+    PaymentEscrowNotExpired,
+    #[msg("Channel is not open")]
+    ChannelNotOpen,
+    #[msg("Voucher amount or nonce is not newer than the last redemption")]
+    StaleVoucher,
+    #[msg("Voucher exceeds channel deposit")]
+    VoucherExceedsDeposit,
+    #[msg("Channel challenge period has not elapsed")]
+    ChallengePeriodActive,
+    #[msg("Missing Ed25519 signature verification instruction")]
+    MissingSignatureVerification,
+    #[msg("Ed25519 signature verification does not match")]
+    InvalidSignatureVerification,
+    #[msg("Stake mint is not enabled")]
+    StakeMintDisabled,
+    #[msg("Invalid health report")]
+    InvalidHealthReport,
+    #[msg("Health report rate limit exceeded")]
+    HealthReportRateLimited,
+    #[msg("Rating is still within the rating window")]
+    RatingNotExpired,
+    #[msg("Service is already listed in the owner's directory")]
+    ServiceAlreadyListed,
+    #[msg("Service must be migrated into the owner's directory first")]
+    ServiceNotListed,
+    #[msg("Signer is not a council member")]
+    NotCouncilMember,
+    #[msg("Council member has already approved this proposal")]
+    AlreadyApproved,
+    #[msg("Invalid proposal status")]
+    InvalidProposalStatus,
+    #[msg("Proposal has not reached the council threshold")]
+    ProposalNotApproved,
+    #[msg("Proposal timelock has not elapsed")]
+    TimelockActive,
+    #[msg("Proposal action does not match this instruction")]
+    ProposalActionMismatch,
+    #[msg("Only suspensions can be proposed as emergencies")]
+    EmergencyActionNotAllowed,
+    #[msg("Account is not a program account of the expected type")]
+    InvalidAccountLayout,
+    #[msg("Agent stake has already been migrated")]
+    StakeAlreadyMigrated,
+    #[msg("Rating has already been migrated")]
+    RatingAlreadyMigrated,
+    #[msg("Payment escrow has not been released")]
+    PaymentEscrowNotReleased,
 }
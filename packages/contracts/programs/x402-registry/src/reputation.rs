@@ -0,0 +1,297 @@
+use anchor_lang::prelude::*;
+
+use crate::state::RegistryConfig;
+use crate::ErrorCode;
+
+pub const MAX_REPUTATION: u64 = 10_000;
+pub const NEUTRAL_REPUTATION: u64 = 5_000;
+
+const FRAC_BITS: u32 = 32;
+const ONE: u128 = 1 << FRAC_BITS;
+
+// 2^(-2^-i) in Q32 for i = 1..=16.
+const EXP2_NEG_TABLE: [u128; 16] = [
+    3037000500,
+    3611622603,
+    3938502376,
+    4112874773,
+    4202935003,
+    4248701965,
+    4271771996,
+    4283353945,
+    4289156690,
+    4292061010,
+    4293513907,
+    4294240540,
+    4294603903,
+    4294785595,
+    4294876445,
+    4294921870,
+];
+
+#[derive(Clone, Copy, Debug)]
+pub struct ReputationParams {
+    pub half_life_seconds: i64,
+    pub value_unit: u64,
+    pub alpha_bps: u16,
+    pub max_alpha_bps: u16,
+}
+
+impl ReputationParams {
+    pub fn from_config(config: &RegistryConfig) -> Self {
+        Self {
+            half_life_seconds: config.reputation_half_life_seconds,
+            value_unit: config.reputation_value_unit,
+            alpha_bps: config.reputation_alpha_bps,
+            max_alpha_bps: config.reputation_max_alpha_bps,
+        }
+    }
+}
+
+/// Applies a success or failure worth `amount` to `current` after decaying it over `elapsed_seconds` of inactivity.
+pub fn calculate_new_reputation(
+    current: u64,
+    success: bool,
+    amount: u64,
+    elapsed_seconds: i64,
+    params: &ReputationParams,
+) -> Result<u64> {
+    let decayed = decay_reputation(current.min(MAX_REPUTATION), elapsed_seconds, params.half_life_seconds)?;
+    let alpha = outcome_alpha_bps(amount, params)?;
+    apply_outcome(decayed, success, alpha)
+}
+
+/// Disputes move reputation at the maximum step regardless of the disputed amount.
+pub fn calculate_dispute_reputation(current: u64, won: bool, params: &ReputationParams) -> Result<u64> {
+    apply_outcome(current.min(MAX_REPUTATION), won, params.max_alpha_bps)
+}
+
+pub fn apply_penalty(current: u64, penalty: u64) -> u64 {
+    current.saturating_sub(penalty)
+}
+
+/// A rater's weight: reputation scaled by `1 + log2(1 + stake_value / value_unit)`. Every rater counts for at least 1.
+pub fn calculate_rating_weight(reputation: u64, stake_value: u64, value_unit: u64) -> Result<u64> {
+    let units = (stake_value / value_unit.max(1)) as u128;
+    let scale = ONE
+        .checked_add(log2(units + 1))
+        .ok_or(ErrorCode::MathOverflow)?;
+    let weight = (reputation.clamp(1, MAX_REPUTATION) as u128)
+        .checked_mul(scale)
+        .ok_or(ErrorCode::MathOverflow)?
+        >> FRAC_BITS;
+
+    Ok(weight as u64)
+}
+
+/// Halves the part of `score` above neutral every `half_life_seconds`. Scores at or below neutral do not recover.
+pub fn decay_reputation(score: u64, elapsed_seconds: i64, half_life_seconds: i64) -> Result<u64> {
+    if score <= NEUTRAL_REPUTATION || elapsed_seconds <= 0 || half_life_seconds <= 0 {
+        return Ok(score);
+    }
+
+    let excess = (score - NEUTRAL_REPUTATION) as u128;
+    let halvings = elapsed_seconds / half_life_seconds;
+    if halvings >= 64 {
+        return Ok(NEUTRAL_REPUTATION);
+    }
+
+    let remainder = (elapsed_seconds % half_life_seconds) as u128;
+    let fraction = (remainder << FRAC_BITS)
+        .checked_div(half_life_seconds as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    let decayed = (excess >> halvings)
+        .checked_mul(exp2_neg(fraction))
+        .ok_or(ErrorCode::MathOverflow)?
+        >> FRAC_BITS;
+
+    Ok(NEUTRAL_REPUTATION + decayed as u64)
+}
+
+/// Step size for an outcome of `amount`: `alpha_bps` scaled by `1 + log2(1 + amount / value_unit)`, capped at
+/// `max_alpha_bps`.
+pub fn outcome_alpha_bps(amount: u64, params: &ReputationParams) -> Result<u16> {
+    let units = (amount / params.value_unit.max(1)) as u128;
+    let weight = ONE
+        .checked_add(log2(units + 1))
+        .ok_or(ErrorCode::MathOverflow)?;
+    let alpha = (params.alpha_bps as u128)
+        .checked_mul(weight)
+        .ok_or(ErrorCode::MathOverflow)?
+        >> FRAC_BITS;
+
+    Ok(alpha.min(params.max_alpha_bps as u128) as u16)
+}
+
+fn apply_outcome(current: u64, success: bool, alpha_bps: u16) -> Result<u64> {
+    let alpha = alpha_bps.min(10_000) as u128;
+    let target = if success { MAX_REPUTATION as u128 } else { 0 };
+    let next = (current as u128)
+        .checked_mul(10_000 - alpha)
+        .and_then(|v| v.checked_add(target * alpha))
+        .ok_or(ErrorCode::MathOverflow)?
+        / 10_000;
+
+    Ok((next as u64).min(MAX_REPUTATION))
+}
+
+/// log2(x) in Q32 for x >= 1.
+fn log2(x: u128) -> u128 {
+    if x <= 1 {
+        return 0;
+    }
+
+    let integer = 127 - x.leading_zeros();
+    let mut y = if integer >= FRAC_BITS {
+        x >> (integer - FRAC_BITS)
+    } else {
+        x << (FRAC_BITS - integer)
+    };
+
+    let mut fraction = 0u128;
+    for bit in (0..FRAC_BITS).rev() {
+        y = (y * y) >> FRAC_BITS;
+        if y >= 2 * ONE {
+            y >>= 1;
+            fraction |= 1 << bit;
+        }
+    }
+
+    ((integer as u128) << FRAC_BITS) | fraction
+}
+
+/// 2^-f in Q32 for a Q32 fraction f in [0, 1), accurate to the top 16 bits of f.
+fn exp2_neg(fraction: u128) -> u128 {
+    let mut result = ONE;
+    for (i, factor) in EXP2_NEG_TABLE.iter().enumerate() {
+        if fraction & (1 << (FRAC_BITS - 1 - i as u32)) != 0 {
+            result = (result * factor) >> FRAC_BITS;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAMS: ReputationParams = ReputationParams {
+        half_life_seconds: 30 * 24 * 60 * 60,
+        value_unit: 1_000_000,
+        alpha_bps: 500,
+        max_alpha_bps: 2000,
+    };
+
+    fn reference_decay(score: u64, elapsed: i64, half_life: i64) -> f64 {
+        if score <= NEUTRAL_REPUTATION || elapsed <= 0 || half_life <= 0 {
+            return score as f64;
+        }
+        let excess = (score - NEUTRAL_REPUTATION) as f64;
+        NEUTRAL_REPUTATION as f64 + excess * 0.5f64.powf(elapsed as f64 / half_life as f64)
+    }
+
+    fn reference_alpha(amount: u64, params: &ReputationParams) -> f64 {
+        let units = (amount / params.value_unit) as f64;
+        (params.alpha_bps as f64 * (1.0 + (1.0 + units).log2())).min(params.max_alpha_bps as f64)
+    }
+
+    fn reference_reputation(current: u64, success: bool, amount: u64, elapsed: i64, params: &ReputationParams) -> f64 {
+        let decayed = reference_decay(current, elapsed, params.half_life_seconds);
+        let alpha = reference_alpha(amount, params) / 10_000.0;
+        let target = if success { MAX_REPUTATION as f64 } else { 0.0 };
+        decayed * (1.0 - alpha) + target * alpha
+    }
+
+    #[test]
+    fn log2_matches_reference() {
+        for x in [1u128, 2, 3, 5, 10, 1000, 1 << 40, u64::MAX as u128] {
+            let expected = (x as f64).log2();
+            let actual = log2(x) as f64 / ONE as f64;
+            assert!((actual - expected).abs() < 1e-6, "log2({}) = {}, expected {}", x, actual, expected);
+        }
+    }
+
+    #[test]
+    fn exp2_neg_matches_reference() {
+        for f in [0.0, 0.125, 0.25, 0.5, 0.6, 0.999] {
+            let expected = 2f64.powf(-f);
+            let actual = exp2_neg((f * ONE as f64) as u128) as f64 / ONE as f64;
+            assert!((actual - expected).abs() < 1e-4, "2^-{} = {}, expected {}", f, actual, expected);
+        }
+    }
+
+    #[test]
+    fn decay_matches_reference() {
+        let half_life = PARAMS.half_life_seconds;
+        for score in [4000u64, 5000, 6000, 8000, 10_000] {
+            for elapsed in [0, 3600, half_life / 3, half_life, half_life * 5 / 2, half_life * 70] {
+                let expected = reference_decay(score, elapsed, half_life);
+                let actual = decay_reputation(score, elapsed, half_life).unwrap() as f64;
+                assert!((actual - expected).abs() <= 1.0, "decay({}, {}) = {}, expected {}", score, elapsed, actual, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn decay_never_raises_low_scores() {
+        assert_eq!(decay_reputation(1000, 10 * PARAMS.half_life_seconds, PARAMS.half_life_seconds).unwrap(), 1000);
+    }
+
+    #[test]
+    fn new_reputation_matches_reference() {
+        for current in [0u64, 2500, 5000, 7500, 10_000] {
+            for amount in [0u64, 1, 1_000_000, 50_000_000, 10_000_000_000, u64::MAX] {
+                for elapsed in [0, 86_400, PARAMS.half_life_seconds * 2] {
+                    for success in [true, false] {
+                        let expected = reference_reputation(current, success, amount, elapsed, &PARAMS);
+                        let actual = calculate_new_reputation(current, success, amount, elapsed, &PARAMS).unwrap() as f64;
+                        assert!(
+                            (actual - expected).abs() <= 2.0,
+                            "reputation({}, {}, {}, {}) = {}, expected {}",
+                            current, success, amount, elapsed, actual, expected
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn larger_transactions_move_reputation_further() {
+        let small = calculate_new_reputation(5000, true, 1_000, 0, &PARAMS).unwrap();
+        let large = calculate_new_reputation(5000, true, 100_000_000, 0, &PARAMS).unwrap();
+        assert!(large > small);
+
+        let capped = calculate_new_reputation(5000, true, u64::MAX, 0, &PARAMS).unwrap();
+        assert_eq!(capped, 5000 + (MAX_REPUTATION - 5000) * PARAMS.max_alpha_bps as u64 / 10_000);
+    }
+
+    #[test]
+    fn dispute_outcomes_use_the_maximum_step() {
+        assert_eq!(calculate_dispute_reputation(5000, false, &PARAMS).unwrap(), 4000);
+        assert_eq!(calculate_dispute_reputation(5000, true, &PARAMS).unwrap(), 6000);
+    }
+
+    #[test]
+    fn rating_weight_scales_with_reputation_and_stake() {
+        assert_eq!(calculate_rating_weight(0, 0, PARAMS.value_unit).unwrap(), 1);
+        assert_eq!(calculate_rating_weight(5000, 0, PARAMS.value_unit).unwrap(), 5000);
+        assert_eq!(calculate_rating_weight(5000, 3 * PARAMS.value_unit, PARAMS.value_unit).unwrap(), 15_000);
+
+        let low_stake = calculate_rating_weight(8000, 1_000_000_000, PARAMS.value_unit).unwrap();
+        let high_stake = calculate_rating_weight(8000, 100_000_000_000, PARAMS.value_unit).unwrap();
+        assert!(high_stake > low_stake);
+    }
+
+    #[test]
+    fn results_stay_in_range() {
+        for current in [0u64, MAX_REPUTATION, u64::MAX] {
+            for success in [true, false] {
+                let next = calculate_new_reputation(current, success, u64::MAX, i64::MAX, &PARAMS).unwrap();
+                assert!(next <= MAX_REPUTATION);
+            }
+        }
+        assert_eq!(apply_penalty(500, 1000), 0);
+    }
+}
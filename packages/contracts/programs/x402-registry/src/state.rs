@@ -7,8 +7,403 @@ pub struct ServiceRating {
     pub rating: u16,
     pub timestamp: i64,
     pub last_update_time: i64,
+    pub weight: u64,
 }
 
 impl ServiceRating {
-    pub const SPACE: usize = 8 + 32 + 32 + 2 + 8 + 8;
+    pub const SPACE: usize = 8 + 32 + 32 + 2 + 8 + 8 + 8;
+    /// Ratings written before they were weighted count as a neutral-reputation rater with no stake.
+    pub const LEGACY_WEIGHT: u64 = crate::reputation::NEUTRAL_REPUTATION;
+}
+
+#[account]
+pub struct ServiceInteraction {
+    pub service: Pubkey,
+    pub agent: Pubkey,
+    pub transactions: u64,
+    pub successful_transactions: u64,
+    pub total_amount: u64,
+    pub first_at: i64,
+    pub last_at: i64,
+    pub bump: u8,
+}
+
+impl ServiceInteraction {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1;
+}
+
+pub const MAX_COUNCIL_MEMBERS: usize = 10;
+
+#[account]
+pub struct RegistryConfig {
+    pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+    pub oracle_authority: Pubkey,
+    pub verifier_authority: Pubkey,
+    pub tap_authority: Pubkey,
+    pub dao_authority: Pubkey,
+    pub arbiter_authority: Pubkey,
+    pub dao_treasury: Pubkey,
+    pub stake_token_mint: Pubkey,
+    pub min_stake_amount: u64,
+    pub min_slash_amount: u64,
+    pub unstake_lock_seconds: i64,
+    pub restake_cooldown_seconds: i64,
+    pub transaction_rate_limit_seconds: i64,
+    pub rating_update_cooldown_seconds: i64,
+    pub slash_cooldown_seconds: i64,
+    pub credit_reputation_threshold: u64,
+    pub dispute_bond_lamports: u64,
+    pub dispute_response_seconds: i64,
+    pub credit_fee_bps: u16,
+    pub credit_repayment_seconds: i64,
+    pub channel_challenge_seconds: i64,
+    pub health_report_interval_seconds: i64,
+    pub uptime_alpha_bps: u16,
+    pub reputation_half_life_seconds: i64,
+    pub reputation_value_unit: u64,
+    pub reputation_alpha_bps: u16,
+    pub reputation_max_alpha_bps: u16,
+    pub reputation_slash_penalty: u64,
+    pub rating_window_seconds: i64,
+    pub council: Vec<Pubkey>,
+    pub council_threshold: u8,
+    pub governance_timelock_seconds: i64,
+    pub proposal_count: u64,
+    pub bump: u8,
+}
+
+impl RegistryConfig {
+    pub const SPACE: usize = 8 + 32 + (1 + 32) + 32 * 7 + 8 + 8 + 8 * 5 + 8 + 8 + 8 + 2 + 8 + 8 + 8 + 2 + 8 + 8 + 2 + 2 + 8 + 8 + (4 + 32 * MAX_COUNCIL_MEMBERS) + 1 + 8 + 8 + 1;
+
+    pub fn is_council_member(&self, key: &Pubkey) -> bool {
+        self.council.contains(key)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ConfigParams {
+    pub oracle_authority: Pubkey,
+    pub verifier_authority: Pubkey,
+    pub tap_authority: Pubkey,
+    pub dao_authority: Pubkey,
+    pub arbiter_authority: Pubkey,
+    pub dao_treasury: Pubkey,
+    pub stake_token_mint: Pubkey,
+    pub min_stake_amount: u64,
+    pub min_slash_amount: u64,
+    pub unstake_lock_seconds: i64,
+    pub restake_cooldown_seconds: i64,
+    pub transaction_rate_limit_seconds: i64,
+    pub rating_update_cooldown_seconds: i64,
+    pub slash_cooldown_seconds: i64,
+    pub credit_reputation_threshold: u64,
+    pub dispute_bond_lamports: u64,
+    pub dispute_response_seconds: i64,
+    pub credit_fee_bps: u16,
+    pub credit_repayment_seconds: i64,
+    pub channel_challenge_seconds: i64,
+    pub health_report_interval_seconds: i64,
+    pub uptime_alpha_bps: u16,
+    pub reputation_half_life_seconds: i64,
+    pub reputation_value_unit: u64,
+    pub reputation_alpha_bps: u16,
+    pub reputation_max_alpha_bps: u16,
+    pub reputation_slash_penalty: u64,
+    pub rating_window_seconds: i64,
+    pub council: Vec<Pubkey>,
+    pub council_threshold: u8,
+    pub governance_timelock_seconds: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ConfigUpdate {
+    pub oracle_authority: Option<Pubkey>,
+    pub verifier_authority: Option<Pubkey>,
+    pub tap_authority: Option<Pubkey>,
+    pub dao_treasury: Option<Pubkey>,
+    pub min_stake_amount: Option<u64>,
+    pub min_slash_amount: Option<u64>,
+    pub unstake_lock_seconds: Option<i64>,
+    pub restake_cooldown_seconds: Option<i64>,
+    pub transaction_rate_limit_seconds: Option<i64>,
+    pub rating_update_cooldown_seconds: Option<i64>,
+    pub slash_cooldown_seconds: Option<i64>,
+    pub credit_reputation_threshold: Option<u64>,
+    pub dispute_bond_lamports: Option<u64>,
+    pub dispute_response_seconds: Option<i64>,
+    pub credit_fee_bps: Option<u16>,
+    pub credit_repayment_seconds: Option<i64>,
+    pub channel_challenge_seconds: Option<i64>,
+    pub health_report_interval_seconds: Option<i64>,
+    pub uptime_alpha_bps: Option<u16>,
+    pub reputation_half_life_seconds: Option<i64>,
+    pub reputation_value_unit: Option<u64>,
+    pub reputation_alpha_bps: Option<u16>,
+    pub reputation_max_alpha_bps: Option<u16>,
+    pub reputation_slash_penalty: Option<u64>,
+    pub rating_window_seconds: Option<i64>,
+}
+
+#[account]
+pub struct Dispute {
+    pub payer: Pubkey,
+    pub provider: Pubkey,
+    pub service: Pubkey,
+    pub transaction_signature: [u8; 64],
+    pub evidence_uri: String,
+    pub response_uri: String,
+    pub bond_lamports: u64,
+    pub provider_bonded: bool,
+    pub opened_at: i64,
+    pub response_deadline: i64,
+    pub resolved_at: i64,
+    pub status: DisputeStatus,
+    pub refund_amount: u64,
+    pub slash_amount: u64,
+    pub bump: u8,
+}
+
+impl Dispute {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 64 + (4 + 256) + (4 + 256) + 8 + 1 + 8 + 8 + 8 + 1 + 8 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DisputeStatus {
+    Open,
+    Responded,
+    ResolvedForPayer,
+    ResolvedForProvider,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DisputeRuling {
+    pub payer_wins: bool,
+    pub refund_amount: u64,
+    pub slash_amount: u64,
+}
+
+#[account]
+pub struct CreditLine {
+    pub agent: Pubkey,
+    pub due_at: i64,
+    pub total_drawn: u64,
+    pub total_fees: u64,
+    pub total_repaid: u64,
+    pub total_seized: u64,
+    pub last_draw_at: i64,
+    pub bump: u8,
+}
+
+impl CreditLine {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct PaymentEscrow {
+    pub payer: Pubkey,
+    pub payee: Pubkey,
+    pub service: Pubkey,
+    pub mint: Pubkey,
+    pub nonce: u64,
+    pub amount: u64,
+    pub deadline: i64,
+    pub created_at: i64,
+    pub settled_at: i64,
+    pub status: PaymentEscrowStatus,
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+impl PaymentEscrow {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentEscrowStatus {
+    Funded,
+    Released,
+    Refunded,
+}
+
+#[account]
+pub struct PaymentChannel {
+    pub payer: Pubkey,
+    pub payee: Pubkey,
+    pub service: Pubkey,
+    pub mint: Pubkey,
+    pub channel_id: u64,
+    pub deposit: u64,
+    pub redeemed: u64,
+    pub last_nonce: u64,
+    pub challenge_seconds: i64,
+    pub opened_at: i64,
+    pub close_requested_at: i64,
+    pub status: ChannelStatus,
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+impl PaymentChannel {
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelStatus {
+    Open,
+    Closing,
+    Closed,
+}
+
+#[account]
+pub struct StakeMint {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub decimals: u8,
+    pub reputation_weight_bps: u32,
+    pub enabled: bool,
+    pub bump: u8,
+    pub escrow_bump: u8,
+}
+
+impl StakeMint {
+    pub const SPACE: usize = 8 + 32 + 32 + 1 + 4 + 1 + 1 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct StakeMintUpdate {
+    pub reputation_weight_bps: Option<u32>,
+    pub enabled: Option<bool>,
+}
+
+#[account]
+pub struct ProbeOperator {
+    pub operator: Pubkey,
+    pub added_at: i64,
+    pub reports: u64,
+    pub bump: u8,
+}
+
+impl ProbeOperator {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct ServiceHealth {
+    pub service: Pubkey,
+    pub uptime_bps: u16,
+    pub latency_ms: u32,
+    pub total_probes: u64,
+    pub successful_probes: u64,
+    pub reports: u64,
+    pub last_operator: Pubkey,
+    pub last_window_end: i64,
+    pub last_reported_at: i64,
+    pub bump: u8,
+}
+
+impl ServiceHealth {
+    pub const SPACE: usize = 8 + 32 + 2 + 4 + 8 + 8 + 8 + 32 + 8 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct HealthReport {
+    pub window_start: i64,
+    pub window_end: i64,
+    pub probes: u32,
+    pub successes: u32,
+    pub average_latency_ms: u32,
+}
+
+#[account]
+pub struct TransactionReceipt {
+    pub service: Pubkey,
+    pub agent: Pubkey,
+    pub nonce: u64,
+    pub amount: u64,
+    pub success: bool,
+    pub recorded_at: i64,
+    pub bump: u8,
+}
+
+impl TransactionReceipt {
+    pub const SPACE: usize = 8 + 32 + 32 + 8 + 8 + 1 + 8 + 1;
+}
+
+pub const SERVICE_PAGE_SIZE: u32 = 32;
+
+#[account]
+pub struct ServiceDirectory {
+    pub owner: Pubkey,
+    pub next_index: u32,
+    pub service_count: u32,
+    pub bump: u8,
+}
+
+impl ServiceDirectory {
+    pub const SPACE: usize = 8 + 32 + 4 + 4 + 1;
+
+    pub fn next_page(&self) -> u32 {
+        self.next_index / SERVICE_PAGE_SIZE
+    }
+}
+
+#[account]
+pub struct ServiceDirectoryPage {
+    pub owner: Pubkey,
+    pub page: u32,
+    pub services: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl ServiceDirectoryPage {
+    pub const SPACE: usize = 8 + 32 + 4 + (4 + 32 * SERVICE_PAGE_SIZE as usize) + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum GovernanceAction {
+    SuspendAgent { agent: Pubkey },
+    BanAgent { agent: Pubkey },
+    SlashForFraud { agent: Pubkey, fraud_amount: u64, evidence_uri: String },
+    SuspendService { service: Pubkey },
+    CloseSuspendedService { service: Pubkey },
+    UnsuspendAgent { agent: Pubkey },
+    UnsuspendService { service: Pubkey },
+    UpdateCouncil { council: Vec<Pubkey>, threshold: u8, timelock_seconds: i64 },
+    UpdateAuthorities { dao_authority: Pubkey, arbiter_authority: Pubkey },
+}
+
+impl GovernanceAction {
+    /// Sized for `UpdateCouncil` with a full council, the largest variant.
+    pub const SPACE: usize = 1 + (4 + 32 * MAX_COUNCIL_MEMBERS) + 1 + 8;
+
+    pub fn is_suspension(&self) -> bool {
+        matches!(self, Self::SuspendAgent { .. } | Self::SuspendService { .. })
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalStatus {
+    Pending,
+    Approved,
+    Executed,
+    Cancelled,
+}
+
+#[account]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: GovernanceAction,
+    pub emergency: bool,
+    pub approvals: Vec<Pubkey>,
+    pub status: ProposalStatus,
+    pub created_at: i64,
+    pub executable_at: i64,
+    pub executed_at: i64,
+    pub bump: u8,
+}
+
+impl Proposal {
+    pub const SPACE: usize = 8 + 8 + 32 + GovernanceAction::SPACE + 1 + (4 + 32 * MAX_COUNCIL_MEMBERS) + 1 + 8 + 8 + 8 + 1;
 }
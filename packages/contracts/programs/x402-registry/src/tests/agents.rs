@@ -0,0 +1,399 @@
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{system_program, AccountSerialize, InstructionData};
+use anchor_spl::token;
+
+use super::harness::{expect_error, instruction, pda};
+use super::registry::*;
+use crate::state::*;
+use crate::{accounts, instruction as ix, Agent, AgentStatus, ErrorCode};
+
+impl Registry {
+    fn restake_ix(&self, agent: &TestAgent, stake_amount: u64) -> Instruction {
+        instruction(
+            accounts::RestakeAgent {
+                config: config_address(),
+                agent: agent.address,
+                signer: agent.wallet,
+                stake_mint: stake_mint_address(&agent.mint),
+                mint: agent.mint,
+                staker_token_account: agent.tokens,
+                escrow_account: escrow_address(&agent.mint),
+                token_program: token::ID,
+            },
+            ix::RestakeAgent { stake_amount },
+        )
+    }
+
+    fn migrate_legacy_stake_ix(&self, agent: &Pubkey) -> Instruction {
+        instruction(
+            accounts::MigrateLegacyStake {
+                config: config_address(),
+                agent: *agent,
+                legacy_escrow: pda(&[b"escrow"]),
+                stake_mint: stake_mint_address(&self.mint),
+                mint: self.mint,
+                escrow_account: escrow_address(&self.mint),
+                payer: self.authority,
+                system_program: system_program::ID,
+                token_program: token::ID,
+            },
+            ix::MigrateLegacyStake {},
+        )
+    }
+
+    /// An agent as written before stakes were kept per mint: no `stake_mint` field, profile strings at full length so
+    /// the account has no slack, and its stake held in the global `["escrow"]` account.
+    fn legacy_agent(&mut self) -> TestAgent {
+        let agent = self.new_agent();
+        let state = Agent {
+            wallet: agent.wallet,
+            did: "d".repeat(256),
+            visa_tap_cert: "v".repeat(512),
+            reputation_score: 7_000,
+            total_spent: 0,
+            total_transactions: 0,
+            successful_transactions: 0,
+            disputes_won: 0,
+            disputes_lost: 0,
+            staked_amount: STAKE,
+            slashed_amount: 0,
+            credit_limit: 0,
+            credit_used: 0,
+            created_at: self.now,
+            last_active: self.now,
+            last_transaction_time: self.now,
+            last_slashed_time: 0,
+            last_slash_evidence: "e".repeat(256),
+            metadata_uri: "m".repeat(256),
+            status: AgentStatus::Active,
+            historical_min_reputation: 7_000,
+            service_count: 0,
+            stake_mint: Pubkey::default(),
+        };
+        let mut data = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+        data.truncate(data.len() - 32);
+        assert_eq!(data.len(), 8 + Agent::SPACE - 32);
+        self.set_raw(agent.address, data, crate::ID);
+
+        let legacy_escrow = pda(&[b"escrow"]);
+        let mint = self.mint;
+        self.create_token_account_at(legacy_escrow, &mint, &legacy_escrow, STAKE);
+        agent
+    }
+
+    fn close_agent_ix(&self, agent: &TestAgent) -> Instruction {
+        instruction(
+            accounts::CloseAgent {
+                agent: agent.address,
+                recipient: agent.wallet,
+            },
+            ix::CloseAgent {},
+        )
+    }
+}
+
+#[test]
+fn registers_an_agent_with_stake() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+
+    let state = registry.agent(&agent);
+    assert_eq!(state.wallet, agent.wallet);
+    assert_eq!(state.staked_amount, STAKE);
+    assert_eq!(state.reputation_score, 7_000);
+    assert!(state.status == AgentStatus::Active);
+    assert_eq!(registry.token_balance(&escrow_address(&registry.mint)), STAKE);
+    assert_eq!(registry.token_balance(&agent.tokens), WALLET_TOKENS - STAKE);
+}
+
+#[test]
+fn initial_reputation_follows_stake() {
+    let mut registry = Registry::new();
+
+    for (stake, reputation) in [(MIN_STAKE, 5_000), (5 * MIN_STAKE, 6_000), (STAKE, 7_000), (WALLET_TOKENS, 7_000)] {
+        let agent = registry.new_agent();
+        registry.send(registry.register_agent_ix(&agent, stake)).unwrap();
+
+        let state = registry.agent(&agent);
+        assert_eq!(state.reputation_score, reputation);
+        assert_eq!(state.historical_min_reputation, reputation);
+        assert_eq!(state.stake_mint, registry.mint);
+    }
+}
+
+#[test]
+fn rejects_stake_below_the_minimum() {
+    let mut registry = Registry::new();
+    let agent = registry.new_agent();
+
+    expect_error(registry.send(registry.register_agent_ix(&agent, MIN_STAKE - 1)), ErrorCode::InvalidStakeAmount);
+    assert!(!registry.exists(&agent.address));
+    assert_eq!(registry.token_balance(&agent.tokens), WALLET_TOKENS);
+}
+
+#[test]
+fn rejects_oversized_profile_strings() {
+    let mut registry = Registry::new();
+    let agent = registry.new_agent();
+    let profile = || ix::RegisterAgent {
+        did: "did:x402:agent".to_string(),
+        visa_tap_cert: String::new(),
+        stake_amount: STAKE,
+        metadata_uri: String::new(),
+    };
+
+    let mut did = profile();
+    did.did = "d".repeat(257);
+    let mut visa_tap_cert = profile();
+    visa_tap_cert.visa_tap_cert = "c".repeat(513);
+    let mut metadata_uri = profile();
+    metadata_uri.metadata_uri = "m".repeat(257);
+
+    for data in [did, visa_tap_cert, metadata_uri] {
+        let mut register = registry.register_agent_ix(&agent, STAKE);
+        register.data = data.data();
+        expect_error(registry.send(register), ErrorCode::StringTooLong);
+    }
+}
+
+#[test]
+fn stakes_only_from_the_signers_token_account() {
+    let mut registry = Registry::new();
+    let agent = registry.new_agent();
+    let other = registry.new_agent();
+    let other_mint = registry.create_mint(DECIMALS);
+    let foreign_tokens = registry.create_token_account(&other_mint, &agent.wallet, WALLET_TOKENS);
+
+    let mut register = registry.register_agent_ix(&agent, STAKE);
+    register.accounts[5].pubkey = other.tokens;
+    expect_error(registry.send(register), ErrorCode::UnauthorizedAccess);
+
+    let mut register = registry.register_agent_ix(&agent, STAKE);
+    register.accounts[5].pubkey = foreign_tokens;
+    expect_error(registry.send(register), ErrorCode::InvalidMint);
+}
+
+#[test]
+fn registers_each_wallet_once() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+
+    assert!(registry.send(registry.register_agent_ix(&agent, STAKE)).is_err());
+    assert_eq!(registry.agent(&agent).staked_amount, STAKE);
+}
+
+#[test]
+fn unstakes_after_the_lock_period() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+
+    expect_error(registry.send(registry.unstake_ix(&agent)), ErrorCode::UnstakeLockPeriod);
+
+    registry.advance(UNSTAKE_LOCK);
+    let stranger = registry.new_wallet();
+    let mut unstake = registry.unstake_ix(&agent);
+    unstake.accounts[6].pubkey = stranger;
+    expect_error(registry.send(unstake), ErrorCode::UnauthorizedAccess);
+
+    registry.send(registry.unstake_ix(&agent)).unwrap();
+    let state = registry.agent(&agent);
+    assert_eq!(state.staked_amount, 0);
+    assert_eq!(state.reputation_score, 0);
+    assert_eq!(state.historical_min_reputation, 7_000);
+    assert_eq!(state.credit_limit, 0);
+    assert!(state.status == AgentStatus::Paused);
+    assert_eq!(registry.token_balance(&agent.tokens), WALLET_TOKENS);
+    assert_eq!(registry.token_balance(&escrow_address(&registry.mint)), 0);
+
+    expect_error(registry.send(registry.unstake_ix(&agent)), ErrorCode::NoStakeToWithdraw);
+}
+
+#[test]
+fn unstake_lock_cannot_overflow() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    registry
+        .send(registry.update_config_ix(ConfigUpdate {
+            unstake_lock_seconds: Some(i64::MAX),
+            ..Default::default()
+        }))
+        .unwrap();
+
+    expect_error(registry.send(registry.unstake_ix(&agent)), ErrorCode::TimestampOverflow);
+}
+
+#[test]
+fn suspended_and_banned_agents_cannot_unstake() {
+    let mut registry = Registry::new();
+    let suspended = registry.register_agent();
+    let banned = registry.register_agent();
+    registry.suspend_agent(&suspended);
+    registry.ban_agent(&banned);
+    registry.advance(UNSTAKE_LOCK);
+
+    expect_error(registry.send(registry.unstake_ix(&suspended)), ErrorCode::AgentSuspended);
+    expect_error(registry.send(registry.unstake_ix(&banned)), ErrorCode::AgentSuspended);
+}
+
+#[test]
+fn restakes_after_the_cooldown() {
+    let mut registry = Registry::new();
+    registry
+        .send(registry.update_config_ix(ConfigUpdate {
+            restake_cooldown_seconds: Some(UNSTAKE_LOCK + RESTAKE_COOLDOWN),
+            ..Default::default()
+        }))
+        .unwrap();
+    let agent = registry.register_agent();
+
+    expect_error(registry.send(registry.restake_ix(&agent, STAKE)), ErrorCode::AlreadyStaked);
+
+    registry.advance(UNSTAKE_LOCK);
+    registry.send(registry.unstake_ix(&agent)).unwrap();
+    expect_error(registry.send(registry.restake_ix(&agent, STAKE)), ErrorCode::RestakeCooldownNotElapsed);
+
+    registry.advance(RESTAKE_COOLDOWN);
+    expect_error(registry.send(registry.restake_ix(&agent, MIN_STAKE - 1)), ErrorCode::InvalidStakeAmount);
+    registry.send(registry.restake_ix(&agent, MIN_STAKE)).unwrap();
+
+    let state = registry.agent(&agent);
+    assert!(state.status == AgentStatus::Active);
+    assert_eq!(state.staked_amount, MIN_STAKE);
+    assert_eq!(state.reputation_score, 5_000);
+    assert_eq!(state.last_active, registry.now);
+    assert_eq!(registry.token_balance(&agent.tokens), WALLET_TOKENS - MIN_STAKE);
+}
+
+#[test]
+fn restaking_never_raises_reputation_above_its_historical_minimum() {
+    let mut registry = Registry::new();
+    let agent = registry.new_agent();
+    registry.send(registry.register_agent_ix(&agent, MIN_STAKE)).unwrap();
+
+    registry.advance(UNSTAKE_LOCK);
+    registry.send(registry.unstake_ix(&agent)).unwrap();
+    registry.send(registry.restake_ix(&agent, STAKE)).unwrap();
+
+    let state = registry.agent(&agent);
+    assert_eq!(state.staked_amount, STAKE);
+    assert_eq!(state.reputation_score, 5_000);
+}
+
+#[test]
+fn banned_agents_cannot_restake() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    registry.advance(UNSTAKE_LOCK);
+    registry.send(registry.unstake_ix(&agent)).unwrap();
+    registry.ban_agent(&agent);
+
+    expect_error(registry.send(registry.restake_ix(&agent, STAKE)), ErrorCode::InvalidAgentStatus);
+}
+
+#[test]
+fn closes_only_unstaked_agents_without_services() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    registry.register_service(&agent);
+
+    expect_error(registry.send(registry.close_agent_ix(&agent)), ErrorCode::CannotCloseWithStake);
+
+    registry.advance(UNSTAKE_LOCK);
+    registry.send(registry.unstake_ix(&agent)).unwrap();
+    expect_error(registry.send(registry.close_agent_ix(&agent)), ErrorCode::CannotCloseWithServices);
+
+    registry.update::<Agent>(&agent.address, |state| state.service_count = 0);
+    let stranger = registry.new_wallet();
+    let mut close = registry.close_agent_ix(&agent);
+    close.accounts[1].pubkey = stranger;
+    expect_error(registry.send(close), ErrorCode::UnauthorizedAccess);
+
+    let rent = registry.lamports(&agent.address);
+    let balance = registry.lamports(&agent.wallet);
+    registry.send(registry.close_agent_ix(&agent)).unwrap();
+    assert!(!registry.exists(&agent.address));
+    assert_eq!(registry.lamports(&agent.wallet), balance + rent);
+}
+
+#[test]
+fn closes_banned_agents_once_unstaked() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    registry.advance(UNSTAKE_LOCK);
+    registry.send(registry.unstake_ix(&agent)).unwrap();
+    registry.ban_agent(&agent);
+
+    registry.send(registry.close_agent_ix(&agent)).unwrap();
+    assert!(!registry.exists(&agent.address));
+}
+
+#[test]
+fn suspended_agents_cannot_close() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    registry.suspend_agent(&agent);
+    registry.update::<Agent>(&agent.address, |state| state.staked_amount = 0);
+
+    expect_error(registry.send(registry.close_agent_ix(&agent)), ErrorCode::InvalidAgentStatus);
+}
+
+#[test]
+fn suspends_unsuspends_and_bans_agents() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+
+    registry.suspend_agent(&agent);
+    assert!(registry.agent(&agent).status == AgentStatus::Suspended);
+
+    let proposal = registry.approved_proposal(GovernanceAction::SuspendAgent { agent: agent.address });
+    expect_error(
+        registry.send(registry.governed_agent_ix(&proposal, &agent, ix::SuspendAgent {})),
+        ErrorCode::InvalidAgentStatus,
+    );
+
+    expect_error(
+        registry.send(registry.governed_agent_ix(&proposal, &agent, ix::UnsuspendAgent {})),
+        ErrorCode::ProposalActionMismatch,
+    );
+    let unsuspend = registry.approved_proposal(GovernanceAction::UnsuspendAgent { agent: agent.address });
+    registry.send(registry.governed_agent_ix(&unsuspend, &agent, ix::UnsuspendAgent {})).unwrap();
+    assert!(registry.agent(&agent).status == AgentStatus::Active);
+
+    let unsuspend = registry.approved_proposal(GovernanceAction::UnsuspendAgent { agent: agent.address });
+    expect_error(
+        registry.send(registry.governed_agent_ix(&unsuspend, &agent, ix::UnsuspendAgent {})),
+        ErrorCode::InvalidAgentStatus,
+    );
+    registry.ban_agent(&agent);
+    assert!(registry.agent(&agent).status == AgentStatus::Banned);
+    expect_error(
+        registry.send(registry.governed_agent_ix(&unsuspend, &agent, ix::UnsuspendAgent {})),
+        ErrorCode::InvalidAgentStatus,
+    );
+}
+
+#[test]
+fn migrates_legacy_stake_into_the_mint_escrow() {
+    let mut registry = Registry::new();
+    let agent = registry.legacy_agent();
+    let legacy_escrow = pda(&[b"escrow"]);
+
+    expect_error(registry.send(registry.migrate_legacy_stake_ix(&config_address())), ErrorCode::InvalidAccountLayout);
+
+    registry.send(registry.migrate_legacy_stake_ix(&agent.address)).unwrap();
+    let state = registry.agent(&agent);
+    assert_eq!(state.stake_mint, registry.mint);
+    assert_eq!(state.staked_amount, STAKE);
+    assert_eq!(state.metadata_uri, "m".repeat(256));
+    assert_eq!(registry.state(&agent.address).unwrap().data.len(), 8 + Agent::SPACE);
+    assert_eq!(registry.token_balance(&legacy_escrow), 0);
+    assert_eq!(registry.token_balance(&escrow_address(&registry.mint)), STAKE);
+
+    expect_error(registry.send(registry.migrate_legacy_stake_ix(&agent.address)), ErrorCode::StakeAlreadyMigrated);
+
+    registry.advance(UNSTAKE_LOCK);
+    registry.send(registry.unstake_ix(&agent)).unwrap();
+    assert_eq!(registry.token_balance(&agent.tokens), WALLET_TOKENS + STAKE);
+}
@@ -0,0 +1,275 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_spl::token;
+
+use super::harness::{expect_error, instruction};
+use super::registry::*;
+use crate::state::*;
+use crate::{accounts, instruction as ix, ErrorCode};
+
+impl Registry {
+    fn transfer_authority_ix(&self, authority: &Pubkey, new_authority: Option<Pubkey>) -> Instruction {
+        instruction(
+            accounts::UpdateConfig {
+                config: config_address(),
+                authority: *authority,
+            },
+            ix::TransferConfigAuthority { new_authority },
+        )
+    }
+
+    fn accept_authority_ix(&self, new_authority: &Pubkey) -> Instruction {
+        instruction(
+            accounts::AcceptConfigAuthority {
+                config: config_address(),
+                new_authority: *new_authority,
+            },
+            ix::AcceptConfigAuthority {},
+        )
+    }
+
+    fn update_stake_mint_ix(&self, mint: &Pubkey, update: StakeMintUpdate) -> Instruction {
+        instruction(
+            accounts::UpdateStakeMint {
+                config: config_address(),
+                stake_mint: stake_mint_address(mint),
+                authority: self.authority,
+            },
+            ix::UpdateStakeMint { update },
+        )
+    }
+}
+
+fn with_signer(mut instruction: Instruction, index: usize, signer: Pubkey) -> Instruction {
+    instruction.accounts[index].pubkey = signer;
+    instruction
+}
+
+#[test]
+fn initializes_config_and_stake_mint() {
+    let registry = Registry::new();
+
+    let config = registry.config();
+    assert_eq!(config.authority, registry.authority);
+    assert_eq!(config.pending_authority, None);
+    assert_eq!(config.oracle_authority, registry.oracle);
+    assert_eq!(config.dao_treasury, registry.treasury);
+    assert_eq!(config.min_stake_amount, MIN_STAKE);
+    assert_eq!(config.council, registry.council);
+    assert_eq!(config.council_threshold, 2);
+    assert_eq!(config.proposal_count, 0);
+
+    let stake_mint: StakeMint = registry.account(&stake_mint_address(&registry.mint));
+    assert_eq!(stake_mint.mint, registry.mint);
+    assert_eq!(stake_mint.token_program, token::ID);
+    assert_eq!(stake_mint.decimals, DECIMALS);
+    assert_eq!(stake_mint.reputation_weight_bps, 10_000);
+    assert!(stake_mint.enabled);
+    assert_eq!(registry.token_balance(&escrow_address(&registry.mint)), 0);
+}
+
+#[test]
+fn only_the_upgrade_authority_initializes_config() {
+    let mut registry = Registry::deployed();
+    let stranger = registry.new_wallet();
+    let initialize = registry.initialize_config_ix(registry.config_params());
+
+    expect_error(registry.send(with_signer(initialize.clone(), 3, stranger)), ErrorCode::UnauthorizedAccess);
+    registry.send(initialize.clone()).unwrap();
+    assert!(registry.send(initialize).is_err());
+}
+
+#[test]
+fn rejects_invalid_config_params() {
+    let cases: Vec<fn(&mut ConfigParams)> = vec![
+        |params| params.min_stake_amount = 0,
+        |params| params.min_slash_amount = 0,
+        |params| params.unstake_lock_seconds = -1,
+        |params| params.dispute_response_seconds = 0,
+        |params| params.credit_repayment_seconds = 0,
+        |params| params.channel_challenge_seconds = 0,
+        |params| params.credit_reputation_threshold = 10_001,
+        |params| params.credit_fee_bps = 10_001,
+        |params| params.uptime_alpha_bps = 0,
+        |params| params.uptime_alpha_bps = 10_001,
+        |params| params.reputation_half_life_seconds = -1,
+        |params| params.reputation_value_unit = 0,
+        |params| params.rating_window_seconds = 0,
+        |params| params.reputation_alpha_bps = 0,
+        |params| params.reputation_alpha_bps = params.reputation_max_alpha_bps + 1,
+        |params| params.reputation_max_alpha_bps = 10_001,
+        |params| params.council = vec![],
+        |params| params.council = (0..=MAX_COUNCIL_MEMBERS).map(|_| Pubkey::new_unique()).collect(),
+        |params| params.council = vec![params.council[0], params.council[0]],
+        |params| params.council_threshold = 0,
+        |params| params.council_threshold = params.council.len() as u8 + 1,
+        |params| params.governance_timelock_seconds = -1,
+    ];
+
+    for edit in cases {
+        let mut registry = Registry::deployed();
+        let mut params = registry.config_params();
+        edit(&mut params);
+        expect_error(registry.send(registry.initialize_config_ix(params)), ErrorCode::InvalidConfig);
+    }
+}
+
+#[test]
+fn updates_config_fields() {
+    let mut registry = Registry::new();
+    let oracle = Pubkey::new_unique();
+
+    registry
+        .send(registry.update_config_ix(ConfigUpdate {
+            oracle_authority: Some(oracle),
+            min_stake_amount: Some(2 * MIN_STAKE),
+            credit_fee_bps: Some(250),
+            ..Default::default()
+        }))
+        .unwrap();
+
+    let config = registry.config();
+    assert_eq!(config.oracle_authority, oracle);
+    assert_eq!(config.min_stake_amount, 2 * MIN_STAKE);
+    assert_eq!(config.credit_fee_bps, 250);
+    assert_eq!(config.verifier_authority, registry.verifier);
+}
+
+#[test]
+fn update_config_keeps_the_config_valid() {
+    let mut registry = Registry::new();
+
+    let update = registry.update_config_ix(ConfigUpdate {
+        uptime_alpha_bps: Some(0),
+        ..Default::default()
+    });
+    expect_error(registry.send(update), ErrorCode::InvalidConfig);
+
+    let update = registry.update_config_ix(ConfigUpdate {
+        reputation_alpha_bps: Some(3_000),
+        ..Default::default()
+    });
+    expect_error(registry.send(update), ErrorCode::InvalidConfig);
+
+    assert_eq!(registry.config().uptime_alpha_bps, registry.config_params().uptime_alpha_bps);
+}
+
+#[test]
+fn only_the_authority_updates_config() {
+    let mut registry = Registry::new();
+    let stranger = registry.new_wallet();
+    let update = registry.update_config_ix(ConfigUpdate {
+        min_stake_amount: Some(1),
+        ..Default::default()
+    });
+
+    expect_error(registry.send(with_signer(update.clone(), 1, stranger)), ErrorCode::UnauthorizedAccess);
+    expect_error(registry.send_unsigned(update, &registry.authority.clone()), anchor_lang::error::ErrorCode::AccountNotSigner);
+}
+
+#[test]
+fn transfers_authority_in_two_steps() {
+    let mut registry = Registry::new();
+    let authority = registry.authority;
+    let successor = registry.new_wallet();
+    let stranger = registry.new_wallet();
+
+    registry.send(registry.transfer_authority_ix(&authority, Some(successor))).unwrap();
+    assert_eq!(registry.config().pending_authority, Some(successor));
+    assert_eq!(registry.config().authority, authority);
+
+    expect_error(registry.send(registry.accept_authority_ix(&stranger)), ErrorCode::UnauthorizedAccess);
+    expect_error(
+        registry.send(registry.transfer_authority_ix(&stranger, Some(stranger))),
+        ErrorCode::UnauthorizedAccess,
+    );
+
+    registry.send(registry.accept_authority_ix(&successor)).unwrap();
+    let config = registry.config();
+    assert_eq!(config.authority, successor);
+    assert_eq!(config.pending_authority, None);
+
+    expect_error(registry.send(registry.update_config_ix(ConfigUpdate::default())), ErrorCode::UnauthorizedAccess);
+    expect_error(registry.send(registry.accept_authority_ix(&successor)), ErrorCode::UnauthorizedAccess);
+}
+
+#[test]
+fn cancels_a_pending_authority_transfer() {
+    let mut registry = Registry::new();
+    let authority = registry.authority;
+    let successor = registry.new_wallet();
+
+    registry.send(registry.transfer_authority_ix(&authority, Some(successor))).unwrap();
+    registry.send(registry.transfer_authority_ix(&authority, None)).unwrap();
+
+    expect_error(registry.send(registry.accept_authority_ix(&successor)), ErrorCode::UnauthorizedAccess);
+    assert_eq!(registry.config().authority, authority);
+}
+
+#[test]
+fn adds_stake_mints_with_a_weight() {
+    let mut registry = Registry::new();
+    let mint = registry.create_mint(6);
+    let stranger = registry.new_wallet();
+
+    expect_error(registry.send(registry.add_stake_mint_ix(&mint, 0)), ErrorCode::InvalidConfig);
+
+    let mut add = registry.add_stake_mint_ix(&mint, 5_000);
+    add.accounts[5].pubkey = stranger;
+    expect_error(registry.send(add), ErrorCode::UnauthorizedAccess);
+
+    registry.send(registry.add_stake_mint_ix(&mint, 5_000)).unwrap();
+    let stake_mint: StakeMint = registry.account(&stake_mint_address(&mint));
+    assert_eq!(stake_mint.decimals, 6);
+    assert_eq!(stake_mint.reputation_weight_bps, 5_000);
+    assert!(registry.send(registry.add_stake_mint_ix(&mint, 5_000)).is_err());
+}
+
+#[test]
+fn updates_and_disables_stake_mints() {
+    let mut registry = Registry::new();
+    let mint = registry.mint;
+
+    let update = registry.update_stake_mint_ix(&mint, StakeMintUpdate {
+        reputation_weight_bps: Some(0),
+        ..Default::default()
+    });
+    expect_error(registry.send(update), ErrorCode::InvalidConfig);
+
+    let update = registry.update_stake_mint_ix(&mint, StakeMintUpdate {
+        reputation_weight_bps: Some(20_000),
+        enabled: Some(false),
+    });
+    registry.send(update).unwrap();
+    let stake_mint: StakeMint = registry.account(&stake_mint_address(&mint));
+    assert_eq!(stake_mint.reputation_weight_bps, 20_000);
+    assert!(!stake_mint.enabled);
+
+    let agent = registry.new_agent();
+    expect_error(registry.send(registry.register_agent_ix(&agent, STAKE)), ErrorCode::StakeMintDisabled);
+
+    let update = registry.update_stake_mint_ix(&mint, StakeMintUpdate {
+        enabled: Some(true),
+        ..Default::default()
+    });
+    registry.send(update).unwrap();
+    registry.send(registry.register_agent_ix(&agent, MIN_STAKE / 2)).unwrap();
+    assert_eq!(registry.agent(&agent).reputation_score, 5_000);
+}
+
+#[test]
+fn weighs_stake_by_mint() {
+    let mut registry = Registry::new();
+    let mint = registry.create_mint(DECIMALS);
+    registry.send(registry.add_stake_mint_ix(&mint, 2_000)).unwrap();
+
+    let agent = registry.new_agent_with_mint(&mint);
+    expect_error(registry.send(registry.register_agent_ix(&agent, 2 * MIN_STAKE)), ErrorCode::InvalidStakeAmount);
+
+    registry.send(registry.register_agent_ix(&agent, STAKE)).unwrap();
+    let state = registry.agent(&agent);
+    assert_eq!(state.stake_mint, mint);
+    assert_eq!(state.staked_amount, STAKE);
+    assert_eq!(state.reputation_score, 5_000);
+    assert_eq!(registry.token_balance(&escrow_address(&mint)), STAKE);
+}
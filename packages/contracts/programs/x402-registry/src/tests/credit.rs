@@ -0,0 +1,291 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_spl::token;
+
+use super::harness::{expect_error, instruction};
+use super::registry::*;
+use crate::state::*;
+use crate::{accounts, instruction as ix, Agent, AgentStatus, ErrorCode, Service};
+
+const SPENT: u64 = 10 * MIN_STAKE;
+const LIMIT: u64 = SPENT / 10;
+const VAULT_FUNDS: u64 = 100 * MIN_STAKE;
+const DRAW: u64 = LIMIT / 2;
+const OWED: u64 = DRAW + DRAW * CREDIT_FEE_BPS as u64 / 10_000;
+
+struct Borrower {
+    agent: TestAgent,
+    owner: TestAgent,
+    service: Pubkey,
+}
+
+impl Registry {
+    fn initialize_credit_vault_ix(&self, mint: &Pubkey, authority: &Pubkey) -> Instruction {
+        instruction(
+            accounts::InitializeCreditVault {
+                config: config_address(),
+                credit_vault: credit_vault_address(),
+                stake_token_mint: *mint,
+                payer: *authority,
+                authority: *authority,
+                system_program: system_program::ID,
+                token_program: token::ID,
+                rent: sysvar::rent::ID,
+            },
+            ix::InitializeCreditVault {},
+        )
+    }
+
+    fn open_credit_line_ix(&self, agent: &TestAgent) -> Instruction {
+        instruction(
+            accounts::OpenCreditLine {
+                credit_line: credit_line_address(&agent.wallet),
+                agent: agent.address,
+                signer: agent.wallet,
+                system_program: system_program::ID,
+            },
+            ix::OpenCreditLine {},
+        )
+    }
+
+    fn draw_credit_ix(&self, agent: &TestAgent, service: &Pubkey, service_tokens: &Pubkey, amount: u64) -> Instruction {
+        instruction(
+            accounts::DrawCredit {
+                config: config_address(),
+                agent: agent.address,
+                credit_line: credit_line_address(&agent.wallet),
+                service: *service,
+                mint: self.mint,
+                credit_vault: credit_vault_address(),
+                service_token_account: *service_tokens,
+                signer: agent.wallet,
+                token_program: token::ID,
+            },
+            ix::DrawCredit { amount },
+        )
+    }
+
+    fn repay_credit_ix(&self, agent: &TestAgent, amount: u64) -> Instruction {
+        instruction(
+            accounts::RepayCredit {
+                config: config_address(),
+                agent: agent.address,
+                credit_line: credit_line_address(&agent.wallet),
+                mint: self.mint,
+                credit_vault: credit_vault_address(),
+                payer_token_account: agent.tokens,
+                signer: agent.wallet,
+                token_program: token::ID,
+            },
+            ix::RepayCredit { amount },
+        )
+    }
+
+    fn settle_default_ix(&self, agent: &TestAgent) -> Instruction {
+        instruction(
+            accounts::SettleCreditDefault {
+                config: config_address(),
+                agent: agent.address,
+                credit_line: credit_line_address(&agent.wallet),
+                stake_mint: stake_mint_address(&self.mint),
+                mint: self.mint,
+                escrow_account: escrow_address(&self.mint),
+                credit_vault: credit_vault_address(),
+                token_program: token::ID,
+            },
+            ix::SettleCreditDefault {},
+        )
+    }
+
+    fn credit_line(&self, agent: &TestAgent) -> CreditLine {
+        self.account(&credit_line_address(&agent.wallet))
+    }
+
+    /// A funded credit vault and an agent whose spending history earns it a credit limit of `LIMIT`.
+    fn borrower(&mut self) -> Borrower {
+        let dao = self.dao;
+        let mint = self.mint;
+        self.send(self.initialize_credit_vault_ix(&mint, &dao)).unwrap();
+        self.set_token_balance(&credit_vault_address(), VAULT_FUNDS);
+
+        let owner = self.register_agent();
+        let service = self.register_service(&owner);
+        let agent = self.register_agent();
+        self.record_transaction(&agent, &service, SPENT, true);
+        self.send(self.open_credit_line_ix(&agent)).unwrap();
+
+        Borrower { agent, owner, service }
+    }
+
+    fn draw(&mut self, borrower: &Borrower, amount: u64) -> std::result::Result<(), ProgramError> {
+        self.send(self.draw_credit_ix(&borrower.agent, &borrower.service, &borrower.owner.tokens, amount))
+    }
+}
+
+#[test]
+fn only_the_dao_initializes_the_credit_vault() {
+    let mut registry = Registry::new();
+    let [dao, stranger] = [registry.dao, registry.new_wallet()];
+    let other_mint = registry.create_mint(DECIMALS);
+    let mint = registry.mint;
+
+    expect_error(registry.send(registry.initialize_credit_vault_ix(&mint, &stranger)), ErrorCode::UnauthorizedAccess);
+    expect_error(registry.send(registry.initialize_credit_vault_ix(&other_mint, &dao)), ErrorCode::InvalidMint);
+
+    registry.send(registry.initialize_credit_vault_ix(&mint, &dao)).unwrap();
+    assert_eq!(registry.token_balance(&credit_vault_address()), 0);
+}
+
+#[test]
+fn opens_a_credit_line() {
+    let mut registry = Registry::new();
+    let borrower = registry.borrower();
+
+    let credit_line = registry.credit_line(&borrower.agent);
+    assert_eq!(credit_line.agent, borrower.agent.wallet);
+    assert_eq!(credit_line.due_at, 0);
+    assert_eq!(credit_line.total_drawn, 0);
+    assert_eq!(registry.agent(&borrower.agent).credit_limit, LIMIT);
+
+    assert!(registry.send(registry.open_credit_line_ix(&borrower.agent)).is_err());
+}
+
+#[test]
+fn draws_credit_to_pay_a_service() {
+    let mut registry = Registry::new();
+    let borrower = registry.borrower();
+
+    expect_error(registry.draw(&borrower, 0), ErrorCode::InvalidAmount);
+    registry.draw(&borrower, DRAW).unwrap();
+
+    let agent = registry.agent(&borrower.agent);
+    assert_eq!(agent.credit_used, OWED);
+    assert_eq!(agent.last_active, registry.now);
+
+    let credit_line = registry.credit_line(&borrower.agent);
+    assert_eq!(credit_line.total_drawn, DRAW);
+    assert_eq!(credit_line.total_fees, OWED - DRAW);
+    assert_eq!(credit_line.due_at, registry.now + CREDIT_REPAYMENT);
+    assert_eq!(registry.token_balance(&borrower.owner.tokens), WALLET_TOKENS - STAKE + DRAW);
+    assert_eq!(registry.token_balance(&credit_vault_address()), VAULT_FUNDS - DRAW);
+
+    registry.advance(1);
+    registry.draw(&borrower, 1).unwrap();
+    assert_eq!(registry.credit_line(&borrower.agent).due_at, registry.now - 1 + CREDIT_REPAYMENT);
+}
+
+#[test]
+fn fees_count_against_the_credit_limit() {
+    let mut registry = Registry::new();
+    let borrower = registry.borrower();
+
+    expect_error(registry.draw(&borrower, LIMIT), ErrorCode::CreditLimitExceeded);
+    registry.draw(&borrower, DRAW).unwrap();
+    expect_error(registry.draw(&borrower, DRAW), ErrorCode::CreditLimitExceeded);
+}
+
+#[test]
+fn draws_only_for_active_services_of_other_agents() {
+    let mut registry = Registry::new();
+    let borrower = registry.borrower();
+
+    let own_service = registry.register_service(&borrower.agent);
+    let own_draw = registry.draw_credit_ix(&borrower.agent, &own_service, &borrower.agent.tokens, DRAW);
+    expect_error(registry.send(own_draw), ErrorCode::SelfPaymentNotAllowed);
+
+    registry.update::<Service>(&borrower.service, |service| service.accepted_tokens = vec![Pubkey::new_unique()]);
+    expect_error(registry.draw(&borrower, DRAW), ErrorCode::InvalidMint);
+
+    registry.send(registry.update_service_ix(&borrower.service, &borrower.owner.wallet, ix::PauseService {})).unwrap();
+    expect_error(registry.draw(&borrower, DRAW), ErrorCode::ServiceNotActive);
+}
+
+#[test]
+fn outstanding_credit_blocks_unstaking() {
+    let mut registry = Registry::new();
+    let borrower = registry.borrower();
+    registry.draw(&borrower, DRAW).unwrap();
+    registry.advance(UNSTAKE_LOCK);
+
+    expect_error(registry.send(registry.unstake_ix(&borrower.agent)), ErrorCode::CreditOutstanding);
+
+    registry.send(registry.repay_credit_ix(&borrower.agent, OWED)).unwrap();
+    registry.send(registry.unstake_ix(&borrower.agent)).unwrap();
+}
+
+#[test]
+fn repays_at_most_what_is_owed() {
+    let mut registry = Registry::new();
+    let borrower = registry.borrower();
+    let tokens = registry.token_balance(&borrower.agent.tokens);
+
+    expect_error(registry.send(registry.repay_credit_ix(&borrower.agent, 1)), ErrorCode::NoCreditOutstanding);
+    registry.draw(&borrower, DRAW).unwrap();
+    expect_error(registry.send(registry.repay_credit_ix(&borrower.agent, 0)), ErrorCode::InvalidAmount);
+
+    registry.send(registry.repay_credit_ix(&borrower.agent, 1)).unwrap();
+    assert_eq!(registry.agent(&borrower.agent).credit_used, OWED - 1);
+
+    registry.send(registry.repay_credit_ix(&borrower.agent, u64::MAX)).unwrap();
+    assert_eq!(registry.agent(&borrower.agent).credit_used, 0);
+    assert_eq!(registry.token_balance(&borrower.agent.tokens), tokens - OWED);
+    assert_eq!(registry.token_balance(&credit_vault_address()), VAULT_FUNDS - DRAW + OWED);
+
+    let credit_line = registry.credit_line(&borrower.agent);
+    assert_eq!(credit_line.total_repaid, OWED);
+    assert_eq!(credit_line.due_at, 0);
+}
+
+#[test]
+fn seizes_stake_when_credit_defaults() {
+    let mut registry = Registry::new();
+    let borrower = registry.borrower();
+    registry.draw(&borrower, DRAW).unwrap();
+    let reputation = registry.agent(&borrower.agent).reputation_score;
+
+    expect_error(registry.send(registry.settle_default_ix(&borrower.agent)), ErrorCode::CreditNotDue);
+    registry.advance(CREDIT_REPAYMENT);
+    expect_error(registry.send(registry.settle_default_ix(&borrower.agent)), ErrorCode::CreditNotDue);
+
+    registry.advance(1);
+    registry.send(registry.settle_default_ix(&borrower.agent)).unwrap();
+
+    let agent = registry.agent(&borrower.agent);
+    assert_eq!(agent.staked_amount, STAKE - OWED);
+    assert_eq!(agent.credit_used, 0);
+    assert_eq!(agent.credit_limit, 0);
+    assert_eq!(agent.reputation_score, reputation - SLASH_PENALTY);
+    assert!(agent.status == AgentStatus::Suspended);
+
+    let credit_line = registry.credit_line(&borrower.agent);
+    assert_eq!(credit_line.total_seized, OWED);
+    assert_eq!(credit_line.due_at, 0);
+    assert_eq!(registry.token_balance(&credit_vault_address()), VAULT_FUNDS - DRAW + OWED);
+    assert_eq!(registry.token_balance(&escrow_address(&registry.mint)), 2 * STAKE - OWED);
+
+    expect_error(registry.send(registry.settle_default_ix(&borrower.agent)), ErrorCode::NoCreditOutstanding);
+}
+
+#[test]
+fn writes_off_defaults_the_stake_cannot_cover() {
+    let mut registry = Registry::new();
+    let borrower = registry.borrower();
+    registry.draw(&borrower, DRAW).unwrap();
+    registry.update::<Agent>(&borrower.agent.address, |agent| agent.staked_amount = OWED / 2);
+    let reputation = registry.agent(&borrower.agent).reputation_score;
+
+    registry.advance(CREDIT_REPAYMENT + 1);
+    registry.send(registry.settle_default_ix(&borrower.agent)).unwrap();
+
+    let agent = registry.agent(&borrower.agent);
+    assert_eq!(agent.staked_amount, 0);
+    assert_eq!(agent.credit_used, 0);
+    assert_eq!(agent.reputation_score, reputation - SLASH_PENALTY);
+    let credit_line = registry.credit_line(&borrower.agent);
+    assert_eq!(credit_line.total_seized, OWED / 2);
+    assert_eq!(credit_line.due_at, 0);
+
+    expect_error(registry.send(registry.settle_default_ix(&borrower.agent)), ErrorCode::NoCreditOutstanding);
+    assert_eq!(registry.agent(&borrower.agent).reputation_score, reputation - SLASH_PENALTY);
+}
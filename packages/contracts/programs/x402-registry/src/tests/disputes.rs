@@ -0,0 +1,324 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use anchor_spl::token;
+
+use super::harness::{expect_error, instruction, pda};
+use super::registry::*;
+use crate::state::*;
+use crate::{accounts, instruction as ix, ErrorCode};
+
+const SIGNATURE: [u8; 64] = [7; 64];
+const REFUND: u64 = MIN_STAKE;
+const SLASH: u64 = 2 * MIN_STAKE;
+
+fn dispute_address(service: &Pubkey, signature: &[u8; 64]) -> Pubkey {
+    pda(&[b"dispute", service.as_ref(), &signature[..32], &signature[32..]])
+}
+
+struct Case {
+    registry: Registry,
+    payer: TestAgent,
+    provider: TestAgent,
+    service: Pubkey,
+    dispute: Pubkey,
+}
+
+impl Registry {
+    fn open_dispute_ix(&self, payer: &TestAgent, service: &Pubkey, evidence_uri: &str) -> Instruction {
+        instruction(
+            accounts::OpenDispute {
+                config: config_address(),
+                dispute: dispute_address(service, &SIGNATURE),
+                service: *service,
+                payer_agent: payer.address,
+                payer: payer.wallet,
+                system_program: system_program::ID,
+            },
+            ix::OpenDispute {
+                transaction_signature: SIGNATURE,
+                evidence_uri: evidence_uri.to_string(),
+            },
+        )
+    }
+
+    fn respond_ix(&self, dispute: &Pubkey, provider: &Pubkey) -> Instruction {
+        instruction(
+            accounts::RespondToDispute {
+                dispute: *dispute,
+                provider: *provider,
+                system_program: system_program::ID,
+            },
+            ix::RespondToDispute {
+                response_uri: "ipfs://response".to_string(),
+            },
+        )
+    }
+
+    fn resolve_ix(&self, case: &Case, ruling: DisputeRuling) -> Instruction {
+        instruction(
+            accounts::ResolveDispute {
+                config: config_address(),
+                dispute: case.dispute,
+                payer_agent: case.payer.address,
+                provider_agent: case.provider.address,
+                payer: case.payer.wallet,
+                provider: case.provider.wallet,
+                stake_mint: stake_mint_address(&case.provider.mint),
+                mint: case.provider.mint,
+                escrow_account: escrow_address(&case.provider.mint),
+                payer_token_account: case.payer.tokens,
+                dao_treasury: self.treasury_tokens,
+                arbiter: self.arbiter,
+                token_program: token::ID,
+            },
+            ix::ResolveDispute { ruling },
+        )
+    }
+
+    fn resolve_expired_ix(&self, case: &Case) -> Instruction {
+        instruction(
+            accounts::ResolveExpiredDispute {
+                config: config_address(),
+                dispute: case.dispute,
+                payer_agent: case.payer.address,
+                provider_agent: case.provider.address,
+                payer: case.payer.wallet,
+            },
+            ix::ResolveExpiredDispute {},
+        )
+    }
+
+    fn dispute(&self, dispute: &Pubkey) -> Dispute {
+        self.account(dispute)
+    }
+}
+
+impl Case {
+    fn new() -> Self {
+        let mut registry = Registry::new();
+        let provider = registry.register_agent();
+        let service = registry.register_service(&provider);
+        let payer = registry.register_agent();
+        Self {
+            registry,
+            payer,
+            provider,
+            service,
+            dispute: dispute_address(&service, &SIGNATURE),
+        }
+    }
+
+    fn opened() -> Self {
+        let mut case = Self::new();
+        let open = case.registry.open_dispute_ix(&case.payer, &case.service, "ipfs://evidence");
+        case.registry.send(open).unwrap();
+        case
+    }
+
+    fn responded() -> Self {
+        let mut case = Self::opened();
+        let respond = case.registry.respond_ix(&case.dispute, &case.provider.wallet);
+        case.registry.send(respond).unwrap();
+        case
+    }
+
+    fn resolve(&mut self, ruling: DisputeRuling) -> std::result::Result<(), ProgramError> {
+        let resolve = self.registry.resolve_ix(self, ruling);
+        self.registry.send(resolve)
+    }
+
+    fn resolve_expired(&mut self) -> std::result::Result<(), ProgramError> {
+        let resolve = self.registry.resolve_expired_ix(self);
+        self.registry.send(resolve)
+    }
+}
+
+fn payer_wins(refund_amount: u64, slash_amount: u64) -> DisputeRuling {
+    DisputeRuling {
+        payer_wins: true,
+        refund_amount,
+        slash_amount,
+    }
+}
+
+fn provider_wins() -> DisputeRuling {
+    DisputeRuling {
+        payer_wins: false,
+        refund_amount: 0,
+        slash_amount: 0,
+    }
+}
+
+#[test]
+fn opens_a_dispute_with_a_bond() {
+    let mut case = Case::new();
+    let registry = &mut case.registry;
+    let balance = registry.lamports(&case.payer.wallet);
+
+    registry.send(registry.open_dispute_ix(&case.payer, &case.service, "ipfs://evidence")).unwrap();
+
+    let dispute = registry.dispute(&case.dispute);
+    assert_eq!(dispute.payer, case.payer.wallet);
+    assert_eq!(dispute.provider, case.provider.wallet);
+    assert_eq!(dispute.service, case.service);
+    assert_eq!(dispute.transaction_signature, SIGNATURE);
+    assert_eq!(dispute.evidence_uri, "ipfs://evidence");
+    assert_eq!(dispute.bond_lamports, DISPUTE_BOND);
+    assert!(!dispute.provider_bonded);
+    assert_eq!(dispute.response_deadline, registry.now + DISPUTE_RESPONSE);
+    assert!(dispute.status == DisputeStatus::Open);
+
+    let rent = Rent::default().minimum_balance(Dispute::SPACE);
+    assert_eq!(registry.lamports(&case.dispute), rent + DISPUTE_BOND);
+    assert_eq!(registry.lamports(&case.payer.wallet), balance - rent - DISPUTE_BOND);
+
+    assert!(registry.send(registry.open_dispute_ix(&case.payer, &case.service, "ipfs://again")).is_err());
+}
+
+#[test]
+fn rejects_invalid_disputes() {
+    let mut case = Case::new();
+    let registry = &mut case.registry;
+
+    expect_error(
+        registry.send(registry.open_dispute_ix(&case.payer, &case.service, &"e".repeat(257))),
+        ErrorCode::StringTooLong,
+    );
+    expect_error(
+        registry.send(registry.open_dispute_ix(&case.provider, &case.service, "ipfs://evidence")),
+        ErrorCode::SelfDisputeNotAllowed,
+    );
+
+    registry.ban_agent(&case.payer);
+    expect_error(
+        registry.send(registry.open_dispute_ix(&case.payer, &case.service, "ipfs://evidence")),
+        ErrorCode::AgentSuspended,
+    );
+}
+
+#[test]
+fn provider_responds_within_the_window() {
+    let mut case = Case::opened();
+    let registry = &mut case.registry;
+
+    expect_error(registry.send(registry.respond_ix(&case.dispute, &case.payer.wallet)), ErrorCode::UnauthorizedAccess);
+
+    registry.send(registry.respond_ix(&case.dispute, &case.provider.wallet)).unwrap();
+    let dispute = registry.dispute(&case.dispute);
+    assert!(dispute.status == DisputeStatus::Responded);
+    assert!(dispute.provider_bonded);
+    assert_eq!(dispute.response_uri, "ipfs://response");
+    assert_eq!(
+        registry.lamports(&case.dispute),
+        Rent::default().minimum_balance(Dispute::SPACE) + 2 * DISPUTE_BOND
+    );
+
+    expect_error(registry.send(registry.respond_ix(&case.dispute, &case.provider.wallet)), ErrorCode::DisputeNotOpen);
+}
+
+#[test]
+fn responses_close_with_the_window() {
+    let mut case = Case::opened();
+    let registry = &mut case.registry;
+
+    registry.advance(DISPUTE_RESPONSE + 1);
+    expect_error(
+        registry.send(registry.respond_ix(&case.dispute, &case.provider.wallet)),
+        ErrorCode::DisputeResponseWindowClosed,
+    );
+}
+
+#[test]
+fn refunds_and_slashes_when_the_payer_wins() {
+    let mut case = Case::responded();
+    let payer_reputation = case.registry.agent(&case.payer).reputation_score;
+    let balance = case.registry.lamports(&case.payer.wallet);
+
+    case.resolve(payer_wins(REFUND, SLASH)).unwrap();
+
+    let registry = &case.registry;
+    let dispute = registry.dispute(&case.dispute);
+    assert!(dispute.status == DisputeStatus::ResolvedForPayer);
+    assert_eq!(dispute.refund_amount, REFUND);
+    assert_eq!(dispute.slash_amount, SLASH);
+    assert_eq!(dispute.resolved_at, registry.now);
+    assert_eq!(registry.lamports(&case.payer.wallet), balance + 2 * DISPUTE_BOND);
+
+    assert_eq!(registry.token_balance(&case.payer.tokens), WALLET_TOKENS - STAKE + REFUND);
+    assert_eq!(registry.token_balance(&registry.treasury_tokens), SLASH);
+
+    let provider = registry.agent(&case.provider);
+    assert_eq!(provider.staked_amount, STAKE - REFUND - SLASH);
+    assert_eq!(provider.slashed_amount, SLASH);
+    assert_eq!(provider.disputes_lost, 1);
+    assert_eq!(provider.last_slash_evidence, "ipfs://evidence");
+    assert!(provider.reputation_score < 7_000 - SLASH_PENALTY);
+
+    let payer = registry.agent(&case.payer);
+    assert_eq!(payer.disputes_won, 1);
+    assert!(payer.reputation_score >= payer_reputation);
+}
+
+#[test]
+fn returns_both_bonds_when_the_provider_wins() {
+    let mut case = Case::responded();
+    let balance = case.registry.lamports(&case.provider.wallet);
+
+    expect_error(case.resolve(DisputeRuling { payer_wins: false, refund_amount: 1, slash_amount: 0 }), ErrorCode::InvalidRuling);
+    expect_error(case.resolve(DisputeRuling { payer_wins: false, refund_amount: 0, slash_amount: 1 }), ErrorCode::InvalidRuling);
+    case.resolve(provider_wins()).unwrap();
+
+    let registry = &case.registry;
+    assert!(registry.dispute(&case.dispute).status == DisputeStatus::ResolvedForProvider);
+    assert_eq!(registry.lamports(&case.provider.wallet), balance + 2 * DISPUTE_BOND);
+    assert_eq!(registry.agent(&case.provider).disputes_won, 1);
+    assert_eq!(registry.agent(&case.provider).staked_amount, STAKE);
+    assert_eq!(registry.agent(&case.payer).disputes_lost, 1);
+
+    expect_error(case.resolve(provider_wins()), ErrorCode::DisputeNotOpen);
+}
+
+#[test]
+fn only_the_arbiter_resolves_within_the_stake() {
+    let mut case = Case::opened();
+    let stranger = case.registry.new_wallet();
+
+    let mut resolve = case.registry.resolve_ix(&case, provider_wins());
+    resolve.accounts[11].pubkey = stranger;
+    expect_error(case.registry.send(resolve), ErrorCode::UnauthorizedAccess);
+
+    expect_error(case.resolve(payer_wins(STAKE, 1)), ErrorCode::InsufficientStake);
+    case.resolve(payer_wins(STAKE, 0)).unwrap();
+    assert_eq!(case.registry.agent(&case.provider).staked_amount, 0);
+}
+
+#[test]
+fn payer_wins_unanswered_disputes_after_the_deadline() {
+    let mut case = Case::opened();
+    let balance = case.registry.lamports(&case.payer.wallet);
+
+    case.registry.advance(DISPUTE_RESPONSE);
+    expect_error(case.resolve_expired(), ErrorCode::DisputeResponseWindowOpen);
+
+    case.registry.advance(1);
+    case.resolve_expired().unwrap();
+
+    let registry = &case.registry;
+    let dispute = registry.dispute(&case.dispute);
+    assert!(dispute.status == DisputeStatus::ResolvedForPayer);
+    assert_eq!(dispute.refund_amount, 0);
+    assert_eq!(registry.lamports(&case.payer.wallet), balance + DISPUTE_BOND);
+    assert_eq!(registry.agent(&case.payer).disputes_won, 1);
+    assert_eq!(registry.agent(&case.provider).disputes_lost, 1);
+
+    expect_error(case.resolve_expired(), ErrorCode::DisputeNotOpen);
+}
+
+#[test]
+fn answered_disputes_wait_for_the_arbiter() {
+    let mut case = Case::responded();
+    case.registry.advance(DISPUTE_RESPONSE + 1);
+
+    expect_error(case.resolve_expired(), ErrorCode::DisputeNotOpen);
+}
@@ -0,0 +1,81 @@
+use crate::ErrorCode;
+
+/// Clients match on these numbers, so variants may only ever be appended.
+#[test]
+fn error_codes_are_stable() {
+    let codes = [
+        (ErrorCode::InvalidStakeAmount, 6000),
+        (ErrorCode::InvalidPrice, 6001),
+        (ErrorCode::InvalidRating, 6002),
+        (ErrorCode::StringTooLong, 6003),
+        (ErrorCode::InvalidTokenList, 6004),
+        (ErrorCode::InsufficientStake, 6005),
+        (ErrorCode::UnauthorizedAccess, 6006),
+        (ErrorCode::NoTransactionHistory, 6007),
+        (ErrorCode::MathOverflow, 6008),
+        (ErrorCode::RateLimitExceeded, 6009),
+        (ErrorCode::AgentSuspended, 6010),
+        (ErrorCode::UnstakeLockPeriod, 6011),
+        (ErrorCode::NoStakeToWithdraw, 6012),
+        (ErrorCode::InvalidMint, 6013),
+        (ErrorCode::InvalidAgentStatus, 6014),
+        (ErrorCode::InvalidServiceStatus, 6015),
+        (ErrorCode::ServiceNotActive, 6016),
+        (ErrorCode::ServiceSuspended, 6017),
+        (ErrorCode::AlreadyStaked, 6018),
+        (ErrorCode::CannotCloseWithStake, 6019),
+        (ErrorCode::SlashRateLimitExceeded, 6020),
+        (ErrorCode::RestakeCooldownNotElapsed, 6021),
+        (ErrorCode::InvalidPDA, 6022),
+        (ErrorCode::SelfRatingNotAllowed, 6023),
+        (ErrorCode::TimestampOverflow, 6024),
+        (ErrorCode::InvalidState, 6025),
+        (ErrorCode::SlashAmountTooLow, 6026),
+        (ErrorCode::AgentNotSuspended, 6027),
+        (ErrorCode::CannotCloseWithServices, 6028),
+        (ErrorCode::RatingUpdateCooldown, 6029),
+        (ErrorCode::FraudAmountTooHigh, 6030),
+        (ErrorCode::InvalidConfig, 6031),
+        (ErrorCode::SelfDisputeNotAllowed, 6032),
+        (ErrorCode::DisputeNotOpen, 6033),
+        (ErrorCode::DisputeResponseWindowClosed, 6034),
+        (ErrorCode::DisputeResponseWindowOpen, 6035),
+        (ErrorCode::InvalidRuling, 6036),
+        (ErrorCode::InvalidAmount, 6037),
+        (ErrorCode::SelfPaymentNotAllowed, 6038),
+        (ErrorCode::CreditLimitExceeded, 6039),
+        (ErrorCode::NoCreditOutstanding, 6040),
+        (ErrorCode::CreditNotDue, 6041),
+        (ErrorCode::CreditOutstanding, 6042),
+        (ErrorCode::InvalidDeadline, 6043),
+        (ErrorCode::PaymentEscrowNotFunded, 6044),
+        (ErrorCode::PaymentEscrowNotExpired, 6045),
+        (ErrorCode::ChannelNotOpen, 6046),
+        (ErrorCode::StaleVoucher, 6047),
+        (ErrorCode::VoucherExceedsDeposit, 6048),
+        (ErrorCode::ChallengePeriodActive, 6049),
+        (ErrorCode::MissingSignatureVerification, 6050),
+        (ErrorCode::InvalidSignatureVerification, 6051),
+        (ErrorCode::StakeMintDisabled, 6052),
+        (ErrorCode::InvalidHealthReport, 6053),
+        (ErrorCode::HealthReportRateLimited, 6054),
+        (ErrorCode::RatingNotExpired, 6055),
+        (ErrorCode::ServiceAlreadyListed, 6056),
+        (ErrorCode::ServiceNotListed, 6057),
+        (ErrorCode::NotCouncilMember, 6058),
+        (ErrorCode::AlreadyApproved, 6059),
+        (ErrorCode::InvalidProposalStatus, 6060),
+        (ErrorCode::ProposalNotApproved, 6061),
+        (ErrorCode::TimelockActive, 6062),
+        (ErrorCode::ProposalActionMismatch, 6063),
+        (ErrorCode::EmergencyActionNotAllowed, 6064),
+        (ErrorCode::InvalidAccountLayout, 6065),
+        (ErrorCode::StakeAlreadyMigrated, 6066),
+        (ErrorCode::RatingAlreadyMigrated, 6067),
+        (ErrorCode::PaymentEscrowNotReleased, 6068),
+    ];
+
+    for (error, code) in codes {
+        assert_eq!(u32::from(error), code, "{:?}", error);
+    }
+}
@@ -0,0 +1,372 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_spl::token;
+
+use super::harness::{expect_error, instruction};
+use super::registry::*;
+use crate::state::*;
+use crate::{accounts, instruction as ix, AgentStatus, ErrorCode, ServiceStatus};
+
+const FRAUD: u64 = MIN_STAKE;
+
+impl Registry {
+    fn cancel_proposal_ix(&self, proposal: &Pubkey, proposer: &Pubkey) -> Instruction {
+        instruction(
+            accounts::CancelProposal {
+                proposal: *proposal,
+                proposer: *proposer,
+            },
+            ix::CancelProposal {},
+        )
+    }
+
+    fn slash_ix(&self, proposal: &Pubkey, agent: &TestAgent) -> Instruction {
+        instruction(
+            accounts::SlashAgent {
+                config: config_address(),
+                agent: agent.address,
+                stake_mint: stake_mint_address(&agent.mint),
+                mint: agent.mint,
+                escrow_account: escrow_address(&agent.mint),
+                dao_treasury: self.treasury_tokens,
+                proposal: *proposal,
+                executor: self.dao,
+                token_program: token::ID,
+            },
+            ix::SlashForFraud {},
+        )
+    }
+
+    fn proposal(&self, proposal: &Pubkey) -> Proposal {
+        self.account(proposal)
+    }
+}
+
+fn slash_action(agent: &TestAgent, fraud_amount: u64) -> GovernanceAction {
+    GovernanceAction::SlashForFraud {
+        agent: agent.address,
+        fraud_amount,
+        evidence_uri: "ipfs://evidence".to_string(),
+    }
+}
+
+#[test]
+fn executes_proposals_after_threshold_and_timelock() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    let [proposer, approver] = [registry.council[0], registry.council[1]];
+    let action = GovernanceAction::SuspendAgent { agent: agent.address };
+
+    let proposal = registry.create_proposal(action.clone(), false);
+    let state = registry.proposal(&proposal);
+    assert_eq!(state.id, 0);
+    assert_eq!(state.proposer, proposer);
+    assert!(state.action == action);
+    assert_eq!(state.approvals, vec![proposer]);
+    assert!(state.status == ProposalStatus::Pending);
+    assert_eq!(registry.config().proposal_count, 1);
+
+    expect_error(
+        registry.send(registry.governed_agent_ix(&proposal, &agent, ix::SuspendAgent {})),
+        ErrorCode::ProposalNotApproved,
+    );
+
+    registry.send(registry.approve_proposal_ix(&proposal, &approver)).unwrap();
+    let state = registry.proposal(&proposal);
+    assert!(state.status == ProposalStatus::Approved);
+    assert_eq!(state.executable_at, registry.now + TIMELOCK);
+
+    registry.advance(TIMELOCK - 1);
+    expect_error(
+        registry.send(registry.governed_agent_ix(&proposal, &agent, ix::SuspendAgent {})),
+        ErrorCode::TimelockActive,
+    );
+
+    registry.advance(1);
+    registry.send(registry.governed_agent_ix(&proposal, &agent, ix::SuspendAgent {})).unwrap();
+    let state = registry.proposal(&proposal);
+    assert!(state.status == ProposalStatus::Executed);
+    assert_eq!(state.executed_at, registry.now);
+    assert!(registry.agent(&agent).status == AgentStatus::Suspended);
+}
+
+#[test]
+fn only_council_members_propose_and_approve() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    let stranger = registry.new_wallet();
+    let action = GovernanceAction::BanAgent { agent: agent.address };
+
+    expect_error(
+        registry.send(registry.create_proposal_ix(&stranger, action.clone(), false)),
+        ErrorCode::NotCouncilMember,
+    );
+
+    let proposal = registry.create_proposal(action, false);
+    let proposer = registry.council[0];
+    expect_error(registry.send(registry.approve_proposal_ix(&proposal, &stranger)), ErrorCode::NotCouncilMember);
+    expect_error(registry.send(registry.approve_proposal_ix(&proposal, &proposer)), ErrorCode::AlreadyApproved);
+
+    let [approver, late] = [registry.council[1], registry.council[2]];
+    registry.send(registry.approve_proposal_ix(&proposal, &approver)).unwrap();
+    expect_error(registry.send(registry.approve_proposal_ix(&proposal, &late)), ErrorCode::InvalidProposalStatus);
+}
+
+#[test]
+fn rejects_proposals_once_the_count_overflows() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    registry.update::<RegistryConfig>(&config_address(), |config| config.proposal_count = u64::MAX);
+
+    let proposer = registry.council[0];
+    let action = GovernanceAction::BanAgent { agent: agent.address };
+    expect_error(registry.send(registry.create_proposal_ix(&proposer, action, false)), ErrorCode::MathOverflow);
+}
+
+#[test]
+fn emergency_proposals_skip_the_timelock() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let service = registry.register_service(&owner);
+    let proposer = registry.council[0];
+
+    expect_error(
+        registry.send(registry.create_proposal_ix(&proposer, GovernanceAction::BanAgent { agent: owner.address }, true)),
+        ErrorCode::EmergencyActionNotAllowed,
+    );
+    expect_error(
+        registry.send(registry.create_proposal_ix(&proposer, slash_action(&owner, FRAUD), true)),
+        ErrorCode::EmergencyActionNotAllowed,
+    );
+
+    let proposal = registry.create_proposal(GovernanceAction::SuspendService { service }, true);
+    let approver = registry.council[1];
+    registry.send(registry.approve_proposal_ix(&proposal, &approver)).unwrap();
+    assert_eq!(registry.proposal(&proposal).executable_at, registry.now);
+
+    registry.send(registry.governed_service_ix(&proposal, &service, ix::SuspendService {})).unwrap();
+    assert!(registry.service(&service).status == ServiceStatus::Suspended);
+    expect_error(
+        registry.send(registry.governed_service_ix(&proposal, &service, ix::SuspendService {})),
+        ErrorCode::ProposalNotApproved,
+    );
+}
+
+#[test]
+fn executes_only_the_approved_action() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    let other = registry.register_agent();
+
+    let proposal = registry.approved_proposal(GovernanceAction::SuspendAgent { agent: agent.address });
+    expect_error(
+        registry.send(registry.governed_agent_ix(&proposal, &other, ix::SuspendAgent {})),
+        ErrorCode::ProposalActionMismatch,
+    );
+    expect_error(
+        registry.send(registry.governed_agent_ix(&proposal, &agent, ix::BanAgent {})),
+        ErrorCode::ProposalActionMismatch,
+    );
+    expect_error(registry.send(registry.slash_ix(&proposal, &agent)), ErrorCode::ProposalActionMismatch);
+
+    registry.send(registry.governed_agent_ix(&proposal, &agent, ix::SuspendAgent {})).unwrap();
+}
+
+#[test]
+fn recounts_approvals_against_the_current_council() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    let proposal = registry.approved_proposal(GovernanceAction::SuspendAgent { agent: agent.address });
+
+    let update = registry.approved_proposal(GovernanceAction::UpdateCouncil {
+        council: vec![registry.dao, registry.tap, registry.oracle],
+        threshold: 2,
+        timelock_seconds: TIMELOCK,
+    });
+    registry.send(registry.governed_config_ix(&update, ix::UpdateCouncil {})).unwrap();
+
+    expect_error(
+        registry.send(registry.governed_agent_ix(&proposal, &agent, ix::SuspendAgent {})),
+        ErrorCode::ProposalNotApproved,
+    );
+}
+
+#[test]
+fn drops_approvals_from_removed_members() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    let proposal = registry.create_proposal(GovernanceAction::SuspendAgent { agent: agent.address }, false);
+
+    let council: Vec<Pubkey> = (0..MAX_COUNCIL_MEMBERS).map(|_| registry.new_wallet()).collect();
+    let update = registry.approved_proposal(GovernanceAction::UpdateCouncil {
+        council: council.clone(),
+        threshold: MAX_COUNCIL_MEMBERS as u8,
+        timelock_seconds: 0,
+    });
+    registry.send(registry.governed_config_ix(&update, ix::UpdateCouncil {})).unwrap();
+
+    for member in &council {
+        registry.send(registry.approve_proposal_ix(&proposal, member)).unwrap();
+    }
+    let state = registry.proposal(&proposal);
+    assert_eq!(state.approvals, council);
+    assert!(state.status == ProposalStatus::Approved);
+
+    registry.send(registry.governed_agent_ix(&proposal, &agent, ix::SuspendAgent {})).unwrap();
+}
+
+#[test]
+fn updates_the_council_by_proposal() {
+    let mut registry = Registry::new();
+    let council = vec![registry.dao, registry.arbiter];
+
+    let invalid = registry.approved_proposal(GovernanceAction::UpdateCouncil {
+        council: council.clone(),
+        threshold: 3,
+        timelock_seconds: 0,
+    });
+    expect_error(registry.send(registry.governed_config_ix(&invalid, ix::UpdateCouncil {})), ErrorCode::InvalidConfig);
+
+    let proposal = registry.create_proposal(
+        GovernanceAction::UpdateCouncil {
+            council: council.clone(),
+            threshold: 1,
+            timelock_seconds: 0,
+        },
+        false,
+    );
+    expect_error(
+        registry.send(registry.governed_config_ix(&proposal, ix::UpdateCouncil {})),
+        ErrorCode::ProposalNotApproved,
+    );
+    let approver = registry.council[1];
+    registry.send(registry.approve_proposal_ix(&proposal, &approver)).unwrap();
+    expect_error(
+        registry.send(registry.governed_config_ix(&proposal, ix::UpdateAuthorities {})),
+        ErrorCode::ProposalActionMismatch,
+    );
+    expect_error(
+        registry.send(registry.governed_config_ix(&proposal, ix::UpdateCouncil {})),
+        ErrorCode::TimelockActive,
+    );
+
+    registry.advance(TIMELOCK);
+    registry.send(registry.governed_config_ix(&proposal, ix::UpdateCouncil {})).unwrap();
+    let config = registry.config();
+    assert_eq!(config.council, council);
+    assert_eq!(config.council_threshold, 1);
+    assert_eq!(config.governance_timelock_seconds, 0);
+}
+
+#[test]
+fn updates_the_dao_and_arbiter_authorities_by_proposal() {
+    let mut registry = Registry::new();
+    let (dao_authority, arbiter_authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let proposal = registry.approved_proposal(GovernanceAction::UpdateAuthorities {
+        dao_authority,
+        arbiter_authority,
+    });
+    registry.send(registry.governed_config_ix(&proposal, ix::UpdateAuthorities {})).unwrap();
+
+    let config = registry.config();
+    assert_eq!(config.dao_authority, dao_authority);
+    assert_eq!(config.arbiter_authority, arbiter_authority);
+}
+
+#[test]
+fn cancels_pending_and_approved_proposals() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    let [proposer, approver] = [registry.council[0], registry.council[1]];
+
+    let proposal = registry.create_proposal(GovernanceAction::SuspendAgent { agent: agent.address }, false);
+    expect_error(registry.send(registry.cancel_proposal_ix(&proposal, &approver)), ErrorCode::UnauthorizedAccess);
+    registry.send(registry.cancel_proposal_ix(&proposal, &proposer)).unwrap();
+    assert!(registry.proposal(&proposal).status == ProposalStatus::Cancelled);
+
+    expect_error(registry.send(registry.approve_proposal_ix(&proposal, &approver)), ErrorCode::InvalidProposalStatus);
+    expect_error(registry.send(registry.cancel_proposal_ix(&proposal, &proposer)), ErrorCode::InvalidProposalStatus);
+
+    let proposal = registry.approved_proposal(GovernanceAction::SuspendAgent { agent: agent.address });
+    registry.send(registry.cancel_proposal_ix(&proposal, &proposer)).unwrap();
+    expect_error(
+        registry.send(registry.governed_agent_ix(&proposal, &agent, ix::SuspendAgent {})),
+        ErrorCode::ProposalNotApproved,
+    );
+}
+
+#[test]
+fn rejects_oversized_slash_evidence() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    let proposer = registry.council[0];
+    let action = GovernanceAction::SlashForFraud {
+        agent: agent.address,
+        fraud_amount: FRAUD,
+        evidence_uri: "e".repeat(257),
+    };
+
+    expect_error(registry.send(registry.create_proposal_ix(&proposer, action, false)), ErrorCode::StringTooLong);
+}
+
+#[test]
+fn slashes_suspended_agents_into_the_treasury() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+
+    let proposal = registry.approved_proposal(slash_action(&agent, FRAUD));
+    expect_error(registry.send(registry.slash_ix(&proposal, &agent)), ErrorCode::AgentNotSuspended);
+
+    registry.suspend_agent(&agent);
+    let reputation = registry.agent(&agent).reputation_score;
+    registry.send(registry.slash_ix(&proposal, &agent)).unwrap();
+
+    let state = registry.agent(&agent);
+    assert_eq!(state.staked_amount, STAKE - 2 * FRAUD);
+    assert_eq!(state.slashed_amount, 2 * FRAUD);
+    assert_eq!(state.disputes_lost, 1);
+    assert_eq!(state.last_slashed_time, registry.now);
+    assert_eq!(state.last_slash_evidence, "ipfs://evidence");
+    assert_eq!(state.reputation_score, reputation - SLASH_PENALTY);
+    assert_eq!(registry.token_balance(&registry.treasury_tokens), 2 * FRAUD);
+    assert_eq!(registry.token_balance(&escrow_address(&registry.mint)), STAKE - 2 * FRAUD);
+    assert!(registry.proposal(&proposal).status == ProposalStatus::Executed);
+}
+
+#[test]
+fn bounds_slash_amounts() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    registry.suspend_agent(&agent);
+
+    let too_high = registry.approved_proposal(slash_action(&agent, STAKE + 1));
+    expect_error(registry.send(registry.slash_ix(&too_high, &agent)), ErrorCode::FraudAmountTooHigh);
+
+    let too_low = registry.approved_proposal(slash_action(&agent, 999_999));
+    expect_error(registry.send(registry.slash_ix(&too_low, &agent)), ErrorCode::SlashAmountTooLow);
+
+    let whole_stake = registry.approved_proposal(slash_action(&agent, STAKE));
+    registry.send(registry.slash_ix(&whole_stake, &agent)).unwrap();
+    assert_eq!(registry.agent(&agent).staked_amount, 0);
+    assert_eq!(registry.token_balance(&registry.treasury_tokens), STAKE);
+}
+
+#[test]
+fn rate_limits_slashing() {
+    let mut registry = Registry::new();
+    let agent = registry.register_agent();
+    registry.suspend_agent(&agent);
+
+    let first = registry.approved_proposal(slash_action(&agent, FRAUD));
+    registry.send(registry.slash_ix(&first, &agent)).unwrap();
+    let slashed_at = registry.now;
+
+    let second = registry.approved_proposal(slash_action(&agent, FRAUD));
+    expect_error(registry.send(registry.slash_ix(&second, &agent)), ErrorCode::SlashRateLimitExceeded);
+
+    let remaining = slashed_at + SLASH_COOLDOWN - registry.now;
+    registry.advance(remaining);
+    registry.send(registry.slash_ix(&second, &agent)).unwrap();
+    assert_eq!(registry.agent(&agent).slashed_amount, 4 * FRAUD);
+}
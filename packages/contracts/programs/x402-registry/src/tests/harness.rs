@@ -0,0 +1,604 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::{
+    deserialize, ProgramResult, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER, SUCCESS,
+};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::solana_program::sysvar::{self, instructions as sysvar_instructions};
+use anchor_lang::solana_program::{bpf_loader, bpf_loader_upgradeable, ed25519_program, system_program};
+use anchor_lang::{AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas};
+use anchor_spl::token;
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signature, Signer};
+
+pub const START_TIME: i64 = 1_700_000_000;
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+pub const MINT_LEN: usize = 82;
+const PRECOMPILE_INVALID_PUBLIC_KEY: u32 = 0;
+/// `PrecompileError::InvalidSignature`, which the runtime reports as a custom error of the failed precompile.
+pub const PRECOMPILE_INVALID_SIGNATURE: u32 = 2;
+const PRECOMPILE_INVALID_DATA_OFFSETS: u32 = 3;
+const PRECOMPILE_INVALID_INSTRUCTION_DATA_SIZE: u32 = 4;
+
+thread_local! {
+    static NOW: Cell<i64> = const { Cell::new(START_TIME) };
+}
+
+#[derive(Clone)]
+pub struct AccountState {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+/// An in-process stand-in for the runtime. Instructions go through the program's real `entry` with accounts laid out
+/// exactly as the loader serializes them, so `realloc` and `close` behave as they do on chain. The clock and rent
+/// sysvars are stubbed, and CPIs into the System and SPL Token programs are emulated. Ed25519 precompile
+/// instructions are verified as the runtime does before the transaction runs. Wallets are real Ed25519 keypairs.
+pub struct Ledger {
+    accounts: HashMap<Pubkey, AccountState>,
+    keypairs: HashMap<Pubkey, Keypair>,
+    pub now: i64,
+}
+
+pub fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &crate::ID).0
+}
+
+pub fn expect_error(result: std::result::Result<(), ProgramError>, error: impl Into<u32>) {
+    assert_eq!(result, Err(ProgramError::Custom(error.into())));
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            set_syscall_stubs(Box::new(Stubs));
+        });
+
+        let mut ledger = Self {
+            accounts: HashMap::new(),
+            keypairs: HashMap::new(),
+            now: START_TIME,
+        };
+        ledger.add_program(system_program::ID, "NativeLoader1111111111111111111111111111111".parse().unwrap());
+        ledger.add_program(token::ID, bpf_loader::ID);
+
+        let rent = Rent::default();
+        let mut rent_data = rent.lamports_per_byte_year.to_le_bytes().to_vec();
+        rent_data.extend_from_slice(&rent.exemption_threshold.to_le_bytes());
+        rent_data.push(rent.burn_percent);
+        ledger.set_raw(sysvar::rent::ID, rent_data, sysvar::ID);
+
+        ledger
+    }
+
+    /// Deploys this program behind the upgradeable loader with `upgrade_authority`.
+    pub fn deploy(&mut self, upgrade_authority: Pubkey) -> Pubkey {
+        let program_data = Pubkey::find_program_address(&[crate::ID.as_ref()], &bpf_loader_upgradeable::ID).0;
+
+        let mut program = 2u32.to_le_bytes().to_vec();
+        program.extend_from_slice(program_data.as_ref());
+        self.set_raw(crate::ID, program, bpf_loader_upgradeable::ID);
+        self.accounts.get_mut(&crate::ID).unwrap().executable = true;
+
+        let mut header = 3u32.to_le_bytes().to_vec();
+        header.extend_from_slice(&0u64.to_le_bytes());
+        header.push(1);
+        header.extend_from_slice(upgrade_authority.as_ref());
+        self.set_raw(program_data, header, bpf_loader_upgradeable::ID);
+
+        program_data
+    }
+
+    pub fn advance(&mut self, seconds: i64) {
+        self.now += seconds;
+    }
+
+    pub fn new_wallet(&mut self) -> Pubkey {
+        let secret = SecretKey::from_bytes(Pubkey::new_unique().as_ref()).unwrap();
+        let public = PublicKey::from(&secret);
+        let wallet = Pubkey::new_from_array(public.to_bytes());
+        self.keypairs.insert(wallet, Keypair { secret, public });
+        self.accounts.insert(wallet, AccountState {
+            lamports: 1_000_000_000_000,
+            data: vec![],
+            owner: system_program::ID,
+            executable: false,
+        });
+        wallet
+    }
+
+    /// The precompile instruction a wallet would prepend to prove `signer` signed `message`.
+    pub fn ed25519_instruction(&self, signer: &Pubkey, message: &[u8]) -> Instruction {
+        const DATA_START: u16 = 16;
+        let public_key_offset = DATA_START;
+        let signature_offset = public_key_offset + 32;
+        let message_offset = signature_offset + 64;
+
+        let mut data = vec![1, 0];
+        for value in [
+            signature_offset,
+            u16::MAX,
+            public_key_offset,
+            u16::MAX,
+            message_offset,
+            message.len() as u16,
+            u16::MAX,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        let keypair = self.keypairs.get(signer).unwrap_or_else(|| panic!("{} is not a wallet", signer));
+        data.extend_from_slice(signer.as_ref());
+        data.extend_from_slice(&keypair.sign(message).to_bytes());
+        data.extend_from_slice(message);
+
+        Instruction {
+            program_id: ed25519_program::ID,
+            accounts: vec![],
+            data,
+        }
+    }
+
+    pub fn create_mint(&mut self, decimals: u8) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        let mut data = vec![0; MINT_LEN];
+        data[36..44].copy_from_slice(&u64::MAX.to_le_bytes());
+        data[44] = decimals;
+        data[45] = 1;
+        self.set_raw(mint, data, token::ID);
+        mint
+    }
+
+    pub fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+        let account = Pubkey::new_unique();
+        self.create_token_account_at(account, mint, owner, amount);
+        account
+    }
+
+    pub fn create_token_account_at(&mut self, account: Pubkey, mint: &Pubkey, owner: &Pubkey, amount: u64) {
+        let mut data = vec![0; TOKEN_ACCOUNT_LEN];
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data[108] = 1;
+        self.set_raw(account, data, token::ID);
+    }
+
+    pub fn token_balance(&self, account: &Pubkey) -> u64 {
+        let data = &self.accounts[account].data;
+        u64::from_le_bytes(data[64..72].try_into().unwrap())
+    }
+
+    pub fn set_token_balance(&mut self, account: &Pubkey, amount: u64) {
+        let state = self.accounts.get_mut(account).unwrap();
+        state.data[64..72].copy_from_slice(&amount.to_le_bytes());
+    }
+
+    pub fn set_raw(&mut self, key: Pubkey, data: Vec<u8>, owner: Pubkey) {
+        self.accounts.insert(key, AccountState {
+            lamports: Rent::default().minimum_balance(data.len()).max(1),
+            data,
+            owner,
+            executable: false,
+        });
+    }
+
+    /// Writes `value` straight into a program-owned account of `space` bytes, bypassing the program.
+    pub fn set_account<T: AccountSerialize>(&mut self, key: Pubkey, value: &T, space: usize) {
+        let mut data = Vec::with_capacity(space);
+        value.try_serialize(&mut data).unwrap();
+        assert!(data.len() <= space, "{} bytes do not fit in {}", data.len(), space);
+        data.resize(space, 0);
+        self.set_raw(key, data, crate::ID);
+    }
+
+    pub fn account<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let state = self.accounts.get(key).unwrap_or_else(|| panic!("account {} does not exist", key));
+        assert_eq!(state.owner, crate::ID, "account {} is not owned by the program", key);
+        T::try_deserialize(&mut state.data.as_slice()).unwrap()
+    }
+
+    /// Edits a program account in place, keeping its size.
+    pub fn update<T: AccountSerialize + AccountDeserialize>(&mut self, key: &Pubkey, edit: impl FnOnce(&mut T)) {
+        let mut value = self.account::<T>(key);
+        edit(&mut value);
+        let state = self.accounts.get_mut(key).unwrap();
+        let mut data = Vec::with_capacity(state.data.len());
+        value.try_serialize(&mut data).unwrap();
+        state.data[..data.len()].copy_from_slice(&data);
+    }
+
+    pub fn state(&self, key: &Pubkey) -> Option<&AccountState> {
+        self.accounts.get(key)
+    }
+
+    pub fn exists(&self, key: &Pubkey) -> bool {
+        self.accounts.contains_key(key)
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.accounts.get(key).map_or(0, |state| state.lamports)
+    }
+
+    pub fn send(&mut self, instruction: Instruction) -> std::result::Result<(), ProgramError> {
+        self.send_transaction(&[instruction])
+    }
+
+    /// Runs `instructions` atomically: any failure rolls back every account, and accounts left without lamports are
+    /// dropped afterwards as the runtime does.
+    pub fn send_transaction(&mut self, instructions: &[Instruction]) -> std::result::Result<(), ProgramError> {
+        instructions
+            .iter()
+            .filter(|instruction| instruction.program_id == ed25519_program::ID)
+            .try_for_each(|instruction| verify_ed25519(instruction, instructions))?;
+
+        let snapshot = self.accounts.clone();
+        let result = instructions.iter().enumerate().try_for_each(|(index, instruction)| {
+            if instruction.program_id == ed25519_program::ID {
+                return Ok(());
+            }
+            assert_eq!(instruction.program_id, crate::ID);
+            self.load_instructions_sysvar(instructions, index);
+            self.process_instruction(instruction)
+        });
+
+        if result.is_err() {
+            self.accounts = snapshot;
+        }
+        self.accounts.remove(&sysvar_instructions::ID);
+        self.accounts.retain(|_, state| state.lamports > 0);
+        result
+    }
+
+    fn add_program(&mut self, key: Pubkey, loader: Pubkey) {
+        self.accounts.insert(key, AccountState {
+            lamports: 1,
+            data: vec![],
+            owner: loader,
+            executable: true,
+        });
+    }
+
+    fn load_instructions_sysvar(&mut self, instructions: &[Instruction], index: usize) {
+        let borrowed = instructions
+            .iter()
+            .map(|instruction| sysvar_instructions::BorrowedInstruction {
+                program_id: &instruction.program_id,
+                accounts: instruction
+                    .accounts
+                    .iter()
+                    .map(|meta| sysvar_instructions::BorrowedAccountMeta {
+                        pubkey: &meta.pubkey,
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                    .collect(),
+                data: &instruction.data,
+            })
+            .collect::<Vec<_>>();
+        let mut data = sysvar_instructions::construct_instructions_data(&borrowed);
+        sysvar_instructions::store_current_index(&mut data, index as u16);
+        self.set_raw(sysvar_instructions::ID, data, sysvar::ID);
+    }
+
+    fn process_instruction(&mut self, instruction: &Instruction) -> std::result::Result<(), ProgramError> {
+        let mut keys: Vec<Pubkey> = vec![];
+        let mut input = vec![];
+        input.extend_from_slice(&(instruction.accounts.len() as u64).to_le_bytes());
+
+        for meta in &instruction.accounts {
+            if let Some(position) = keys.iter().position(|key| *key == meta.pubkey) {
+                input.push(position as u8);
+                input.extend_from_slice(&[0; 7]);
+                continue;
+            }
+            keys.push(meta.pubkey);
+
+            let (is_signer, is_writable) = instruction
+                .accounts
+                .iter()
+                .filter(|other| other.pubkey == meta.pubkey)
+                .fold((false, false), |(signer, writable), other| {
+                    (signer || other.is_signer, writable || other.is_writable)
+                });
+            let state = self.accounts.get(&meta.pubkey).cloned().unwrap_or(AccountState {
+                lamports: 0,
+                data: vec![],
+                owner: system_program::ID,
+                executable: false,
+            });
+
+            input.extend_from_slice(&[NON_DUP_MARKER, is_signer as u8, is_writable as u8, state.executable as u8]);
+            input.extend_from_slice(&[0; 4]);
+            input.extend_from_slice(meta.pubkey.as_ref());
+            input.extend_from_slice(state.owner.as_ref());
+            input.extend_from_slice(&state.lamports.to_le_bytes());
+            input.extend_from_slice(&(state.data.len() as u64).to_le_bytes());
+            input.extend_from_slice(&state.data);
+            input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            input.resize(input.len().next_multiple_of(8), 0);
+            input.extend_from_slice(&u64::MAX.to_le_bytes());
+        }
+
+        input.extend_from_slice(&(instruction.data.len() as u64).to_le_bytes());
+        input.extend_from_slice(&instruction.data);
+        input.extend_from_slice(instruction.program_id.as_ref());
+
+        let mut buffer = vec![0u64; input.len().div_ceil(8)];
+        // SAFETY: the buffer is 8-byte aligned and large enough to hold `input`.
+        let aligned = unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, input.len()) };
+        aligned.copy_from_slice(&input);
+
+        NOW.with(|now| now.set(self.now));
+        // SAFETY: `input` follows the loader's serialization layout, including the realloc padding.
+        let (program_id, infos, data) = unsafe { deserialize(buffer.as_mut_ptr() as *mut u8) };
+        let lamports_before: u128 = keys.iter().map(|key| self.lamports(key) as u128).sum();
+        crate::entry(program_id, &infos, data)?;
+
+        let mut lamports_after = 0u128;
+        for key in &keys {
+            let info = infos.iter().find(|info| info.key == key).unwrap();
+            let state = AccountState {
+                lamports: info.lamports(),
+                data: info.data.borrow().to_vec(),
+                owner: *info.owner,
+                executable: info.executable,
+            };
+            lamports_after += state.lamports as u128;
+
+            if !info.is_writable {
+                let before = self.accounts.get(key);
+                assert!(
+                    before.map_or(state.lamports == 0 && state.data.is_empty(), |before| {
+                        before.lamports == state.lamports && before.data == state.data && before.owner == state.owner
+                    }),
+                    "read-only account {} was modified",
+                    key
+                );
+                continue;
+            }
+            self.accounts.insert(*key, state);
+        }
+        assert_eq!(lamports_before, lamports_after, "instruction did not conserve lamports");
+
+        Ok(())
+    }
+}
+
+/// Checks every signature an Ed25519 precompile instruction claims, reading offsets as the runtime does.
+fn verify_ed25519(instruction: &Instruction, instructions: &[Instruction]) -> std::result::Result<(), ProgramError> {
+    const OFFSETS_START: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+
+    let data = &instruction.data;
+    let count = *data.first().ok_or(ProgramError::Custom(PRECOMPILE_INVALID_INSTRUCTION_DATA_SIZE))? as usize;
+    if data.len() < OFFSETS_START + count * OFFSETS_LEN || (count == 0 && data.len() > OFFSETS_START) {
+        return Err(ProgramError::Custom(PRECOMPILE_INVALID_INSTRUCTION_DATA_SIZE));
+    }
+
+    let field = |offsets: &[u8], at: usize| u16::from_le_bytes([offsets[2 * at], offsets[2 * at + 1]]) as usize;
+    let slice = |index: usize, start: usize, len: usize| {
+        let source = match index {
+            0xffff => data,
+            index => &instructions.get(index).ok_or(ProgramError::Custom(PRECOMPILE_INVALID_DATA_OFFSETS))?.data,
+        };
+        source.get(start..start + len).ok_or(ProgramError::Custom(PRECOMPILE_INVALID_DATA_OFFSETS))
+    };
+
+    data[OFFSETS_START..].chunks(OFFSETS_LEN).take(count).try_for_each(|offsets| {
+        let signature = Signature::try_from(slice(field(offsets, 1), field(offsets, 0), 64)?)
+            .map_err(|_| ProgramError::Custom(PRECOMPILE_INVALID_SIGNATURE))?;
+        let public_key = PublicKey::from_bytes(slice(field(offsets, 3), field(offsets, 2), 32)?)
+            .map_err(|_| ProgramError::Custom(PRECOMPILE_INVALID_PUBLIC_KEY))?;
+        let message = slice(field(offsets, 6), field(offsets, 4), field(offsets, 5))?;
+        public_key
+            .verify_strict(message, &signature)
+            .map_err(|_| ProgramError::Custom(PRECOMPILE_INVALID_SIGNATURE))
+    })
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: NOW.with(Cell::get),
+            ..Clock::default()
+        };
+        // SAFETY: the caller passes a pointer to a `Clock`.
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        // SAFETY: the caller passes a pointer to a `Rent`.
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &crate::ID))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| ProgramError::InvalidSeeds)?;
+
+        let accounts = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                let info = account_infos
+                    .iter()
+                    .find(|info| *info.key == meta.pubkey)
+                    .ok_or(ProgramError::NotEnoughAccountKeys)?;
+                if meta.is_signer && !info.is_signer && !signers.contains(info.key) {
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+                Ok(info)
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        if instruction.program_id == system_program::ID {
+            system_instruction(&accounts, &instruction.data)
+        } else if instruction.program_id == token::ID {
+            token_instruction(&accounts, &instruction.data)
+        } else {
+            Err(ProgramError::IncorrectProgramId)
+        }
+    }
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::try_from(&data[offset..offset + 32]).unwrap()
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> ProgramResult {
+    let remaining = from.lamports().checked_sub(amount).ok_or(ProgramError::Custom(1))?;
+    **from.try_borrow_mut_lamports()? = remaining;
+    **to.try_borrow_mut_lamports()? += amount;
+    Ok(())
+}
+
+// SystemError::AccountAlreadyInUse
+fn allocate(account: &AccountInfo, space: u64) -> ProgramResult {
+    if !account.data_is_empty() || *account.owner != system_program::ID {
+        return Err(ProgramError::Custom(0));
+    }
+    account.realloc(space as usize, true)
+}
+
+fn system_instruction(accounts: &[&AccountInfo], data: &[u8]) -> ProgramResult {
+    match u32::from_le_bytes(data[0..4].try_into().unwrap()) {
+        0 => {
+            if accounts[1].lamports() > 0 {
+                return Err(ProgramError::Custom(0));
+            }
+            allocate(accounts[1], read_u64(data, 12))?;
+            move_lamports(accounts[0], accounts[1], read_u64(data, 4))?;
+            accounts[1].assign(&read_pubkey(data, 20));
+            Ok(())
+        }
+        1 => {
+            accounts[0].assign(&read_pubkey(data, 4));
+            Ok(())
+        }
+        2 => {
+            if !accounts[0].data_is_empty() {
+                return Err(ProgramError::InvalidArgument);
+            }
+            move_lamports(accounts[0], accounts[1], read_u64(data, 4))
+        }
+        8 => allocate(accounts[0], read_u64(data, 4)),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn token_account(info: &AccountInfo) -> std::result::Result<(Pubkey, Pubkey, u64), ProgramError> {
+    let data = info.try_borrow_data()?;
+    if *info.owner != token::ID || data.len() != TOKEN_ACCOUNT_LEN || data[108] == 0 {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    Ok((read_pubkey(&data, 0), read_pubkey(&data, 32), read_u64(&data, 64)))
+}
+
+fn set_token_amount(info: &AccountInfo, amount: u64) -> ProgramResult {
+    info.try_borrow_mut_data()?[64..72].copy_from_slice(&amount.to_le_bytes());
+    Ok(())
+}
+
+fn token_transfer(
+    source: &AccountInfo,
+    destination: &AccountInfo,
+    authority: &AccountInfo,
+    amount: u64,
+    checked: Option<(&AccountInfo, u8)>,
+) -> ProgramResult {
+    let (source_mint, source_owner, source_amount) = token_account(source)?;
+    let (destination_mint, _, destination_amount) = token_account(destination)?;
+
+    if source_mint != destination_mint {
+        return Err(ProgramError::Custom(3));
+    }
+    if source_owner != *authority.key {
+        return Err(ProgramError::Custom(4));
+    }
+    if let Some((mint, decimals)) = checked {
+        if *mint.key != source_mint {
+            return Err(ProgramError::Custom(3));
+        }
+        if mint.try_borrow_data()?[44] != decimals {
+            return Err(ProgramError::Custom(18));
+        }
+    }
+    let remaining = source_amount.checked_sub(amount).ok_or(ProgramError::Custom(1))?;
+
+    if source.key == destination.key {
+        return Ok(());
+    }
+    set_token_amount(source, remaining)?;
+    set_token_amount(destination, destination_amount + amount)
+}
+
+fn token_instruction(accounts: &[&AccountInfo], data: &[u8]) -> ProgramResult {
+    match data[0] {
+        3 => token_transfer(accounts[0], accounts[1], accounts[2], read_u64(data, 1), None),
+        12 => token_transfer(accounts[0], accounts[2], accounts[3], read_u64(data, 1), Some((accounts[1], data[9]))),
+        9 => {
+            let (_, owner, amount) = token_account(accounts[0])?;
+            if amount != 0 {
+                return Err(ProgramError::Custom(11));
+            }
+            if owner != *accounts[2].key {
+                return Err(ProgramError::Custom(4));
+            }
+            move_lamports(accounts[0], accounts[1], accounts[0].lamports())?;
+            accounts[0].try_borrow_mut_data()?.fill(0);
+            Ok(())
+        }
+        18 => {
+            let (account, mint) = (accounts[0], accounts[1]);
+            if *account.owner != token::ID || account.data_len() != TOKEN_ACCOUNT_LEN {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            if *mint.owner != token::ID || mint.try_borrow_data()?[45] != 1 {
+                return Err(ProgramError::Custom(2));
+            }
+            let mut state = account.try_borrow_mut_data()?;
+            if state[108] != 0 {
+                return Err(ProgramError::Custom(6));
+            }
+            state[0..32].copy_from_slice(mint.key.as_ref());
+            state[32..64].copy_from_slice(&data[1..33]);
+            state[108] = 1;
+            Ok(())
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
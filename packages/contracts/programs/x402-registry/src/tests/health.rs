@@ -0,0 +1,228 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+
+use super::harness::{expect_error, instruction, pda};
+use super::registry::*;
+use crate::state::*;
+use crate::{accounts, instruction as ix, ErrorCode};
+
+fn probe_operator_address(operator: &Pubkey) -> Pubkey {
+    pda(&[b"probe_operator", operator.as_ref()])
+}
+
+fn health_address(service: &Pubkey) -> Pubkey {
+    pda(&[b"service_health", service.as_ref()])
+}
+
+impl Registry {
+    fn add_probe_operator_ix(&self, operator: &Pubkey, authority: &Pubkey) -> Instruction {
+        instruction(
+            accounts::AddProbeOperator {
+                config: config_address(),
+                probe_operator: probe_operator_address(operator),
+                authority: *authority,
+                system_program: system_program::ID,
+            },
+            ix::AddProbeOperator { operator: *operator },
+        )
+    }
+
+    fn remove_probe_operator_ix(&self, operator: &Pubkey, authority: &Pubkey) -> Instruction {
+        instruction(
+            accounts::RemoveProbeOperator {
+                config: config_address(),
+                probe_operator: probe_operator_address(operator),
+                authority: *authority,
+            },
+            ix::RemoveProbeOperator {},
+        )
+    }
+
+    fn open_service_health_ix(&self, service: &Pubkey, payer: &Pubkey) -> Instruction {
+        instruction(
+            accounts::OpenServiceHealth {
+                service_health: health_address(service),
+                service: *service,
+                payer: *payer,
+                system_program: system_program::ID,
+            },
+            ix::OpenServiceHealth {},
+        )
+    }
+
+    fn report_health_ix(&self, service: &Pubkey, operator: &Pubkey, report: HealthReport) -> Instruction {
+        instruction(
+            accounts::ReportHealth {
+                config: config_address(),
+                probe_operator: probe_operator_address(operator),
+                service_health: health_address(service),
+                service: *service,
+                operator: *operator,
+            },
+            ix::ReportHealth { report },
+        )
+    }
+
+    fn health(&self, service: &Pubkey) -> ServiceHealth {
+        self.account(&health_address(service))
+    }
+
+    /// A probe operator watching a service whose health account is open.
+    fn monitored_service(&mut self) -> (Pubkey, Pubkey) {
+        let owner = self.register_agent();
+        let service = self.register_service(&owner);
+        let [authority, operator] = [self.authority, self.new_wallet()];
+        self.send(self.add_probe_operator_ix(&operator, &authority)).unwrap();
+        self.send(self.open_service_health_ix(&service, &operator)).unwrap();
+        (service, operator)
+    }
+
+    /// A report covering the `HEALTH_INTERVAL` seconds up to now.
+    fn report(&self, probes: u32, successes: u32, average_latency_ms: u32) -> HealthReport {
+        HealthReport {
+            window_start: self.now - HEALTH_INTERVAL,
+            window_end: self.now,
+            probes,
+            successes,
+            average_latency_ms,
+        }
+    }
+}
+
+#[test]
+fn only_the_authority_manages_probe_operators() {
+    let mut registry = Registry::new();
+    let [authority, operator, stranger] = [registry.authority, registry.new_wallet(), registry.new_wallet()];
+
+    expect_error(registry.send(registry.add_probe_operator_ix(&operator, &stranger)), ErrorCode::UnauthorizedAccess);
+    registry.send(registry.add_probe_operator_ix(&operator, &authority)).unwrap();
+
+    let probe_operator: ProbeOperator = registry.account(&probe_operator_address(&operator));
+    assert_eq!(probe_operator.operator, operator);
+    assert_eq!(probe_operator.added_at, registry.now);
+    assert_eq!(probe_operator.reports, 0);
+    assert!(registry.send(registry.add_probe_operator_ix(&operator, &authority)).is_err());
+
+    expect_error(registry.send(registry.remove_probe_operator_ix(&operator, &stranger)), ErrorCode::UnauthorizedAccess);
+    registry.send(registry.remove_probe_operator_ix(&operator, &authority)).unwrap();
+    assert!(!registry.exists(&probe_operator_address(&operator)));
+}
+
+#[test]
+fn opens_service_health_once() {
+    let mut registry = Registry::new();
+    let (service, operator) = registry.monitored_service();
+
+    let health = registry.health(&service);
+    assert_eq!(health.service, service);
+    assert_eq!(health.reports, 0);
+    assert_eq!(health.last_operator, Pubkey::default());
+    assert!(registry.send(registry.open_service_health_ix(&service, &operator)).is_err());
+}
+
+#[test]
+fn records_the_first_report_directly() {
+    let mut registry = Registry::new();
+    let (service, operator) = registry.monitored_service();
+    registry.advance(HEALTH_INTERVAL);
+
+    registry.send(registry.report_health_ix(&service, &operator, registry.report(10, 9, 250))).unwrap();
+
+    let health = registry.health(&service);
+    assert_eq!(health.uptime_bps, 9_000);
+    assert_eq!(health.latency_ms, 250);
+    assert_eq!(health.total_probes, 10);
+    assert_eq!(health.successful_probes, 9);
+    assert_eq!(health.reports, 1);
+    assert_eq!(health.last_operator, operator);
+    assert_eq!(health.last_window_end, registry.now);
+    assert_eq!(health.last_reported_at, registry.now);
+    assert_eq!(registry.service(&service).uptime_percent, 90);
+
+    let probe_operator: ProbeOperator = registry.account(&probe_operator_address(&operator));
+    assert_eq!(probe_operator.reports, 1);
+}
+
+#[test]
+fn smooths_later_reports() {
+    let mut registry = Registry::new();
+    let (service, operator) = registry.monitored_service();
+    registry.advance(HEALTH_INTERVAL);
+    registry.send(registry.report_health_ix(&service, &operator, registry.report(10, 10, 200))).unwrap();
+
+    registry.advance(HEALTH_INTERVAL);
+    registry.send(registry.report_health_ix(&service, &operator, registry.report(10, 5, 700))).unwrap();
+
+    let health = registry.health(&service);
+    assert_eq!(health.uptime_bps, 9_000);
+    assert_eq!(health.latency_ms, 300);
+    assert_eq!(health.total_probes, 20);
+    assert_eq!(health.successful_probes, 15);
+    assert_eq!(registry.service(&service).uptime_percent, 90);
+
+    registry.advance(HEALTH_INTERVAL);
+    registry.send(registry.report_health_ix(&service, &operator, registry.report(10, 0, 5_000))).unwrap();
+
+    let health = registry.health(&service);
+    assert_eq!(health.uptime_bps, 7_200);
+    assert_eq!(health.latency_ms, 300);
+}
+
+#[test]
+fn rejects_invalid_reports() {
+    let mut registry = Registry::new();
+    let (service, operator) = registry.monitored_service();
+    registry.advance(HEALTH_INTERVAL);
+    let now = registry.now;
+
+    let cases = [
+        registry.report(0, 0, 100),
+        registry.report(10, 11, 100),
+        HealthReport { window_start: now, window_end: now, ..registry.report(10, 10, 100) },
+        HealthReport { window_start: now - 1, window_end: now + 1, ..registry.report(10, 10, 100) },
+    ];
+    for report in cases {
+        expect_error(registry.send(registry.report_health_ix(&service, &operator, report)), ErrorCode::InvalidHealthReport);
+    }
+
+    registry.send(registry.report_health_ix(&service, &operator, registry.report(10, 10, 100))).unwrap();
+    registry.advance(HEALTH_INTERVAL);
+    let overlapping = HealthReport { window_start: now - 1, ..registry.report(10, 10, 100) };
+    expect_error(registry.send(registry.report_health_ix(&service, &operator, overlapping)), ErrorCode::InvalidHealthReport);
+}
+
+#[test]
+fn rate_limits_reports() {
+    let mut registry = Registry::new();
+    let (service, operator) = registry.monitored_service();
+    registry.advance(HEALTH_INTERVAL);
+    registry.send(registry.report_health_ix(&service, &operator, registry.report(10, 10, 100))).unwrap();
+
+    registry.advance(HEALTH_INTERVAL - 1);
+    let report = HealthReport { window_start: registry.now - 1, ..registry.report(10, 10, 100) };
+    expect_error(registry.send(registry.report_health_ix(&service, &operator, report)), ErrorCode::HealthReportRateLimited);
+
+    registry.advance(1);
+    registry.send(registry.report_health_ix(&service, &operator, registry.report(10, 10, 100))).unwrap();
+    assert_eq!(registry.health(&service).reports, 2);
+}
+
+#[test]
+fn only_registered_operators_report() {
+    let mut registry = Registry::new();
+    let (service, operator) = registry.monitored_service();
+    let [authority, stranger] = [registry.authority, registry.new_wallet()];
+    registry.advance(HEALTH_INTERVAL);
+
+    expect_error(
+        registry.send(registry.report_health_ix(&service, &stranger, registry.report(10, 10, 100))),
+        anchor_lang::error::ErrorCode::AccountNotInitialized,
+    );
+
+    registry.send(registry.remove_probe_operator_ix(&operator, &authority)).unwrap();
+    expect_error(
+        registry.send(registry.report_health_ix(&service, &operator, registry.report(10, 10, 100))),
+        anchor_lang::error::ErrorCode::AccountNotInitialized,
+    );
+}
@@ -0,0 +1,16 @@
+mod harness;
+mod registry;
+
+mod agents;
+mod config;
+mod credit;
+mod disputes;
+mod errors;
+mod governance;
+mod health;
+mod payments;
+mod properties;
+mod ratings;
+mod services;
+mod sizing;
+mod transactions;
@@ -0,0 +1,426 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_spl::token;
+
+use super::harness::{expect_error, instruction, pda};
+use super::registry::*;
+use crate::state::*;
+use crate::{accounts, instruction as ix, voucher_message, ErrorCode};
+
+const AMOUNT: u64 = 50_000_000;
+const DEPOSIT: u64 = 100_000_000;
+const DEADLINE: i64 = 600;
+
+fn payment_escrow_address(payer: &Pubkey, service: &Pubkey, nonce: u64) -> Pubkey {
+    pda(&[b"payment_escrow", payer.as_ref(), service.as_ref(), &nonce.to_le_bytes()])
+}
+
+fn payment_vault_address(escrow: &Pubkey) -> Pubkey {
+    pda(&[b"payment_vault", escrow.as_ref()])
+}
+
+fn channel_address(payer: &Pubkey, service: &Pubkey, channel_id: u64) -> Pubkey {
+    pda(&[b"channel", payer.as_ref(), service.as_ref(), &channel_id.to_le_bytes()])
+}
+
+fn channel_vault_address(channel: &Pubkey) -> Pubkey {
+    pda(&[b"channel_vault", channel.as_ref()])
+}
+
+/// A service and a payer holding tokens the service accepts. The payer does not need to be registered.
+struct Market {
+    registry: Registry,
+    owner: TestAgent,
+    payer: TestAgent,
+    service: Pubkey,
+}
+
+impl Market {
+    fn new() -> Self {
+        let mut registry = Registry::new();
+        let owner = registry.register_agent();
+        let service = registry.register_service(&owner);
+        let payer = registry.new_agent();
+        Self { registry, owner, payer, service }
+    }
+
+    fn escrow(&self, nonce: u64) -> Pubkey {
+        payment_escrow_address(&self.payer.wallet, &self.service, nonce)
+    }
+
+    fn channel(&self) -> Pubkey {
+        channel_address(&self.payer.wallet, &self.service, 0)
+    }
+
+    fn create_escrow_ix(&self, nonce: u64, amount: u64, deadline: i64) -> Instruction {
+        let escrow = self.escrow(nonce);
+        instruction(
+            accounts::CreatePaymentEscrow {
+                payment_escrow: escrow,
+                vault: payment_vault_address(&escrow),
+                service: self.service,
+                mint: self.payer.mint,
+                payer_token_account: self.payer.tokens,
+                payer: self.payer.wallet,
+                system_program: system_program::ID,
+                token_program: token::ID,
+                rent: sysvar::rent::ID,
+            },
+            ix::CreatePaymentEscrow { nonce, amount, deadline },
+        )
+    }
+
+    fn release_escrow_ix(&self, nonce: u64, authority: &Pubkey) -> Instruction {
+        let escrow = self.escrow(nonce);
+        instruction(
+            accounts::ReleasePaymentEscrow {
+                config: config_address(),
+                payment_escrow: escrow,
+                vault: payment_vault_address(&escrow),
+                mint: self.payer.mint,
+                payee_token_account: self.owner.tokens,
+                payer: self.payer.wallet,
+                authority: *authority,
+                token_program: token::ID,
+            },
+            ix::ReleasePaymentEscrow {},
+        )
+    }
+
+    fn refund_escrow_ix(&self, nonce: u64) -> Instruction {
+        let escrow = self.escrow(nonce);
+        instruction(
+            accounts::RefundPaymentEscrow {
+                payment_escrow: escrow,
+                vault: payment_vault_address(&escrow),
+                mint: self.payer.mint,
+                payer_token_account: self.payer.tokens,
+                payer: self.payer.wallet,
+                token_program: token::ID,
+            },
+            ix::RefundPaymentEscrow {},
+        )
+    }
+
+    fn open_channel_ix(&self, deposit: u64) -> Instruction {
+        let channel = self.channel();
+        instruction(
+            accounts::OpenChannel {
+                config: config_address(),
+                channel,
+                vault: channel_vault_address(&channel),
+                service: self.service,
+                mint: self.payer.mint,
+                payer_token_account: self.payer.tokens,
+                payer: self.payer.wallet,
+                system_program: system_program::ID,
+                token_program: token::ID,
+                rent: sysvar::rent::ID,
+            },
+            ix::OpenChannel { channel_id: 0, deposit },
+        )
+    }
+
+    fn top_up_ix(&self, payer: &TestAgent, amount: u64) -> Instruction {
+        let channel = self.channel();
+        instruction(
+            accounts::TopUpChannel {
+                channel,
+                vault: channel_vault_address(&channel),
+                mint: self.payer.mint,
+                payer_token_account: payer.tokens,
+                payer: payer.wallet,
+                token_program: token::ID,
+            },
+            ix::TopUpChannel { amount },
+        )
+    }
+
+    fn redeem_ix(&self, amount: u64, nonce: u64) -> Instruction {
+        let channel = self.channel();
+        instruction(
+            accounts::RedeemVoucher {
+                channel,
+                vault: channel_vault_address(&channel),
+                mint: self.payer.mint,
+                payee_token_account: self.owner.tokens,
+                payee: self.owner.wallet,
+                instructions_sysvar: sysvar::instructions::ID,
+                token_program: token::ID,
+            },
+            ix::RedeemVoucher { amount, nonce },
+        )
+    }
+
+    /// The payer's signature over a cumulative voucher, followed by the payee redeeming it.
+    fn voucher(&self, amount: u64, nonce: u64) -> Vec<Instruction> {
+        vec![
+            self.registry.ed25519_instruction(&self.payer.wallet, &voucher_message(&self.channel(), amount, nonce)),
+            self.redeem_ix(amount, nonce),
+        ]
+    }
+
+    fn request_close_ix(&self, payer: &Pubkey) -> Instruction {
+        instruction(
+            accounts::RequestChannelClose {
+                channel: self.channel(),
+                payer: *payer,
+            },
+            ix::RequestChannelClose {},
+        )
+    }
+
+    fn finalize_ix(&self, authority: &Pubkey) -> Instruction {
+        let channel = self.channel();
+        instruction(
+            accounts::FinalizeChannelClose {
+                channel,
+                vault: channel_vault_address(&channel),
+                mint: self.payer.mint,
+                payer_token_account: self.payer.tokens,
+                payer: self.payer.wallet,
+                authority: *authority,
+                token_program: token::ID,
+            },
+            ix::FinalizeChannelClose {},
+        )
+    }
+
+    fn send(&mut self, instruction: Instruction) -> std::result::Result<(), ProgramError> {
+        self.registry.send(instruction)
+    }
+
+    fn send_transaction(&mut self, instructions: &[Instruction]) -> std::result::Result<(), ProgramError> {
+        self.registry.send_transaction(instructions)
+    }
+
+    fn payment_escrow(&self, nonce: u64) -> PaymentEscrow {
+        self.registry.account(&self.escrow(nonce))
+    }
+
+    fn payment_channel(&self) -> PaymentChannel {
+        self.registry.account(&self.channel())
+    }
+}
+
+#[test]
+fn funds_a_payment_escrow() {
+    let mut market = Market::new();
+    let deadline = market.registry.now + DEADLINE;
+    market.send(market.create_escrow_ix(1, AMOUNT, deadline)).unwrap();
+
+    let escrow = market.payment_escrow(1);
+    assert_eq!(escrow.payer, market.payer.wallet);
+    assert_eq!(escrow.payee, market.owner.wallet);
+    assert_eq!(escrow.service, market.service);
+    assert_eq!(escrow.mint, market.payer.mint);
+    assert_eq!(escrow.nonce, 1);
+    assert_eq!(escrow.amount, AMOUNT);
+    assert_eq!(escrow.deadline, deadline);
+    assert!(escrow.status == PaymentEscrowStatus::Funded);
+    assert_eq!(market.registry.token_balance(&payment_vault_address(&market.escrow(1))), AMOUNT);
+    assert_eq!(market.registry.token_balance(&market.payer.tokens), WALLET_TOKENS - AMOUNT);
+
+    assert!(market.send(market.create_escrow_ix(1, AMOUNT, deadline)).is_err());
+}
+
+#[test]
+fn rejects_invalid_escrows() {
+    let mut market = Market::new();
+    let now = market.registry.now;
+
+    expect_error(market.send(market.create_escrow_ix(1, 0, now + DEADLINE)), ErrorCode::InvalidAmount);
+    expect_error(market.send(market.create_escrow_ix(1, AMOUNT, now)), ErrorCode::InvalidDeadline);
+
+    let mut own = market.create_escrow_ix(1, AMOUNT, now + DEADLINE);
+    own.accounts[0].pubkey = payment_escrow_address(&market.owner.wallet, &market.service, 1);
+    own.accounts[1].pubkey = payment_vault_address(&own.accounts[0].pubkey);
+    own.accounts[4].pubkey = market.owner.tokens;
+    own.accounts[5].pubkey = market.owner.wallet;
+    expect_error(market.send(own), ErrorCode::SelfPaymentNotAllowed);
+
+    let payer = market.payer;
+    let other_mint = market.registry.create_mint(DECIMALS);
+    market.payer = market.registry.new_agent_with_mint(&other_mint);
+    expect_error(market.send(market.create_escrow_ix(1, AMOUNT, now + DEADLINE)), ErrorCode::InvalidMint);
+
+    market.payer = payer;
+    let owner = market.owner.wallet;
+    let pause = market.registry.update_service_ix(&market.service, &owner, ix::PauseService {});
+    market.send(pause).unwrap();
+    expect_error(market.send(market.create_escrow_ix(1, AMOUNT, now + DEADLINE)), ErrorCode::ServiceNotActive);
+}
+
+#[test]
+fn payer_or_oracle_releases_the_escrow() {
+    let mut market = Market::new();
+    let deadline = market.registry.now + DEADLINE;
+    market.send(market.create_escrow_ix(1, AMOUNT, deadline)).unwrap();
+    market.send(market.create_escrow_ix(2, AMOUNT, deadline)).unwrap();
+
+    let stranger = market.registry.new_wallet();
+    expect_error(market.send(market.release_escrow_ix(1, &stranger)), ErrorCode::UnauthorizedAccess);
+
+    let vault_rent = market.registry.lamports(&payment_vault_address(&market.escrow(1)));
+    let balance = market.registry.lamports(&market.payer.wallet);
+    let payer = market.payer.wallet;
+    market.send(market.release_escrow_ix(1, &payer)).unwrap();
+
+    let escrow = market.payment_escrow(1);
+    assert!(escrow.status == PaymentEscrowStatus::Released);
+    assert_eq!(escrow.settled_at, market.registry.now);
+    assert!(!market.registry.exists(&payment_vault_address(&market.escrow(1))));
+    assert_eq!(market.registry.lamports(&market.payer.wallet), balance + vault_rent);
+    assert_eq!(market.registry.token_balance(&market.owner.tokens), WALLET_TOKENS - STAKE + AMOUNT);
+
+    let oracle = market.registry.oracle;
+    market.send(market.release_escrow_ix(2, &oracle)).unwrap();
+    assert!(market.payment_escrow(2).status == PaymentEscrowStatus::Released);
+    assert_eq!(market.registry.token_balance(&market.owner.tokens), WALLET_TOKENS - STAKE + 2 * AMOUNT);
+}
+
+#[test]
+fn settles_only_funded_escrows() {
+    let mut market = Market::new();
+    let deadline = market.registry.now + DEADLINE;
+    market.send(market.create_escrow_ix(1, AMOUNT, deadline)).unwrap();
+    let escrow = market.escrow(1);
+    market.registry.update::<PaymentEscrow>(&escrow, |escrow| {
+        escrow.status = PaymentEscrowStatus::Released;
+    });
+
+    let payer = market.payer.wallet;
+    expect_error(market.send(market.release_escrow_ix(1, &payer)), ErrorCode::PaymentEscrowNotFunded);
+    market.registry.advance(DEADLINE + 1);
+    expect_error(market.send(market.refund_escrow_ix(1)), ErrorCode::PaymentEscrowNotFunded);
+}
+
+#[test]
+fn refunds_expired_escrows() {
+    let mut market = Market::new();
+    let deadline = market.registry.now + DEADLINE;
+    market.send(market.create_escrow_ix(1, AMOUNT, deadline)).unwrap();
+
+    market.registry.advance(DEADLINE);
+    expect_error(market.send(market.refund_escrow_ix(1)), ErrorCode::PaymentEscrowNotExpired);
+
+    market.registry.advance(1);
+    market.send(market.refund_escrow_ix(1)).unwrap();
+
+    let escrow = market.payment_escrow(1);
+    assert!(escrow.status == PaymentEscrowStatus::Refunded);
+    assert_eq!(escrow.settled_at, market.registry.now);
+    assert_eq!(market.registry.token_balance(&market.payer.tokens), WALLET_TOKENS);
+    assert!(!market.registry.exists(&payment_vault_address(&market.escrow(1))));
+}
+
+#[test]
+fn opens_and_tops_up_a_channel() {
+    let mut market = Market::new();
+    expect_error(market.send(market.open_channel_ix(0)), ErrorCode::InvalidAmount);
+    market.send(market.open_channel_ix(DEPOSIT)).unwrap();
+
+    let channel = market.payment_channel();
+    assert_eq!(channel.payer, market.payer.wallet);
+    assert_eq!(channel.payee, market.owner.wallet);
+    assert_eq!(channel.deposit, DEPOSIT);
+    assert_eq!(channel.challenge_seconds, CHANNEL_CHALLENGE);
+    assert!(channel.status == ChannelStatus::Open);
+
+    let stranger = market.registry.new_agent();
+    expect_error(market.send(market.top_up_ix(&stranger, DEPOSIT)), ErrorCode::UnauthorizedAccess);
+    expect_error(market.send(market.top_up_ix(&market.payer, 0)), ErrorCode::InvalidAmount);
+    market.send(market.top_up_ix(&market.payer, DEPOSIT)).unwrap();
+
+    assert_eq!(market.payment_channel().deposit, 2 * DEPOSIT);
+    assert_eq!(market.registry.token_balance(&channel_vault_address(&market.channel())), 2 * DEPOSIT);
+    assert_eq!(market.registry.token_balance(&market.payer.tokens), WALLET_TOKENS - 2 * DEPOSIT);
+}
+
+#[test]
+fn redeems_cumulative_vouchers() {
+    let mut market = Market::new();
+    market.send(market.open_channel_ix(DEPOSIT)).unwrap();
+    let payee_tokens = market.registry.token_balance(&market.owner.tokens);
+
+    market.send_transaction(&market.voucher(AMOUNT / 2, 1)).unwrap();
+    market.send_transaction(&market.voucher(AMOUNT, 2)).unwrap();
+
+    let channel = market.payment_channel();
+    assert_eq!(channel.redeemed, AMOUNT);
+    assert_eq!(channel.last_nonce, 2);
+    assert_eq!(market.registry.token_balance(&market.owner.tokens), payee_tokens + AMOUNT);
+
+    expect_error(market.send_transaction(&market.voucher(2 * AMOUNT, 2)), ErrorCode::StaleVoucher);
+    expect_error(market.send_transaction(&market.voucher(AMOUNT, 3)), ErrorCode::StaleVoucher);
+    expect_error(market.send_transaction(&market.voucher(DEPOSIT + 1, 3)), ErrorCode::VoucherExceedsDeposit);
+    market.send_transaction(&market.voucher(DEPOSIT, 3)).unwrap();
+}
+
+#[test]
+fn vouchers_need_the_payers_signature() {
+    let mut market = Market::new();
+    market.send(market.open_channel_ix(DEPOSIT)).unwrap();
+
+    expect_error(market.send(market.redeem_ix(AMOUNT, 1)), ErrorCode::MissingSignatureVerification);
+
+    let mut forged = market.voucher(AMOUNT, 1);
+    let message = voucher_message(&market.channel(), AMOUNT, 1);
+    forged[0] = market.registry.ed25519_instruction(&market.owner.wallet, &message);
+    expect_error(market.send_transaction(&forged), ErrorCode::InvalidSignatureVerification);
+
+    let mut inflated = market.voucher(AMOUNT, 1);
+    inflated[1] = market.redeem_ix(DEPOSIT, 1);
+    expect_error(market.send_transaction(&inflated), ErrorCode::InvalidSignatureVerification);
+
+    let mut stranger = market.voucher(AMOUNT, 1);
+    stranger[1].accounts[4].pubkey = market.payer.wallet;
+    expect_error(market.send_transaction(&stranger), ErrorCode::UnauthorizedAccess);
+    assert_eq!(market.payment_channel().redeemed, 0);
+}
+
+#[test]
+fn payer_closes_after_the_challenge_period() {
+    let mut market = Market::new();
+    market.send(market.open_channel_ix(DEPOSIT)).unwrap();
+    market.send_transaction(&market.voucher(AMOUNT, 1)).unwrap();
+    let payer = market.payer.wallet;
+
+    expect_error(market.send(market.finalize_ix(&payer)), ErrorCode::ChannelNotOpen);
+    expect_error(market.send(market.request_close_ix(&market.owner.wallet)), ErrorCode::UnauthorizedAccess);
+    market.send(market.request_close_ix(&payer)).unwrap();
+
+    let channel = market.payment_channel();
+    assert!(channel.status == ChannelStatus::Closing);
+    assert_eq!(channel.close_requested_at, market.registry.now);
+    expect_error(market.send(market.request_close_ix(&payer)), ErrorCode::ChannelNotOpen);
+    expect_error(market.send(market.top_up_ix(&market.payer, DEPOSIT)), ErrorCode::ChannelNotOpen);
+
+    market.registry.advance(CHANNEL_CHALLENGE - 1);
+    expect_error(market.send(market.finalize_ix(&payer)), ErrorCode::ChallengePeriodActive);
+
+    market.send_transaction(&market.voucher(2 * AMOUNT, 2)).unwrap();
+    market.registry.advance(1);
+    let stranger = market.registry.new_wallet();
+    expect_error(market.send(market.finalize_ix(&stranger)), ErrorCode::UnauthorizedAccess);
+    market.send(market.finalize_ix(&payer)).unwrap();
+
+    assert!(market.payment_channel().status == ChannelStatus::Closed);
+    assert!(!market.registry.exists(&channel_vault_address(&market.channel())));
+    assert_eq!(market.registry.token_balance(&market.payer.tokens), WALLET_TOKENS - 2 * AMOUNT);
+    expect_error(market.send_transaction(&market.voucher(DEPOSIT, 3)), anchor_lang::error::ErrorCode::AccountNotInitialized);
+}
+
+#[test]
+fn payee_closes_immediately_and_refunds_the_rest() {
+    let mut market = Market::new();
+    market.send(market.open_channel_ix(DEPOSIT)).unwrap();
+    market.send_transaction(&market.voucher(AMOUNT / 5, 1)).unwrap();
+
+    let owner = market.owner.wallet;
+    market.send(market.finalize_ix(&owner)).unwrap();
+
+    assert!(market.payment_channel().status == ChannelStatus::Closed);
+    assert_eq!(market.registry.token_balance(&market.payer.tokens), WALLET_TOKENS - AMOUNT / 5);
+}
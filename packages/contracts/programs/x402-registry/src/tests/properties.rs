@@ -0,0 +1,70 @@
+use proptest::prelude::*;
+
+use crate::calculate_slash_amount;
+use crate::reputation::{calculate_new_reputation, ReputationParams, MAX_REPUTATION};
+
+fn params() -> impl Strategy<Value = ReputationParams> {
+    (0..=10 * 365 * 24 * 60 * 60i64, 1..=u64::MAX, 1..=10_000u16)
+        .prop_flat_map(|(half_life_seconds, value_unit, max_alpha_bps)| {
+            (1..=max_alpha_bps).prop_map(move |alpha_bps| ReputationParams {
+                half_life_seconds,
+                value_unit,
+                alpha_bps,
+                max_alpha_bps,
+            })
+        })
+}
+
+proptest! {
+    #[test]
+    fn reputation_stays_within_bounds(
+        current in any::<u64>(),
+        success in any::<bool>(),
+        amount in any::<u64>(),
+        elapsed in 0..=i64::MAX,
+        params in params(),
+    ) {
+        let reputation = calculate_new_reputation(current, success, amount, elapsed, &params).unwrap();
+        prop_assert!(reputation <= MAX_REPUTATION);
+    }
+
+    #[test]
+    fn success_never_scores_below_failure(
+        current in 0..=MAX_REPUTATION,
+        amount in any::<u64>(),
+        elapsed in 0..=i64::MAX,
+        params in params(),
+    ) {
+        let success = calculate_new_reputation(current, true, amount, elapsed, &params).unwrap();
+        let failure = calculate_new_reputation(current, false, amount, elapsed, &params).unwrap();
+        prop_assert!(success >= failure);
+    }
+
+    #[test]
+    fn outcomes_move_reputation_in_their_direction(
+        current in 0..=MAX_REPUTATION,
+        amount in any::<u64>(),
+        params in params(),
+    ) {
+        prop_assert!(calculate_new_reputation(current, true, amount, 0, &params).unwrap() >= current);
+        prop_assert!(calculate_new_reputation(current, false, amount, 0, &params).unwrap() <= current);
+    }
+
+    #[test]
+    fn slash_is_double_the_fraud_capped_at_the_stake(
+        fraud_amount in 0..=u64::MAX / 2,
+        staked_amount in any::<u64>(),
+    ) {
+        let slash = calculate_slash_amount(fraud_amount, staked_amount).unwrap();
+        prop_assert_eq!(slash, (2 * fraud_amount).min(staked_amount));
+        prop_assert!(slash <= staked_amount);
+    }
+
+    #[test]
+    fn slash_rejects_fraud_that_overflows_when_doubled(
+        fraud_amount in u64::MAX / 2 + 1..=u64::MAX,
+        staked_amount in any::<u64>(),
+    ) {
+        prop_assert!(calculate_slash_amount(fraud_amount, staked_amount).is_err());
+    }
+}
@@ -0,0 +1,305 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+
+use super::harness::{expect_error, instruction};
+use super::registry::*;
+use crate::state::*;
+use crate::{accounts, instruction as ix, ErrorCode, Service};
+
+impl Registry {
+    fn rate_service_ix(&self, rater: &TestAgent, service: &Pubkey, rating: u16) -> Instruction {
+        instruction(
+            accounts::RateService {
+                config: config_address(),
+                service: *service,
+                agent: rater.address,
+                stake_mint: stake_mint_address(&rater.mint),
+                interaction: interaction_address(service, &rater.address),
+                service_rating: rating_address(service, &rater.wallet),
+                rater: rater.wallet,
+                system_program: system_program::ID,
+            },
+            ix::RateService { rating },
+        )
+    }
+
+    fn update_rating_ix(&self, rater: &TestAgent, service: &Pubkey, new_rating: u16) -> Instruction {
+        instruction(
+            accounts::UpdateRating {
+                config: config_address(),
+                service: *service,
+                agent: rater.address,
+                stake_mint: stake_mint_address(&rater.mint),
+                service_rating: rating_address(service, &rater.wallet),
+                rater: rater.wallet,
+            },
+            ix::UpdateRating { new_rating },
+        )
+    }
+
+    fn prune_rating_ix(&self, rater: &TestAgent, service: &Pubkey) -> Instruction {
+        instruction(
+            accounts::PruneRating {
+                config: config_address(),
+                service: *service,
+                service_rating: rating_address(service, &rater.wallet),
+                rater: rater.wallet,
+            },
+            ix::PruneRating {},
+        )
+    }
+
+    fn migrate_rating_ix(&self, rater: &TestAgent, service: &Pubkey) -> Instruction {
+        instruction(
+            accounts::MigrateRating {
+                service: *service,
+                service_rating: rating_address(service, &rater.wallet),
+                payer: self.authority,
+                system_program: system_program::ID,
+            },
+            ix::MigrateRating {},
+        )
+    }
+
+    /// A rating as written before ratings were weighted: 90 bytes, without the trailing `weight`.
+    fn legacy_rating(&mut self, rater: &TestAgent, service: &Pubkey, rating: u16) {
+        let state = ServiceRating {
+            service: *service,
+            agent: rater.wallet,
+            rating,
+            timestamp: self.now,
+            last_update_time: self.now,
+            weight: 0,
+        };
+        let mut data = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+        data.truncate(ServiceRating::SPACE - 8);
+        self.set_raw(rating_address(service, &rater.wallet), data, crate::ID);
+    }
+
+    fn rating(&self, rater: &TestAgent, service: &Pubkey) -> ServiceRating {
+        self.account(&rating_address(service, &rater.wallet))
+    }
+
+    /// An agent that has paid for `service` once and may therefore rate it.
+    fn customer(&mut self, service: &Pubkey, stake_amount: u64) -> TestAgent {
+        let agent = self.new_agent();
+        self.send(self.register_agent_ix(&agent, stake_amount)).unwrap();
+        self.record_transaction(&agent, service, 1_000_000, true);
+        agent
+    }
+}
+
+fn rated_service() -> (Registry, TestAgent, Pubkey) {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let service = registry.register_service(&owner);
+    (registry, owner, service)
+}
+
+#[test]
+fn rates_a_service_after_paying_for_it() {
+    let (mut registry, _, service) = rated_service();
+    let rater = registry.customer(&service, STAKE);
+
+    registry.send(registry.rate_service_ix(&rater, &service, 400)).unwrap();
+
+    let rating = registry.rating(&rater, &service);
+    assert_eq!(rating.service, service);
+    assert_eq!(rating.agent, rater.wallet);
+    assert_eq!(rating.rating, 400);
+    assert_eq!(rating.timestamp, registry.now);
+    assert!(rating.weight > 0);
+
+    let state = registry.service(&service);
+    assert_eq!(state.total_ratings, 1);
+    assert_eq!(state.total_rating_sum, 400);
+    assert_eq!(state.total_rating_weight, rating.weight);
+    assert_eq!(state.average_rating, 400);
+    assert_eq!(state.reputation_score, 800);
+
+    assert!(registry.send(registry.rate_service_ix(&rater, &service, 300)).is_err());
+}
+
+#[test]
+fn weights_ratings_by_stake() {
+    let (mut registry, _, service) = rated_service();
+    let small = registry.customer(&service, MIN_STAKE);
+    let large = registry.customer(&service, WALLET_TOKENS);
+
+    registry.send(registry.rate_service_ix(&small, &service, 100)).unwrap();
+    registry.send(registry.rate_service_ix(&large, &service, 500)).unwrap();
+
+    let small_weight = registry.rating(&small, &service).weight;
+    let large_weight = registry.rating(&large, &service).weight;
+    assert!(large_weight > small_weight);
+
+    let state = registry.service(&service);
+    let expected = (100 * small_weight + 500 * large_weight) / (small_weight + large_weight);
+    assert_eq!(state.average_rating as u64, expected);
+    assert!(state.average_rating > 300);
+    assert_eq!(state.total_rating_sum, 600);
+}
+
+#[test]
+fn rejects_ratings_out_of_range() {
+    let (mut registry, _, service) = rated_service();
+    let rater = registry.customer(&service, STAKE);
+
+    expect_error(registry.send(registry.rate_service_ix(&rater, &service, 0)), ErrorCode::InvalidRating);
+    expect_error(registry.send(registry.rate_service_ix(&rater, &service, 501)), ErrorCode::InvalidRating);
+    assert!(!registry.exists(&rating_address(&service, &rater.wallet)));
+}
+
+#[test]
+fn rates_only_services_the_agent_has_used() {
+    let (mut registry, owner, service) = rated_service();
+    let stranger = registry.register_agent();
+    assert!(registry.send(registry.rate_service_ix(&stranger, &service, 400)).is_err());
+
+    let rater = registry.customer(&service, STAKE);
+    registry.update::<ServiceInteraction>(&interaction_address(&service, &rater.address), |interaction| {
+        interaction.transactions = 0;
+    });
+    expect_error(registry.send(registry.rate_service_ix(&rater, &service, 400)), ErrorCode::NoTransactionHistory);
+
+    registry.record_transaction(&owner, &service, 1_000_000, true);
+    expect_error(registry.send(registry.rate_service_ix(&owner, &service, 500)), ErrorCode::SelfRatingNotAllowed);
+}
+
+#[test]
+fn rates_only_active_services() {
+    let (mut registry, owner, service) = rated_service();
+    let rater = registry.customer(&service, STAKE);
+    registry.send(registry.update_service_ix(&service, &owner.wallet, ix::PauseService {})).unwrap();
+
+    expect_error(registry.send(registry.rate_service_ix(&rater, &service, 400)), ErrorCode::ServiceNotActive);
+}
+
+#[test]
+fn updates_a_rating_after_the_cooldown() {
+    let (mut registry, _, service) = rated_service();
+    let rater = registry.customer(&service, STAKE);
+    registry.send(registry.rate_service_ix(&rater, &service, 400)).unwrap();
+
+    registry.advance(RATING_COOLDOWN - 1);
+    expect_error(registry.send(registry.update_rating_ix(&rater, &service, 200)), ErrorCode::RatingUpdateCooldown);
+
+    registry.advance(1);
+    expect_error(registry.send(registry.update_rating_ix(&rater, &service, 501)), ErrorCode::InvalidRating);
+    registry.send(registry.update_rating_ix(&rater, &service, 200)).unwrap();
+
+    let rating = registry.rating(&rater, &service);
+    assert_eq!(rating.rating, 200);
+    assert_eq!(rating.last_update_time, registry.now);
+
+    let state = registry.service(&service);
+    assert_eq!(state.total_ratings, 1);
+    assert_eq!(state.total_rating_sum, 200);
+    assert_eq!(state.average_rating, 200);
+
+    let stranger = registry.new_wallet();
+    let mut update = registry.update_rating_ix(&rater, &service, 300);
+    update.accounts[5].pubkey = stranger;
+    assert!(registry.send(update).is_err());
+}
+
+#[test]
+fn update_rating_requires_consistent_totals() {
+    let (mut registry, _, service) = rated_service();
+    let rater = registry.customer(&service, STAKE);
+    registry.send(registry.rate_service_ix(&rater, &service, 400)).unwrap();
+    registry.advance(RATING_COOLDOWN);
+
+    registry.update::<Service>(&service, |state| state.total_ratings = 0);
+    expect_error(registry.send(registry.update_rating_ix(&rater, &service, 200)), ErrorCode::InvalidState);
+}
+
+#[test]
+fn prunes_expired_ratings() {
+    let (mut registry, _, service) = rated_service();
+    let rater = registry.customer(&service, STAKE);
+    let other = registry.customer(&service, STAKE);
+    registry.send(registry.rate_service_ix(&rater, &service, 400)).unwrap();
+
+    registry.advance(RATING_WINDOW - 1);
+    expect_error(registry.send(registry.prune_rating_ix(&rater, &service)), ErrorCode::RatingNotExpired);
+
+    registry.advance(1);
+    let mut prune = registry.prune_rating_ix(&rater, &service);
+    prune.accounts[3].pubkey = other.wallet;
+    expect_error(registry.send(prune), ErrorCode::UnauthorizedAccess);
+
+    let rating_address = rating_address(&service, &rater.wallet);
+    let rent = registry.lamports(&rating_address);
+    let balance = registry.lamports(&rater.wallet);
+    registry.send(registry.prune_rating_ix(&rater, &service)).unwrap();
+
+    assert!(!registry.exists(&rating_address));
+    assert_eq!(registry.lamports(&rater.wallet), balance + rent);
+    let state = registry.service(&service);
+    assert_eq!(state.total_ratings, 0);
+    assert_eq!(state.total_rating_sum, 0);
+    assert_eq!(state.total_rating_weight, 0);
+    assert_eq!(state.average_rating, 0);
+}
+
+#[test]
+fn migrates_legacy_ratings_at_the_legacy_weight() {
+    let (mut registry, owner, service) = rated_service();
+    let first = registry.customer(&service, STAKE);
+    let second = registry.customer(&service, STAKE);
+    registry.legacy_rating(&first, &service, 400);
+    registry.legacy_rating(&second, &service, 300);
+    registry.update::<Service>(&service, |state| {
+        state.total_ratings = 2;
+        state.total_rating_sum = 700;
+        state.average_rating = 350;
+    });
+
+    let other_service = registry.register_service(&owner);
+    let mut misdirected = registry.migrate_rating_ix(&first, &service);
+    misdirected.accounts[0].pubkey = other_service;
+    expect_error(registry.send(misdirected), ErrorCode::InvalidPDA);
+
+    registry.send(registry.migrate_rating_ix(&first, &service)).unwrap();
+    assert_eq!(registry.state(&rating_address(&service, &first.wallet)).unwrap().data.len(), ServiceRating::SPACE);
+    assert_eq!(registry.rating(&first, &service).weight, ServiceRating::LEGACY_WEIGHT);
+    let state = registry.service(&service);
+    assert_eq!(state.total_rating_weight, 2 * ServiceRating::LEGACY_WEIGHT);
+    assert_eq!(state.weighted_rating_sum, 700 * ServiceRating::LEGACY_WEIGHT);
+    assert_eq!(state.average_rating, 350);
+
+    expect_error(registry.send(registry.migrate_rating_ix(&first, &service)), ErrorCode::RatingAlreadyMigrated);
+
+    registry.advance(RATING_WINDOW);
+    registry.send(registry.prune_rating_ix(&first, &service)).unwrap();
+    let state = registry.service(&service);
+    assert_eq!(state.total_ratings, 1);
+    assert_eq!(state.total_rating_weight, ServiceRating::LEGACY_WEIGHT);
+    assert_eq!(state.average_rating, 300);
+
+    registry.send(registry.migrate_rating_ix(&second, &service)).unwrap();
+    assert_eq!(registry.service(&service).total_rating_weight, ServiceRating::LEGACY_WEIGHT);
+}
+
+#[test]
+fn new_ratings_on_a_legacy_service_keep_its_legacy_average() {
+    let (mut registry, _, service) = rated_service();
+    registry.update::<Service>(&service, |state| {
+        state.total_ratings = 2;
+        state.total_rating_sum = 700;
+        state.average_rating = 350;
+    });
+    let rater = registry.customer(&service, STAKE);
+
+    registry.send(registry.rate_service_ix(&rater, &service, 500)).unwrap();
+
+    let weight = registry.rating(&rater, &service).weight;
+    let state = registry.service(&service);
+    assert_eq!(state.total_rating_weight, 2 * ServiceRating::LEGACY_WEIGHT + weight);
+    assert_eq!(state.weighted_rating_sum, 700 * ServiceRating::LEGACY_WEIGHT + 500 * weight);
+    assert_eq!(state.average_rating as u64, state.weighted_rating_sum / state.total_rating_weight);
+    assert!(state.average_rating > 350);
+}
@@ -0,0 +1,481 @@
+use std::ops::{Deref, DerefMut};
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_spl::token;
+
+use super::harness::{instruction, pda, Ledger};
+use crate::state::*;
+use crate::{accounts, instruction as ix, Agent, Service};
+
+pub const DECIMALS: u8 = 9;
+pub const MIN_STAKE: u64 = 1_000_000_000;
+pub const STAKE: u64 = 10_000_000_000;
+pub const WALLET_TOKENS: u64 = 100_000_000_000;
+pub const TIMELOCK: i64 = 60;
+pub const RATE_LIMIT: i64 = 10;
+pub const UNSTAKE_LOCK: i64 = 3_600;
+pub const RESTAKE_COOLDOWN: i64 = 3_600;
+pub const RATING_COOLDOWN: i64 = 60;
+pub const RATING_WINDOW: i64 = 90 * 24 * 60 * 60;
+pub const SLASH_COOLDOWN: i64 = 24 * 60 * 60;
+pub const SLASH_PENALTY: u64 = 500;
+pub const DISPUTE_BOND: u64 = 1_000_000_000;
+pub const DISPUTE_RESPONSE: i64 = 24 * 60 * 60;
+pub const CREDIT_FEE_BPS: u16 = 100;
+pub const CREDIT_REPAYMENT: i64 = 7 * 24 * 60 * 60;
+pub const CHANNEL_CHALLENGE: i64 = 3_600;
+pub const HEALTH_INTERVAL: i64 = 600;
+
+pub struct TestAgent {
+    pub wallet: Pubkey,
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    pub tokens: Pubkey,
+}
+
+/// A deployed and configured registry with one enabled stake mint. The council is the DAO, arbiter and verifier
+/// authorities with a threshold of two.
+pub struct Registry {
+    pub ledger: Ledger,
+    pub authority: Pubkey,
+    pub oracle: Pubkey,
+    pub verifier: Pubkey,
+    pub tap: Pubkey,
+    pub dao: Pubkey,
+    pub arbiter: Pubkey,
+    pub treasury: Pubkey,
+    pub council: Vec<Pubkey>,
+    pub mint: Pubkey,
+    pub treasury_tokens: Pubkey,
+    pub program_data: Pubkey,
+}
+
+impl Deref for Registry {
+    type Target = Ledger;
+
+    fn deref(&self) -> &Ledger {
+        &self.ledger
+    }
+}
+
+impl DerefMut for Registry {
+    fn deref_mut(&mut self) -> &mut Ledger {
+        &mut self.ledger
+    }
+}
+
+pub fn config_address() -> Pubkey {
+    pda(&[b"config"])
+}
+
+pub fn agent_address(wallet: &Pubkey) -> Pubkey {
+    pda(&[b"agent", wallet.as_ref()])
+}
+
+pub fn stake_mint_address(mint: &Pubkey) -> Pubkey {
+    pda(&[b"stake_mint", mint.as_ref()])
+}
+
+pub fn escrow_address(mint: &Pubkey) -> Pubkey {
+    pda(&[b"escrow", mint.as_ref()])
+}
+
+pub fn directory_address(owner: &Pubkey) -> Pubkey {
+    pda(&[b"service_directory", owner.as_ref()])
+}
+
+pub fn page_address(owner: &Pubkey, page: u32) -> Pubkey {
+    pda(&[b"service_page", owner.as_ref(), &page.to_le_bytes()])
+}
+
+pub fn service_address(owner: &Pubkey, index: u32) -> Pubkey {
+    pda(&[b"service", owner.as_ref(), &index.to_le_bytes()])
+}
+
+pub fn interaction_address(service: &Pubkey, agent: &Pubkey) -> Pubkey {
+    pda(&[b"interaction", service.as_ref(), agent.as_ref()])
+}
+
+pub fn rating_address(service: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    pda(&[b"rating", service.as_ref(), wallet.as_ref()])
+}
+
+pub fn proposal_address(id: u64) -> Pubkey {
+    pda(&[b"proposal", &id.to_le_bytes()])
+}
+
+pub fn credit_vault_address() -> Pubkey {
+    pda(&[b"credit_vault"])
+}
+
+pub fn credit_line_address(wallet: &Pubkey) -> Pubkey {
+    pda(&[b"credit", wallet.as_ref()])
+}
+
+impl Registry {
+    /// A deployed program whose config has not been initialized yet.
+    pub fn deployed() -> Self {
+        let mut ledger = Ledger::new();
+        let authority = ledger.new_wallet();
+        let program_data = ledger.deploy(authority);
+        let [oracle, verifier, tap, dao, arbiter, treasury] = [(); 6].map(|_| ledger.new_wallet());
+        let mint = ledger.create_mint(DECIMALS);
+        let treasury_tokens = ledger.create_token_account(&mint, &treasury, 0);
+
+        Self {
+            ledger,
+            authority,
+            oracle,
+            verifier,
+            tap,
+            dao,
+            arbiter,
+            treasury,
+            council: vec![dao, arbiter, verifier],
+            mint,
+            treasury_tokens,
+            program_data,
+        }
+    }
+
+    pub fn new() -> Self {
+        let mut registry = Self::deployed();
+        let params = registry.config_params();
+        registry.send(registry.initialize_config_ix(params)).unwrap();
+        let mint = registry.mint;
+        registry.send(registry.add_stake_mint_ix(&mint, 10_000)).unwrap();
+        registry
+    }
+
+    pub fn send(&mut self, instruction: Instruction) -> std::result::Result<(), ProgramError> {
+        self.ledger.send(instruction)
+    }
+
+    pub fn send_transaction(&mut self, instructions: &[Instruction]) -> std::result::Result<(), ProgramError> {
+        self.ledger.send_transaction(instructions)
+    }
+
+    pub fn config_params(&self) -> ConfigParams {
+        ConfigParams {
+            oracle_authority: self.oracle,
+            verifier_authority: self.verifier,
+            tap_authority: self.tap,
+            dao_authority: self.dao,
+            arbiter_authority: self.arbiter,
+            dao_treasury: self.treasury,
+            stake_token_mint: self.mint,
+            min_stake_amount: MIN_STAKE,
+            min_slash_amount: 1_000_000,
+            unstake_lock_seconds: UNSTAKE_LOCK,
+            restake_cooldown_seconds: RESTAKE_COOLDOWN,
+            transaction_rate_limit_seconds: RATE_LIMIT,
+            rating_update_cooldown_seconds: RATING_COOLDOWN,
+            slash_cooldown_seconds: SLASH_COOLDOWN,
+            credit_reputation_threshold: 5_000,
+            dispute_bond_lamports: DISPUTE_BOND,
+            dispute_response_seconds: DISPUTE_RESPONSE,
+            credit_fee_bps: CREDIT_FEE_BPS,
+            credit_repayment_seconds: CREDIT_REPAYMENT,
+            channel_challenge_seconds: CHANNEL_CHALLENGE,
+            health_report_interval_seconds: HEALTH_INTERVAL,
+            uptime_alpha_bps: 2_000,
+            reputation_half_life_seconds: 30 * 24 * 60 * 60,
+            reputation_value_unit: 1_000_000,
+            reputation_alpha_bps: 500,
+            reputation_max_alpha_bps: 2_000,
+            reputation_slash_penalty: SLASH_PENALTY,
+            rating_window_seconds: RATING_WINDOW,
+            council: self.council.clone(),
+            council_threshold: 2,
+            governance_timelock_seconds: TIMELOCK,
+        }
+    }
+
+    pub fn config(&self) -> RegistryConfig {
+        self.account(&config_address())
+    }
+
+    pub fn agent(&self, agent: &TestAgent) -> Agent {
+        self.account(&agent.address)
+    }
+
+    pub fn service(&self, service: &Pubkey) -> Service {
+        self.account(service)
+    }
+
+    pub fn initialize_config_ix(&self, params: ConfigParams) -> Instruction {
+        instruction(
+            accounts::InitializeConfig {
+                config: config_address(),
+                program: crate::ID,
+                program_data: self.program_data,
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            ix::InitializeConfig { params },
+        )
+    }
+
+    pub fn update_config_ix(&self, update: ConfigUpdate) -> Instruction {
+        instruction(
+            accounts::UpdateConfig {
+                config: config_address(),
+                authority: self.authority,
+            },
+            ix::UpdateConfig { update },
+        )
+    }
+
+    pub fn add_stake_mint_ix(&self, mint: &Pubkey, reputation_weight_bps: u32) -> Instruction {
+        instruction(
+            accounts::AddStakeMint {
+                config: config_address(),
+                stake_mint: stake_mint_address(mint),
+                escrow_account: escrow_address(mint),
+                mint: *mint,
+                payer: self.authority,
+                authority: self.authority,
+                system_program: system_program::ID,
+                token_program: token::ID,
+                rent: sysvar::rent::ID,
+            },
+            ix::AddStakeMint { reputation_weight_bps },
+        )
+    }
+
+    pub fn new_agent(&mut self) -> TestAgent {
+        let mint = self.mint;
+        self.new_agent_with_mint(&mint)
+    }
+
+    pub fn new_agent_with_mint(&mut self, mint: &Pubkey) -> TestAgent {
+        let wallet = self.new_wallet();
+        let tokens = self.create_token_account(mint, &wallet, WALLET_TOKENS);
+        TestAgent {
+            wallet,
+            address: agent_address(&wallet),
+            mint: *mint,
+            tokens,
+        }
+    }
+
+    pub fn register_agent_ix(&self, agent: &TestAgent, stake_amount: u64) -> Instruction {
+        instruction(
+            accounts::RegisterAgent {
+                config: config_address(),
+                agent: agent.address,
+                signer: agent.wallet,
+                stake_mint: stake_mint_address(&agent.mint),
+                mint: agent.mint,
+                staker_token_account: agent.tokens,
+                escrow_account: escrow_address(&agent.mint),
+                token_program: token::ID,
+                system_program: system_program::ID,
+            },
+            ix::RegisterAgent {
+                did: format!("did:x402:{}", agent.wallet),
+                visa_tap_cert: String::new(),
+                stake_amount,
+                metadata_uri: "https://example.com/agent.json".to_string(),
+            },
+        )
+    }
+
+    pub fn register_agent(&mut self) -> TestAgent {
+        let agent = self.new_agent();
+        self.send(self.register_agent_ix(&agent, STAKE)).unwrap();
+        agent
+    }
+
+    pub fn unstake_ix(&self, agent: &TestAgent) -> Instruction {
+        instruction(
+            accounts::UnstakeAgent {
+                config: config_address(),
+                agent: agent.address,
+                stake_mint: stake_mint_address(&agent.mint),
+                mint: agent.mint,
+                escrow_account: escrow_address(&agent.mint),
+                recipient_token_account: agent.tokens,
+                signer: agent.wallet,
+                token_program: token::ID,
+            },
+            ix::UnstakeAgent {},
+        )
+    }
+
+    pub fn register_service_ix(&self, owner: &TestAgent) -> Instruction {
+        let directory = directory_address(&owner.wallet);
+        let (next_index, next_page) = match self.exists(&directory) {
+            true => {
+                let directory: ServiceDirectory = self.account(&directory);
+                (directory.next_index, directory.next_page())
+            }
+            false => (0, 0),
+        };
+
+        instruction(
+            accounts::RegisterService {
+                config: config_address(),
+                directory,
+                page: page_address(&owner.wallet, next_page),
+                service: service_address(&owner.wallet, next_index),
+                agent: owner.address,
+                owner: owner.wallet,
+                system_program: system_program::ID,
+            },
+            ix::RegisterService {
+                url: format!("https://example.com/{}", next_index),
+                name: "Weather".to_string(),
+                description: "Forecasts".to_string(),
+                category: "data".to_string(),
+                price_per_call: 1_000,
+                accepted_tokens: vec![self.mint],
+            },
+        )
+    }
+
+    pub fn register_service(&mut self, owner: &TestAgent) -> Pubkey {
+        let register = self.register_service_ix(owner);
+        let service = register.accounts[3].pubkey;
+        self.send(register).unwrap();
+        service
+    }
+
+    pub fn record_transaction_ix(&self, agent: &TestAgent, service: &Pubkey, amount: u64, success: bool) -> Instruction {
+        instruction(
+            accounts::RecordTransaction {
+                config: config_address(),
+                agent: agent.address,
+                service: *service,
+                interaction: interaction_address(service, &agent.address),
+                authority: self.oracle,
+                system_program: system_program::ID,
+            },
+            ix::RecordTransaction {
+                amount,
+                success,
+                response_time_ms: 120,
+            },
+        )
+    }
+
+    pub fn record_transaction(&mut self, agent: &TestAgent, service: &Pubkey, amount: u64, success: bool) {
+        self.send(self.record_transaction_ix(agent, service, amount, success)).unwrap();
+        self.advance(RATE_LIMIT);
+    }
+
+    pub fn update_service_ix(&self, service: &Pubkey, owner: &Pubkey, data: impl anchor_lang::InstructionData) -> Instruction {
+        instruction(
+            accounts::UpdateService {
+                service: *service,
+                owner: *owner,
+            },
+            data,
+        )
+    }
+
+    pub fn create_proposal_ix(&self, proposer: &Pubkey, action: GovernanceAction, emergency: bool) -> Instruction {
+        instruction(
+            accounts::CreateProposal {
+                config: config_address(),
+                proposal: proposal_address(self.config().proposal_count),
+                proposer: *proposer,
+                system_program: system_program::ID,
+            },
+            ix::CreateProposal { action, emergency },
+        )
+    }
+
+    pub fn approve_proposal_ix(&self, proposal: &Pubkey, approver: &Pubkey) -> Instruction {
+        instruction(
+            accounts::ApproveProposal {
+                config: config_address(),
+                proposal: *proposal,
+                approver: *approver,
+            },
+            ix::ApproveProposal {},
+        )
+    }
+
+    pub fn create_proposal(&mut self, action: GovernanceAction, emergency: bool) -> Pubkey {
+        let proposal = proposal_address(self.config().proposal_count);
+        let proposer = self.council[0];
+        self.send(self.create_proposal_ix(&proposer, action, emergency)).unwrap();
+        proposal
+    }
+
+    /// Creates a proposal, approves it with a second council member and waits out the timelock.
+    pub fn approved_proposal(&mut self, action: GovernanceAction) -> Pubkey {
+        let proposal = self.create_proposal(action, false);
+        let approver = self.council[1];
+        self.send(self.approve_proposal_ix(&proposal, &approver)).unwrap();
+        self.advance(TIMELOCK);
+        proposal
+    }
+
+    pub fn governed_agent_ix(&self, proposal: &Pubkey, agent: &TestAgent, data: impl anchor_lang::InstructionData) -> Instruction {
+        instruction(
+            accounts::GovernedAgentAction {
+                config: config_address(),
+                proposal: *proposal,
+                agent: agent.address,
+                executor: self.dao,
+            },
+            data,
+        )
+    }
+
+    pub fn governed_service_ix(
+        &self,
+        proposal: &Pubkey,
+        service: &Pubkey,
+        data: impl anchor_lang::InstructionData,
+    ) -> Instruction {
+        instruction(
+            accounts::GovernedServiceAction {
+                config: config_address(),
+                proposal: *proposal,
+                service: *service,
+                executor: self.dao,
+            },
+            data,
+        )
+    }
+
+    pub fn governed_config_ix(&self, proposal: &Pubkey, data: impl anchor_lang::InstructionData) -> Instruction {
+        instruction(
+            accounts::GovernedConfigAction {
+                config: config_address(),
+                proposal: *proposal,
+                executor: self.dao,
+            },
+            data,
+        )
+    }
+
+    pub fn suspend_agent(&mut self, agent: &TestAgent) {
+        let proposal = self.approved_proposal(GovernanceAction::SuspendAgent { agent: agent.address });
+        self.send(self.governed_agent_ix(&proposal, agent, ix::SuspendAgent {})).unwrap();
+    }
+
+    pub fn ban_agent(&mut self, agent: &TestAgent) {
+        let proposal = self.approved_proposal(GovernanceAction::BanAgent { agent: agent.address });
+        self.send(self.governed_agent_ix(&proposal, agent, ix::BanAgent {})).unwrap();
+    }
+
+    pub fn suspend_service(&mut self, service: &Pubkey) {
+        let proposal = self.approved_proposal(GovernanceAction::SuspendService { service: *service });
+        self.send(self.governed_service_ix(&proposal, service, ix::SuspendService {})).unwrap();
+    }
+
+    /// Sends `instruction` without `signer`'s signature.
+    pub fn send_unsigned(&mut self, mut instruction: Instruction, signer: &Pubkey) -> std::result::Result<(), ProgramError> {
+        for meta in instruction.accounts.iter_mut().filter(|meta| meta.pubkey == *signer) {
+            meta.is_signer = false;
+        }
+        self.send(instruction)
+    }
+}
@@ -0,0 +1,459 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::InstructionData;
+
+use super::harness::{expect_error, instruction};
+use super::registry::*;
+use crate::state::*;
+use crate::{accounts, instruction as ix, Agent, ErrorCode, Service, ServiceStatus};
+
+impl Registry {
+    fn verify_service_ix(&self, service: &Pubkey, verifier: &Pubkey) -> Instruction {
+        instruction(
+            accounts::VerifyService {
+                config: config_address(),
+                service: *service,
+                verifier: *verifier,
+            },
+            ix::VerifyService {},
+        )
+    }
+
+    fn visa_tap_ix(&self, service: &Pubkey, authority: &Pubkey, verified: bool) -> Instruction {
+        instruction(
+            accounts::SetVisaTapVerified {
+                config: config_address(),
+                service: *service,
+                authority: *authority,
+            },
+            ix::SetVisaTapVerified { verified },
+        )
+    }
+
+    fn close_service_ix(&self, service: &Pubkey) -> Instruction {
+        let state = self.service(service);
+        instruction(
+            accounts::CloseService {
+                service: *service,
+                agent: agent_address(&state.owner),
+                directory: directory_address(&state.owner),
+                page: page_address(&state.owner, state.directory_page()),
+                recipient: state.owner,
+            },
+            ix::CloseService {},
+        )
+    }
+
+    fn close_suspended_service_ix(&self, proposal: &Pubkey, service: &Pubkey) -> Instruction {
+        let state = self.service(service);
+        instruction(
+            accounts::CloseSuspendedService {
+                config: config_address(),
+                service: *service,
+                agent: agent_address(&state.owner),
+                directory: directory_address(&state.owner),
+                page: page_address(&state.owner, state.directory_page()),
+                proposal: *proposal,
+                recipient: self.treasury,
+                executor: self.dao,
+            },
+            ix::CloseSuspendedService {},
+        )
+    }
+
+    fn migrate_service_ix(&self, service: &Pubkey, owner: &Pubkey, payer: &Pubkey) -> Instruction {
+        let directory = directory_address(owner);
+        let next_page = match self.exists(&directory) {
+            true => self.account::<ServiceDirectory>(&directory).next_page(),
+            false => 0,
+        };
+        instruction(
+            accounts::MigrateService {
+                service: *service,
+                directory,
+                page: page_address(owner, next_page),
+                payer: *payer,
+                system_program: system_program::ID,
+            },
+            ix::MigrateService { owner: *owner },
+        )
+    }
+
+    /// Writes a service as it was laid out before directories existed, without the trailing `directory_index`. Its
+    /// strings are at their limits, so the account has no padding to read the missing field from.
+    fn legacy_service(&mut self, owner: &TestAgent) -> Pubkey {
+        let service = Pubkey::new_unique();
+        let mut accepted_tokens = vec![self.mint];
+        accepted_tokens.extend((0..4).map(|_| Pubkey::new_unique()));
+        let state = Service {
+            url: "u".repeat(512),
+            name: "n".repeat(128),
+            description: "d".repeat(1024),
+            category: "c".repeat(64),
+            owner: owner.wallet,
+            price_per_call: 1_000,
+            accepted_tokens,
+            total_calls: 3,
+            successful_calls: 2,
+            total_revenue: 5_000,
+            total_response_time_sum: 0,
+            average_response_time_ms: 0,
+            uptime_percent: 100,
+            reputation_score: 0,
+            total_ratings: 0,
+            total_rating_sum: 0,
+            average_rating: 0,
+            verified: true,
+            visa_tap_verified: false,
+            created_at: self.now,
+            last_updated: self.now,
+            status: ServiceStatus::Active,
+            total_rating_weight: 0,
+            weighted_rating_sum: 0,
+            directory_index: None,
+        };
+        let mut data = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+        data.truncate(data.len() - 1);
+        assert_eq!(data.len(), 8 + Service::SPACE - 5);
+        self.set_raw(service, data, crate::ID);
+        self.update::<Agent>(&owner.address, |agent| agent.service_count += 1);
+        service
+    }
+}
+
+fn service_data() -> ix::RegisterService {
+    ix::RegisterService {
+        url: "https://example.com".to_string(),
+        name: "Weather".to_string(),
+        description: "Forecasts".to_string(),
+        category: "data".to_string(),
+        price_per_call: 1_000,
+        accepted_tokens: vec![Pubkey::new_unique()],
+    }
+}
+
+fn update_data(new_price_per_call: Option<u64>, new_url: Option<String>, new_description: Option<String>) -> ix::UpdateService {
+    ix::UpdateService {
+        new_price_per_call,
+        new_url,
+        new_description,
+    }
+}
+
+#[test]
+fn registers_a_service_into_the_owners_directory() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let service = registry.register_service(&owner);
+
+    let state = registry.service(&service);
+    assert_eq!(state.owner, owner.wallet);
+    assert_eq!(state.price_per_call, 1_000);
+    assert_eq!(state.accepted_tokens, vec![registry.mint]);
+    assert_eq!(state.uptime_percent, 100);
+    assert_eq!(state.directory_index, Some(0));
+    assert!(state.status == ServiceStatus::Active);
+    assert!(!state.verified);
+
+    let directory: ServiceDirectory = registry.account(&directory_address(&owner.wallet));
+    assert_eq!(directory.owner, owner.wallet);
+    assert_eq!(directory.next_index, 1);
+    assert_eq!(directory.service_count, 1);
+    let page: ServiceDirectoryPage = registry.account(&page_address(&owner.wallet, 0));
+    assert_eq!(page.services, vec![service]);
+    assert_eq!(registry.agent(&owner).service_count, 1);
+}
+
+#[test]
+fn starts_a_new_page_when_one_fills_up() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let services: Vec<Pubkey> = (0..=SERVICE_PAGE_SIZE).map(|_| registry.register_service(&owner)).collect();
+
+    let first: ServiceDirectoryPage = registry.account(&page_address(&owner.wallet, 0));
+    let second: ServiceDirectoryPage = registry.account(&page_address(&owner.wallet, 1));
+    assert_eq!(first.services, services[..SERVICE_PAGE_SIZE as usize]);
+    assert_eq!(second.page, 1);
+    assert_eq!(second.services, vec![services[SERVICE_PAGE_SIZE as usize]]);
+    assert_eq!(registry.service(&services[SERVICE_PAGE_SIZE as usize]).directory_index, Some(SERVICE_PAGE_SIZE));
+    assert_eq!(registry.agent(&owner).service_count, SERVICE_PAGE_SIZE + 1);
+}
+
+#[test]
+fn validates_service_listings() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+
+    type Edit = fn(&mut ix::RegisterService);
+    let cases: Vec<(Edit, ErrorCode)> = vec![
+        (|data| data.url = "u".repeat(513), ErrorCode::StringTooLong),
+        (|data| data.name = "n".repeat(129), ErrorCode::StringTooLong),
+        (|data| data.description = "d".repeat(1025), ErrorCode::StringTooLong),
+        (|data| data.category = "c".repeat(65), ErrorCode::StringTooLong),
+        (|data| data.price_per_call = 0, ErrorCode::InvalidPrice),
+        (|data| data.accepted_tokens = vec![], ErrorCode::InvalidTokenList),
+        (|data| data.accepted_tokens = vec![Pubkey::new_unique(); 6], ErrorCode::InvalidTokenList),
+    ];
+
+    for (edit, error) in cases {
+        let mut data = service_data();
+        edit(&mut data);
+        let mut register = registry.register_service_ix(&owner);
+        register.data = data.data();
+        expect_error(registry.send(register), error);
+    }
+    assert!(!registry.exists(&directory_address(&owner.wallet)));
+}
+
+#[test]
+fn only_active_staked_owners_list_services() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let other = registry.register_agent();
+
+    let mut register = registry.register_service_ix(&owner);
+    register.accounts[4].pubkey = other.address;
+    expect_error(registry.send(register), ErrorCode::UnauthorizedAccess);
+
+    registry.update::<Agent>(&owner.address, |agent| agent.staked_amount = MIN_STAKE - 1);
+    expect_error(registry.send(registry.register_service_ix(&owner)), ErrorCode::InsufficientStake);
+
+    registry.suspend_agent(&other);
+    expect_error(registry.send(registry.register_service_ix(&other)), ErrorCode::AgentSuspended);
+}
+
+#[test]
+fn updates_service_details() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let service = registry.register_service(&owner);
+    registry.advance(5);
+
+    let data = update_data(Some(2_000), Some("https://example.com/v2".to_string()), Some("Hourly".to_string()));
+    registry.send(registry.update_service_ix(&service, &owner.wallet, data)).unwrap();
+
+    let state = registry.service(&service);
+    assert_eq!(state.price_per_call, 2_000);
+    assert_eq!(state.url, "https://example.com/v2");
+    assert_eq!(state.description, "Hourly");
+    assert_eq!(state.last_updated, registry.now);
+
+    let data = update_data(Some(0), None, None);
+    expect_error(registry.send(registry.update_service_ix(&service, &owner.wallet, data)), ErrorCode::InvalidPrice);
+    let data = update_data(None, Some("u".repeat(513)), None);
+    expect_error(registry.send(registry.update_service_ix(&service, &owner.wallet, data)), ErrorCode::StringTooLong);
+    let data = update_data(None, None, Some("d".repeat(1025)));
+    expect_error(registry.send(registry.update_service_ix(&service, &owner.wallet, data)), ErrorCode::StringTooLong);
+
+    let stranger = registry.new_wallet();
+    let data = update_data(Some(1), None, None);
+    expect_error(registry.send(registry.update_service_ix(&service, &stranger, data)), ErrorCode::UnauthorizedAccess);
+
+    registry.suspend_service(&service);
+    let data = update_data(Some(1), None, None);
+    expect_error(registry.send(registry.update_service_ix(&service, &owner.wallet, data)), ErrorCode::ServiceSuspended);
+}
+
+#[test]
+fn pauses_unpauses_and_deprecates_services() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let service = registry.register_service(&owner);
+    let status = |registry: &Registry| registry.service(&service).status;
+
+    registry.send(registry.update_service_ix(&service, &owner.wallet, ix::PauseService {})).unwrap();
+    assert!(status(&registry) == ServiceStatus::Paused);
+    expect_error(
+        registry.send(registry.update_service_ix(&service, &owner.wallet, ix::PauseService {})),
+        ErrorCode::InvalidServiceStatus,
+    );
+
+    registry.send(registry.update_service_ix(&service, &owner.wallet, ix::UnpauseService {})).unwrap();
+    assert!(status(&registry) == ServiceStatus::Active);
+    expect_error(
+        registry.send(registry.update_service_ix(&service, &owner.wallet, ix::UnpauseService {})),
+        ErrorCode::InvalidServiceStatus,
+    );
+
+    let stranger = registry.new_wallet();
+    expect_error(
+        registry.send(registry.update_service_ix(&service, &stranger, ix::PauseService {})),
+        ErrorCode::UnauthorizedAccess,
+    );
+
+    registry.send(registry.update_service_ix(&service, &owner.wallet, ix::DeprecateService {})).unwrap();
+    assert!(status(&registry) == ServiceStatus::Deprecated);
+    expect_error(
+        registry.send(registry.update_service_ix(&service, &owner.wallet, ix::PauseService {})),
+        ErrorCode::InvalidServiceStatus,
+    );
+    expect_error(
+        registry.send(registry.update_service_ix(&service, &owner.wallet, ix::UnpauseService {})),
+        ErrorCode::InvalidServiceStatus,
+    );
+}
+
+#[test]
+fn suspends_and_unsuspends_services() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let service = registry.register_service(&owner);
+    let unsuspend = registry.approved_proposal(GovernanceAction::UnsuspendService { service });
+
+    expect_error(
+        registry.send(registry.governed_service_ix(&unsuspend, &service, ix::UnsuspendService {})),
+        ErrorCode::InvalidServiceStatus,
+    );
+
+    registry.suspend_service(&service);
+    assert!(registry.service(&service).status == ServiceStatus::Suspended);
+    expect_error(
+        registry.send(registry.update_service_ix(&service, &owner.wallet, ix::DeprecateService {})),
+        ErrorCode::ServiceSuspended,
+    );
+    expect_error(
+        registry.send(registry.update_service_ix(&service, &owner.wallet, ix::UnpauseService {})),
+        ErrorCode::InvalidServiceStatus,
+    );
+
+    let other = registry.register_service(&owner);
+    expect_error(
+        registry.send(registry.governed_service_ix(&unsuspend, &other, ix::UnsuspendService {})),
+        ErrorCode::ProposalActionMismatch,
+    );
+    registry.send(registry.governed_service_ix(&unsuspend, &service, ix::UnsuspendService {})).unwrap();
+    assert!(registry.service(&service).status == ServiceStatus::Active);
+}
+
+#[test]
+fn verifies_services() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let service = registry.register_service(&owner);
+    let (verifier, tap) = (registry.verifier, registry.tap);
+
+    expect_error(registry.send(registry.verify_service_ix(&service, &owner.wallet)), ErrorCode::UnauthorizedAccess);
+    registry.send(registry.verify_service_ix(&service, &verifier)).unwrap();
+    assert!(registry.service(&service).verified);
+
+    expect_error(registry.send(registry.visa_tap_ix(&service, &verifier, true)), ErrorCode::UnauthorizedAccess);
+    registry.send(registry.visa_tap_ix(&service, &tap, true)).unwrap();
+    assert!(registry.service(&service).visa_tap_verified);
+    registry.send(registry.visa_tap_ix(&service, &tap, false)).unwrap();
+    assert!(!registry.service(&service).visa_tap_verified);
+}
+
+#[test]
+fn closes_deprecated_services() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let kept = registry.register_service(&owner);
+    let service = registry.register_service(&owner);
+
+    expect_error(registry.send(registry.close_service_ix(&service)), ErrorCode::InvalidServiceStatus);
+
+    registry.send(registry.update_service_ix(&service, &owner.wallet, ix::DeprecateService {})).unwrap();
+    let stranger = registry.new_wallet();
+    let mut close = registry.close_service_ix(&service);
+    close.accounts[4].pubkey = stranger;
+    expect_error(registry.send(close), ErrorCode::UnauthorizedAccess);
+
+    let rent = registry.lamports(&service);
+    let balance = registry.lamports(&owner.wallet);
+    registry.send(registry.close_service_ix(&service)).unwrap();
+
+    assert!(!registry.exists(&service));
+    assert_eq!(registry.lamports(&owner.wallet), balance + rent);
+    assert_eq!(registry.agent(&owner).service_count, 1);
+    let directory: ServiceDirectory = registry.account(&directory_address(&owner.wallet));
+    assert_eq!(directory.service_count, 1);
+    assert_eq!(directory.next_index, 2);
+    let page: ServiceDirectoryPage = registry.account(&page_address(&owner.wallet, 0));
+    assert_eq!(page.services, vec![kept]);
+}
+
+#[test]
+fn closes_suspended_services_through_governance() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let service = registry.register_service(&owner);
+    let active = registry.register_service(&owner);
+
+    let proposal = registry.approved_proposal(GovernanceAction::CloseSuspendedService { service: active });
+    expect_error(
+        registry.send(registry.close_suspended_service_ix(&proposal, &active)),
+        ErrorCode::InvalidServiceStatus,
+    );
+
+    registry.suspend_service(&service);
+    expect_error(
+        registry.send(registry.close_suspended_service_ix(&proposal, &service)),
+        ErrorCode::ProposalActionMismatch,
+    );
+
+    let proposal = registry.approved_proposal(GovernanceAction::CloseSuspendedService { service });
+    let mut close = registry.close_suspended_service_ix(&proposal, &service);
+    close.accounts[6].pubkey = owner.wallet;
+    expect_error(registry.send(close), ErrorCode::UnauthorizedAccess);
+
+    let rent = registry.lamports(&service);
+    let treasury = registry.lamports(&registry.treasury);
+    registry.send(registry.close_suspended_service_ix(&proposal, &service)).unwrap();
+
+    assert!(!registry.exists(&service));
+    assert_eq!(registry.lamports(&registry.treasury), treasury + rent);
+    assert_eq!(registry.agent(&owner).service_count, 1);
+    let page: ServiceDirectoryPage = registry.account(&page_address(&owner.wallet, 0));
+    assert_eq!(page.services, vec![active]);
+    assert!(registry.account::<Proposal>(&proposal).status == ProposalStatus::Executed);
+}
+
+#[test]
+fn migrates_legacy_services_into_the_directory() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let listed = registry.register_service(&owner);
+    let legacy = registry.legacy_service(&owner);
+
+    expect_error(
+        registry.send(registry.update_service_ix(&legacy, &owner.wallet, ix::DeprecateService {})),
+        anchor_lang::error::ErrorCode::AccountDidNotDeserialize,
+    );
+
+    let payer = registry.new_wallet();
+    let other = registry.register_agent();
+    expect_error(
+        registry.send(registry.migrate_service_ix(&listed, &owner.wallet, &payer)),
+        ErrorCode::ServiceAlreadyListed,
+    );
+    expect_error(
+        registry.send(registry.migrate_service_ix(&owner.address, &owner.wallet, &payer)),
+        ErrorCode::InvalidAccountLayout,
+    );
+    expect_error(registry.send(registry.migrate_service_ix(&legacy, &other.wallet, &payer)), ErrorCode::InvalidPDA);
+    registry.send(registry.migrate_service_ix(&legacy, &owner.wallet, &payer)).unwrap();
+
+    assert_eq!(registry.state(&legacy).unwrap().data.len(), 8 + Service::SPACE);
+    let state = registry.service(&legacy);
+    assert_eq!(state.directory_index, Some(1));
+    assert_eq!(state.description.len(), 1024);
+    assert_eq!(state.accepted_tokens.len(), 5);
+    assert_eq!(state.total_revenue, 5_000);
+    assert!(state.verified);
+    let page: ServiceDirectoryPage = registry.account(&page_address(&owner.wallet, 0));
+    assert_eq!(page.services, vec![listed, legacy]);
+
+    expect_error(
+        registry.send(registry.migrate_service_ix(&legacy, &owner.wallet, &payer)),
+        ErrorCode::ServiceAlreadyListed,
+    );
+    registry.send(registry.update_service_ix(&legacy, &owner.wallet, ix::DeprecateService {})).unwrap();
+    registry.send(registry.close_service_ix(&legacy)).unwrap();
+    assert_eq!(registry.agent(&owner).service_count, 1);
+
+    registry.send(registry.update_service_ix(&listed, &owner.wallet, ix::DeprecateService {})).unwrap();
+    registry.update::<Service>(&listed, |service| service.directory_index = None);
+    expect_error(registry.send(registry.close_service_ix(&listed)), ErrorCode::ServiceNotListed);
+}
@@ -0,0 +1,344 @@
+use anchor_lang::prelude::*;
+use anchor_lang::AccountSerialize;
+
+use super::registry::*;
+use crate::state::*;
+use crate::{Agent, AgentStatus, Service, ServiceStatus};
+
+fn serialized_len(account: &impl AccountSerialize) -> usize {
+    let mut data = vec![];
+    account.try_serialize(&mut data).unwrap();
+    data.len()
+}
+
+fn text(len: usize) -> String {
+    "x".repeat(len)
+}
+
+fn keys(count: usize) -> Vec<Pubkey> {
+    (0..count).map(|_| Pubkey::new_unique()).collect()
+}
+
+fn largest_agent() -> Agent {
+    Agent {
+        wallet: Pubkey::new_unique(),
+        did: text(256),
+        visa_tap_cert: text(512),
+        reputation_score: u64::MAX,
+        total_spent: u64::MAX,
+        total_transactions: u64::MAX,
+        successful_transactions: u64::MAX,
+        disputes_won: u32::MAX,
+        disputes_lost: u32::MAX,
+        staked_amount: u64::MAX,
+        slashed_amount: u64::MAX,
+        credit_limit: u64::MAX,
+        credit_used: u64::MAX,
+        created_at: i64::MAX,
+        last_active: i64::MAX,
+        last_transaction_time: i64::MAX,
+        last_slashed_time: i64::MAX,
+        last_slash_evidence: text(256),
+        metadata_uri: text(256),
+        status: AgentStatus::Banned,
+        historical_min_reputation: u64::MAX,
+        service_count: u32::MAX,
+        stake_mint: Pubkey::new_unique(),
+    }
+}
+
+fn largest_service(directory_index: Option<u32>) -> Service {
+    Service {
+        url: text(512),
+        name: text(128),
+        description: text(1024),
+        category: text(64),
+        owner: Pubkey::new_unique(),
+        price_per_call: u64::MAX,
+        accepted_tokens: keys(5),
+        total_calls: u64::MAX,
+        successful_calls: u64::MAX,
+        total_revenue: u64::MAX,
+        total_response_time_sum: u64::MAX,
+        average_response_time_ms: u32::MAX,
+        uptime_percent: u8::MAX,
+        reputation_score: u64::MAX,
+        total_ratings: u32::MAX,
+        total_rating_sum: u64::MAX,
+        average_rating: u16::MAX,
+        verified: true,
+        visa_tap_verified: true,
+        created_at: i64::MAX,
+        last_updated: i64::MAX,
+        status: ServiceStatus::Suspended,
+        total_rating_weight: u64::MAX,
+        weighted_rating_sum: u64::MAX,
+        directory_index,
+    }
+}
+
+#[test]
+fn agent_space_excludes_the_discriminator() {
+    assert_eq!(serialized_len(&largest_agent()), 8 + Agent::SPACE);
+}
+
+#[test]
+fn service_space_excludes_the_discriminator() {
+    assert_eq!(serialized_len(&largest_service(Some(u32::MAX))), 8 + Service::SPACE);
+}
+
+#[test]
+fn config_space_fits_a_full_council() {
+    let config = RegistryConfig {
+        authority: Pubkey::new_unique(),
+        pending_authority: Some(Pubkey::new_unique()),
+        oracle_authority: Pubkey::new_unique(),
+        verifier_authority: Pubkey::new_unique(),
+        tap_authority: Pubkey::new_unique(),
+        dao_authority: Pubkey::new_unique(),
+        arbiter_authority: Pubkey::new_unique(),
+        dao_treasury: Pubkey::new_unique(),
+        stake_token_mint: Pubkey::new_unique(),
+        min_stake_amount: u64::MAX,
+        min_slash_amount: u64::MAX,
+        unstake_lock_seconds: i64::MAX,
+        restake_cooldown_seconds: i64::MAX,
+        transaction_rate_limit_seconds: i64::MAX,
+        rating_update_cooldown_seconds: i64::MAX,
+        slash_cooldown_seconds: i64::MAX,
+        credit_reputation_threshold: u64::MAX,
+        dispute_bond_lamports: u64::MAX,
+        dispute_response_seconds: i64::MAX,
+        credit_fee_bps: u16::MAX,
+        credit_repayment_seconds: i64::MAX,
+        channel_challenge_seconds: i64::MAX,
+        health_report_interval_seconds: i64::MAX,
+        uptime_alpha_bps: u16::MAX,
+        reputation_half_life_seconds: i64::MAX,
+        reputation_value_unit: u64::MAX,
+        reputation_alpha_bps: u16::MAX,
+        reputation_max_alpha_bps: u16::MAX,
+        reputation_slash_penalty: u64::MAX,
+        rating_window_seconds: i64::MAX,
+        council: keys(MAX_COUNCIL_MEMBERS),
+        council_threshold: u8::MAX,
+        governance_timelock_seconds: i64::MAX,
+        proposal_count: u64::MAX,
+        bump: u8::MAX,
+    };
+
+    assert_eq!(serialized_len(&config), RegistryConfig::SPACE);
+}
+
+#[test]
+fn proposal_space_fits_the_largest_action() {
+    let proposal = Proposal {
+        id: u64::MAX,
+        proposer: Pubkey::new_unique(),
+        action: GovernanceAction::UpdateCouncil {
+            council: keys(MAX_COUNCIL_MEMBERS),
+            threshold: u8::MAX,
+            timelock_seconds: i64::MAX,
+        },
+        emergency: true,
+        approvals: keys(MAX_COUNCIL_MEMBERS),
+        status: ProposalStatus::Cancelled,
+        created_at: i64::MAX,
+        executable_at: i64::MAX,
+        executed_at: i64::MAX,
+        bump: u8::MAX,
+    };
+
+    assert_eq!(serialized_len(&proposal), Proposal::SPACE);
+}
+
+#[test]
+fn dispute_space_fits_full_evidence_and_response() {
+    let dispute = Dispute {
+        payer: Pubkey::new_unique(),
+        provider: Pubkey::new_unique(),
+        service: Pubkey::new_unique(),
+        transaction_signature: [u8::MAX; 64],
+        evidence_uri: text(256),
+        response_uri: text(256),
+        bond_lamports: u64::MAX,
+        provider_bonded: true,
+        opened_at: i64::MAX,
+        response_deadline: i64::MAX,
+        resolved_at: i64::MAX,
+        status: DisputeStatus::ResolvedForProvider,
+        refund_amount: u64::MAX,
+        slash_amount: u64::MAX,
+        bump: u8::MAX,
+    };
+
+    assert_eq!(serialized_len(&dispute), Dispute::SPACE);
+}
+
+#[test]
+fn directory_page_space_fits_a_full_page() {
+    let page = ServiceDirectoryPage {
+        owner: Pubkey::new_unique(),
+        page: u32::MAX,
+        services: keys(SERVICE_PAGE_SIZE as usize),
+        bump: u8::MAX,
+    };
+
+    assert_eq!(serialized_len(&page), ServiceDirectoryPage::SPACE);
+}
+
+#[test]
+fn fixed_size_accounts_match_their_space() {
+    let key = Pubkey::new_unique;
+
+    assert_eq!(
+        serialized_len(&ServiceRating {
+            service: key(),
+            agent: key(),
+            rating: 0,
+            timestamp: 0,
+            last_update_time: 0,
+            weight: 0,
+        }),
+        ServiceRating::SPACE
+    );
+    assert_eq!(
+        serialized_len(&ServiceInteraction {
+            service: key(),
+            agent: key(),
+            transactions: 0,
+            successful_transactions: 0,
+            total_amount: 0,
+            first_at: 0,
+            last_at: 0,
+            bump: 0,
+        }),
+        ServiceInteraction::SPACE
+    );
+    assert_eq!(
+        serialized_len(&CreditLine {
+            agent: key(),
+            due_at: 0,
+            total_drawn: 0,
+            total_fees: 0,
+            total_repaid: 0,
+            total_seized: 0,
+            last_draw_at: 0,
+            bump: 0,
+        }),
+        CreditLine::SPACE
+    );
+    assert_eq!(
+        serialized_len(&PaymentEscrow {
+            payer: key(),
+            payee: key(),
+            service: key(),
+            mint: key(),
+            nonce: 0,
+            amount: 0,
+            deadline: 0,
+            created_at: 0,
+            settled_at: 0,
+            status: PaymentEscrowStatus::Funded,
+            bump: 0,
+            vault_bump: 0,
+        }),
+        PaymentEscrow::SPACE
+    );
+    assert_eq!(
+        serialized_len(&PaymentChannel {
+            payer: key(),
+            payee: key(),
+            service: key(),
+            mint: key(),
+            channel_id: 0,
+            deposit: 0,
+            redeemed: 0,
+            last_nonce: 0,
+            challenge_seconds: 0,
+            opened_at: 0,
+            close_requested_at: 0,
+            status: ChannelStatus::Open,
+            bump: 0,
+            vault_bump: 0,
+        }),
+        PaymentChannel::SPACE
+    );
+    assert_eq!(
+        serialized_len(&StakeMint {
+            mint: key(),
+            token_program: key(),
+            decimals: 0,
+            reputation_weight_bps: 0,
+            enabled: true,
+            bump: 0,
+            escrow_bump: 0,
+        }),
+        StakeMint::SPACE
+    );
+    assert_eq!(
+        serialized_len(&ProbeOperator {
+            operator: key(),
+            added_at: 0,
+            reports: 0,
+            bump: 0,
+        }),
+        ProbeOperator::SPACE
+    );
+    assert_eq!(
+        serialized_len(&ServiceHealth {
+            service: key(),
+            uptime_bps: 0,
+            latency_ms: 0,
+            total_probes: 0,
+            successful_probes: 0,
+            reports: 0,
+            last_operator: key(),
+            last_window_end: 0,
+            last_reported_at: 0,
+            bump: 0,
+        }),
+        ServiceHealth::SPACE
+    );
+    assert_eq!(
+        serialized_len(&TransactionReceipt {
+            service: key(),
+            agent: key(),
+            nonce: 0,
+            amount: 0,
+            success: true,
+            recorded_at: 0,
+            bump: 0,
+        }),
+        TransactionReceipt::SPACE
+    );
+    assert_eq!(
+        serialized_len(&ServiceDirectory {
+            owner: key(),
+            next_index: 0,
+            service_count: 0,
+            bump: 0,
+        }),
+        ServiceDirectory::SPACE
+    );
+}
+
+#[test]
+fn instructions_allocate_the_declared_space() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let service = registry.register_service(&owner);
+    let payer = registry.register_agent();
+    registry.record_transaction(&payer, &service, 1_000_000, true);
+    let proposal = registry.create_proposal(GovernanceAction::SuspendService { service }, false);
+
+    let allocated = |key: &Pubkey| registry.state(key).unwrap().data.len();
+    assert_eq!(allocated(&config_address()), RegistryConfig::SPACE);
+    assert_eq!(allocated(&stake_mint_address(&registry.mint)), StakeMint::SPACE);
+    assert_eq!(allocated(&owner.address), 8 + Agent::SPACE);
+    assert_eq!(allocated(&service), 8 + Service::SPACE);
+    assert_eq!(allocated(&directory_address(&owner.wallet)), ServiceDirectory::SPACE);
+    assert_eq!(allocated(&page_address(&owner.wallet, 0)), ServiceDirectoryPage::SPACE);
+    assert_eq!(allocated(&interaction_address(&service, &payer.address)), ServiceInteraction::SPACE);
+    assert_eq!(allocated(&proposal), Proposal::SPACE);
+}
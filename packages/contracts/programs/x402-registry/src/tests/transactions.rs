@@ -0,0 +1,301 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::{system_program, sysvar};
+
+use super::harness::{expect_error, instruction, pda, PRECOMPILE_INVALID_SIGNATURE};
+use super::registry::*;
+use crate::state::*;
+use crate::{accounts, instruction as ix, receipt_message, Agent, ErrorCode, ServiceStatus};
+
+const AMOUNT: u64 = 10_000_000;
+
+fn receipt_address(service: &Pubkey, wallet: &Pubkey, nonce: u64) -> Pubkey {
+    pda(&[b"receipt", service.as_ref(), wallet.as_ref(), &nonce.to_le_bytes()])
+}
+
+impl Registry {
+    fn cosigned_ix(&self, agent: &TestAgent, service: &Pubkey, amount: u64, nonce: u64) -> Instruction {
+        instruction(
+            accounts::RecordCosignedTransaction {
+                config: config_address(),
+                agent: agent.address,
+                service: *service,
+                interaction: interaction_address(service, &agent.address),
+                receipt: receipt_address(service, &agent.wallet, nonce),
+                payment_escrow: None,
+                payer: agent.wallet,
+                instructions_sysvar: sysvar::instructions::ID,
+                system_program: system_program::ID,
+            },
+            ix::RecordCosignedTransaction {
+                amount,
+                success: true,
+                response_time_ms: 80,
+                nonce,
+            },
+        )
+    }
+
+    /// The payer's and owner's signatures over the receipt, followed by the instruction that records it.
+    fn cosigned_transaction(&self, agent: &TestAgent, service: &Pubkey, amount: u64, nonce: u64) -> Vec<Instruction> {
+        let owner = self.service(service).owner;
+        let message = receipt_message(service, &agent.wallet, amount, true, 80, nonce);
+        vec![
+            self.ed25519_instruction(&agent.wallet, &message),
+            self.ed25519_instruction(&owner, &message),
+            self.cosigned_ix(agent, service, amount, nonce),
+        ]
+    }
+}
+
+fn marketplace() -> (Registry, TestAgent, Pubkey) {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let service = registry.register_service(&owner);
+    let agent = registry.register_agent();
+    (registry, agent, service)
+}
+
+#[test]
+fn records_a_successful_transaction() {
+    let (mut registry, agent, service) = marketplace();
+    registry.send(registry.record_transaction_ix(&agent, &service, AMOUNT, true)).unwrap();
+
+    let state = registry.agent(&agent);
+    assert_eq!(state.total_transactions, 1);
+    assert_eq!(state.successful_transactions, 1);
+    assert_eq!(state.total_spent, AMOUNT);
+    assert!(state.reputation_score > 7_000);
+    assert_eq!(state.credit_limit, AMOUNT / 10);
+    assert_eq!(state.last_transaction_time, registry.now);
+
+    let service_state = registry.service(&service);
+    assert_eq!(service_state.total_calls, 1);
+    assert_eq!(service_state.successful_calls, 1);
+    assert_eq!(service_state.total_revenue, AMOUNT);
+    assert_eq!(service_state.average_response_time_ms, 120);
+
+    let interaction: ServiceInteraction = registry.account(&interaction_address(&service, &agent.address));
+    assert_eq!(interaction.service, service);
+    assert_eq!(interaction.agent, agent.address);
+    assert_eq!(interaction.transactions, 1);
+    assert_eq!(interaction.successful_transactions, 1);
+    assert_eq!(interaction.total_amount, AMOUNT);
+    assert_eq!(interaction.first_at, registry.now);
+}
+
+#[test]
+fn failed_transactions_lower_reputation() {
+    let (mut registry, agent, service) = marketplace();
+    registry.record_transaction(&agent, &service, AMOUNT, false);
+
+    let state = registry.agent(&agent);
+    assert_eq!(state.total_transactions, 1);
+    assert_eq!(state.successful_transactions, 0);
+    assert_eq!(state.total_spent, 0);
+    assert!(state.reputation_score < 7_000);
+
+    let service_state = registry.service(&service);
+    assert_eq!(service_state.total_calls, 1);
+    assert_eq!(service_state.total_revenue, 0);
+
+    registry.record_transaction(&agent, &service, AMOUNT, true);
+    let interaction: ServiceInteraction = registry.account(&interaction_address(&service, &agent.address));
+    assert_eq!(interaction.transactions, 2);
+    assert_eq!(interaction.successful_transactions, 1);
+    assert_eq!(interaction.last_at, registry.now - RATE_LIMIT);
+}
+
+#[test]
+fn only_the_oracle_records_transactions() {
+    let (mut registry, agent, service) = marketplace();
+    let mut record = registry.record_transaction_ix(&agent, &service, AMOUNT, true);
+    record.accounts[4].pubkey = agent.wallet;
+
+    expect_error(registry.send(record), ErrorCode::UnauthorizedAccess);
+}
+
+#[test]
+fn rate_limits_transactions_per_agent() {
+    let (mut registry, agent, service) = marketplace();
+    registry.send(registry.record_transaction_ix(&agent, &service, AMOUNT, true)).unwrap();
+
+    registry.advance(RATE_LIMIT - 1);
+    expect_error(
+        registry.send(registry.record_transaction_ix(&agent, &service, AMOUNT, true)),
+        ErrorCode::RateLimitExceeded,
+    );
+    registry.advance(1);
+    registry.send(registry.record_transaction_ix(&agent, &service, AMOUNT, true)).unwrap();
+}
+
+#[test]
+fn requires_an_active_service_and_agent() {
+    let (mut registry, agent, service) = marketplace();
+    let owner = registry.service(&service).owner;
+
+    registry.send(registry.update_service_ix(&service, &owner, ix::PauseService {})).unwrap();
+    expect_error(
+        registry.send(registry.record_transaction_ix(&agent, &service, AMOUNT, true)),
+        ErrorCode::ServiceNotActive,
+    );
+    registry.send(registry.update_service_ix(&service, &owner, ix::UnpauseService {})).unwrap();
+
+    registry.suspend_agent(&agent);
+    expect_error(
+        registry.send(registry.record_transaction_ix(&agent, &service, AMOUNT, true)),
+        ErrorCode::AgentSuspended,
+    );
+    assert!(registry.service(&service).status == ServiceStatus::Active);
+}
+
+#[test]
+fn rejects_agents_outside_their_pda() {
+    let (mut registry, agent, service) = marketplace();
+    let impostor = TestAgent {
+        address: Pubkey::new_unique(),
+        ..registry.new_agent()
+    };
+    let state: Agent = registry.agent(&agent);
+    registry.set_account(impostor.address, &state, 8 + Agent::SPACE);
+
+    expect_error(
+        registry.send(registry.record_transaction_ix(&impostor, &service, AMOUNT, true)),
+        ErrorCode::InvalidPDA,
+    );
+}
+
+#[test]
+fn records_cosigned_receipts() {
+    let (mut registry, agent, service) = marketplace();
+    registry.send_transaction(&registry.cosigned_transaction(&agent, &service, AMOUNT, 7)).unwrap();
+
+    let receipt: TransactionReceipt = registry.account(&receipt_address(&service, &agent.wallet, 7));
+    assert_eq!(receipt.service, service);
+    assert_eq!(receipt.agent, agent.wallet);
+    assert_eq!(receipt.nonce, 7);
+    assert_eq!(receipt.amount, AMOUNT);
+    assert!(receipt.success);
+    assert_eq!(receipt.recorded_at, registry.now);
+    assert_eq!(registry.state(&receipt_address(&service, &agent.wallet, 7)).unwrap().data.len(), TransactionReceipt::SPACE);
+
+    let state = registry.agent(&agent);
+    assert_eq!(state.total_transactions, 1);
+    assert_eq!(state.successful_transactions, 1);
+    assert_eq!(state.total_spent, 0);
+    assert_eq!(state.credit_limit, 0);
+    assert_eq!(registry.service(&service).average_response_time_ms, 80);
+
+    registry.advance(RATE_LIMIT);
+    assert!(registry.send_transaction(&registry.cosigned_transaction(&agent, &service, AMOUNT, 7)).is_err());
+    registry.send_transaction(&registry.cosigned_transaction(&agent, &service, AMOUNT, 8)).unwrap();
+    assert_eq!(registry.agent(&agent).total_transactions, 2);
+}
+
+#[test]
+fn cosigned_receipts_need_both_signatures() {
+    let (mut registry, agent, service) = marketplace();
+    let transaction = registry.cosigned_transaction(&agent, &service, AMOUNT, 1);
+
+    expect_error(
+        registry.send(transaction[2].clone()),
+        ErrorCode::MissingSignatureVerification,
+    );
+    expect_error(
+        registry.send_transaction(&transaction[1..]),
+        ErrorCode::MissingSignatureVerification,
+    );
+
+    let mut swapped = transaction.clone();
+    swapped.swap(0, 1);
+    expect_error(registry.send_transaction(&swapped), ErrorCode::InvalidSignatureVerification);
+
+    let stranger = registry.new_wallet();
+    let mut forged = transaction.clone();
+    forged[1] = registry.ed25519_instruction(&stranger, &receipt_message(&service, &agent.wallet, AMOUNT, true, 80, 1));
+    expect_error(registry.send_transaction(&forged), ErrorCode::InvalidSignatureVerification);
+
+    let mut tampered = transaction.clone();
+    tampered[1].data[48] ^= 1;
+    expect_error(registry.send_transaction(&tampered), PRECOMPILE_INVALID_SIGNATURE);
+
+    let mut inflated = transaction.clone();
+    inflated[2] = registry.cosigned_ix(&agent, &service, 2 * AMOUNT, 1);
+    expect_error(registry.send_transaction(&inflated), ErrorCode::InvalidSignatureVerification);
+
+    let mut misplaced = transaction;
+    misplaced[0] = registry.record_transaction_ix(&agent, &service, AMOUNT, true);
+    expect_error(registry.send_transaction(&misplaced), ErrorCode::MissingSignatureVerification);
+    assert!(!registry.exists(&receipt_address(&service, &agent.wallet, 1)));
+}
+
+#[test]
+fn cosigned_spend_counts_only_up_to_a_released_escrow() {
+    let (mut registry, agent, service) = marketplace();
+    let (escrow, bump) = Pubkey::find_program_address(
+        &[b"payment_escrow", agent.wallet.as_ref(), service.as_ref(), &1u64.to_le_bytes()],
+        &crate::ID,
+    );
+    let state = PaymentEscrow {
+        payer: agent.wallet,
+        payee: registry.service(&service).owner,
+        service,
+        mint: registry.mint,
+        nonce: 1,
+        amount: AMOUNT / 2,
+        deadline: registry.now,
+        created_at: registry.now,
+        settled_at: 0,
+        status: PaymentEscrowStatus::Funded,
+        bump,
+        vault_bump: 0,
+    };
+    registry.set_account(escrow, &state, PaymentEscrow::SPACE);
+
+    let mut transaction = registry.cosigned_transaction(&agent, &service, AMOUNT, 1);
+    transaction[2].accounts[5] = AccountMeta::new_readonly(escrow, false);
+    expect_error(registry.send_transaction(&transaction), ErrorCode::PaymentEscrowNotReleased);
+
+    registry.update::<PaymentEscrow>(&escrow, |state| state.status = PaymentEscrowStatus::Released);
+    registry.send_transaction(&transaction).unwrap();
+    assert_eq!(registry.agent(&agent).total_spent, AMOUNT / 2);
+    assert!(registry.agent(&agent).reputation_score > 7_000);
+    assert_eq!(registry.service(&service).total_revenue, AMOUNT / 2);
+
+    registry.advance(RATE_LIMIT);
+    let mut other_payment = registry.cosigned_transaction(&agent, &service, AMOUNT, 2);
+    other_payment[2].accounts[5] = AccountMeta::new_readonly(escrow, false);
+    assert!(registry.send_transaction(&other_payment).is_err());
+    assert_eq!(registry.agent(&agent).total_spent, AMOUNT / 2);
+}
+
+#[test]
+fn unfunded_cosigned_receipts_do_not_raise_reputation() {
+    let (mut registry, agent, service) = marketplace();
+    let reputation = registry.agent(&agent).reputation_score;
+
+    registry.send_transaction(&registry.cosigned_transaction(&agent, &service, u64::MAX, 1)).unwrap();
+    registry.advance(RATE_LIMIT);
+    registry.send_transaction(&registry.cosigned_transaction(&agent, &service, u64::MAX, 2)).unwrap();
+
+    let state = registry.agent(&agent);
+    assert_eq!(state.successful_transactions, 2);
+    assert_eq!(state.reputation_score, reputation);
+    assert_eq!(registry.service(&service).total_revenue, 0);
+    let interaction: ServiceInteraction = registry.account(&interaction_address(&service, &agent.address));
+    assert_eq!(interaction.successful_transactions, 2);
+    assert_eq!(interaction.total_amount, 0);
+}
+
+#[test]
+fn cosigned_receipts_reject_self_payment() {
+    let mut registry = Registry::new();
+    let owner = registry.register_agent();
+    let service = registry.register_service(&owner);
+
+    expect_error(
+        registry.send_transaction(&registry.cosigned_transaction(&owner, &service, AMOUNT, 1)),
+        ErrorCode::SelfPaymentNotAllowed,
+    );
+    assert_eq!(registry.agent(&owner).total_transactions, 0);
+}
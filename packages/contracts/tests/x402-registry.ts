@@ -1,8 +1,22 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { X402Registry } from "../target/types/x402_registry";
-import { PublicKey, Keypair, LAMPORTS_PER_SOL, SystemProgram } from "@solana/web3.js";
-import { TOKEN_PROGRAM_ID, createMint, createAccount, mintTo, getAccount } from "@solana/spl-token";
+import {
+  PublicKey,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  SystemProgram,
+  Ed25519Program,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  createMint,
+  createAccount,
+  mintTo,
+  getAccount,
+} from "@solana/spl-token";
 import { assert } from "chai";
 
 describe("x402-registry", () => {
@@ -13,23 +27,99 @@ describe("x402-registry", () => {
 
   let mint: PublicKey;
   let userTokenAccount: PublicKey;
+  let stakeMintPda: PublicKey;
   let escrowAccount: PublicKey;
   let daoTreasuryAccount: PublicKey;
 
   const user = Keypair.generate();
-  const oracleAuthority = Keypair.fromSecretKey(
-    new Uint8Array([/* oracle private key matching ORACLE_AUTHORITY */])
-  );
-  const verifierAuthority = Keypair.fromSecretKey(
-    new Uint8Array([/* verifier private key matching VERIFIER_AUTHORITY */])
-  );
-  const tapAuthority = Keypair.fromSecretKey(
-    new Uint8Array([/* TAP private key matching TAP_AUTHORITY */])
-  );
-  const daoAuthority = Keypair.fromSecretKey(
-    new Uint8Array([/* DAO private key matching DAO_AUTHORITY */])
+  const oracleAuthority = Keypair.generate();
+  const verifierAuthority = Keypair.generate();
+  const tapAuthority = Keypair.generate();
+  const daoAuthority = Keypair.generate();
+  const arbiterAuthority = Keypair.generate();
+
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
   );
 
+  const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+  const proposalAddress = (id: number) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("proposal"), new anchor.BN(id).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  const createProposal = async (action, emergency = false) => {
+    const { proposalCount } = await program.account.registryConfig.fetch(configPda);
+    const proposal = proposalAddress(proposalCount.toNumber());
+
+    await program.methods
+      .createProposal(action, emergency)
+      .accounts({
+        config: configPda,
+        proposal,
+        proposer: daoAuthority.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([daoAuthority])
+      .rpc();
+
+    return proposal;
+  };
+
+  const approvedProposal = async (action, emergency = false) => {
+    const proposal = await createProposal(action, emergency);
+
+    await program.methods
+      .approveProposal()
+      .accounts({
+        config: configPda,
+        proposal,
+        approver: arbiterAuthority.publicKey,
+      })
+      .signers([arbiterAuthority])
+      .rpc();
+
+    return proposal;
+  };
+
+  const servicePage = (owner: PublicKey, index: number) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("service_page"),
+        owner.toBuffer(),
+        new anchor.BN(Math.floor(index / 32)).toArrayLike(Buffer, "le", 4),
+      ],
+      program.programId
+    )[0];
+
+  const serviceAccounts = async (owner: PublicKey) => {
+    const [directory] = PublicKey.findProgramAddressSync(
+      [Buffer.from("service_directory"), owner.toBuffer()],
+      program.programId
+    );
+    const existing = await program.account.serviceDirectory.fetchNullable(directory);
+    const index = existing ? existing.nextIndex : 0;
+    const [service] = PublicKey.findProgramAddressSync(
+      [Buffer.from("service"), owner.toBuffer(), new anchor.BN(index).toArrayLike(Buffer, "le", 4)],
+      program.programId
+    );
+
+    return { service, directory, page: servicePage(owner, index) };
+  };
+
+  const serviceListing = async (service: PublicKey) => {
+    const { owner, directoryIndex } = await program.account.service.fetch(service);
+    const [directory] = PublicKey.findProgramAddressSync(
+      [Buffer.from("service_directory"), owner.toBuffer()],
+      program.programId
+    );
+
+    return { directory, page: servicePage(owner, directoryIndex) };
+  };
+
   before(async () => {
     const airdropSignature = await provider.connection.requestAirdrop(
       user.publicKey,
@@ -37,6 +127,12 @@ describe("x402-registry", () => {
     );
     await provider.connection.confirmTransaction(airdropSignature);
 
+    const daoAirdropSignature = await provider.connection.requestAirdrop(
+      daoAuthority.publicKey,
+      2 * LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(daoAirdropSignature);
+
     mint = await createMint(
       provider.connection,
       user,
@@ -52,10 +148,12 @@ describe("x402-registry", () => {
       user.publicKey
     );
 
-    escrowAccount = await createAccount(
-      provider.connection,
-      user,
-      mint,
+    [stakeMintPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stake_mint"), mint.toBuffer()],
+      program.programId
+    );
+    [escrowAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), mint.toBuffer()],
       program.programId
     );
 
@@ -74,6 +172,281 @@ describe("x402-registry", () => {
       user,
       10_000_000_000
     );
+
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+
+    await program.methods
+      .initializeConfig({
+        oracleAuthority: oracleAuthority.publicKey,
+        verifierAuthority: verifierAuthority.publicKey,
+        tapAuthority: tapAuthority.publicKey,
+        daoAuthority: daoAuthority.publicKey,
+        arbiterAuthority: arbiterAuthority.publicKey,
+        daoTreasury: daoAuthority.publicKey,
+        stakeTokenMint: mint,
+        minStakeAmount: new anchor.BN(1_000_000_000),
+        minSlashAmount: new anchor.BN(1_000_000),
+        unstakeLockSeconds: new anchor.BN(30 * 24 * 60 * 60),
+        restakeCooldownSeconds: new anchor.BN(7 * 24 * 60 * 60),
+        transactionRateLimitSeconds: new anchor.BN(10),
+        ratingUpdateCooldownSeconds: new anchor.BN(7 * 24 * 60 * 60),
+        slashCooldownSeconds: new anchor.BN(24 * 60 * 60),
+        creditReputationThreshold: new anchor.BN(9000),
+        disputeBondLamports: new anchor.BN(100_000_000),
+        disputeResponseSeconds: new anchor.BN(3 * 24 * 60 * 60),
+        creditFeeBps: 100,
+        creditRepaymentSeconds: new anchor.BN(30 * 24 * 60 * 60),
+        channelChallengeSeconds: new anchor.BN(24 * 60 * 60),
+        healthReportIntervalSeconds: new anchor.BN(5 * 60),
+        uptimeAlphaBps: 2000,
+        reputationHalfLifeSeconds: new anchor.BN(30 * 24 * 60 * 60),
+        reputationValueUnit: new anchor.BN(1_000_000),
+        reputationAlphaBps: 500,
+        reputationMaxAlphaBps: 2000,
+        reputationSlashPenalty: new anchor.BN(1000),
+        ratingWindowSeconds: new anchor.BN(180 * 24 * 60 * 60),
+        council: [daoAuthority.publicKey, arbiterAuthority.publicKey, verifierAuthority.publicKey],
+        councilThreshold: 2,
+        governanceTimelockSeconds: new anchor.BN(2),
+      })
+      .accounts({
+        config: configPda,
+        programData,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .addStakeMint(10_000)
+      .accounts({
+        config: configPda,
+        stakeMint: stakeMintPda,
+        escrowAccount,
+        mint,
+        payer: provider.wallet.publicKey,
+        authority: provider.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+  });
+
+  describe("config", () => {
+    it("stores the initial parameters", async () => {
+      const config = await program.account.registryConfig.fetch(configPda);
+      assert.ok(config.authority.equals(provider.wallet.publicKey));
+      assert.ok(config.stakeTokenMint.equals(mint));
+      assert.equal(config.minStakeAmount.toNumber(), 1_000_000_000);
+    });
+
+    it("rejects updates from a non-authority", async () => {
+      try {
+        await program.methods
+          .updateConfig({
+            oracleAuthority: null,
+            verifierAuthority: null,
+            tapAuthority: null,
+            daoTreasury: null,
+            minStakeAmount: new anchor.BN(1),
+            minSlashAmount: null,
+            unstakeLockSeconds: null,
+            restakeCooldownSeconds: null,
+            transactionRateLimitSeconds: null,
+            ratingUpdateCooldownSeconds: null,
+            slashCooldownSeconds: null,
+            creditReputationThreshold: null,
+            disputeBondLamports: null,
+            disputeResponseSeconds: null,
+            creditFeeBps: null,
+            creditRepaymentSeconds: null,
+            channelChallengeSeconds: null,
+            healthReportIntervalSeconds: null,
+            uptimeAlphaBps: null,
+            reputationHalfLifeSeconds: null,
+            reputationValueUnit: null,
+            reputationAlphaBps: null,
+            reputationMaxAlphaBps: null,
+            reputationSlashPenalty: null,
+            ratingWindowSeconds: null,
+          })
+          .accounts({ config: configPda, authority: user.publicKey })
+          .signers([user])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "UnauthorizedAccess");
+      }
+    });
+
+    it("transfers authority in two steps", async () => {
+      const newAuthority = Keypair.generate();
+
+      await program.methods
+        .transferConfigAuthority(newAuthority.publicKey)
+        .accounts({ config: configPda, authority: provider.wallet.publicKey })
+        .rpc();
+
+      let config = await program.account.registryConfig.fetch(configPda);
+      assert.ok(config.authority.equals(provider.wallet.publicKey));
+      assert.ok(config.pendingAuthority.equals(newAuthority.publicKey));
+
+      await program.methods
+        .acceptConfigAuthority()
+        .accounts({ config: configPda, newAuthority: newAuthority.publicKey })
+        .signers([newAuthority])
+        .rpc();
+
+      config = await program.account.registryConfig.fetch(configPda);
+      assert.ok(config.authority.equals(newAuthority.publicKey));
+      assert.isNull(config.pendingAuthority);
+
+      await program.methods
+        .transferConfigAuthority(provider.wallet.publicKey)
+        .accounts({ config: configPda, authority: newAuthority.publicKey })
+        .signers([newAuthority])
+        .rpc();
+      await program.methods
+        .acceptConfigAuthority()
+        .accounts({ config: configPda, newAuthority: provider.wallet.publicKey })
+        .rpc();
+    });
+  });
+
+  describe("stake_mints", () => {
+    const staker = Keypair.generate();
+    let token2022Mint: PublicKey;
+    let token2022StakeMint: PublicKey;
+    let token2022Escrow: PublicKey;
+
+    before(async () => {
+      const airdropSig = await provider.connection.requestAirdrop(
+        staker.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropSig);
+
+      token2022Mint = await createMint(
+        provider.connection,
+        user,
+        user.publicKey,
+        null,
+        6,
+        undefined,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      [token2022StakeMint] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_mint"), token2022Mint.toBuffer()],
+        program.programId
+      );
+      [token2022Escrow] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), token2022Mint.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("rejects stake mints added by a non-authority", async () => {
+      try {
+        await program.methods
+          .addStakeMint(20_000)
+          .accounts({
+            config: configPda,
+            stakeMint: token2022StakeMint,
+            escrowAccount: token2022Escrow,
+            mint: token2022Mint,
+            payer: user.publicKey,
+            authority: user.publicKey,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .signers([user])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "UnauthorizedAccess");
+      }
+    });
+
+    it("stakes a Token-2022 mint and weights it into reputation", async () => {
+      await program.methods
+        .addStakeMint(20_000)
+        .accounts({
+          config: configPda,
+          stakeMint: token2022StakeMint,
+          escrowAccount: token2022Escrow,
+          mint: token2022Mint,
+          payer: provider.wallet.publicKey,
+          authority: provider.wallet.publicKey,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .rpc();
+
+      const stakerTokenAccount = await createAccount(
+        provider.connection,
+        staker,
+        token2022Mint,
+        staker.publicKey,
+        undefined,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+      await mintTo(
+        provider.connection,
+        user,
+        token2022Mint,
+        stakerTokenAccount,
+        user,
+        500_000_000,
+        [],
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      const [stakerAgentPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("agent"), staker.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .registerAgent("did:x402:token2022", "cert", new anchor.BN(500_000_000), "https://metadata.example.com")
+        .accounts({
+          agent: stakerAgentPda,
+          signer: staker.publicKey,
+          stakeMint: token2022StakeMint,
+          mint: token2022Mint,
+          stakerTokenAccount,
+          escrowAccount: token2022Escrow,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([staker])
+        .rpc();
+
+      const agent = await program.account.agent.fetch(stakerAgentPda);
+      assert.ok(agent.stakeMint.equals(token2022Mint));
+      assert.equal(agent.stakedAmount.toNumber(), 500_000_000);
+      assert.equal(agent.reputationScore.toNumber(), 5000);
+
+      const escrow = await getAccount(provider.connection, token2022Escrow, undefined, TOKEN_2022_PROGRAM_ID);
+      assert.equal(Number(escrow.amount), 500_000_000);
+    });
+
+    it("blocks new stake in a disabled mint", async () => {
+      await program.methods
+        .updateStakeMint({ reputationWeightBps: null, enabled: false })
+        .accounts({
+          config: configPda,
+          stakeMint: token2022StakeMint,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const stakeMint = await program.account.stakeMint.fetch(token2022StakeMint);
+      assert.equal(stakeMint.enabled, false);
+      assert.equal(stakeMint.reputationWeightBps, 20_000);
+    });
   });
 
   describe("register_agent", () => {
@@ -94,6 +467,8 @@ describe("x402-registry", () => {
           agent: agentPda,
           signer: user.publicKey,
           stakerTokenAccount: userTokenAccount,
+          stakeMint: stakeMintPda,
+          mint,
           escrowAccount: escrowAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -128,6 +503,8 @@ describe("x402-registry", () => {
             agent: agentPda,
             signer: tempUser.publicKey,
             stakerTokenAccount: userTokenAccount,
+            stakeMint: stakeMintPda,
+            mint,
             escrowAccount: escrowAccount,
             tokenProgram: TOKEN_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
@@ -144,7 +521,7 @@ describe("x402-registry", () => {
 
   describe("register_service", () => {
     it("registers a service with valid parameters", async () => {
-      const service = Keypair.generate();
+      const { service, directory, page } = await serviceAccounts(user.publicKey);
 
       await program.methods
         .registerService(
@@ -156,23 +533,31 @@ describe("x402-registry", () => {
           [mint]
         )
         .accounts({
-          service: service.publicKey,
+          service,
+          directory,
+          page,
           owner: user.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([user, service])
+        .signers([user])
         .rpc();
 
-      const serviceAccount = await program.account.service.fetch(service.publicKey);
+      const serviceAccount = await program.account.service.fetch(service);
 
       assert.equal(serviceAccount.name, "Weather API");
       assert.equal(serviceAccount.pricePerCall.toNumber(), 1_000_000);
       assert.equal(serviceAccount.totalCalls.toNumber(), 0);
       assert.equal(serviceAccount.verified, false);
+
+      const directoryAccount = await program.account.serviceDirectory.fetch(directory);
+      const pageAccount = await program.account.serviceDirectoryPage.fetch(page);
+      assert.equal(serviceAccount.directoryIndex, directoryAccount.nextIndex - 1);
+      assert.equal(directoryAccount.serviceCount, directoryAccount.nextIndex);
+      assert.isTrue(pageAccount.services.some((listed) => listed.equals(service)));
     });
 
     it("fails with zero price", async () => {
-      const service = Keypair.generate();
+      const { service, directory, page } = await serviceAccounts(user.publicKey);
 
       try {
         await program.methods
@@ -185,11 +570,13 @@ describe("x402-registry", () => {
             [mint]
           )
           .accounts({
-            service: service.publicKey,
+            service,
+            directory,
+            page,
             owner: user.publicKey,
             systemProgram: SystemProgram.programId,
           })
-          .signers([user, service])
+          .signers([user])
           .rpc();
 
         assert.fail("Should have failed with InvalidPrice");
@@ -210,7 +597,7 @@ describe("x402-registry", () => {
       );
       agentPda = agentAccount;
 
-      const service = Keypair.generate();
+      const { service, directory, page } = await serviceAccounts(user.publicKey);
       await program.methods
         .registerService(
           "https://api.example.com",
@@ -221,14 +608,16 @@ describe("x402-registry", () => {
           [mint]
         )
         .accounts({
-          service: service.publicKey,
+          service,
+          directory,
+          page,
           owner: user.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([user, service])
+        .signers([user])
         .rpc();
 
-      servicePda = service.publicKey;
+      servicePda = service;
     });
 
     it("records successful transaction with oracle authority", async () => {
@@ -315,311 +704,1444 @@ describe("x402-registry", () => {
       );
       agentPda = agentAccount;
 
-      const service = Keypair.generate();
+      const { service, directory, page } = await serviceAccounts(user.publicKey);
+      await program.methods
+        .registerService(
+          "https://api.example.com",
+          "Rateable Service",
+          "Description",
+          "data",
+          new anchor.BN(1_000_000),
+          [mint]
+        )
+        .accounts({
+          service,
+          directory,
+          page,
+          owner: user.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+
+      servicePda = service;
+
+      await program.methods
+        .recordTransaction(
+          new anchor.BN(1_000_000),
+          true,
+          150
+        )
+        .accounts({
+          agent: agentPda,
+          service: servicePda,
+          authority: oracleAuthority.publicKey,
+        })
+        .signers([oracleAuthority])
+        .rpc();
+    });
+
+    it("rates service with valid agent", async () => {
+      await program.methods
+        .rateService(400)
+        .accounts({
+          service: servicePda,
+          agent: agentPda,
+          stakeMint: stakeMintPda,
+          rater: user.publicKey,
+        })
+        .signers([user])
+        .rpc();
+
+      const serviceAccount = await program.account.service.fetch(servicePda);
+
+      assert.equal(serviceAccount.totalRatings, 1);
+      assert.equal(serviceAccount.averageRating, 400);
+
+      const [ratingPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("rating"), servicePda.toBuffer(), user.publicKey.toBuffer()],
+        program.programId
+      );
+      const rating = await program.account.serviceRating.fetch(ratingPda);
+      assert.isAbove(rating.weight.toNumber(), 0);
+      assert.equal(serviceAccount.totalRatingWeight.toNumber(), rating.weight.toNumber());
+    });
+
+    it("does not prune ratings inside the rating window", async () => {
+      const [ratingPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("rating"), servicePda.toBuffer(), user.publicKey.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .pruneRating()
+          .accounts({
+            service: servicePda,
+            serviceRating: ratingPda,
+            rater: user.publicKey,
+          })
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "RatingNotExpired");
+      }
+    });
+
+    it("fails without transaction history with the service", async () => {
+      const newUser = Keypair.generate();
+      const [newAgentPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("agent"), newUser.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .registerAgent(
+          "did:x402:newagent",
+          "cert",
+          new anchor.BN(1_000_000_000),
+          "https://metadata.example.com"
+        )
+        .accounts({
+          agent: newAgentPda,
+          signer: newUser.publicKey,
+          stakerTokenAccount: userTokenAccount,
+          stakeMint: stakeMintPda,
+          mint,
+          escrowAccount: escrowAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([newUser])
+        .rpc();
+
+      try {
+        await program.methods
+          .rateService(300)
+          .accounts({
+            service: servicePda,
+            agent: newAgentPda,
+            stakeMint: stakeMintPda,
+            rater: newUser.publicKey,
+          })
+          .signers([newUser])
+          .rpc();
+
+        assert.fail("Should have failed without an interaction record");
+      } catch (error) {
+        assert.include(error.toString(), "AccountNotInitialized");
+      }
+    });
+  });
+
+  describe("verify_service", () => {
+    let servicePda: PublicKey;
+
+    before(async () => {
+      const { service, directory, page } = await serviceAccounts(user.publicKey);
+      await program.methods
+        .registerService(
+          "https://api.example.com",
+          "Verifiable Service",
+          "Description",
+          "data",
+          new anchor.BN(1_000_000),
+          [mint]
+        )
+        .accounts({
+          service,
+          directory,
+          page,
+          owner: user.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+
+      servicePda = service;
+    });
+
+    it("verifies service with verifier authority", async () => {
+      await program.methods
+        .verifyService()
+        .accounts({
+          service: servicePda,
+          verifier: verifierAuthority.publicKey,
+        })
+        .signers([verifierAuthority])
+        .rpc();
+
+      const serviceAccount = await program.account.service.fetch(servicePda);
+      assert.equal(serviceAccount.verified, true);
+    });
+
+    it("fails without verifier authority", async () => {
+      const { service, directory, page } = await serviceAccounts(user.publicKey);
+      await program.methods
+        .registerService(
+          "https://api.example.com",
+          "Unverified Service",
+          "Description",
+          "data",
+          new anchor.BN(1_000_000),
+          [mint]
+        )
+        .accounts({
+          service,
+          directory,
+          page,
+          owner: user.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+
+      try {
+        await program.methods
+          .verifyService()
+          .accounts({
+            service,
+            verifier: user.publicKey,
+          })
+          .signers([user])
+          .rpc();
+
+        assert.fail("Should have failed with UnauthorizedAccess");
+      } catch (error) {
+        assert.include(error.toString(), "UnauthorizedAccess");
+      }
+    });
+  });
+
+  describe("set_visa_tap_verified", () => {
+    let servicePda: PublicKey;
+
+    before(async () => {
+      const { service, directory, page } = await serviceAccounts(user.publicKey);
+      await program.methods
+        .registerService(
+          "https://api.example.com",
+          "TAP Service",
+          "Description",
+          "data",
+          new anchor.BN(1_000_000),
+          [mint]
+        )
+        .accounts({
+          service,
+          directory,
+          page,
+          owner: user.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+
+      servicePda = service;
+    });
+
+    it("sets TAP verification with TAP authority", async () => {
+      await program.methods
+        .setVisaTapVerified(true)
+        .accounts({
+          service: servicePda,
+          authority: tapAuthority.publicKey,
+        })
+        .signers([tapAuthority])
+        .rpc();
+
+      const serviceAccount = await program.account.service.fetch(servicePda);
+      assert.equal(serviceAccount.visaTapVerified, true);
+    });
+
+    it("fails without TAP authority", async () => {
+      try {
+        await program.methods
+          .setVisaTapVerified(true)
+          .accounts({
+            service: servicePda,
+            authority: user.publicKey,
+          })
+          .signers([user])
+          .rpc();
+
+        assert.fail("Should have failed with UnauthorizedAccess");
+      } catch (error) {
+        assert.include(error.toString(), "UnauthorizedAccess");
+      }
+    });
+  });
+
+  describe("slash_for_fraud", () => {
+    let agentPda: PublicKey;
+
+    before(async () => {
+      const fraudAgent = Keypair.generate();
+      const [agentAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("agent"), fraudAgent.publicKey.toBuffer()],
+        program.programId
+      );
+      agentPda = agentAccount;
+
+      await program.methods
+        .registerAgent(
+          "did:x402:fraudagent",
+          "cert",
+          new anchor.BN(5_000_000_000),
+          "https://metadata.example.com"
+        )
+        .accounts({
+          agent: agentPda,
+          signer: fraudAgent.publicKey,
+          stakerTokenAccount: userTokenAccount,
+          stakeMint: stakeMintPda,
+          mint,
+          escrowAccount: escrowAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([fraudAgent])
+        .rpc();
+    });
+
+    const slash = (proposal: PublicKey) =>
+      program.methods
+        .slashForFraud()
+        .accounts({
+          agent: agentPda,
+          stakeMint: stakeMintPda,
+          mint,
+          escrowAccount: escrowAccount,
+          daoTreasury: daoTreasuryAccount,
+          proposal,
+          executor: user.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+
+    it("slashes a suspended agent through an approved proposal", async () => {
+      const suspension = await approvedProposal({ suspendAgent: { agent: agentPda } }, true);
+      await program.methods
+        .suspendAgent()
+        .accounts({
+          config: configPda,
+          proposal: suspension,
+          agent: agentPda,
+          executor: user.publicKey,
+        })
+        .signers([user])
+        .rpc();
+
+      const agentBefore = await program.account.agent.fetch(agentPda);
+      const stakeBefore = agentBefore.stakedAmount.toNumber();
+      assert.isDefined(agentBefore.status.suspended);
+
+      const proposal = await approvedProposal({
+        slashForFraud: {
+          agent: agentPda,
+          fraudAmount: new anchor.BN(1_000_000_000),
+          evidenceUri: "https://evidence.example.com",
+        },
+      });
+
+      try {
+        await slash(proposal);
+        assert.fail("Should have failed with TimelockActive");
+      } catch (error) {
+        assert.include(error.toString(), "TimelockActive");
+      }
+
+      await sleep(3000);
+      await slash(proposal);
+
+      const agentAfter = await program.account.agent.fetch(agentPda);
+      const stakeAfter = agentAfter.stakedAmount.toNumber();
+
+      assert.isBelow(stakeAfter, stakeBefore);
+      assert.isAbove(agentAfter.slashedAmount.toNumber(), 0);
+
+      const executed = await program.account.proposal.fetch(proposal);
+      assert.isDefined(executed.status.executed);
+    });
+
+    it("rejects an executed proposal", async () => {
+      const { proposalCount } = await program.account.registryConfig.fetch(configPda);
+
+      try {
+        await slash(proposalAddress(proposalCount.toNumber() - 1));
+        assert.fail("Should have failed with ProposalNotApproved");
+      } catch (error) {
+        assert.include(error.toString(), "ProposalNotApproved");
+      }
+    });
+  });
+
+  describe("governance", () => {
+    let agentPda: PublicKey;
+
+    before(() => {
+      [agentPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("agent"), user.publicKey.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("rejects proposals from outside the council", async () => {
+      const { proposalCount } = await program.account.registryConfig.fetch(configPda);
+
+      try {
+        await program.methods
+          .createProposal({ banAgent: { agent: agentPda } }, false)
+          .accounts({
+            config: configPda,
+            proposal: proposalAddress(proposalCount.toNumber()),
+            proposer: user.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user])
+          .rpc();
+        assert.fail("Should have failed with NotCouncilMember");
+      } catch (error) {
+        assert.include(error.toString(), "NotCouncilMember");
+      }
+    });
+
+    it("limits emergency proposals to suspensions", async () => {
+      try {
+        await createProposal({ banAgent: { agent: agentPda } }, true);
+        assert.fail("Should have failed with EmergencyActionNotAllowed");
+      } catch (error) {
+        assert.include(error.toString(), "EmergencyActionNotAllowed");
+      }
+    });
+
+    it("waits for the council threshold", async () => {
+      const proposal = await createProposal({ suspendAgent: { agent: agentPda } }, true);
+
+      try {
+        await program.methods
+          .approveProposal()
+          .accounts({ config: configPda, proposal, approver: daoAuthority.publicKey })
+          .signers([daoAuthority])
+          .rpc();
+        assert.fail("Should have failed with AlreadyApproved");
+      } catch (error) {
+        assert.include(error.toString(), "AlreadyApproved");
+      }
+
+      try {
+        await program.methods
+          .suspendAgent()
+          .accounts({ config: configPda, proposal, agent: agentPda, executor: user.publicKey })
+          .signers([user])
+          .rpc();
+        assert.fail("Should have failed with ProposalNotApproved");
+      } catch (error) {
+        assert.include(error.toString(), "ProposalNotApproved");
+      }
+
+      await program.methods
+        .cancelProposal()
+        .accounts({ proposal, proposer: daoAuthority.publicKey })
+        .signers([daoAuthority])
+        .rpc();
+
+      const cancelled = await program.account.proposal.fetch(proposal);
+      assert.isDefined(cancelled.status.cancelled);
+      assert.equal(cancelled.approvals.length, 1);
+    });
+
+    it("only executes the proposed action", async () => {
+      const proposal = await approvedProposal({ banAgent: { agent: agentPda } });
+
+      try {
+        await program.methods
+          .suspendAgent()
+          .accounts({ config: configPda, proposal, agent: agentPda, executor: user.publicKey })
+          .signers([user])
+          .rpc();
+        assert.fail("Should have failed with ProposalActionMismatch");
+      } catch (error) {
+        assert.include(error.toString(), "ProposalActionMismatch");
+      }
+
+      await program.methods
+        .cancelProposal()
+        .accounts({ proposal, proposer: daoAuthority.publicKey })
+        .signers([daoAuthority])
+        .rpc();
+    });
+  });
+
+  describe("disputes", () => {
+    const payer = Keypair.generate();
+    const serviceOwner = Keypair.generate();
+    let service: PublicKey;
+    const transactionSignature = Array.from({ length: 64 }, (_, i) => i);
+    let payerTokenAccount: PublicKey;
+    let payerAgentPda: PublicKey;
+    let providerAgentPda: PublicKey;
+    let disputePda: PublicKey;
+
+    const registerAgent = async (wallet: Keypair, agentPda: PublicKey) => {
+      const airdropSig = await provider.connection.requestAirdrop(
+        wallet.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropSig);
+
+      const tokenAccount = await createAccount(
+        provider.connection,
+        wallet,
+        mint,
+        wallet.publicKey
+      );
+      await mintTo(provider.connection, user, mint, tokenAccount, user, 2_000_000_000);
+
+      await program.methods
+        .registerAgent("did:x402:dispute", "cert", new anchor.BN(2_000_000_000), "https://metadata.example.com")
+        .accounts({
+          agent: agentPda,
+          signer: wallet.publicKey,
+          stakerTokenAccount: tokenAccount,
+          stakeMint: stakeMintPda,
+          mint,
+          escrowAccount: escrowAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([wallet])
+        .rpc();
+
+      return tokenAccount;
+    };
+
+    before(async () => {
+      [payerAgentPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("agent"), payer.publicKey.toBuffer()],
+        program.programId
+      );
+      [providerAgentPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("agent"), serviceOwner.publicKey.toBuffer()],
+        program.programId
+      );
+      [disputePda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("dispute"),
+          service.toBuffer(),
+          Buffer.from(transactionSignature.slice(0, 32)),
+          Buffer.from(transactionSignature.slice(32)),
+        ],
+        program.programId
+      );
+
+      payerTokenAccount = await registerAgent(payer, payerAgentPda);
+      await registerAgent(serviceOwner, providerAgentPda);
+
+      const registration = await serviceAccounts(serviceOwner.publicKey);
+      service = registration.service;
+
+      await program.methods
+        .registerService(
+          "https://api.disputed.com",
+          "Disputed Service",
+          "Service used in dispute tests",
+          "data",
+          new anchor.BN(1_000_000),
+          [mint]
+        )
+        .accounts({
+          service,
+          directory: registration.directory,
+          page: registration.page,
+          agent: providerAgentPda,
+          owner: serviceOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([serviceOwner])
+        .rpc();
+    });
+
+    it("opens a dispute with a bond", async () => {
+      await program.methods
+        .openDispute(transactionSignature, "https://evidence.example.com")
+        .accounts({
+          dispute: disputePda,
+          service,
+          payerAgent: payerAgentPda,
+          payer: payer.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payer])
+        .rpc();
+
+      const dispute = await program.account.dispute.fetch(disputePda);
+      assert.ok(dispute.provider.equals(serviceOwner.publicKey));
+      assert.equal(dispute.bondLamports.toNumber(), 100_000_000);
+      assert.equal(dispute.status.open !== undefined, true);
+    });
+
+    it("rejects a dispute against the payer's own service", async () => {
+      try {
+        await program.methods
+          .openDispute(transactionSignature.slice().reverse(), "https://evidence.example.com")
+          .accounts({
+            service,
+            payerAgent: providerAgentPda,
+            payer: serviceOwner.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([serviceOwner])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "SelfDisputeNotAllowed");
+      }
+    });
+
+    it("lets the provider respond with a matching bond", async () => {
+      await program.methods
+        .respondToDispute("https://response.example.com")
+        .accounts({
+          dispute: disputePda,
+          provider: serviceOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([serviceOwner])
+        .rpc();
+
+      const dispute = await program.account.dispute.fetch(disputePda);
+      assert.isTrue(dispute.providerBonded);
+      assert.equal(dispute.status.responded !== undefined, true);
+    });
+
+    it("rejects early timeout resolution", async () => {
+      try {
+        await program.methods
+          .resolveExpiredDispute()
+          .accounts({
+            config: configPda,
+            dispute: disputePda,
+            payerAgent: payerAgentPda,
+            providerAgent: providerAgentPda,
+            payer: payer.publicKey,
+          })
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "DisputeNotOpen");
+      }
+    });
+
+    it("settles bonds, counters and refund on the arbiter's ruling", async () => {
+      const payerBalanceBefore = await provider.connection.getBalance(payer.publicKey);
+      const providerBefore = await program.account.agent.fetch(providerAgentPda);
+
+      await program.methods
+        .resolveDispute({
+          payerWins: true,
+          refundAmount: new anchor.BN(1_000_000),
+          slashAmount: new anchor.BN(0),
+        })
+        .accounts({
+          dispute: disputePda,
+          payerAgent: payerAgentPda,
+          providerAgent: providerAgentPda,
+          payer: payer.publicKey,
+          provider: serviceOwner.publicKey,
+          stakeMint: stakeMintPda,
+          mint,
+          escrowAccount: escrowAccount,
+          payerTokenAccount: payerTokenAccount,
+          daoTreasury: daoTreasuryAccount,
+          arbiter: arbiterAuthority.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([arbiterAuthority])
+        .rpc();
+
+      const dispute = await program.account.dispute.fetch(disputePda);
+      assert.equal(dispute.status.resolvedForPayer !== undefined, true);
+
+      const payerAgent = await program.account.agent.fetch(payerAgentPda);
+      const providerAgent = await program.account.agent.fetch(providerAgentPda);
+      assert.equal(payerAgent.disputesWon, 1);
+      assert.equal(providerAgent.disputesLost, 1);
+      assert.isBelow(
+        providerAgent.reputationScore.toNumber(),
+        providerBefore.reputationScore.toNumber()
+      );
+      assert.equal(
+        providerAgent.stakedAmount.toNumber(),
+        providerBefore.stakedAmount.toNumber() - 1_000_000
+      );
+
+      const payerBalanceAfter = await provider.connection.getBalance(payer.publicKey);
+      assert.equal(payerBalanceAfter - payerBalanceBefore, 200_000_000);
+    });
+  });
+
+  describe("credit", () => {
+    const borrower = Keypair.generate();
+    let borrowerAgentPda: PublicKey;
+    let creditLinePda: PublicKey;
+    let creditVaultPda: PublicKey;
+
+    before(async () => {
+      [borrowerAgentPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("agent"), borrower.publicKey.toBuffer()],
+        program.programId
+      );
+      [creditLinePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("credit"), borrower.publicKey.toBuffer()],
+        program.programId
+      );
+      [creditVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("credit_vault")],
+        program.programId
+      );
+
+      const airdropSig = await provider.connection.requestAirdrop(
+        borrower.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropSig);
+
+      const borrowerTokenAccount = await createAccount(
+        provider.connection,
+        borrower,
+        mint,
+        borrower.publicKey
+      );
+      await mintTo(provider.connection, user, mint, borrowerTokenAccount, user, 1_000_000_000);
+
+      await program.methods
+        .registerAgent("did:x402:borrower", "cert", new anchor.BN(1_000_000_000), "https://metadata.example.com")
+        .accounts({
+          agent: borrowerAgentPda,
+          signer: borrower.publicKey,
+          stakerTokenAccount: borrowerTokenAccount,
+          stakeMint: stakeMintPda,
+          mint,
+          escrowAccount: escrowAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([borrower])
+        .rpc();
+
+      await program.methods
+        .initializeCreditVault()
+        .accounts({
+          creditVault: creditVaultPda,
+          stakeTokenMint: mint,
+          payer: user.publicKey,
+          authority: daoAuthority.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user, daoAuthority])
+        .rpc();
+
+      await mintTo(provider.connection, user, mint, creditVaultPda, user, 1_000_000_000);
+
+      await program.methods
+        .openCreditLine()
+        .accounts({
+          creditLine: creditLinePda,
+          agent: borrowerAgentPda,
+          signer: borrower.publicKey,
+        })
+        .signers([borrower])
+        .rpc();
+    });
+
+    it("opens an empty credit line", async () => {
+      const creditLine = await program.account.creditLine.fetch(creditLinePda);
+      assert.ok(creditLine.agent.equals(borrower.publicKey));
+      assert.equal(creditLine.dueAt.toNumber(), 0);
+    });
+
+    it("rejects default settlement with nothing outstanding", async () => {
+      try {
+        await program.methods
+          .settleCreditDefault()
+          .accounts({
+            agent: borrowerAgentPda,
+            creditLine: creditLinePda,
+            stakeMint: stakeMintPda,
+            mint,
+            escrowAccount: escrowAccount,
+            creditVault: creditVaultPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "NoCreditOutstanding");
+      }
+    });
+  });
+
+  describe("payment_escrow", () => {
+    const seller = Keypair.generate();
+    let service: PublicKey;
+    let sellerTokenAccount: PublicKey;
+
+    const escrowAddresses = (nonce: number) => {
+      const [paymentEscrow] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("payment_escrow"),
+          user.publicKey.toBuffer(),
+          service.toBuffer(),
+          new anchor.BN(nonce).toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+      const [vault] = PublicKey.findProgramAddressSync(
+        [Buffer.from("payment_vault"), paymentEscrow.toBuffer()],
+        program.programId
+      );
+      return { paymentEscrow, vault };
+    };
+
+    const createEscrow = (nonce: number, deadline: number) => {
+      const { paymentEscrow, vault } = escrowAddresses(nonce);
+      return program.methods
+        .createPaymentEscrow(new anchor.BN(nonce), new anchor.BN(1_000_000), new anchor.BN(deadline))
+        .accounts({
+          paymentEscrow,
+          vault,
+          service,
+          mint,
+          payerTokenAccount: userTokenAccount,
+          payer: user.publicKey,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+    };
+
+    before(async () => {
+      const airdropSig = await provider.connection.requestAirdrop(
+        seller.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropSig);
+
+      sellerTokenAccount = await createAccount(
+        provider.connection,
+        seller,
+        mint,
+        seller.publicKey
+      );
+      await mintTo(provider.connection, user, mint, sellerTokenAccount, user, 1_000_000_000);
+
+      const [sellerAgentPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("agent"), seller.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .registerAgent("did:x402:seller", "cert", new anchor.BN(1_000_000_000), "https://metadata.example.com")
+        .accounts({
+          agent: sellerAgentPda,
+          signer: seller.publicKey,
+          stakerTokenAccount: sellerTokenAccount,
+          stakeMint: stakeMintPda,
+          mint,
+          escrowAccount: escrowAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([seller])
+        .rpc();
+
+      const registration = await serviceAccounts(seller.publicKey);
+      service = registration.service;
+
+      await program.methods
+        .registerService(
+          "https://api.escrowed.com",
+          "Escrowed Service",
+          "Service paid through escrow",
+          "data",
+          new anchor.BN(1_000_000),
+          [mint]
+        )
+        .accounts({
+          service,
+          directory: registration.directory,
+          page: registration.page,
+          agent: sellerAgentPda,
+          owner: seller.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([seller])
+        .rpc();
+    });
+
+    it("rejects a deadline in the past", async () => {
+      try {
+        await createEscrow(1, Math.floor(Date.now() / 1000) - 60);
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "InvalidDeadline");
+      }
+    });
+
+    it("funds and releases to the service owner on the payer's signature", async () => {
+      const { paymentEscrow, vault } = escrowAddresses(2);
+      await createEscrow(2, Math.floor(Date.now() / 1000) + 3600);
+
+      const vaultAccount = await getAccount(provider.connection, vault);
+      assert.equal(Number(vaultAccount.amount), 1_000_000);
+
+      const sellerBefore = await getAccount(provider.connection, sellerTokenAccount);
+
+      await program.methods
+        .releasePaymentEscrow()
+        .accounts({
+          paymentEscrow,
+          vault,
+          payeeTokenAccount: sellerTokenAccount,
+          payer: user.publicKey,
+          authority: user.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+
+      const sellerAfter = await getAccount(provider.connection, sellerTokenAccount);
+      assert.equal(Number(sellerAfter.amount) - Number(sellerBefore.amount), 1_000_000);
+
+      const escrow = await program.account.paymentEscrow.fetch(paymentEscrow);
+      assert.equal(escrow.status.released !== undefined, true);
+    });
+
+    it("rejects a refund before the deadline", async () => {
+      const { paymentEscrow, vault } = escrowAddresses(3);
+      await createEscrow(3, Math.floor(Date.now() / 1000) + 3600);
+
+      try {
+        await program.methods
+          .refundPaymentEscrow()
+          .accounts({
+            paymentEscrow,
+            vault,
+            payerTokenAccount: userTokenAccount,
+            payer: user.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "PaymentEscrowNotExpired");
+      }
+    });
+  });
+
+  describe("cosigned_receipts", () => {
+    const buyer = Keypair.generate();
+    const seller = Keypair.generate();
+    let service: PublicKey;
+    let buyerAgentPda: PublicKey;
+
+    const receiptMessage = (amount: number, nonce: number) =>
+      Buffer.concat([
+        service.toBuffer(),
+        buyer.publicKey.toBuffer(),
+        new anchor.BN(amount).toArrayLike(Buffer, "le", 8),
+        Buffer.from([1]),
+        new anchor.BN(120).toArrayLike(Buffer, "le", 4),
+        new anchor.BN(nonce).toArrayLike(Buffer, "le", 8),
+      ]);
+
+    const record = (amount: number, nonce: number, signers: Keypair[]) => {
+      const [receipt] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("receipt"),
+          service.toBuffer(),
+          buyer.publicKey.toBuffer(),
+          new anchor.BN(nonce).toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+
+      return program.methods
+        .recordCosignedTransaction(new anchor.BN(amount), true, 120, new anchor.BN(nonce))
+        .accounts({
+          config: configPda,
+          agent: buyerAgentPda,
+          service,
+          receipt,
+          payer: seller.publicKey,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .preInstructions(
+          signers.map((signer) =>
+            Ed25519Program.createInstructionWithPrivateKey({
+              privateKey: signer.secretKey,
+              message: receiptMessage(amount, nonce),
+            })
+          )
+        )
+        .signers([seller])
+        .rpc();
+    };
+
+    before(async () => {
+      for (const wallet of [buyer, seller]) {
+        const airdropSig = await provider.connection.requestAirdrop(
+          wallet.publicKey,
+          2 * LAMPORTS_PER_SOL
+        );
+        await provider.connection.confirmTransaction(airdropSig);
+      }
+
+      const agents: PublicKey[] = [];
+      for (const wallet of [buyer, seller]) {
+        const tokenAccount = await createAccount(provider.connection, wallet, mint, wallet.publicKey);
+        await mintTo(provider.connection, user, mint, tokenAccount, user, 1_000_000_000);
+
+        const [agentPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("agent"), wallet.publicKey.toBuffer()],
+          program.programId
+        );
+        agents.push(agentPda);
+
+        await program.methods
+          .registerAgent("did:x402:receipts", "cert", new anchor.BN(1_000_000_000), "https://metadata.example.com")
+          .accounts({
+            agent: agentPda,
+            signer: wallet.publicKey,
+            stakerTokenAccount: tokenAccount,
+            stakeMint: stakeMintPda,
+            mint,
+            escrowAccount: escrowAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([wallet])
+          .rpc();
+      }
+      buyerAgentPda = agents[0];
+
+      const registration = await serviceAccounts(seller.publicKey);
+      service = registration.service;
+
       await program.methods
         .registerService(
-          "https://api.example.com",
-          "Rateable Service",
-          "Description",
+          "https://api.receipts.com",
+          "Receipt Service",
+          "Service recorded through co-signed receipts",
           "data",
-          new anchor.BN(1_000_000),
+          new anchor.BN(1_000),
           [mint]
         )
         .accounts({
-          service: service.publicKey,
-          owner: user.publicKey,
+          service,
+          directory: registration.directory,
+          page: registration.page,
+          agent: agents[1],
+          owner: seller.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([user, service])
+        .signers([seller])
         .rpc();
+    });
 
-      servicePda = service.publicKey;
+    it("rejects a receipt without the service owner's signature", async () => {
+      try {
+        await record(50_000, 1, [buyer, buyer]);
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "InvalidSignatureVerification");
+      }
+    });
 
-      await program.methods
-        .recordTransaction(
-          new anchor.BN(1_000_000),
-          true,
-          150
-        )
-        .accounts({
-          agent: agentPda,
-          service: servicePda,
-          authority: oracleAuthority.publicKey,
-        })
-        .signers([oracleAuthority])
-        .rpc();
+    it("records a receipt signed by the payer and the service owner", async () => {
+      await record(50_000, 1, [buyer, seller]);
+
+      const agent = await program.account.agent.fetch(buyerAgentPda);
+      assert.equal(agent.totalTransactions.toNumber(), 1);
+      assert.equal(agent.totalSpent.toNumber(), 50_000);
+
+      const serviceAccount = await program.account.service.fetch(service);
+      assert.equal(serviceAccount.successfulCalls.toNumber(), 1);
+      assert.equal(serviceAccount.totalRevenue.toNumber(), 50_000);
     });
 
-    it("rates service with valid agent", async () => {
-      await program.methods
-        .rateService(400)
+    it("rejects a replayed receipt nonce", async () => {
+      try {
+        await record(50_000, 1, [buyer, seller]);
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "already in use");
+      }
+    });
+  });
+
+  describe("payment_channels", () => {
+    const seller = Keypair.generate();
+    let service: PublicKey;
+    let sellerTokenAccount: PublicKey;
+
+    const [channel] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("channel"),
+        user.publicKey.toBuffer(),
+        service.toBuffer(),
+        new anchor.BN(0).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const [vault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("channel_vault"), channel.toBuffer()],
+      program.programId
+    );
+
+    const voucher = (amount: number, nonce: number, signer: Keypair = user) =>
+      Ed25519Program.createInstructionWithPrivateKey({
+        privateKey: signer.secretKey,
+        message: Buffer.concat([
+          channel.toBuffer(),
+          new anchor.BN(amount).toArrayLike(Buffer, "le", 8),
+          new anchor.BN(nonce).toArrayLike(Buffer, "le", 8),
+        ]),
+      });
+
+    const redeem = (amount: number, nonce: number, signer: Keypair = user) =>
+      program.methods
+        .redeemVoucher(new anchor.BN(amount), new anchor.BN(nonce))
         .accounts({
-          service: servicePda,
-          agent: agentPda,
-          rater: user.publicKey,
+          channel,
+          vault,
+          payeeTokenAccount: sellerTokenAccount,
+          payee: seller.publicKey,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
-        .signers([user])
+        .preInstructions([voucher(amount, nonce, signer)])
+        .signers([seller])
         .rpc();
 
-      const serviceAccount = await program.account.service.fetch(servicePda);
+    before(async () => {
+      const airdropSig = await provider.connection.requestAirdrop(
+        seller.publicKey,
+        2 * LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropSig);
 
-      assert.equal(serviceAccount.totalRatings, 1);
-      assert.equal(serviceAccount.averageRating, 400);
-    });
+      sellerTokenAccount = await createAccount(
+        provider.connection,
+        seller,
+        mint,
+        seller.publicKey
+      );
+      await mintTo(provider.connection, user, mint, sellerTokenAccount, user, 1_000_000_000);
 
-    it("fails without transaction history", async () => {
-      const newUser = Keypair.generate();
-      const [newAgentPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("agent"), newUser.publicKey.toBuffer()],
+      const [sellerAgentPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("agent"), seller.publicKey.toBuffer()],
         program.programId
       );
 
       await program.methods
-        .registerAgent(
-          "did:x402:newagent",
-          "cert",
-          new anchor.BN(1_000_000_000),
-          "https://metadata.example.com"
-        )
+        .registerAgent("did:x402:channel-seller", "cert", new anchor.BN(1_000_000_000), "https://metadata.example.com")
         .accounts({
-          agent: newAgentPda,
-          signer: newUser.publicKey,
-          stakerTokenAccount: userTokenAccount,
+          agent: sellerAgentPda,
+          signer: seller.publicKey,
+          stakerTokenAccount: sellerTokenAccount,
+          stakeMint: stakeMintPda,
+          mint,
           escrowAccount: escrowAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
-        .signers([newUser])
+        .signers([seller])
         .rpc();
 
-      try {
-        await program.methods
-          .rateService(300)
-          .accounts({
-            service: servicePda,
-            agent: newAgentPda,
-            rater: newUser.publicKey,
-          })
-          .signers([newUser])
-          .rpc();
-
-        assert.fail("Should have failed with NoTransactionHistory");
-      } catch (error) {
-        assert.include(error.toString(), "NoTransactionHistory");
-      }
-    });
-  });
+      const registration = await serviceAccounts(seller.publicKey);
+      service = registration.service;
 
-  describe("verify_service", () => {
-    let servicePda: PublicKey;
-
-    before(async () => {
-      const service = Keypair.generate();
       await program.methods
         .registerService(
-          "https://api.example.com",
-          "Verifiable Service",
-          "Description",
+          "https://api.streamed.com",
+          "Streamed Service",
+          "Service paid through a channel",
           "data",
-          new anchor.BN(1_000_000),
+          new anchor.BN(1_000),
           [mint]
         )
         .accounts({
-          service: service.publicKey,
-          owner: user.publicKey,
+          service,
+          directory: registration.directory,
+          page: registration.page,
+          agent: sellerAgentPda,
+          owner: seller.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([user, service])
+        .signers([seller])
         .rpc();
 
-      servicePda = service.publicKey;
-    });
-
-    it("verifies service with verifier authority", async () => {
       await program.methods
-        .verifyService()
+        .openChannel(new anchor.BN(0), new anchor.BN(1_000_000))
         .accounts({
-          service: servicePda,
-          verifier: verifierAuthority.publicKey,
+          config: configPda,
+          channel,
+          vault,
+          service,
+          mint,
+          payerTokenAccount: userTokenAccount,
+          payer: user.publicKey,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
-        .signers([verifierAuthority])
+        .signers([user])
         .rpc();
+    });
 
-      const serviceAccount = await program.account.service.fetch(servicePda);
-      assert.equal(serviceAccount.verified, true);
+    it("opens a channel with the deposit in its vault", async () => {
+      const state = await program.account.paymentChannel.fetch(channel);
+      assert.equal(state.payee.toString(), seller.publicKey.toString());
+      assert.equal(state.deposit.toNumber(), 1_000_000);
+
+      const vaultAccount = await getAccount(provider.connection, vault);
+      assert.equal(Number(vaultAccount.amount), 1_000_000);
     });
 
-    it("fails without verifier authority", async () => {
-      const service = Keypair.generate();
+    it("pays out the difference for each newer voucher", async () => {
+      const before = await getAccount(provider.connection, sellerTokenAccount);
+
+      await redeem(100_000, 1);
+      await redeem(250_000, 5);
+
+      const after = await getAccount(provider.connection, sellerTokenAccount);
+      assert.equal(Number(after.amount) - Number(before.amount), 250_000);
+
+      const state = await program.account.paymentChannel.fetch(channel);
+      assert.equal(state.redeemed.toNumber(), 250_000);
+      assert.equal(state.lastNonce.toNumber(), 5);
+    });
+
+    it("rejects a replayed voucher", async () => {
+      try {
+        await redeem(250_000, 5);
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "StaleVoucher");
+      }
+    });
+
+    it("rejects a voucher not signed by the payer", async () => {
+      try {
+        await redeem(300_000, 6, seller);
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "InvalidSignatureVerification");
+      }
+    });
+
+    it("keeps the payer from closing during the challenge period", async () => {
       await program.methods
-        .registerService(
-          "https://api.example.com",
-          "Unverified Service",
-          "Description",
-          "data",
-          new anchor.BN(1_000_000),
-          [mint]
-        )
-        .accounts({
-          service: service.publicKey,
-          owner: user.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([user, service])
+        .requestChannelClose()
+        .accounts({ channel, payer: user.publicKey })
+        .signers([user])
         .rpc();
 
       try {
         await program.methods
-          .verifyService()
+          .finalizeChannelClose()
           .accounts({
-            service: service.publicKey,
-            verifier: user.publicKey,
+            channel,
+            vault,
+            payerTokenAccount: userTokenAccount,
+            payer: user.publicKey,
+            authority: user.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([user])
           .rpc();
-
-        assert.fail("Should have failed with UnauthorizedAccess");
-      } catch (error) {
-        assert.include(error.toString(), "UnauthorizedAccess");
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "ChallengePeriodActive");
       }
     });
+
+    it("lets the payee redeem during the challenge period and close", async () => {
+      await redeem(400_000, 7);
+
+      const payerBefore = await getAccount(provider.connection, userTokenAccount);
+
+      await program.methods
+        .finalizeChannelClose()
+        .accounts({
+          channel,
+          vault,
+          payerTokenAccount: userTokenAccount,
+          payer: user.publicKey,
+          authority: seller.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([seller])
+        .rpc();
+
+      const payerAfter = await getAccount(provider.connection, userTokenAccount);
+      assert.equal(Number(payerAfter.amount) - Number(payerBefore.amount), 600_000);
+    });
   });
 
-  describe("set_visa_tap_verified", () => {
-    let servicePda: PublicKey;
+  describe("service_health", () => {
+    const prober = Keypair.generate();
+    let service: PublicKey;
+    let probeOperatorPda: PublicKey;
+    let serviceHealthPda: PublicKey;
 
     before(async () => {
-      const service = Keypair.generate();
+      [probeOperatorPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("probe_operator"), prober.publicKey.toBuffer()],
+        program.programId
+      );
+      [serviceHealthPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("service_health"), service.toBuffer()],
+        program.programId
+      );
+
+      const airdropSig = await provider.connection.requestAirdrop(
+        prober.publicKey,
+        LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdropSig);
+
+      const registration = await serviceAccounts(user.publicKey);
+      service = registration.service;
+
       await program.methods
         .registerService(
-          "https://api.example.com",
-          "TAP Service",
+          "https://health.example.com",
+          "Health Service",
           "Description",
           "data",
           new anchor.BN(1_000_000),
           [mint]
         )
         .accounts({
-          service: service.publicKey,
+          service,
+          directory: registration.directory,
+          page: registration.page,
           owner: user.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([user, service])
+        .signers([user])
         .rpc();
 
-      servicePda = service.publicKey;
-    });
-
-    it("sets TAP verification with TAP authority", async () => {
       await program.methods
-        .setVisaTapVerified(true)
+        .openServiceHealth()
         .accounts({
-          service: servicePda,
-          authority: tapAuthority.publicKey,
+          serviceHealth: serviceHealthPda,
+          service,
+          payer: prober.publicKey,
         })
-        .signers([tapAuthority])
+        .signers([prober])
         .rpc();
+    });
 
-      const serviceAccount = await program.account.service.fetch(servicePda);
-      assert.equal(serviceAccount.visaTapVerified, true);
+    const report = (probes: number, successes: number, windowStart: number, windowEnd: number) => ({
+      windowStart: new anchor.BN(windowStart),
+      windowEnd: new anchor.BN(windowEnd),
+      probes,
+      successes,
+      averageLatencyMs: 250,
     });
 
-    it("fails without TAP authority", async () => {
+    it("rejects reports from operators that are not whitelisted", async () => {
+      const now = Math.floor(Date.now() / 1000);
       try {
         await program.methods
-          .setVisaTapVerified(true)
+          .reportHealth(report(10, 9, now - 600, now - 60))
           .accounts({
-            service: servicePda,
-            authority: user.publicKey,
+            config: configPda,
+            probeOperator: probeOperatorPda,
+            serviceHealth: serviceHealthPda,
+            service,
+            operator: prober.publicKey,
           })
-          .signers([user])
+          .signers([prober])
           .rpc();
-
-        assert.fail("Should have failed with UnauthorizedAccess");
-      } catch (error) {
-        assert.include(error.toString(), "UnauthorizedAccess");
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "AccountNotInitialized");
       }
     });
-  });
-
-  describe("slash_for_fraud", () => {
-    let agentPda: PublicKey;
-
-    before(async () => {
-      const fraudAgent = Keypair.generate();
-      const [agentAccount] = PublicKey.findProgramAddressSync(
-        [Buffer.from("agent"), fraudAgent.publicKey.toBuffer()],
-        program.programId
-      );
-      agentPda = agentAccount;
 
+    it("updates uptime from a whitelisted operator's report", async () => {
       await program.methods
-        .registerAgent(
-          "did:x402:fraudagent",
-          "cert",
-          new anchor.BN(5_000_000_000),
-          "https://metadata.example.com"
-        )
+        .addProbeOperator(prober.publicKey)
         .accounts({
-          agent: agentPda,
-          signer: fraudAgent.publicKey,
-          stakerTokenAccount: userTokenAccount,
-          escrowAccount: escrowAccount,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
+          config: configPda,
+          probeOperator: probeOperatorPda,
+          authority: provider.wallet.publicKey,
         })
-        .signers([fraudAgent])
         .rpc();
-    });
-
-    it("slashes agent with DAO authority", async () => {
-      const agentBefore = await program.account.agent.fetch(agentPda);
-      const stakeBefore = agentBefore.stakedAmount.toNumber();
 
+      const now = Math.floor(Date.now() / 1000);
       await program.methods
-        .slashForFraud(
-          new anchor.BN(1_000_000_000),
-          "https://evidence.example.com"
-        )
+        .reportHealth(report(10, 9, now - 600, now - 60))
         .accounts({
-          agent: agentPda,
-          escrowAccount: escrowAccount,
-          daoTreasury: daoTreasuryAccount,
-          escrowAuthority: daoAuthority.publicKey,
-          tokenProgram: TOKEN_PROGRAM_ID,
+          config: configPda,
+          probeOperator: probeOperatorPda,
+          serviceHealth: serviceHealthPda,
+          service,
+          operator: prober.publicKey,
         })
-        .signers([daoAuthority])
+        .signers([prober])
         .rpc();
 
-      const agentAfter = await program.account.agent.fetch(agentPda);
-      const stakeAfter = agentAfter.stakedAmount.toNumber();
+      const health = await program.account.serviceHealth.fetch(serviceHealthPda);
+      assert.equal(health.uptimeBps, 9000);
+      assert.equal(health.latencyMs, 250);
+      assert.equal(health.totalProbes.toNumber(), 10);
 
-      assert.isBelow(stakeAfter, stakeBefore);
-      assert.isAbove(agentAfter.slashedAmount.toNumber(), 0);
+      const serviceAccount = await program.account.service.fetch(service);
+      assert.equal(serviceAccount.uptimePercent, 90);
     });
 
-    it("fails without DAO authority", async () => {
+    it("rate limits reports per service", async () => {
+      const now = Math.floor(Date.now() / 1000);
       try {
         await program.methods
-          .slashForFraud(
-            new anchor.BN(1_000_000_000),
-            "https://evidence.example.com"
-          )
+          .reportHealth(report(10, 10, now - 50, now - 10))
           .accounts({
-            agent: agentPda,
-            escrowAccount: escrowAccount,
-            daoTreasury: daoTreasuryAccount,
-            escrowAuthority: user.publicKey,
-            tokenProgram: TOKEN_PROGRAM_ID,
+            config: configPda,
+            probeOperator: probeOperatorPda,
+            serviceHealth: serviceHealthPda,
+            service,
+            operator: prober.publicKey,
           })
-          .signers([user])
+          .signers([prober])
           .rpc();
-
-        assert.fail("Should have failed with UnauthorizedAccess");
-      } catch (error) {
-        assert.include(error.toString(), "UnauthorizedAccess");
+        assert.fail("Should have failed");
+      } catch (err) {
+        assert.include(err.toString(), "HealthReportRateLimited");
       }
     });
+
+    it("stops accepting reports once the operator is removed", async () => {
+      await program.methods
+        .removeProbeOperator()
+        .accounts({
+          config: configPda,
+          probeOperator: probeOperatorPda,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      const operator = await provider.connection.getAccountInfo(probeOperatorPda);
+      assert.isNull(operator);
+    });
   });
 
   describe("complete_service_lifecycle", () => {
@@ -672,6 +2194,8 @@ describe("x402-registry", () => {
           agent: agentPda,
           signer: testUser.publicKey,
           stakerTokenAccount: testUserTokenAccount,
+          stakeMint: stakeMintPda,
+          mint,
           escrowAccount: escrowAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -682,7 +2206,7 @@ describe("x402-registry", () => {
       let agentData = await program.account.agent.fetch(agentPda);
       assert.equal(agentData.serviceCount, 0);
 
-      const service = Keypair.generate();
+      const { service, directory, page } = await serviceAccounts(testUser.publicKey);
       await program.methods
         .registerService(
           "https://api.lifecycle.com",
@@ -693,15 +2217,17 @@ describe("x402-registry", () => {
           [mint]
         )
         .accounts({
-          service: service.publicKey,
+          service,
+          directory,
+          page,
           agent: agentPda,
           owner: testUser.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([testUser, service])
+        .signers([testUser])
         .rpc();
 
-      servicePda = service.publicKey;
+      servicePda = service;
 
       agentData = await program.account.agent.fetch(agentPda);
       assert.equal(agentData.serviceCount, 1);
@@ -722,6 +2248,7 @@ describe("x402-registry", () => {
         .closeService()
         .accounts({
           service: servicePda,
+          ...(await serviceListing(servicePda)),
           agent: agentPda,
           recipient: testUser.publicKey,
         })
@@ -744,9 +2271,9 @@ describe("x402-registry", () => {
     let testUser: Keypair;
     let testUserTokenAccount: PublicKey;
     let agentPda: PublicKey;
-    let service1: Keypair;
-    let service2: Keypair;
-    let service3: Keypair;
+    let service1: PublicKey;
+    let service2: PublicKey;
+    let service3: PublicKey;
 
     before(async () => {
       testUser = Keypair.generate();
@@ -790,6 +2317,8 @@ describe("x402-registry", () => {
           agent: agentPda,
           signer: testUser.publicKey,
           stakerTokenAccount: testUserTokenAccount,
+          stakeMint: stakeMintPda,
+          mint,
           escrowAccount: escrowAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -802,7 +2331,8 @@ describe("x402-registry", () => {
       let agentData = await program.account.agent.fetch(agentPda);
       assert.equal(agentData.serviceCount, 0);
 
-      service1 = Keypair.generate();
+      const registration1 = await serviceAccounts(testUser.publicKey);
+      service1 = registration1.service;
       await program.methods
         .registerService(
           "https://api.service1.com",
@@ -813,18 +2343,21 @@ describe("x402-registry", () => {
           [mint]
         )
         .accounts({
-          service: service1.publicKey,
+          service: service1,
+          directory: registration1.directory,
+          page: registration1.page,
           agent: agentPda,
           owner: testUser.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([testUser, service1])
+        .signers([testUser])
         .rpc();
 
       agentData = await program.account.agent.fetch(agentPda);
       assert.equal(agentData.serviceCount, 1);
 
-      service2 = Keypair.generate();
+      const registration2 = await serviceAccounts(testUser.publicKey);
+      service2 = registration2.service;
       await program.methods
         .registerService(
           "https://api.service2.com",
@@ -835,18 +2368,21 @@ describe("x402-registry", () => {
           [mint]
         )
         .accounts({
-          service: service2.publicKey,
+          service: service2,
+          directory: registration2.directory,
+          page: registration2.page,
           agent: agentPda,
           owner: testUser.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([testUser, service2])
+        .signers([testUser])
         .rpc();
 
       agentData = await program.account.agent.fetch(agentPda);
       assert.equal(agentData.serviceCount, 2);
 
-      service3 = Keypair.generate();
+      const registration3 = await serviceAccounts(testUser.publicKey);
+      service3 = registration3.service;
       await program.methods
         .registerService(
           "https://api.service3.com",
@@ -857,12 +2393,14 @@ describe("x402-registry", () => {
           [mint]
         )
         .accounts({
-          service: service3.publicKey,
+          service: service3,
+          directory: registration3.directory,
+          page: registration3.page,
           agent: agentPda,
           owner: testUser.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([testUser, service3])
+        .signers([testUser])
         .rpc();
 
       agentData = await program.account.agent.fetch(agentPda);
@@ -871,7 +2409,7 @@ describe("x402-registry", () => {
       await program.methods
         .deprecateService()
         .accounts({
-          service: service1.publicKey,
+          service: service1,
           owner: testUser.publicKey,
         })
         .signers([testUser])
@@ -880,7 +2418,8 @@ describe("x402-registry", () => {
       await program.methods
         .closeService()
         .accounts({
-          service: service1.publicKey,
+          service: service1,
+          ...(await serviceListing(service1)),
           agent: agentPda,
           recipient: testUser.publicKey,
         })
@@ -893,7 +2432,7 @@ describe("x402-registry", () => {
       await program.methods
         .deprecateService()
         .accounts({
-          service: service2.publicKey,
+          service: service2,
           owner: testUser.publicKey,
         })
         .signers([testUser])
@@ -902,7 +2441,8 @@ describe("x402-registry", () => {
       await program.methods
         .closeService()
         .accounts({
-          service: service2.publicKey,
+          service: service2,
+          ...(await serviceListing(service2)),
           agent: agentPda,
           recipient: testUser.publicKey,
         })
@@ -915,7 +2455,7 @@ describe("x402-registry", () => {
       await program.methods
         .deprecateService()
         .accounts({
-          service: service3.publicKey,
+          service: service3,
           owner: testUser.publicKey,
         })
         .signers([testUser])
@@ -924,7 +2464,8 @@ describe("x402-registry", () => {
       await program.methods
         .closeService()
         .accounts({
-          service: service3.publicKey,
+          service: service3,
+          ...(await serviceListing(service3)),
           agent: agentPda,
           recipient: testUser.publicKey,
         })
@@ -933,6 +2474,12 @@ describe("x402-registry", () => {
 
       agentData = await program.account.agent.fetch(agentPda);
       assert.equal(agentData.serviceCount, 0);
+
+      const { directory, page } = await serviceAccounts(testUser.publicKey);
+      const directoryAccount = await program.account.serviceDirectory.fetch(directory);
+      assert.equal(directoryAccount.nextIndex, 3);
+      assert.equal(directoryAccount.serviceCount, 0);
+      assert.isEmpty((await program.account.serviceDirectoryPage.fetch(page)).services);
     });
   });
 
@@ -984,6 +2531,8 @@ describe("x402-registry", () => {
           agent: agentPda,
           signer: testUser.publicKey,
           stakerTokenAccount: testUserTokenAccount,
+          stakeMint: stakeMintPda,
+          mint,
           escrowAccount: escrowAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -991,7 +2540,7 @@ describe("x402-registry", () => {
         .signers([testUser])
         .rpc();
 
-      const service = Keypair.generate();
+      const { service, directory, page } = await serviceAccounts(testUser.publicKey);
       await program.methods
         .registerService(
           "https://api.suspended.com",
@@ -1002,42 +2551,52 @@ describe("x402-registry", () => {
           [mint]
         )
         .accounts({
-          service: service.publicKey,
+          service,
+          directory,
+          page,
           agent: agentPda,
           owner: testUser.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([testUser, service])
+        .signers([testUser])
         .rpc();
 
-      servicePda = service.publicKey;
+      servicePda = service;
     });
 
     it("closes suspended service and decrements service_count", async () => {
       let agentData = await program.account.agent.fetch(agentPda);
       assert.equal(agentData.serviceCount, 1);
 
+      const suspension = await approvedProposal({ suspendService: { service: servicePda } }, true);
       await program.methods
         .suspendService()
         .accounts({
+          config: configPda,
+          proposal: suspension,
           service: servicePda,
-          authority: daoAuthority.publicKey,
+          executor: testUser.publicKey,
         })
-        .signers([daoAuthority])
+        .signers([testUser])
         .rpc();
 
       const serviceData = await program.account.service.fetch(servicePda);
       assert.equal(serviceData.status.suspended !== undefined, true);
 
+      const closure = await approvedProposal({ closeSuspendedService: { service: servicePda } });
+      await sleep(3000);
+
       await program.methods
         .closeSuspendedService()
         .accounts({
           service: servicePda,
+          ...(await serviceListing(servicePda)),
           agent: agentPda,
-          recipient: daoTreasuryAccount,
-          authority: daoAuthority.publicKey,
+          proposal: closure,
+          recipient: daoAuthority.publicKey,
+          executor: testUser.publicKey,
         })
-        .signers([daoAuthority])
+        .signers([testUser])
         .rpc();
 
       agentData = await program.account.agent.fetch(agentPda);
@@ -1101,6 +2660,8 @@ describe("x402-registry", () => {
           agent: agentPda,
           signer: testUser.publicKey,
           stakerTokenAccount: testUserTokenAccount,
+          stakeMint: stakeMintPda,
+          mint,
           escrowAccount: escrowAccount,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -1108,7 +2669,7 @@ describe("x402-registry", () => {
         .signers([testUser])
         .rpc();
 
-      const service = Keypair.generate();
+      const { service, directory, page } = await serviceAccounts(testUser.publicKey);
       await program.methods
         .registerService(
           "https://api.fullcycle.com",
@@ -1119,12 +2680,14 @@ describe("x402-registry", () => {
           [mint]
         )
         .accounts({
-          service: service.publicKey,
+          service,
+          directory,
+          page,
           agent: agentPda,
           owner: testUser.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([testUser, service])
+        .signers([testUser])
         .rpc();
 
       const agentData = await program.account.agent.fetch(agentPda);
@@ -1148,7 +2711,7 @@ describe("x402-registry", () => {
       await program.methods
         .deprecateService()
         .accounts({
-          service: service.publicKey,
+          service,
           owner: testUser.publicKey,
         })
         .signers([testUser])
@@ -1157,7 +2720,8 @@ describe("x402-registry", () => {
       await program.methods
         .closeService()
         .accounts({
-          service: service.publicKey,
+          service,
+          ...(await serviceListing(service)),
           agent: agentPda,
           recipient: testUser.publicKey,
         })
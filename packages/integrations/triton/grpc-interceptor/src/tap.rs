@@ -2,6 +2,11 @@ use serde::{Deserialize, Serialize};
 use anyhow::{Result, anyhow};
 use std::collections::HashMap;
 use reqwest::Client;
+use std::sync::Arc;
+use base64::Engine as _;
+use x402_upl::tap::{DidResolver, InMemoryNonceStore, NonceStore};
+
+const DEFAULT_SIGNATURE_WINDOW_SECONDS: i64 = 300;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TAPIdentity {
@@ -27,6 +32,8 @@ pub struct TAPVerifier {
     registry_url: String,
     http_client: Client,
     identity_cache: std::sync::Arc<tokio::sync::RwLock<HashMap<String, TAPIdentity>>>,
+    nonce_store: Arc<dyn NonceStore>,
+    did_resolver: Option<Arc<DidResolver>>,
 }
 
 impl TAPVerifier {
@@ -35,9 +42,21 @@ impl TAPVerifier {
             registry_url,
             http_client: Client::new(),
             identity_cache: std::sync::Arc::new(tokio::sync::RwLock::new(HashMap::new())),
+            nonce_store: Arc::new(InMemoryNonceStore::default()),
+            did_resolver: None,
         }
     }
 
+    pub fn with_did_resolver(mut self, did_resolver: Arc<DidResolver>) -> Self {
+        self.did_resolver = Some(did_resolver);
+        self
+    }
+
+    pub fn with_nonce_store(mut self, nonce_store: Arc<dyn NonceStore>) -> Self {
+        self.nonce_store = nonce_store;
+        self
+    }
+
     pub async fn verify_metadata(
         &self,
         metadata: &tonic::metadata::MetadataMap,
@@ -45,11 +64,12 @@ impl TAPVerifier {
     ) -> Result<TAPIdentity> {
         let signature = self.parse_signature(signature_header)?;
 
-        if let Some(expires) = signature.expires {
-            let now = chrono::Utc::now().timestamp();
-            if now > expires {
-                return Err(anyhow!("Signature expired"));
-            }
+        let now = chrono::Utc::now().timestamp();
+        let expires = signature
+            .expires
+            .unwrap_or(signature.created + DEFAULT_SIGNATURE_WINDOW_SECONDS);
+        if now > expires {
+            return Err(anyhow!("Signature expired"));
         }
 
         let identity = self.get_identity(&signature.key_id).await?;
@@ -58,9 +78,67 @@ impl TAPVerifier {
             return Err(anyhow!("Algorithm mismatch"));
         }
 
+        let nonce = signature.nonce.as_deref()
+            .ok_or_else(|| anyhow!("Missing nonce"))?;
+
+        self.verify_signature(metadata, &signature, &identity, expires)?;
+
+        let fresh = self.nonce_store
+            .check_and_insert(&signature.key_id, nonce, expires as u64)
+            .await?;
+        if !fresh {
+            return Err(anyhow!("Nonce already used (replay)"));
+        }
+
         Ok(identity)
     }
 
+    /// Checks the signature over the covered metadata entries. `(created)`,
+    /// `(expires)` and `(nonce)` stand for the signature parameters; `(nonce)`
+    /// must be covered so the nonce cannot be swapped on a captured request.
+    fn verify_signature(
+        &self,
+        metadata: &tonic::metadata::MetadataMap,
+        signature: &TAPSignature,
+        identity: &TAPIdentity,
+        expires: i64,
+    ) -> Result<()> {
+        if signature.algorithm != "ed25519" {
+            return Err(anyhow!("Unsupported algorithm: {}", signature.algorithm));
+        }
+        if !signature.headers.iter().any(|h| h == "(nonce)") {
+            return Err(anyhow!("Signature does not cover the nonce"));
+        }
+
+        let mut lines = Vec::with_capacity(signature.headers.len());
+        for header in &signature.headers {
+            let value = match header.as_str() {
+                "(created)" => signature.created.to_string(),
+                "(expires)" => expires.to_string(),
+                "(nonce)" => signature.nonce.clone().unwrap_or_default(),
+                name => metadata
+                    .get(name)
+                    .ok_or_else(|| anyhow!("Missing signed header: {}", name))?
+                    .to_str()
+                    .map_err(|_| anyhow!("Invalid signed header: {}", name))?
+                    .trim()
+                    .to_string(),
+            };
+            lines.push(format!("{}: {}", header, value));
+        }
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let public_key: [u8; 32] = engine.decode(&identity.public_key)?
+            .try_into()
+            .map_err(|_| anyhow!("Invalid public key length"))?;
+        let public_key = ed25519_dalek::VerifyingKey::from_bytes(&public_key)?;
+        let signature_bytes = ed25519_dalek::Signature::from_slice(&engine.decode(&signature.signature)?)?;
+
+        public_key
+            .verify_strict(lines.join("\n").as_bytes(), &signature_bytes)
+            .map_err(|_| anyhow!("Invalid signature"))
+    }
+
     fn parse_signature(&self, header: &str) -> Result<TAPSignature> {
         let mut parts: HashMap<String, String> = HashMap::new();
 
@@ -112,6 +190,18 @@ impl TAPVerifier {
     }
 
     async fn get_identity(&self, key_id: &str) -> Result<TAPIdentity> {
+        // DID identities are not kept in `identity_cache`: the resolver's own
+        // cache expires them, so key rotations and bans take effect.
+        if let (Some(resolver), true) = (&self.did_resolver, key_id.starts_with("did:")) {
+            let public_key = resolver.resolve_key(key_id).await?;
+            return Ok(TAPIdentity {
+                key_id: key_id.to_string(),
+                algorithm: "ed25519".to_string(),
+                public_key: base64::engine::general_purpose::STANDARD.encode(public_key.as_bytes()),
+                domain: key_id.split('#').next().unwrap_or(key_id).to_string(),
+            });
+        }
+
         {
             let cache = self.identity_cache.read().await;
             if let Some(identity) = cache.get(key_id) {
@@ -0,0 +1,232 @@
+use anyhow::Result;
+use futures::StreamExt;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::clock::MAX_PROCESSING_AGE;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{info, warn};
+use x402_upl::registry::REGISTRY_PROGRAM_ID;
+
+use crate::source::{self, IndexedTransaction};
+use crate::store::{Checkpoint, EventStore};
+
+const FINALIZED_CHECKPOINT: &str = "finalized";
+const BACKFILL_CURSOR: &str = "backfill_cursor";
+const BACKFILL_TARGET: &str = "backfill_target";
+const STATUS_BATCH_SIZE: usize = 256;
+/// A transaction's blockhash is at most as new as the slot it was seen in and stays valid for
+/// `MAX_PROCESSING_AGE` blocks. Skipped slots stretch that, so allow twice as many slots.
+const BLOCKHASH_EXPIRY_SLOTS: u64 = 2 * MAX_PROCESSING_AGE as u64;
+
+#[derive(Debug, Clone)]
+pub struct IndexerConfig {
+    pub rpc_url: String,
+    pub ws_url: String,
+    pub program_id: Pubkey,
+    pub reconcile_interval: Duration,
+}
+
+impl Default for IndexerConfig {
+    fn default() -> Self {
+        Self {
+            rpc_url: "https://api.devnet.solana.com".to_string(),
+            ws_url: "wss://api.devnet.solana.com".to_string(),
+            program_id: *REGISTRY_PROGRAM_ID,
+            reconcile_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+pub struct Indexer {
+    config: IndexerConfig,
+    store: EventStore,
+    rpc_client: RpcClient,
+}
+
+impl Indexer {
+    pub fn new(config: IndexerConfig, store: EventStore) -> Self {
+        let rpc_client = RpcClient::new_with_commitment(config.rpc_url.clone(), CommitmentConfig::confirmed());
+
+        Self {
+            config,
+            store,
+            rpc_client,
+        }
+    }
+
+    pub fn store(&self) -> &EventStore {
+        &self.store
+    }
+
+    /// Replays history back to the finalized checkpoint, writing each page as
+    /// it is read. `BACKFILL_CURSOR` records the oldest signature written so an
+    /// interrupted run resumes there; `BACKFILL_TARGET` is the newest signature
+    /// that run covered, so only newer history is read again.
+    pub async fn backfill(&self) -> Result<usize> {
+        let until = self.checkpoint_signature(FINALIZED_CHECKPOINT).await?;
+        let interrupted = self.checkpoint_signature(BACKFILL_CURSOR).await?;
+
+        let mut inserted = 0;
+        if let Some(cursor) = interrupted {
+            let target = self.checkpoint_signature(BACKFILL_TARGET).await?;
+            let (newer, newest) = self.backfill_range(None, target.or(until), false).await?;
+            inserted += newer;
+            if let Some(newest) = newest {
+                self.store.save_checkpoint(BACKFILL_TARGET, &newest).await?;
+            }
+            inserted += self.backfill_range(Some(cursor), until, true).await?.0;
+        } else {
+            inserted += self.backfill_range(None, until, true).await?.0;
+        }
+
+        self.store.delete_checkpoint(BACKFILL_CURSOR).await?;
+        self.store.delete_checkpoint(BACKFILL_TARGET).await?;
+
+        info!(inserted, "backfill complete");
+        Ok(inserted)
+    }
+
+    /// Indexes signatures older than `before` back to `until`, returning the
+    /// number of events inserted and the newest signature read. With `track`,
+    /// progress is checkpointed after every page.
+    async fn backfill_range(
+        &self,
+        mut before: Option<Signature>,
+        until: Option<Signature>,
+        track: bool,
+    ) -> Result<(usize, Option<Checkpoint>)> {
+        let mut inserted = 0;
+        let mut newest = None;
+
+        loop {
+            let page = source::fetch_signature_page(&self.rpc_client, &self.config.program_id, before, until).await?;
+
+            if newest.is_none() {
+                newest = page.signatures.first().map(|(signature, slot)| Checkpoint {
+                    slot: *slot,
+                    signature: signature.to_string(),
+                });
+                if let Some(newest) = newest.as_ref().filter(|_| track && before.is_none()) {
+                    self.store.save_checkpoint(BACKFILL_TARGET, newest).await?;
+                }
+            }
+
+            for transaction in source::fetch_page_transactions(&self.rpc_client, &self.config.program_id, &page).await? {
+                inserted += self.store.insert(&transaction).await?;
+            }
+
+            let Some((oldest, slot)) = page.oldest else {
+                break;
+            };
+            if track {
+                let cursor = Checkpoint {
+                    slot,
+                    signature: oldest.to_string(),
+                };
+                self.store.save_checkpoint(BACKFILL_CURSOR, &cursor).await?;
+            }
+            if page.exhausted {
+                break;
+            }
+            before = Some(oldest);
+        }
+
+        Ok((inserted, newest))
+    }
+
+    async fn checkpoint_signature(&self, name: &str) -> Result<Option<Signature>> {
+        self.store
+            .checkpoint(name)
+            .await?
+            .map(|checkpoint| Signature::from_str(&checkpoint.signature).map_err(Into::into))
+            .transpose()
+    }
+
+    pub async fn reconcile(&self) -> Result<()> {
+        let finalized_slot = self
+            .rpc_client
+            .get_slot_with_commitment(CommitmentConfig::finalized())
+            .await?;
+
+        let signatures = self.store.unfinalized_signatures(finalized_slot).await?;
+
+        for batch in signatures.chunks(STATUS_BATCH_SIZE) {
+            let parsed = batch
+                .iter()
+                .map(|(s, _)| Signature::from_str(s))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+
+            let statuses = self
+                .rpc_client
+                .get_signature_statuses_with_history(&parsed)
+                .await?
+                .value;
+
+            for ((signature, slot), status) in batch.iter().zip(statuses) {
+                let dropped = match status {
+                    Some(status) if status.err.is_some() => true,
+                    Some(status) => {
+                        if status.satisfies_commitment(CommitmentConfig::finalized()) {
+                            self.store.finalize(signature).await?;
+                        }
+                        false
+                    }
+                    // An unknown status may just be a lagging node; only once the blockhash has expired can the
+                    // transaction no longer land.
+                    None => finalized_slot > slot.saturating_add(BLOCKHASH_EXPIRY_SLOTS),
+                };
+
+                if dropped {
+                    let removed = self.store.rollback(signature).await?;
+                    warn!(%signature, removed, "rolled back events from a dropped transaction");
+                }
+            }
+        }
+
+        if let Some(checkpoint) = self.store.latest_finalized().await? {
+            self.store.save_checkpoint(FINALIZED_CHECKPOINT, &checkpoint).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        loop {
+            let pubsub_client = PubsubClient::new(&self.config.ws_url).await?;
+            let mut live = source::subscribe(&pubsub_client, self.config.program_id).await?;
+
+            self.backfill().await?;
+            self.reconcile().await?;
+
+            let mut reconcile = tokio::time::interval(self.config.reconcile_interval);
+
+            loop {
+                tokio::select! {
+                    transaction = live.next() => match transaction {
+                        Some(transaction) => self.ingest(&transaction).await?,
+                        None => break,
+                    },
+                    _ = reconcile.tick() => {
+                        if let Err(e) = self.reconcile().await {
+                            warn!(error = %e, "reconcile failed");
+                        }
+                    }
+                }
+            }
+
+            warn!("log subscription closed, reconnecting");
+        }
+    }
+
+    async fn ingest(&self, transaction: &IndexedTransaction) -> Result<()> {
+        let inserted = self.store.insert(transaction).await?;
+        if inserted > 0 {
+            info!(signature = %transaction.signature, slot = transaction.slot, inserted, "indexed events");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+pub mod indexer;
+pub mod source;
+pub mod store;
+
+pub use indexer::{Indexer, IndexerConfig};
+pub use source::IndexedTransaction;
+pub use store::{EventQuery, EventStore, StoredEvent};
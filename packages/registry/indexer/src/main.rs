@@ -0,0 +1,98 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use solana_sdk::pubkey::Pubkey;
+use std::time::Duration;
+use x402_registry_indexer::{EventQuery, EventStore, Indexer, IndexerConfig};
+use x402_upl::registry::REGISTRY_PROGRAM_ID;
+
+#[derive(Parser)]
+#[command(name = "x402-indexer", about = "Index x402-registry events into SQLite or Postgres")]
+struct Cli {
+    #[arg(long, env = "DATABASE_URL", default_value = "sqlite://x402-registry-events.db?mode=rwc")]
+    database_url: String,
+
+    #[arg(long, env = "SOLANA_RPC_URL", default_value = "https://api.devnet.solana.com")]
+    rpc_url: String,
+
+    #[arg(long, env = "SOLANA_WS_URL", default_value = "wss://api.devnet.solana.com")]
+    ws_url: String,
+
+    #[arg(long, env = "REGISTRY_PROGRAM_ID")]
+    program_id: Option<Pubkey>,
+
+    #[arg(long, default_value_t = 30)]
+    reconcile_interval: u64,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    Run,
+    Backfill,
+    Query {
+        #[arg(long)]
+        event_type: Option<String>,
+        #[arg(long)]
+        agent: Option<String>,
+        #[arg(long)]
+        service: Option<String>,
+        #[arg(long)]
+        from_slot: Option<u64>,
+        #[arg(long)]
+        to_slot: Option<u64>,
+        #[arg(long)]
+        finalized: bool,
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let cli = Cli::parse();
+    let store = EventStore::connect(&cli.database_url).await?;
+
+    let config = IndexerConfig {
+        rpc_url: cli.rpc_url,
+        ws_url: cli.ws_url,
+        program_id: cli.program_id.unwrap_or(*REGISTRY_PROGRAM_ID),
+        reconcile_interval: Duration::from_secs(cli.reconcile_interval),
+    };
+
+    match cli.command {
+        Command::Run => Indexer::new(config, store).run().await,
+        Command::Backfill => {
+            let indexer = Indexer::new(config, store);
+            indexer.backfill().await?;
+            indexer.reconcile().await
+        }
+        Command::Query {
+            event_type,
+            agent,
+            service,
+            from_slot,
+            to_slot,
+            finalized,
+            limit,
+        } => {
+            let events = store
+                .query(&EventQuery {
+                    event_type,
+                    agent,
+                    service,
+                    from_slot,
+                    to_slot,
+                    finalized_only: finalized,
+                    limit: Some(limit),
+                })
+                .await?;
+
+            println!("{}", serde_json::to_string_pretty(&events)?);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::{anyhow, Result};
+use futures::stream::{BoxStream, StreamExt};
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::{RpcTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+use std::str::FromStr;
+use x402_upl::registry::RegistryEvent;
+
+const SIGNATURE_PAGE_SIZE: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct IndexedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub events: Vec<RegistryEvent>,
+}
+
+pub async fn fetch_transaction(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    signature: &Signature,
+) -> Result<Option<IndexedTransaction>> {
+    let transaction = rpc_client
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Json),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await?;
+
+    let meta = match transaction.transaction.meta {
+        Some(meta) if meta.err.is_none() => meta,
+        _ => return Ok(None),
+    };
+
+    let logs: Option<Vec<String>> = meta.log_messages.into();
+
+    Ok(Some(IndexedTransaction {
+        signature: signature.to_string(),
+        slot: transaction.slot,
+        block_time: transaction.block_time,
+        events: RegistryEvent::parse_logs(&logs.unwrap_or_default(), program_id),
+    }))
+}
+
+/// One page of the program's signature history, newest first.
+#[derive(Debug, Clone)]
+pub struct SignaturePage {
+    /// Successful signatures with their slots.
+    pub signatures: Vec<(Signature, u64)>,
+    /// The oldest signature on the page, failed or not; pass it as `before` to
+    /// read the next page.
+    pub oldest: Option<(Signature, u64)>,
+    pub exhausted: bool,
+}
+
+pub async fn fetch_signature_page(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    before: Option<Signature>,
+    until: Option<Signature>,
+) -> Result<SignaturePage> {
+    let page = rpc_client
+        .get_signatures_for_address_with_config(
+            program_id,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until,
+                limit: Some(SIGNATURE_PAGE_SIZE),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )
+        .await?;
+
+    let exhausted = page.len() < SIGNATURE_PAGE_SIZE;
+    let oldest = page
+        .last()
+        .map(|status| Ok::<_, anyhow::Error>((Signature::from_str(&status.signature)?, status.slot)))
+        .transpose()?;
+    let signatures = page
+        .into_iter()
+        .filter(|status| status.err.is_none())
+        .map(|status| Ok((Signature::from_str(&status.signature)?, status.slot)))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(SignaturePage {
+        signatures,
+        oldest,
+        exhausted,
+    })
+}
+
+/// Fetches the page's transactions oldest first, keeping those that emitted events.
+pub async fn fetch_page_transactions(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    page: &SignaturePage,
+) -> Result<Vec<IndexedTransaction>> {
+    let mut transactions = Vec::new();
+    for (signature, _) in page.signatures.iter().rev() {
+        if let Some(transaction) = fetch_transaction(rpc_client, program_id, signature).await? {
+            if !transaction.events.is_empty() {
+                transactions.push(transaction);
+            }
+        }
+    }
+
+    Ok(transactions)
+}
+
+pub async fn subscribe(
+    pubsub_client: &PubsubClient,
+    program_id: Pubkey,
+) -> Result<BoxStream<'_, IndexedTransaction>> {
+    let (stream, _unsubscribe) = pubsub_client
+        .logs_subscribe(
+            RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )
+        .await
+        .map_err(|e| anyhow!("logs subscription failed: {}", e))?;
+
+    Ok(stream
+        .filter_map(move |response| async move {
+            if response.value.err.is_some() {
+                return None;
+            }
+
+            let events = RegistryEvent::parse_logs(&response.value.logs, &program_id);
+            (!events.is_empty()).then(|| IndexedTransaction {
+                signature: response.value.signature,
+                slot: response.context.slot,
+                block_time: None,
+                events,
+            })
+        })
+        .boxed())
+}
@@ -0,0 +1,362 @@
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::any::{install_default_drivers, AnyPoolOptions, AnyRow};
+use sqlx::{AnyPool, Row};
+
+use crate::source::IndexedTransaction;
+
+const SCHEMA: [&str; 6] = [
+    "CREATE TABLE IF NOT EXISTS registry_events (
+        signature TEXT NOT NULL,
+        event_index INTEGER NOT NULL,
+        slot BIGINT NOT NULL,
+        block_time BIGINT,
+        event_type TEXT NOT NULL,
+        agent TEXT,
+        service TEXT,
+        data TEXT NOT NULL,
+        finalized BOOLEAN NOT NULL DEFAULT FALSE,
+        PRIMARY KEY (signature, event_index)
+    )",
+    "CREATE INDEX IF NOT EXISTS registry_events_type ON registry_events (event_type, slot)",
+    "CREATE INDEX IF NOT EXISTS registry_events_agent ON registry_events (agent, slot)",
+    "CREATE INDEX IF NOT EXISTS registry_events_service ON registry_events (service, slot)",
+    "CREATE INDEX IF NOT EXISTS registry_events_finalized ON registry_events (finalized, slot)",
+    "CREATE TABLE IF NOT EXISTS indexer_checkpoints (
+        name TEXT PRIMARY KEY,
+        slot BIGINT NOT NULL,
+        signature TEXT NOT NULL
+    )",
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredEvent {
+    pub signature: String,
+    pub event_index: i64,
+    pub slot: i64,
+    pub block_time: Option<i64>,
+    pub event_type: String,
+    pub agent: Option<String>,
+    pub service: Option<String>,
+    pub data: serde_json::Value,
+    pub finalized: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EventQuery {
+    pub event_type: Option<String>,
+    pub agent: Option<String>,
+    pub service: Option<String>,
+    pub from_slot: Option<u64>,
+    pub to_slot: Option<u64>,
+    pub finalized_only: bool,
+    pub limit: Option<usize>,
+}
+
+enum Arg {
+    Text(String),
+    Int(i64),
+}
+
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub slot: u64,
+    pub signature: String,
+}
+
+pub struct EventStore {
+    pool: AnyPool,
+}
+
+impl EventStore {
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        install_default_drivers();
+
+        let pool = AnyPoolOptions::new()
+            .max_connections(5)
+            .connect(database_url)
+            .await?;
+
+        let store = Self { pool };
+        store.migrate().await?;
+
+        Ok(store)
+    }
+
+    pub async fn migrate(&self) -> Result<()> {
+        for statement in SCHEMA {
+            sqlx::query(statement).execute(&self.pool).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn insert(&self, transaction: &IndexedTransaction) -> Result<usize> {
+        let mut db = self.pool.begin().await?;
+        let mut inserted = 0;
+
+        for (index, event) in transaction.events.iter().enumerate() {
+            let result = sqlx::query(
+                "INSERT INTO registry_events
+                    (signature, event_index, slot, block_time, event_type, agent, service, data, finalized)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, FALSE)
+                 ON CONFLICT (signature, event_index) DO NOTHING",
+            )
+            .bind(&transaction.signature)
+            .bind(index as i64)
+            .bind(transaction.slot as i64)
+            .bind(transaction.block_time)
+            .bind(event.name())
+            .bind(event.agent().map(|key| key.to_string()))
+            .bind(event.service().map(|key| key.to_string()))
+            .bind(event.to_json().to_string())
+            .execute(&mut *db)
+            .await?;
+
+            inserted += result.rows_affected() as usize;
+        }
+
+        db.commit().await?;
+        Ok(inserted)
+    }
+
+    pub async fn query(&self, query: &EventQuery) -> Result<Vec<StoredEvent>> {
+        let mut sql = String::from(
+            "SELECT signature, event_index, slot, block_time, event_type, agent, service, data, finalized
+             FROM registry_events WHERE 1 = 1",
+        );
+        let mut args: Vec<Arg> = Vec::new();
+
+        if let Some(event_type) = &query.event_type {
+            args.push(Arg::Text(event_type.clone()));
+            sql.push_str(&format!(" AND event_type = ${}", args.len()));
+        }
+        if let Some(agent) = &query.agent {
+            args.push(Arg::Text(agent.clone()));
+            sql.push_str(&format!(" AND agent = ${}", args.len()));
+        }
+        if let Some(service) = &query.service {
+            args.push(Arg::Text(service.clone()));
+            sql.push_str(&format!(" AND service = ${}", args.len()));
+        }
+        if let Some(from_slot) = query.from_slot {
+            args.push(Arg::Int(from_slot as i64));
+            sql.push_str(&format!(" AND slot >= ${}", args.len()));
+        }
+        if let Some(to_slot) = query.to_slot {
+            args.push(Arg::Int(to_slot as i64));
+            sql.push_str(&format!(" AND slot <= ${}", args.len()));
+        }
+        if query.finalized_only {
+            sql.push_str(" AND finalized = TRUE");
+        }
+
+        sql.push_str(" ORDER BY slot DESC, signature, event_index");
+        sql.push_str(&format!(" LIMIT {}", query.limit.unwrap_or(100)));
+
+        let mut statement = sqlx::query(&sql);
+        for arg in args {
+            statement = match arg {
+                Arg::Text(value) => statement.bind(value),
+                Arg::Int(value) => statement.bind(value),
+            };
+        }
+
+        let rows = statement.fetch_all(&self.pool).await?;
+        rows.iter().map(Self::row_to_event).collect()
+    }
+
+    /// Unfinalized signatures seen at or below `max_slot`, with the slot each was seen at.
+    pub async fn unfinalized_signatures(&self, max_slot: u64) -> Result<Vec<(String, u64)>> {
+        let rows = sqlx::query(
+            "SELECT signature, MAX(slot) AS slot FROM registry_events
+             WHERE finalized = FALSE AND slot <= $1
+             GROUP BY signature",
+        )
+        .bind(max_slot as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get::<String, _>("signature"), row.get::<i64, _>("slot") as u64))
+            .collect())
+    }
+
+    pub async fn finalize(&self, signature: &str) -> Result<()> {
+        sqlx::query("UPDATE registry_events SET finalized = TRUE WHERE signature = $1")
+            .bind(signature)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn rollback(&self, signature: &str) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM registry_events WHERE signature = $1 AND finalized = FALSE")
+            .bind(signature)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn latest_finalized(&self) -> Result<Option<Checkpoint>> {
+        let row = sqlx::query(
+            "SELECT signature, slot FROM registry_events WHERE finalized = TRUE
+             ORDER BY slot DESC, signature LIMIT 1",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| Checkpoint {
+            slot: row.get::<i64, _>("slot") as u64,
+            signature: row.get("signature"),
+        }))
+    }
+
+    pub async fn checkpoint(&self, name: &str) -> Result<Option<Checkpoint>> {
+        let row = sqlx::query("SELECT slot, signature FROM indexer_checkpoints WHERE name = $1")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| Checkpoint {
+            slot: row.get::<i64, _>("slot") as u64,
+            signature: row.get("signature"),
+        }))
+    }
+
+    pub async fn save_checkpoint(&self, name: &str, checkpoint: &Checkpoint) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO indexer_checkpoints (name, slot, signature) VALUES ($1, $2, $3)
+             ON CONFLICT (name) DO UPDATE SET slot = excluded.slot, signature = excluded.signature",
+        )
+        .bind(name)
+        .bind(checkpoint.slot as i64)
+        .bind(&checkpoint.signature)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_checkpoint(&self, name: &str) -> Result<()> {
+        sqlx::query("DELETE FROM indexer_checkpoints WHERE name = $1")
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    fn row_to_event(row: &AnyRow) -> Result<StoredEvent> {
+        Ok(StoredEvent {
+            signature: row.try_get("signature")?,
+            event_index: row.try_get("event_index")?,
+            slot: row.try_get("slot")?,
+            block_time: row.try_get("block_time")?,
+            event_type: row.try_get("event_type")?,
+            agent: row.try_get("agent")?,
+            service: row.try_get("service")?,
+            data: serde_json::from_str(&row.try_get::<String, _>("data")?)?,
+            finalized: row.try_get("finalized")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+    use x402_upl::registry::events::ServiceRatedEvent;
+    use x402_upl::registry::RegistryEvent;
+
+    async fn store(name: &str) -> EventStore {
+        let path = std::env::temp_dir().join(format!("x402-indexer-{}-{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        EventStore::connect(&format!("sqlite://{}?mode=rwc", path.display())).await.unwrap()
+    }
+
+    fn transaction(signature: &str, slot: u64, events: usize) -> IndexedTransaction {
+        let event = RegistryEvent::ServiceRated(ServiceRatedEvent {
+            service: Pubkey::new_unique(),
+            rating: 5,
+            new_average: 500,
+            timestamp: 0,
+        });
+
+        IndexedTransaction {
+            signature: signature.to_string(),
+            slot,
+            block_time: None,
+            events: vec![event; events],
+        }
+    }
+
+    fn all() -> EventQuery {
+        EventQuery {
+            limit: Some(1_000),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn inserts_each_event_once() {
+        let store = store("insert").await;
+
+        assert_eq!(store.insert(&transaction("a", 10, 2)).await.unwrap(), 2);
+        assert_eq!(store.insert(&transaction("a", 10, 2)).await.unwrap(), 0);
+
+        let events = store.query(&all()).await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|event| event.event_type == "ServiceRatedEvent" && !event.finalized));
+    }
+
+    #[tokio::test]
+    async fn rolls_back_only_unfinalized_events() {
+        let store = store("rollback").await;
+        store.insert(&transaction("kept", 10, 1)).await.unwrap();
+        store.insert(&transaction("dropped", 11, 1)).await.unwrap();
+        store.finalize("kept").await.unwrap();
+
+        assert_eq!(store.rollback("dropped").await.unwrap(), 1);
+        assert_eq!(store.rollback("kept").await.unwrap(), 0);
+
+        let events = store.query(&all()).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].signature, "kept");
+        assert!(events[0].finalized);
+    }
+
+    #[tokio::test]
+    async fn finalizes_up_to_a_slot() {
+        let store = store("finalize").await;
+        store.insert(&transaction("old", 10, 1)).await.unwrap();
+        store.insert(&transaction("new", 20, 1)).await.unwrap();
+        assert!(store.latest_finalized().await.unwrap().is_none());
+
+        assert_eq!(store.unfinalized_signatures(15).await.unwrap(), vec![("old".to_string(), 10)]);
+        store.finalize("old").await.unwrap();
+        assert!(store.unfinalized_signatures(15).await.unwrap().is_empty());
+
+        let latest = store.latest_finalized().await.unwrap().unwrap();
+        assert_eq!((latest.slot, latest.signature.as_str()), (10, "old"));
+
+        let finalized = store.query(&EventQuery { finalized_only: true, ..all() }).await.unwrap();
+        assert_eq!(finalized.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn saves_and_clears_checkpoints() {
+        let store = store("checkpoint").await;
+        assert!(store.checkpoint("backfill_cursor").await.unwrap().is_none());
+
+        let first = Checkpoint { slot: 10, signature: "a".to_string() };
+        store.save_checkpoint("backfill_cursor", &first).await.unwrap();
+        let second = Checkpoint { slot: 5, signature: "b".to_string() };
+        store.save_checkpoint("backfill_cursor", &second).await.unwrap();
+
+        let resumed = store.checkpoint("backfill_cursor").await.unwrap().unwrap();
+        assert_eq!((resumed.slot, resumed.signature.as_str()), (5, "b"));
+
+        store.delete_checkpoint("backfill_cursor").await.unwrap();
+        assert!(store.checkpoint("backfill_cursor").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+use x402_upl::registry::{
+    HealthReport, OnChainDiscovery, OnChainDiscoveryConfig, RegistryClient, ServiceStatus, REGISTRY_PROGRAM_ID,
+};
+use x402_upl::{HealthConfig, HealthMonitor};
+
+#[derive(Parser)]
+#[command(name = "x402-prober", about = "Probe registered x402 services and report their health on-chain")]
+struct Cli {
+    #[arg(long, env = "PROBER_KEYPAIR")]
+    keypair: String,
+
+    #[arg(long, env = "SOLANA_RPC_URL", default_value = "https://api.devnet.solana.com")]
+    rpc_url: String,
+
+    #[arg(long, env = "REGISTRY_PROGRAM_ID")]
+    program_id: Option<Pubkey>,
+
+    #[arg(long, default_value_t = 30)]
+    probe_interval: u64,
+
+    #[arg(long, default_value_t = 300)]
+    report_interval: u64,
+
+    #[arg(long, default_value_t = 5)]
+    timeout: u64,
+}
+
+struct Window {
+    start: i64,
+    end: i64,
+    probes: u32,
+    successes: u32,
+    latency_sum_ms: u64,
+}
+
+impl Window {
+    fn new(start: i64) -> Self {
+        Self {
+            start,
+            end: start,
+            probes: 0,
+            successes: 0,
+            latency_sum_ms: 0,
+        }
+    }
+
+    fn report(&self) -> Option<HealthReport> {
+        if self.probes == 0 || self.end <= self.start {
+            return None;
+        }
+
+        let average_latency_ms = if self.successes > 0 {
+            (self.latency_sum_ms / self.successes as u64).min(u32::MAX as u64) as u32
+        } else {
+            0
+        };
+
+        Some(HealthReport {
+            window_start: self.start,
+            window_end: self.end,
+            probes: self.probes,
+            successes: self.successes,
+            average_latency_ms,
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let cli = Cli::parse();
+    let program_id = cli.program_id.unwrap_or(*REGISTRY_PROGRAM_ID);
+    let operator = Arc::new(
+        read_keypair_file(&cli.keypair).map_err(|e| anyhow!("Failed to read keypair {}: {}", cli.keypair, e))?,
+    );
+
+    let rpc_client = Arc::new(RpcClient::new_with_commitment(cli.rpc_url.clone(), CommitmentConfig::confirmed()));
+    let registry = Arc::new(RegistryClient::with_rpc_client(rpc_client, program_id));
+    let discovery = Arc::new(OnChainDiscovery::new(OnChainDiscoveryConfig {
+        rpc_url: cli.rpc_url,
+        program_id,
+        ..Default::default()
+    }));
+    let monitor = HealthMonitor::new(HealthConfig {
+        timeout: cli.timeout,
+        ..Default::default()
+    })?;
+
+    let mut windows: HashMap<Pubkey, Window> = HashMap::new();
+    let mut probe = tokio::time::interval(Duration::from_secs(cli.probe_interval));
+    let mut report = tokio::time::interval(Duration::from_secs(cli.report_interval));
+    report.tick().await;
+
+    loop {
+        tokio::select! {
+            _ = probe.tick() => {
+                if let Err(e) = probe_services(&discovery, &monitor, &mut windows).await {
+                    warn!(error = %e, "probe round failed");
+                }
+            }
+            _ = report.tick() => report_windows(&registry, &operator, &mut windows).await,
+        }
+    }
+}
+
+async fn probe_services(
+    discovery: &Arc<OnChainDiscovery>,
+    monitor: &HealthMonitor,
+    windows: &mut HashMap<Pubkey, Window>,
+) -> Result<()> {
+    let services = discovery.fetch_services().await?;
+
+    let targets: Vec<_> = services
+        .iter()
+        .filter(|(_, service)| service.status == ServiceStatus::Active)
+        .map(|(address, service)| (*address, discovery.to_service_info(address, service)))
+        .collect();
+
+    let started = unix_timestamp();
+    let infos: Vec<_> = targets.iter().map(|(_, info)| info.clone()).collect();
+    let results = monitor.probe_all(&infos).await;
+    let finished = unix_timestamp();
+
+    for ((address, _), result) in targets.iter().zip(results) {
+        let window = windows.entry(*address).or_insert_with(|| Window::new(started));
+        window.end = finished;
+        window.probes += 1;
+        if result.valid_402 {
+            window.successes += 1;
+            window.latency_sum_ms += result.latency_ms;
+        }
+    }
+
+    info!(services = targets.len(), "probe round complete");
+    Ok(())
+}
+
+async fn report_windows(registry: &Arc<RegistryClient>, operator: &Arc<Keypair>, windows: &mut HashMap<Pubkey, Window>) {
+    let pending: Vec<_> = windows
+        .iter()
+        .filter_map(|(service, window)| window.report().map(|report| (*service, report)))
+        .collect();
+
+    for (service, report) in pending {
+        let client = registry.clone();
+        let signer = operator.clone();
+        let probes = report.probes;
+        let successes = report.successes;
+
+        let submitted = tokio::task::spawn_blocking(move || client.report_health(&signer, &service, report)).await;
+
+        match submitted {
+            Ok(Ok(signature)) => {
+                info!(%service, %signature, probes, successes, "reported health");
+                windows.remove(&service);
+            }
+            Ok(Err(e)) => warn!(%service, error = %e, "health report failed"),
+            Err(e) => warn!(%service, error = %e, "health report task failed"),
+        }
+    }
+}
+
+fn unix_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
@@ -1,6 +1,6 @@
 use serde_json::json;
 use solana_sdk::signature::Keypair;
-use x402_upl::{SolanaX402Client, X402Config, ServiceDiscovery, DiscoveryConfig};
+use x402_upl::{SolanaX402Client, X402Config, ServiceDiscovery, DiscoveryConfig, ServiceSelector};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -39,7 +39,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             Some(0.05),
             Some(0.7),
             None,
-            1
+            10
         )
         .await?;
 
@@ -48,12 +48,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(1);
     }
 
-    let service = &services[0];
-    println!("Using service: {}", service.name);
+    let selector = ServiceSelector::default();
 
-    if let Some(pricing) = &service.pricing {
-        println!("Price: {} {}\n", pricing.amount, pricing.asset);
+    for ranked in selector.rank(&services, &client.get_service_stats()) {
+        println!("  {:.3}  {}", ranked.score, ranked.service.name);
     }
+    println!();
 
     let prompts = vec![
         "Explain quantum computing in simple terms",
@@ -72,8 +72,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             "model": "llama-3.1-8b"
         });
 
-        match client.post::<serde_json::Value>(&service.resource, Some(request_body), None).await {
-            Ok(result) => {
+        match client.call_best::<serde_json::Value>(&selector, &services, Some(request_body)).await {
+            Ok((service, result)) => {
+                println!("Served by: {}", service.name);
+
                 if let Some(choices) = result.get("choices") {
                     if let Some(first_choice) = choices.get(0) {
                         if let Some(message) = first_choice.get("message") {
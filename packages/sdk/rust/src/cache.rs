@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub enum CacheLookup<V> {
+    Fresh(V),
+    Stale(V),
+    Miss,
+}
+
+struct CacheEntry<V> {
+    value: V,
+    fetched_at: Instant,
+    refreshing: bool,
+}
+
+pub struct TtlCache<V> {
+    ttl: Duration,
+    stale_ttl: Duration,
+    entries: Mutex<HashMap<String, CacheEntry<V>>>,
+}
+
+impl<V: Clone> TtlCache<V> {
+    pub fn new(ttl: Duration, stale_ttl: Duration) -> Self {
+        Self {
+            ttl,
+            stale_ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    pub fn get(&self, key: &str) -> CacheLookup<V> {
+        if !self.is_enabled() {
+            return CacheLookup::Miss;
+        }
+
+        let entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some(entry) => {
+                let age = entry.fetched_at.elapsed();
+                if age < self.ttl {
+                    CacheLookup::Fresh(entry.value.clone())
+                } else if age < self.ttl + self.stale_ttl {
+                    CacheLookup::Stale(entry.value.clone())
+                } else {
+                    CacheLookup::Miss
+                }
+            }
+            None => CacheLookup::Miss,
+        }
+    }
+
+    pub fn insert(&self, key: &str, value: V) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        let max_age = self.ttl + self.stale_ttl;
+        entries.retain(|_, entry| entry.fetched_at.elapsed() < max_age);
+        entries.insert(
+            key.to_string(),
+            CacheEntry {
+                value,
+                fetched_at: Instant::now(),
+                refreshing: false,
+            },
+        );
+    }
+
+    pub fn begin_refresh(&self, key: &str) -> bool {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get_mut(key) {
+            Some(entry) if !entry.refreshing => {
+                entry.refreshing = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn end_refresh(&self, key: &str) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(key) {
+            entry.refreshing = false;
+        }
+    }
+
+    pub fn values(&self) -> Vec<V> {
+        self.entries
+            .lock()
+            .unwrap()
+            .values()
+            .map(|entry| entry.value.clone())
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
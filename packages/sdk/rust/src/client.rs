@@ -1,5 +1,8 @@
+use crate::discovery::X402Service;
 use crate::error::{Result, X402Error};
-use crate::types::{PaymentPayload, PaymentRequirements, PaymentMetrics, PaymentRecord};
+use crate::selection::ServiceSelector;
+use crate::tap::TAPClient;
+use crate::types::{PaymentPayload, PaymentRequirements, PaymentMetrics, PaymentRecord, ServiceStats};
 use base64::{engine::general_purpose, Engine as _};
 use reqwest::{Client as HttpClient, Method, StatusCode};
 use serde::de::DeserializeOwned;
@@ -17,7 +20,7 @@ use spl_token::instruction::transfer_checked;
 use std::str::FromStr;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 lazy_static::lazy_static! {
     pub static ref CASH_MINT: Pubkey = Pubkey::from_str("CASHx9KJUStyftLFWGvEVf59SGeG9sh5FfcnZMVPCASH").unwrap();
@@ -55,6 +58,8 @@ pub struct SolanaX402Client {
     metrics: Arc<Mutex<PaymentMetrics>>,
     payment_history: Arc<Mutex<Vec<PaymentRecord>>>,
     hourly_spending: Arc<Mutex<HashMap<i64, f64>>>,
+    service_stats: Arc<Mutex<HashMap<String, ServiceStats>>>,
+    tap_client: Option<Arc<TAPClient>>,
 }
 
 impl SolanaX402Client {
@@ -86,9 +91,16 @@ impl SolanaX402Client {
             metrics: Arc::new(Mutex::new(PaymentMetrics::default())),
             payment_history: Arc::new(Mutex::new(Vec::new())),
             hourly_spending: Arc::new(Mutex::new(HashMap::new())),
+            service_stats: Arc::new(Mutex::new(HashMap::new())),
+            tap_client: None,
         })
     }
 
+    pub fn with_tap_client(mut self, tap_client: Arc<TAPClient>) -> Self {
+        self.tap_client = Some(tap_client);
+        self
+    }
+
     pub async fn get<T: DeserializeOwned>(
         &self,
         url: &str,
@@ -106,12 +118,58 @@ impl SolanaX402Client {
         self.request(Method::POST, url, data, params).await
     }
 
+    pub async fn call_best<T: DeserializeOwned>(
+        &self,
+        selector: &ServiceSelector,
+        candidates: &[X402Service],
+        data: Option<Value>,
+    ) -> Result<(X402Service, T)> {
+        let ranked = selector.rank(candidates, &self.get_service_stats());
+        let mut last_error = None;
+
+        for candidate in ranked.into_iter().take(selector.config().max_attempts.max(1)) {
+            let method = Method::from_bytes(candidate.service.method.to_uppercase().as_bytes())
+                .unwrap_or(Method::GET);
+
+            match self.request(method, &candidate.service.resource, data.clone(), None).await {
+                Ok(result) => return Ok((candidate.service, result)),
+                // Failing over after paying would pay the next candidate as well.
+                Err(
+                    e @ (X402Error::InsufficientBalance(_)
+                    | X402Error::PaymentUnconfirmed { .. }
+                    | X402Error::PaidRequestFailed { .. }),
+                ) => return Err(e),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            X402Error::InvalidConfig("No candidate services to call".to_string())
+        }))
+    }
+
     async fn request<T: DeserializeOwned>(
         &self,
         method: Method,
         url: &str,
         data: Option<Value>,
         params: Option<Vec<(&str, &str)>>,
+    ) -> Result<T> {
+        let mut latency = Duration::ZERO;
+        let result = self.send_request(method, url, data, params, &mut latency).await;
+        self.record_call(url, result.is_ok(), latency.as_millis() as u64);
+        result
+    }
+
+    /// Adds the time spent on HTTP round trips to `latency`, leaving out the
+    /// on-chain payment so paid services are not ranked as slower.
+    async fn send_request<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        url: &str,
+        data: Option<Value>,
+        params: Option<Vec<(&str, &str)>>,
+        latency: &mut Duration,
     ) -> Result<T> {
         let mut request_builder = self.http_client.request(method.clone(), url);
 
@@ -125,11 +183,23 @@ impl SolanaX402Client {
             }
         }
 
-        let response = request_builder.send().await?;
+        let started = Instant::now();
+        let response = self.execute(request_builder).await;
+        *latency += started.elapsed();
+        let response = response?;
 
         if response.status() == StatusCode::PAYMENT_REQUIRED {
             let requirements: PaymentRequirements = response.json().await?;
-            let payment_header = self.create_payment(&requirements).await?;
+            let (payment_header, signature) = self.create_payment(&requirements).await?;
+
+            if let Ok(amount) = requirements.amount.parse::<f64>() {
+                self.service_stats
+                    .lock()
+                    .unwrap()
+                    .entry(url.to_string())
+                    .or_default()
+                    .total_spent += amount;
+            }
 
             let mut retry_request = self.http_client.request(method, url);
 
@@ -145,17 +215,37 @@ impl SolanaX402Client {
 
             retry_request = retry_request.header("X-Payment", payment_header);
 
-            let retry_response = retry_request.send().await?;
-            retry_response.error_for_status_ref()?;
+            let started = Instant::now();
+            let retried = async {
+                let retry_response = self.execute(retry_request).await?;
+                retry_response.error_for_status_ref()?;
+                retry_response.json::<T>().await.map_err(X402Error::from)
+            }
+            .await;
+            *latency += started.elapsed();
 
-            return retry_response.json().await.map_err(|e| e.into());
+            return retried.map_err(|e| X402Error::PaidRequestFailed {
+                signature,
+                source: Box::new(e),
+            });
         }
 
         response.error_for_status_ref()?;
         response.json().await.map_err(|e| e.into())
     }
 
-    async fn create_payment(&self, requirements: &PaymentRequirements) -> Result<String> {
+    async fn execute(&self, request_builder: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let mut request = request_builder.build()?;
+
+        if let Some(tap_client) = &self.tap_client {
+            tap_client.sign_reqwest(&mut request)?;
+        }
+
+        Ok(self.http_client.execute(request).await?)
+    }
+
+    /// Pays `requirements` and returns the `X-Payment` header with the payment signature.
+    async fn create_payment(&self, requirements: &PaymentRequirements) -> Result<(String, String)> {
         let recipient = Pubkey::from_str(&requirements.pay_to)
             .map_err(|e| X402Error::InvalidConfig(e.to_string()))?;
 
@@ -197,7 +287,7 @@ impl SolanaX402Client {
         };
 
         let json = serde_json::to_string(&payload)?;
-        Ok(general_purpose::STANDARD.encode(json.as_bytes()))
+        Ok((general_purpose::STANDARD.encode(json.as_bytes()), signature))
     }
 
     async fn send_sol_payment(&self, recipient: &Pubkey, amount: f64) -> Result<String> {
@@ -225,12 +315,7 @@ impl SolanaX402Client {
             recent_blockhash,
         );
 
-        let signature = self
-            .rpc_client
-            .send_and_confirm_transaction(&transaction)
-            .map_err(|e| X402Error::Transaction(e.to_string()))?;
-
-        Ok(signature.to_string())
+        self.submit_payment(&transaction)
     }
 
     async fn send_token_payment(
@@ -286,12 +371,22 @@ impl SolanaX402Client {
             recent_blockhash,
         );
 
-        let signature = self
-            .rpc_client
-            .send_and_confirm_transaction(&transaction)
-            .map_err(|e| X402Error::Transaction(e.to_string()))?;
+        self.submit_payment(&transaction)
+    }
+
+    /// Sends and confirms a signed payment. Once it has been sent the transfer may land even if
+    /// confirmation fails, so that failure carries the signature and must not be retried elsewhere.
+    fn submit_payment(&self, transaction: &Transaction) -> Result<String> {
+        let signature = transaction.signatures[0].to_string();
+
+        self.rpc_client
+            .send_and_confirm_transaction(transaction)
+            .map_err(|e| X402Error::PaymentUnconfirmed {
+                signature: signature.clone(),
+                reason: e.to_string(),
+            })?;
 
-        Ok(signature.to_string())
+        Ok(signature)
     }
 
     pub fn get_balance(&self, currency: &str) -> Result<f64> {
@@ -396,6 +491,25 @@ impl SolanaX402Client {
         Ok(records)
     }
 
+    pub fn get_service_stats(&self) -> HashMap<String, ServiceStats> {
+        self.service_stats.lock().unwrap().clone()
+    }
+
+    fn record_call(&self, url: &str, success: bool, latency_ms: u64) {
+        let mut stats = self.service_stats.lock().unwrap();
+        let entry = stats.entry(url.to_string()).or_default();
+
+        entry.calls += 1;
+        if success {
+            entry.successes += 1;
+        }
+        entry.total_latency_ms += latency_ms;
+        entry.last_called = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+    }
+
     pub fn get_spent_this_hour(&self) -> f64 {
         let current_hour = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -1,12 +1,22 @@
-use crate::error::Result;
+use crate::cache::{CacheLookup, TtlCache};
+use crate::error::{Result, X402Error};
+use crate::health::HealthMonitor;
+use crate::query::{paginate, Page, QueryFilters};
+use crate::snapshot::DiscoverySnapshot;
 use crate::types::X402ServiceInfo;
+use async_trait::async_trait;
+use ed25519_dalek::{Keypair, PublicKey};
+use futures::stream::Stream;
 use reqwest::Client;
-use serde_json::Value;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub struct DiscoveryConfig {
     pub registry_url: String,
     pub timeout: u64,
+    pub cache_ttl: u64,
+    pub cache_stale_ttl: u64,
 }
 
 impl Default for DiscoveryConfig {
@@ -14,15 +24,362 @@ impl Default for DiscoveryConfig {
         Self {
             registry_url: "https://registry.x402.network".to_string(),
             timeout: 10,
+            cache_ttl: 60,
+            cache_stale_ttl: 300,
         }
     }
 }
 
 pub use X402ServiceInfo as X402Service;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    Relevance,
+    Price,
+    Reputation,
+    Uptime,
+    Newest,
+}
+
+impl SortBy {
+    pub fn as_str(&self) -> &str {
+        match self {
+            SortBy::Relevance => "relevance",
+            SortBy::Price => "price",
+            SortBy::Reputation => "reputation",
+            SortBy::Uptime => "uptime",
+            SortBy::Newest => "newest",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_str(&self) -> &str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveryQuery {
+    pub filters: QueryFilters,
+    pub query: Option<String>,
+    pub category: Option<String>,
+    pub owner: Option<String>,
+    pub max_price: Option<f64>,
+    pub min_uptime: Option<f64>,
+    pub asset: Option<String>,
+    pub network: Option<String>,
+    pub capabilities: Vec<String>,
+    pub tags: Vec<String>,
+    pub sort: Option<(SortBy, SortOrder)>,
+    pub min_health: Option<f64>,
+}
+
+impl DiscoveryQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn search(mut self, query: &str) -> Self {
+        self.query = Some(query.to_string());
+        self
+    }
+
+    pub fn category(mut self, category: &str) -> Self {
+        self.category = Some(category.to_string());
+        self
+    }
+
+    pub fn owner(mut self, owner: &str) -> Self {
+        self.owner = Some(owner.to_string());
+        self
+    }
+
+    pub fn max_price(mut self, price: f64) -> Self {
+        self.max_price = Some(price);
+        self
+    }
+
+    pub fn min_reputation(mut self, reputation: f64) -> Self {
+        self.filters.min_reputation = Some(reputation);
+        self
+    }
+
+    pub fn min_uptime(mut self, uptime: f64) -> Self {
+        self.min_uptime = Some(uptime);
+        self
+    }
+
+    pub fn asset(mut self, asset: &str) -> Self {
+        self.asset = Some(asset.to_string());
+        self
+    }
+
+    pub fn network(mut self, network: &str) -> Self {
+        self.network = Some(network.to_string());
+        self
+    }
+
+    pub fn capability(mut self, capability: &str) -> Self {
+        self.capabilities.push(capability.to_string());
+        self
+    }
+
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    pub fn verified_only(mut self) -> Self {
+        self.filters.verified_only = true;
+        self
+    }
+
+    pub fn sort_by(mut self, sort: SortBy, order: SortOrder) -> Self {
+        self.sort = Some((sort, order));
+        self
+    }
+
+    pub fn min_health(mut self, score: f64) -> Self {
+        self.min_health = Some(score);
+        self
+    }
+
+    pub fn page_size(mut self, limit: usize) -> Self {
+        self.filters.limit = Some(limit);
+        self
+    }
+
+    pub fn cursor(mut self, cursor: &str) -> Self {
+        self.filters.cursor = Some(cursor.to_string());
+        self
+    }
+
+    pub fn matches(&self, service: &X402Service) -> bool {
+        if let Some(text) = &self.query {
+            let text = text.to_lowercase();
+            if !service.name.to_lowercase().contains(&text)
+                && !service.description.to_lowercase().contains(&text)
+            {
+                return false;
+            }
+        }
+        if let Some(category) = &self.category {
+            if !service.category.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(category)) {
+                return false;
+            }
+        }
+        if let Some(owner) = &self.owner {
+            if service.owner.as_ref() != Some(owner) {
+                return false;
+            }
+        }
+        if self.filters.verified_only && service.verified != Some(true) {
+            return false;
+        }
+        if let Some(max_price) = self.max_price {
+            let price = service
+                .pricing
+                .as_ref()
+                .and_then(|p| p.amount.parse::<f64>().ok())
+                .unwrap_or(f64::INFINITY);
+            if price > max_price {
+                return false;
+            }
+        }
+        if let Some(asset) = &self.asset {
+            if service.pricing.as_ref().map(|p| &p.asset) != Some(asset) {
+                return false;
+            }
+        }
+        if let Some(network) = &self.network {
+            if service.pricing.as_ref().map(|p| &p.network) != Some(network) {
+                return false;
+            }
+        }
+        if let Some(min_reputation) = self.filters.min_reputation {
+            if service.reputation.unwrap_or(0.0) < min_reputation {
+                return false;
+            }
+        }
+        if let Some(min_uptime) = self.min_uptime {
+            if service.uptime.unwrap_or(0.0) < min_uptime {
+                return false;
+            }
+        }
+
+        self.capabilities.iter().all(|c| service.capabilities.contains(c))
+            && self.tags.iter().all(|t| service.tags.contains(t))
+    }
+
+    pub fn apply(&self, services: &[X402Service]) -> Result<Page<X402Service>> {
+        let mut matched: Vec<X402Service> = services
+            .iter()
+            .filter(|service| self.matches(service))
+            .cloned()
+            .collect();
+
+        if let Some((sort, order)) = self.sort {
+            let price = |s: &X402Service| {
+                s.pricing
+                    .as_ref()
+                    .and_then(|p| p.amount.parse::<f64>().ok())
+                    .unwrap_or(f64::INFINITY)
+            };
+            matched.sort_by(|a, b| {
+                let ordering = match sort {
+                    SortBy::Price => price(a).total_cmp(&price(b)),
+                    SortBy::Reputation => a.reputation.unwrap_or(0.0).total_cmp(&b.reputation.unwrap_or(0.0)),
+                    SortBy::Uptime => a.uptime.unwrap_or(0.0).total_cmp(&b.uptime.unwrap_or(0.0)),
+                    SortBy::Newest => a.created_at.unwrap_or(0).cmp(&b.created_at.unwrap_or(0)),
+                    SortBy::Relevance => std::cmp::Ordering::Equal,
+                };
+                match order {
+                    SortOrder::Asc => ordering,
+                    SortOrder::Desc => ordering.reverse(),
+                }
+            });
+        }
+
+        let offset = self
+            .filters
+            .cursor
+            .as_deref()
+            .map(|c| c.parse::<usize>().map_err(|e| X402Error::InvalidConfig(e.to_string())))
+            .transpose()?
+            .unwrap_or(0);
+        let limit = self.filters.limit.unwrap_or(matched.len()).max(1);
+        let next_cursor = (matched.len() > offset + limit).then(|| (offset + limit).to_string());
+
+        Ok(Page {
+            items: matched.into_iter().skip(offset).take(limit).collect(),
+            next_cursor,
+        })
+    }
+
+    pub fn cache_key(&self) -> String {
+        self.to_query_pairs()
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    pub fn to_query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        self.filters.append_to(&mut params);
+
+        if let Some(q) = &self.query {
+            params.push(("query", q.clone()));
+        }
+        if let Some(c) = &self.category {
+            params.push(("category", c.clone()));
+        }
+        if let Some(o) = &self.owner {
+            params.push(("owner", o.clone()));
+        }
+        if let Some(p) = self.max_price {
+            params.push(("maxPrice", p.to_string()));
+        }
+        if let Some(u) = self.min_uptime {
+            params.push(("minUptime", u.to_string()));
+        }
+        if let Some(a) = &self.asset {
+            params.push(("asset", a.clone()));
+        }
+        if let Some(n) = &self.network {
+            params.push(("network", n.clone()));
+        }
+        if !self.capabilities.is_empty() {
+            params.push(("capabilities", self.capabilities.join(",")));
+        }
+        if !self.tags.is_empty() {
+            params.push(("tags", self.tags.join(",")));
+        }
+        if let Some((sort, order)) = &self.sort {
+            params.push(("sortBy", sort.as_str().to_string()));
+            params.push(("order", order.as_str().to_string()));
+        }
+
+        params
+    }
+}
+
+#[async_trait]
+pub trait DiscoveryBackend: Send + Sync {
+    async fn discover_page(&self, query: &DiscoveryQuery) -> Result<Page<X402Service>>;
+
+    async fn get_service(&self, service_id: &str) -> Result<X402Service>;
+}
+
+pub fn discover_stream<'a>(
+    backend: &'a dyn DiscoveryBackend,
+    query: DiscoveryQuery,
+) -> impl Stream<Item = Result<X402Service>> + 'a {
+    paginate(move |cursor| {
+        let mut page_query = query.clone();
+        if cursor.is_some() {
+            page_query.filters.cursor = cursor;
+        }
+        async move { backend.discover_page(&page_query).await }
+    })
+}
+
+pub struct FallbackDiscovery {
+    backends: Vec<Arc<dyn DiscoveryBackend>>,
+}
+
+impl FallbackDiscovery {
+    pub fn new(backends: Vec<Arc<dyn DiscoveryBackend>>) -> Self {
+        Self { backends }
+    }
+}
+
+#[async_trait]
+impl DiscoveryBackend for FallbackDiscovery {
+    async fn discover_page(&self, query: &DiscoveryQuery) -> Result<Page<X402Service>> {
+        let mut last_error = None;
+
+        for backend in &self.backends {
+            match backend.discover_page(query).await {
+                Ok(page) => return Ok(page),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| X402Error::InvalidConfig("No discovery backends configured".to_string())))
+    }
+
+    async fn get_service(&self, service_id: &str) -> Result<X402Service> {
+        let mut last_error = None;
+
+        for backend in &self.backends {
+            match backend.get_service(service_id).await {
+                Ok(service) => return Ok(service),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| X402Error::InvalidConfig("No discovery backends configured".to_string())))
+    }
+}
+
 pub struct ServiceDiscovery {
     config: DiscoveryConfig,
     client: Client,
+    page_cache: Arc<TtlCache<Page<X402Service>>>,
+    service_cache: Arc<TtlCache<X402Service>>,
+    snapshot: RwLock<Option<Arc<DiscoverySnapshot>>>,
+    health: Option<Arc<HealthMonitor>>,
 }
 
 impl ServiceDiscovery {
@@ -31,7 +388,22 @@ impl ServiceDiscovery {
             .timeout(std::time::Duration::from_secs(config.timeout))
             .build()?;
 
-        Ok(Self { config, client })
+        let ttl = Duration::from_secs(config.cache_ttl);
+        let stale_ttl = Duration::from_secs(config.cache_stale_ttl);
+
+        Ok(Self {
+            config,
+            client,
+            page_cache: Arc::new(TtlCache::new(ttl, stale_ttl)),
+            service_cache: Arc::new(TtlCache::new(ttl, stale_ttl)),
+            snapshot: RwLock::new(None),
+            health: None,
+        })
+    }
+
+    pub fn with_health_monitor(mut self, health: Arc<HealthMonitor>) -> Self {
+        self.health = Some(health);
+        self
     }
 
     pub async fn discover(
@@ -43,43 +415,179 @@ impl ServiceDiscovery {
         min_uptime: Option<f64>,
         limit: usize,
     ) -> Result<Vec<X402Service>> {
-        let mut url = format!("{}/services/discover", self.config.registry_url);
-        let mut params = vec![("limit", limit.to_string())];
+        let mut discovery_query = DiscoveryQuery::new().page_size(limit);
 
         if let Some(q) = query {
-            params.push(("query", q.to_string()));
+            discovery_query = discovery_query.search(q);
         }
         if let Some(c) = category {
-            params.push(("category", c.to_string()));
+            discovery_query = discovery_query.category(c);
         }
         if let Some(p) = max_price {
-            params.push(("maxPrice", p.to_string()));
+            discovery_query = discovery_query.max_price(p);
         }
         if let Some(r) = min_reputation {
-            params.push(("minReputation", r.to_string()));
+            discovery_query = discovery_query.min_reputation(r);
         }
         if let Some(u) = min_uptime {
-            params.push(("minUptime", u.to_string()));
+            discovery_query = discovery_query.min_uptime(u);
+        }
+
+        Ok(self.discover_page(&discovery_query).await?.items)
+    }
+
+    pub async fn discover_page(&self, query: &DiscoveryQuery) -> Result<Page<X402Service>> {
+        let mut page = self.fetch_cached_page(query).await?;
+
+        if let (Some(health), Some(min_health)) = (&self.health, query.min_health) {
+            page.items = health.filter_healthy(page.items, min_health);
         }
 
-        let response = self.client.get(&url).query(&params).send().await?;
+        Ok(page)
+    }
+
+    async fn fetch_cached_page(&self, query: &DiscoveryQuery) -> Result<Page<X402Service>> {
+        let key = query.cache_key();
 
-        response.error_for_status_ref()?;
+        match self.page_cache.get(&key) {
+            CacheLookup::Fresh(page) => return Ok(page),
+            CacheLookup::Stale(page) => {
+                if self.page_cache.begin_refresh(&key) {
+                    let client = self.client.clone();
+                    let registry_url = self.config.registry_url.clone();
+                    let cache = self.page_cache.clone();
+                    let query = query.clone();
 
-        let services: Vec<X402Service> = response.json().await?;
+                    tokio::spawn(async move {
+                        match fetch_page(&client, &registry_url, &query).await {
+                            Ok(page) => cache.insert(&key, page),
+                            Err(_) => cache.end_refresh(&key),
+                        }
+                    });
+                }
+                return Ok(page);
+            }
+            CacheLookup::Miss => {}
+        }
 
-        Ok(services)
+        match fetch_page(&self.client, &self.config.registry_url, query).await {
+            Ok(page) => {
+                for service in &page.items {
+                    self.service_cache.insert(&service.id, service.clone());
+                }
+                self.page_cache.insert(&key, page.clone());
+                Ok(page)
+            }
+            Err(e) => match self.snapshot() {
+                Some(snapshot) => query.apply(&snapshot.services),
+                None => Err(e),
+            },
+        }
+    }
+
+    pub fn discover_stream<'a>(
+        &'a self,
+        query: DiscoveryQuery,
+    ) -> impl Stream<Item = Result<X402Service>> + 'a {
+        discover_stream(self, query)
     }
 
     pub async fn get_service(&self, service_id: &str) -> Result<X402Service> {
-        let url = format!("{}/services/{}", self.config.registry_url, service_id);
+        match self.service_cache.get(service_id) {
+            CacheLookup::Fresh(service) => return Ok(service),
+            CacheLookup::Stale(service) => {
+                if self.service_cache.begin_refresh(service_id) {
+                    let client = self.client.clone();
+                    let registry_url = self.config.registry_url.clone();
+                    let cache = self.service_cache.clone();
+                    let service_id = service_id.to_string();
 
-        let response = self.client.get(&url).send().await?;
+                    tokio::spawn(async move {
+                        match fetch_service(&client, &registry_url, &service_id).await {
+                            Ok(service) => cache.insert(&service_id, service),
+                            Err(_) => cache.end_refresh(&service_id),
+                        }
+                    });
+                }
+                return Ok(service);
+            }
+            CacheLookup::Miss => {}
+        }
+
+        match fetch_service(&self.client, &self.config.registry_url, service_id).await {
+            Ok(service) => {
+                self.service_cache.insert(service_id, service.clone());
+                Ok(service)
+            }
+            Err(e) => self
+                .snapshot()
+                .and_then(|snapshot| snapshot.services.iter().find(|s| s.id == service_id).cloned())
+                .ok_or(e),
+        }
+    }
+
+    pub fn clear_cache(&self) {
+        self.page_cache.clear();
+        self.service_cache.clear();
+    }
+
+    pub fn export_snapshot(&self, keypair: &Keypair) -> Result<DiscoverySnapshot> {
+        let mut services = self.service_cache.values();
+        if let Some(snapshot) = self.snapshot() {
+            for service in &snapshot.services {
+                if !services.iter().any(|s| s.id == service.id) {
+                    services.push(service.clone());
+                }
+            }
+        }
+        services.sort_by(|a, b| a.id.cmp(&b.id));
+
+        DiscoverySnapshot::new(services, keypair)
+    }
 
-        response.error_for_status_ref()?;
+    pub fn import_snapshot(&self, snapshot: DiscoverySnapshot, trusted_key: &PublicKey) -> Result<usize> {
+        snapshot.verify(trusted_key)?;
 
-        let service: X402Service = response.json().await?;
+        let count = snapshot.services.len();
+        *self.snapshot.write().unwrap() = Some(Arc::new(snapshot));
+
+        Ok(count)
+    }
+
+    fn snapshot(&self) -> Option<Arc<DiscoverySnapshot>> {
+        self.snapshot.read().unwrap().clone()
+    }
+}
+
+async fn fetch_page(client: &Client, registry_url: &str, query: &DiscoveryQuery) -> Result<Page<X402Service>> {
+    let url = format!("{}/services/discover", registry_url);
+
+    let response = client.get(&url).query(&query.to_query_pairs()).send().await?;
+
+    response.error_for_status_ref()?;
+
+    Page::from_value(response.json().await?)
+}
+
+async fn fetch_service(client: &Client, registry_url: &str, service_id: &str) -> Result<X402Service> {
+    let url = format!("{}/services/{}", registry_url, service_id);
+
+    let response = client.get(&url).send().await?;
+
+    response.error_for_status_ref()?;
+
+    let service: X402Service = response.json().await?;
+
+    Ok(service)
+}
+
+#[async_trait]
+impl DiscoveryBackend for ServiceDiscovery {
+    async fn discover_page(&self, query: &DiscoveryQuery) -> Result<Page<X402Service>> {
+        ServiceDiscovery::discover_page(self, query).await
+    }
 
-        Ok(service)
+    async fn get_service(&self, service_id: &str) -> Result<X402Service> {
+        ServiceDiscovery::get_service(self, service_id).await
     }
 }
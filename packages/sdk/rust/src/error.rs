@@ -10,12 +10,24 @@ pub enum X402Error {
     #[error("Payment failed: {0}")]
     PaymentFailed(String),
 
+    #[error("Request failed after payment {signature}: {source}")]
+    PaidRequestFailed {
+        signature: String,
+        source: Box<X402Error>,
+    },
+
+    #[error("Payment {signature} was submitted but not confirmed: {reason}")]
+    PaymentUnconfirmed { signature: String, reason: String },
+
     #[error("Insufficient balance: {0}")]
     InsufficientBalance(String),
 
     #[error("Network error: {0}")]
     Network(#[from] reqwest::Error),
 
+    #[error("HTTP {status}: {body}")]
+    HttpStatus { status: u16, body: String },
+
     #[error("Solana error: {0}")]
     Solana(String),
 
@@ -30,6 +42,21 @@ pub enum X402Error {
 
     #[error("Transaction error: {0}")]
     Transaction(String),
+
+    #[error("Key error: {0}")]
+    Key(String),
+
+    #[error("DID resolution error: {0}")]
+    Did(String),
+
+    #[error("Signature error: {0}")]
+    Signature(String),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 impl From<solana_client::client_error::ClientError> for X402Error {
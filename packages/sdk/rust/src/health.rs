@@ -0,0 +1,218 @@
+use futures::future::join_all;
+use reqwest::{Client, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::client::CASH_MINT;
+use crate::discovery::X402Service;
+use crate::error::Result;
+use crate::types::{PaymentRequirements, ServicePricing};
+
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+    pub timeout: u64,
+    pub window: usize,
+    pub slow_latency_ms: u64,
+    pub price_tolerance: f64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            timeout: 5,
+            window: 20,
+            slow_latency_ms: 2000,
+            price_tolerance: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeResult {
+    pub service_id: String,
+    pub resource: String,
+    pub timestamp: u64,
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    pub valid_402: bool,
+    pub price_mismatch: bool,
+    pub advertised: Option<ServicePricing>,
+    pub live: Option<PaymentRequirements>,
+    pub error: Option<String>,
+}
+
+impl ProbeResult {
+    fn score(&self, config: &HealthConfig) -> f64 {
+        if !self.valid_402 {
+            return 0.0;
+        }
+
+        let slowness = (self.latency_ms as f64 / config.slow_latency_ms.max(1) as f64).min(1.0);
+        let score = 1.0 - 0.5 * slowness;
+
+        if self.price_mismatch {
+            score * 0.5
+        } else {
+            score
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthScore {
+    pub score: f64,
+    pub samples: usize,
+    pub average_latency_ms: f64,
+    pub price_mismatch: bool,
+    pub last_probe: u64,
+}
+
+pub struct HealthMonitor {
+    config: HealthConfig,
+    client: Client,
+    history: Mutex<HashMap<String, VecDeque<ProbeResult>>>,
+}
+
+impl HealthMonitor {
+    pub fn new(config: HealthConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout))
+            .build()?;
+
+        Ok(Self {
+            config,
+            client,
+            history: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub async fn probe(&self, service: &X402Service) -> ProbeResult {
+        let method = Method::from_bytes(service.method.to_uppercase().as_bytes()).unwrap_or(Method::GET);
+
+        let started = Instant::now();
+        let response = self.client.request(method, &service.resource).send().await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let mut result = ProbeResult {
+            service_id: service.id.clone(),
+            resource: service.resource.clone(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64,
+            status: None,
+            latency_ms,
+            valid_402: false,
+            price_mismatch: false,
+            advertised: service.pricing.clone(),
+            live: None,
+            error: None,
+        };
+
+        match response {
+            Ok(response) => {
+                result.status = Some(response.status().as_u16());
+
+                if response.status() != StatusCode::PAYMENT_REQUIRED {
+                    result.error = Some(format!("Expected 402, got {}", response.status()));
+                } else {
+                    match response.json::<PaymentRequirements>().await {
+                        Ok(requirements) => {
+                            result.valid_402 = true;
+                            result.price_mismatch = service
+                                .pricing
+                                .as_ref()
+                                .map(|pricing| !self.pricing_matches(pricing, &requirements))
+                                .unwrap_or(false);
+                            result.live = Some(requirements);
+                        }
+                        Err(e) => result.error = Some(format!("Invalid payment requirements: {}", e)),
+                    }
+                }
+            }
+            Err(e) => result.error = Some(e.to_string()),
+        }
+
+        self.record(result.clone());
+        result
+    }
+
+    pub async fn probe_all(&self, services: &[X402Service]) -> Vec<ProbeResult> {
+        join_all(services.iter().map(|service| self.probe(service))).await
+    }
+
+    pub fn record(&self, result: ProbeResult) {
+        let mut history = self.history.lock().unwrap();
+        let samples = history.entry(result.service_id.clone()).or_default();
+
+        samples.push_back(result);
+        while samples.len() > self.config.window.max(1) {
+            samples.pop_front();
+        }
+    }
+
+    pub fn health(&self, service_id: &str) -> Option<HealthScore> {
+        self.history
+            .lock()
+            .unwrap()
+            .get(service_id)
+            .and_then(|samples| self.summarize(samples))
+    }
+
+    pub fn scores(&self) -> HashMap<String, HealthScore> {
+        self.history
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(id, samples)| self.summarize(samples).map(|score| (id.clone(), score)))
+            .collect()
+    }
+
+    pub fn price_mismatches(&self) -> Vec<ProbeResult> {
+        self.history
+            .lock()
+            .unwrap()
+            .values()
+            .filter_map(|samples| samples.back())
+            .filter(|latest| latest.price_mismatch)
+            .cloned()
+            .collect()
+    }
+
+    pub fn filter_healthy(&self, services: Vec<X402Service>, min_score: f64) -> Vec<X402Service> {
+        let scores = self.scores();
+        services
+            .into_iter()
+            .filter(|service| scores.get(&service.id).map_or(true, |h| h.score >= min_score))
+            .collect()
+    }
+
+    fn summarize(&self, samples: &VecDeque<ProbeResult>) -> Option<HealthScore> {
+        let latest = samples.back()?;
+        let count = samples.len() as f64;
+
+        Some(HealthScore {
+            score: samples.iter().map(|s| s.score(&self.config)).sum::<f64>() / count,
+            samples: samples.len(),
+            average_latency_ms: samples.iter().map(|s| s.latency_ms as f64).sum::<f64>() / count,
+            price_mismatch: latest.price_mismatch,
+            last_probe: latest.timestamp,
+        })
+    }
+
+    fn pricing_matches(&self, advertised: &ServicePricing, live: &PaymentRequirements) -> bool {
+        let amounts_match = match (advertised.amount.parse::<f64>(), live.amount.parse::<f64>()) {
+            (Ok(a), Ok(b)) => (a - b).abs() <= a.abs() * self.config.price_tolerance + f64::EPSILON,
+            _ => advertised.amount == live.amount,
+        };
+
+        let assets_match = advertised.asset == live.asset
+            || (advertised.asset == "CASH" && live.asset == CASH_MINT.to_string());
+
+        amounts_match && assets_match
+    }
+}
@@ -1,13 +1,24 @@
+pub mod cache;
 pub mod client;
 pub mod discovery;
 pub mod error;
+pub mod health;
+pub mod query;
+pub mod registry;
+pub mod selection;
+pub mod snapshot;
 pub mod types;
 pub mod tap;
 
 pub use client::{SolanaX402Client, X402Config, CASH_MINT, TOKEN_2022_PROGRAM_ID, CASH_DECIMALS};
-pub use discovery::{ServiceDiscovery, DiscoveryConfig, X402Service};
+pub use discovery::{ServiceDiscovery, DiscoveryBackend, DiscoveryConfig, DiscoveryQuery, FallbackDiscovery, SortBy, SortOrder, X402Service};
+pub use registry::{OnChainDiscovery, OnChainDiscoveryConfig, RegistryClient};
 pub use error::{X402Error, Result};
-pub use types::{PaymentRequirements, PaymentPayload, PaymentMetrics, PaymentRecord};
+pub use health::{HealthConfig, HealthMonitor, HealthScore, ProbeResult};
+pub use query::{Page, QueryFilters};
+pub use selection::{RankedService, SelectionConfig, SelectionWeights, ServiceSelector};
+pub use snapshot::DiscoverySnapshot;
+pub use types::{PaymentRequirements, PaymentPayload, PaymentMetrics, PaymentRecord, ServiceStats};
 pub use tap::{
     RFC9421Signature,
     SignatureAlgorithm,
@@ -17,4 +28,15 @@ pub use tap::{
     TAPClient,
     TAPConfig,
     AgentIdentity,
+    AgentQuery,
+    KeyRotation,
+    TAPKeys,
+    KeyFormat,
+    Jwk,
+    NonceStore,
+    InMemoryNonceStore,
+    RFC9421Verifier,
+    DidDocument,
+    DidResolver,
+    DidResolverConfig,
 };
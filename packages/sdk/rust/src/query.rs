@@ -0,0 +1,90 @@
+use futures::stream::{self, Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use url::Url;
+
+use crate::error::{Result, X402Error};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryFilters {
+    pub min_reputation: Option<f64>,
+    pub verified_only: bool,
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+}
+
+impl QueryFilters {
+    pub fn append_to(&self, pairs: &mut Vec<(&'static str, String)>) {
+        if let Some(r) = self.min_reputation {
+            pairs.push(("minReputation", r.to_string()));
+        }
+        if self.verified_only {
+            pairs.push(("verified", "true".to_string()));
+        }
+        if let Some(l) = self.limit {
+            pairs.push(("limit", l.to_string()));
+        }
+        if let Some(c) = &self.cursor {
+            pairs.push(("cursor", c.clone()));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    #[serde(alias = "agents", alias = "services")]
+    pub items: Vec<T>,
+    #[serde(rename = "nextCursor", default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PageResponse<T> {
+    Paged(Page<T>),
+    List(Vec<T>),
+}
+
+impl<T: serde::de::DeserializeOwned> Page<T> {
+    pub fn from_value(value: serde_json::Value) -> Result<Self> {
+        Ok(match serde_json::from_value(value)? {
+            PageResponse::Paged(page) => page,
+            PageResponse::List(items) => Page { items, next_cursor: None },
+        })
+    }
+}
+
+pub fn build_url(base: &str, pairs: &[(&'static str, String)]) -> Result<String> {
+    let mut url = Url::parse(base).map_err(|e| X402Error::InvalidConfig(e.to_string()))?;
+
+    if !pairs.is_empty() {
+        let mut query = url.query_pairs_mut();
+        for (key, value) in pairs {
+            query.append_pair(key, value);
+        }
+    }
+
+    Ok(url.into())
+}
+
+pub fn paginate<T, F, Fut>(fetch_page: F) -> impl Stream<Item = Result<T>>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<Page<T>>>,
+{
+    stream::try_unfold((fetch_page, Some(None)), |(mut fetch_page, cursor)| async move {
+        let cursor: Option<String> = match cursor {
+            Some(cursor) => cursor,
+            None => return Ok(None),
+        };
+
+        let page = fetch_page(cursor).await?;
+        let next = page.next_cursor.filter(|c| !c.is_empty()).map(Some);
+
+        Ok(Some((
+            stream::iter(page.items.into_iter().map(Ok::<T, X402Error>)),
+            (fetch_page, next),
+        )))
+    })
+    .try_flatten()
+}
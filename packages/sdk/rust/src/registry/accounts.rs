@@ -0,0 +1,651 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::X402Error;
+
+pub fn account_discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("account:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash[..8]);
+    discriminator
+}
+
+pub(crate) fn decode_account<T: BorshDeserialize>(name: &str, data: &[u8]) -> Result<T, X402Error> {
+    if data.len() < 8 || data[..8] != account_discriminator(name) {
+        return Err(X402Error::Solana(format!("Account is not a registry {}", name)));
+    }
+
+    let mut body = &data[8..];
+    T::deserialize(&mut body).map_err(|e| X402Error::Solana(format!("Failed to decode {}: {}", name, e)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub enum AgentStatus {
+    Active,
+    Paused,
+    Suspended,
+    Banned,
+}
+
+impl AgentStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AgentStatus::Active => "active",
+            AgentStatus::Paused => "paused",
+            AgentStatus::Suspended => "suspended",
+            AgentStatus::Banned => "banned",
+        }
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct Agent {
+    pub wallet: Pubkey,
+    pub did: String,
+    pub visa_tap_cert: String,
+    pub reputation_score: u64,
+    pub total_spent: u64,
+    pub total_transactions: u64,
+    pub successful_transactions: u64,
+    pub disputes_won: u32,
+    pub disputes_lost: u32,
+    pub staked_amount: u64,
+    pub slashed_amount: u64,
+    pub credit_limit: u64,
+    pub credit_used: u64,
+    pub created_at: i64,
+    pub last_active: i64,
+    pub last_transaction_time: i64,
+    pub last_slashed_time: i64,
+    pub last_slash_evidence: String,
+    pub metadata_uri: String,
+    pub status: AgentStatus,
+    pub historical_min_reputation: u64,
+    pub service_count: u32,
+    pub stake_mint: Pubkey,
+}
+
+impl Agent {
+    pub const DISCRIMINATOR_NAME: &'static str = "Agent";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub enum ServiceStatus {
+    Active,
+    Paused,
+    Deprecated,
+    Suspended,
+}
+
+impl ServiceStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ServiceStatus::Active => "active",
+            ServiceStatus::Paused => "paused",
+            ServiceStatus::Deprecated => "deprecated",
+            ServiceStatus::Suspended => "suspended",
+        }
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct Service {
+    pub url: String,
+    pub name: String,
+    pub description: String,
+    pub category: String,
+    pub owner: Pubkey,
+    pub price_per_call: u64,
+    pub accepted_tokens: Vec<Pubkey>,
+    pub total_calls: u64,
+    pub successful_calls: u64,
+    pub total_revenue: u64,
+    pub total_response_time_sum: u64,
+    pub average_response_time_ms: u32,
+    pub uptime_percent: u8,
+    pub reputation_score: u64,
+    pub total_ratings: u32,
+    pub total_rating_sum: u64,
+    pub average_rating: u16,
+    pub verified: bool,
+    pub visa_tap_verified: bool,
+    pub created_at: i64,
+    pub last_updated: i64,
+    pub status: ServiceStatus,
+    pub total_rating_weight: u64,
+    pub weighted_rating_sum: u64,
+    pub directory_index: Option<u32>,
+}
+
+impl Service {
+    pub const DISCRIMINATOR_NAME: &'static str = "Service";
+    /// The rating weights and `directory_index`, which services written before them lack.
+    const LEGACY_TRAILING_LEN: usize = 8 + 8 + (1 + 4);
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+
+    /// Also decodes services written before the trailing fields existed, reading those fields as zero, as the
+    /// program does when `migrate_service` grows the account.
+    pub fn try_from_legacy_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        let mut padded = data.to_vec();
+        padded.resize(data.len() + Self::LEGACY_TRAILING_LEN, 0);
+        Self::try_from_account_data(&padded)
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ServiceRating {
+    pub service: Pubkey,
+    pub agent: Pubkey,
+    pub rating: u16,
+    pub timestamp: i64,
+    pub last_update_time: i64,
+    pub weight: u64,
+}
+
+impl ServiceRating {
+    pub const DISCRIMINATOR_NAME: &'static str = "ServiceRating";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ServiceInteraction {
+    pub service: Pubkey,
+    pub agent: Pubkey,
+    pub transactions: u64,
+    pub successful_transactions: u64,
+    pub total_amount: u64,
+    pub first_at: i64,
+    pub last_at: i64,
+    pub bump: u8,
+}
+
+impl ServiceInteraction {
+    pub const DISCRIMINATOR_NAME: &'static str = "ServiceInteraction";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct RegistryConfig {
+    pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+    pub oracle_authority: Pubkey,
+    pub verifier_authority: Pubkey,
+    pub tap_authority: Pubkey,
+    pub dao_authority: Pubkey,
+    pub arbiter_authority: Pubkey,
+    pub dao_treasury: Pubkey,
+    pub stake_token_mint: Pubkey,
+    pub min_stake_amount: u64,
+    pub min_slash_amount: u64,
+    pub unstake_lock_seconds: i64,
+    pub restake_cooldown_seconds: i64,
+    pub transaction_rate_limit_seconds: i64,
+    pub rating_update_cooldown_seconds: i64,
+    pub slash_cooldown_seconds: i64,
+    pub credit_reputation_threshold: u64,
+    pub dispute_bond_lamports: u64,
+    pub dispute_response_seconds: i64,
+    pub credit_fee_bps: u16,
+    pub credit_repayment_seconds: i64,
+    pub channel_challenge_seconds: i64,
+    pub health_report_interval_seconds: i64,
+    pub uptime_alpha_bps: u16,
+    pub reputation_half_life_seconds: i64,
+    pub reputation_value_unit: u64,
+    pub reputation_alpha_bps: u16,
+    pub reputation_max_alpha_bps: u16,
+    pub reputation_slash_penalty: u64,
+    pub rating_window_seconds: i64,
+    pub council: Vec<Pubkey>,
+    pub council_threshold: u8,
+    pub governance_timelock_seconds: i64,
+    pub proposal_count: u64,
+    pub bump: u8,
+}
+
+impl RegistryConfig {
+    pub const DISCRIMINATOR_NAME: &'static str = "RegistryConfig";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+}
+
+#[derive(Debug, Clone, BorshSerialize)]
+pub struct ConfigParams {
+    pub oracle_authority: Pubkey,
+    pub verifier_authority: Pubkey,
+    pub tap_authority: Pubkey,
+    pub dao_authority: Pubkey,
+    pub arbiter_authority: Pubkey,
+    pub dao_treasury: Pubkey,
+    pub stake_token_mint: Pubkey,
+    pub min_stake_amount: u64,
+    pub min_slash_amount: u64,
+    pub unstake_lock_seconds: i64,
+    pub restake_cooldown_seconds: i64,
+    pub transaction_rate_limit_seconds: i64,
+    pub rating_update_cooldown_seconds: i64,
+    pub slash_cooldown_seconds: i64,
+    pub credit_reputation_threshold: u64,
+    pub dispute_bond_lamports: u64,
+    pub dispute_response_seconds: i64,
+    pub credit_fee_bps: u16,
+    pub credit_repayment_seconds: i64,
+    pub channel_challenge_seconds: i64,
+    pub health_report_interval_seconds: i64,
+    pub uptime_alpha_bps: u16,
+    pub reputation_half_life_seconds: i64,
+    pub reputation_value_unit: u64,
+    pub reputation_alpha_bps: u16,
+    pub reputation_max_alpha_bps: u16,
+    pub reputation_slash_penalty: u64,
+    pub rating_window_seconds: i64,
+    pub council: Vec<Pubkey>,
+    pub council_threshold: u8,
+    pub governance_timelock_seconds: i64,
+}
+
+#[derive(Debug, Clone, Default, BorshSerialize)]
+pub struct ConfigUpdate {
+    pub oracle_authority: Option<Pubkey>,
+    pub verifier_authority: Option<Pubkey>,
+    pub tap_authority: Option<Pubkey>,
+    pub dao_treasury: Option<Pubkey>,
+    pub min_stake_amount: Option<u64>,
+    pub min_slash_amount: Option<u64>,
+    pub unstake_lock_seconds: Option<i64>,
+    pub restake_cooldown_seconds: Option<i64>,
+    pub transaction_rate_limit_seconds: Option<i64>,
+    pub rating_update_cooldown_seconds: Option<i64>,
+    pub slash_cooldown_seconds: Option<i64>,
+    pub credit_reputation_threshold: Option<u64>,
+    pub dispute_bond_lamports: Option<u64>,
+    pub dispute_response_seconds: Option<i64>,
+    pub credit_fee_bps: Option<u16>,
+    pub credit_repayment_seconds: Option<i64>,
+    pub channel_challenge_seconds: Option<i64>,
+    pub health_report_interval_seconds: Option<i64>,
+    pub uptime_alpha_bps: Option<u16>,
+    pub reputation_half_life_seconds: Option<i64>,
+    pub reputation_value_unit: Option<u64>,
+    pub reputation_alpha_bps: Option<u16>,
+    pub reputation_max_alpha_bps: Option<u16>,
+    pub reputation_slash_penalty: Option<u64>,
+    pub rating_window_seconds: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub enum DisputeStatus {
+    Open,
+    Responded,
+    ResolvedForPayer,
+    ResolvedForProvider,
+}
+
+impl DisputeStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DisputeStatus::Open => "open",
+            DisputeStatus::Responded => "responded",
+            DisputeStatus::ResolvedForPayer => "resolved_for_payer",
+            DisputeStatus::ResolvedForProvider => "resolved_for_provider",
+        }
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct Dispute {
+    pub payer: Pubkey,
+    pub provider: Pubkey,
+    pub service: Pubkey,
+    pub transaction_signature: [u8; 64],
+    pub evidence_uri: String,
+    pub response_uri: String,
+    pub bond_lamports: u64,
+    pub provider_bonded: bool,
+    pub opened_at: i64,
+    pub response_deadline: i64,
+    pub resolved_at: i64,
+    pub status: DisputeStatus,
+    pub refund_amount: u64,
+    pub slash_amount: u64,
+    pub bump: u8,
+}
+
+impl Dispute {
+    pub const DISCRIMINATOR_NAME: &'static str = "Dispute";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+}
+
+#[derive(Debug, Clone, BorshSerialize)]
+pub struct DisputeRuling {
+    pub payer_wins: bool,
+    pub refund_amount: u64,
+    pub slash_amount: u64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct CreditLine {
+    pub agent: Pubkey,
+    pub due_at: i64,
+    pub total_drawn: u64,
+    pub total_fees: u64,
+    pub total_repaid: u64,
+    pub total_seized: u64,
+    pub last_draw_at: i64,
+    pub bump: u8,
+}
+
+impl CreditLine {
+    pub const DISCRIMINATOR_NAME: &'static str = "CreditLine";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub enum PaymentEscrowStatus {
+    Funded,
+    Released,
+    Refunded,
+}
+
+impl PaymentEscrowStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PaymentEscrowStatus::Funded => "funded",
+            PaymentEscrowStatus::Released => "released",
+            PaymentEscrowStatus::Refunded => "refunded",
+        }
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct PaymentEscrow {
+    pub payer: Pubkey,
+    pub payee: Pubkey,
+    pub service: Pubkey,
+    pub mint: Pubkey,
+    pub nonce: u64,
+    pub amount: u64,
+    pub deadline: i64,
+    pub created_at: i64,
+    pub settled_at: i64,
+    pub status: PaymentEscrowStatus,
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+impl PaymentEscrow {
+    pub const DISCRIMINATOR_NAME: &'static str = "PaymentEscrow";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub enum ChannelStatus {
+    Open,
+    Closing,
+    Closed,
+}
+
+impl ChannelStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ChannelStatus::Open => "open",
+            ChannelStatus::Closing => "closing",
+            ChannelStatus::Closed => "closed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct PaymentChannel {
+    pub payer: Pubkey,
+    pub payee: Pubkey,
+    pub service: Pubkey,
+    pub mint: Pubkey,
+    pub channel_id: u64,
+    pub deposit: u64,
+    pub redeemed: u64,
+    pub last_nonce: u64,
+    pub challenge_seconds: i64,
+    pub opened_at: i64,
+    pub close_requested_at: i64,
+    pub status: ChannelStatus,
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+impl PaymentChannel {
+    pub const DISCRIMINATOR_NAME: &'static str = "PaymentChannel";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct StakeMint {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub decimals: u8,
+    pub reputation_weight_bps: u32,
+    pub enabled: bool,
+    pub bump: u8,
+    pub escrow_bump: u8,
+}
+
+impl StakeMint {
+    pub const DISCRIMINATOR_NAME: &'static str = "StakeMint";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+
+    pub fn stake_value(&self, amount: u64) -> u64 {
+        (amount as u128 * self.reputation_weight_bps as u128 / 10_000).min(u64::MAX as u128) as u64
+    }
+}
+
+#[derive(Debug, Clone, Default, BorshSerialize)]
+pub struct StakeMintUpdate {
+    pub reputation_weight_bps: Option<u32>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ProbeOperator {
+    pub operator: Pubkey,
+    pub added_at: i64,
+    pub reports: u64,
+    pub bump: u8,
+}
+
+impl ProbeOperator {
+    pub const DISCRIMINATOR_NAME: &'static str = "ProbeOperator";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ServiceHealth {
+    pub service: Pubkey,
+    pub uptime_bps: u16,
+    pub latency_ms: u32,
+    pub total_probes: u64,
+    pub successful_probes: u64,
+    pub reports: u64,
+    pub last_operator: Pubkey,
+    pub last_window_end: i64,
+    pub last_reported_at: i64,
+    pub bump: u8,
+}
+
+impl ServiceHealth {
+    pub const DISCRIMINATOR_NAME: &'static str = "ServiceHealth";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+}
+
+#[derive(Debug, Clone, BorshSerialize)]
+pub struct HealthReport {
+    pub window_start: i64,
+    pub window_end: i64,
+    pub probes: u32,
+    pub successes: u32,
+    pub average_latency_ms: u32,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct TransactionReceipt {
+    pub service: Pubkey,
+    pub agent: Pubkey,
+    pub nonce: u64,
+    pub amount: u64,
+    pub success: bool,
+    pub recorded_at: i64,
+    pub bump: u8,
+}
+
+impl TransactionReceipt {
+    pub const DISCRIMINATOR_NAME: &'static str = "TransactionReceipt";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ServiceDirectory {
+    pub owner: Pubkey,
+    pub next_index: u32,
+    pub service_count: u32,
+    pub bump: u8,
+}
+
+impl ServiceDirectory {
+    pub const DISCRIMINATOR_NAME: &'static str = "ServiceDirectory";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+
+    pub fn page_count(&self) -> u32 {
+        self.next_index.div_ceil(super::SERVICE_PAGE_SIZE)
+    }
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ServiceDirectoryPage {
+    pub owner: Pubkey,
+    pub page: u32,
+    pub services: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl ServiceDirectoryPage {
+    pub const DISCRIMINATOR_NAME: &'static str = "ServiceDirectoryPage";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum GovernanceAction {
+    SuspendAgent { agent: Pubkey },
+    BanAgent { agent: Pubkey },
+    SlashForFraud { agent: Pubkey, fraud_amount: u64, evidence_uri: String },
+    SuspendService { service: Pubkey },
+    CloseSuspendedService { service: Pubkey },
+    UnsuspendAgent { agent: Pubkey },
+    UnsuspendService { service: Pubkey },
+    UpdateCouncil { council: Vec<Pubkey>, threshold: u8, timelock_seconds: i64 },
+    UpdateAuthorities { dao_authority: Pubkey, arbiter_authority: Pubkey },
+}
+
+impl GovernanceAction {
+    pub fn as_str(&self) -> &str {
+        match self {
+            GovernanceAction::SuspendAgent { .. } => "suspend_agent",
+            GovernanceAction::BanAgent { .. } => "ban_agent",
+            GovernanceAction::SlashForFraud { .. } => "slash_for_fraud",
+            GovernanceAction::SuspendService { .. } => "suspend_service",
+            GovernanceAction::CloseSuspendedService { .. } => "close_suspended_service",
+            GovernanceAction::UnsuspendAgent { .. } => "unsuspend_agent",
+            GovernanceAction::UnsuspendService { .. } => "unsuspend_service",
+            GovernanceAction::UpdateCouncil { .. } => "update_council",
+            GovernanceAction::UpdateAuthorities { .. } => "update_authorities",
+        }
+    }
+
+    /// The agent or service the action applies to; `None` for config changes.
+    pub fn target(&self) -> Option<Pubkey> {
+        match self {
+            GovernanceAction::SuspendAgent { agent }
+            | GovernanceAction::BanAgent { agent }
+            | GovernanceAction::SlashForFraud { agent, .. }
+            | GovernanceAction::UnsuspendAgent { agent } => Some(*agent),
+            GovernanceAction::SuspendService { service }
+            | GovernanceAction::CloseSuspendedService { service }
+            | GovernanceAction::UnsuspendService { service } => Some(*service),
+            GovernanceAction::UpdateCouncil { .. } | GovernanceAction::UpdateAuthorities { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub enum ProposalStatus {
+    Pending,
+    Approved,
+    Executed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: GovernanceAction,
+    pub emergency: bool,
+    pub approvals: Vec<Pubkey>,
+    pub status: ProposalStatus,
+    pub created_at: i64,
+    pub executable_at: i64,
+    pub executed_at: i64,
+    pub bump: u8,
+}
+
+impl Proposal {
+    pub const DISCRIMINATOR_NAME: &'static str = "Proposal";
+
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, X402Error> {
+        decode_account(Self::DISCRIMINATOR_NAME, data)
+    }
+}
@@ -0,0 +1,173 @@
+use base64::{engine::general_purpose, Engine as _};
+use solana_sdk::ed25519_program;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+
+use super::accounts::{ChannelStatus, PaymentChannel};
+use super::REGISTRY_PROGRAM_ID;
+use crate::error::{Result, X402Error};
+
+/// Leads every voucher message, followed by the program ID, so a voucher signature is only valid here.
+const VOUCHER_DOMAIN: &[u8] = b"x402-registry:voucher";
+const VOUCHER_MESSAGE_LEN: usize = VOUCHER_DOMAIN.len() + 32 + 48;
+const SIGNED_VOUCHER_LEN: usize = VOUCHER_MESSAGE_LEN + 32 + 64;
+
+const ED25519_OFFSETS_START: u16 = 2;
+const ED25519_OFFSETS_SIZE: u16 = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Voucher {
+    pub program_id: Pubkey,
+    pub channel: Pubkey,
+    pub amount: u64,
+    pub nonce: u64,
+}
+
+impl Voucher {
+    /// A voucher for a channel of the default registry deployment.
+    pub fn new(channel: Pubkey, amount: u64, nonce: u64) -> Self {
+        Self {
+            program_id: *REGISTRY_PROGRAM_ID,
+            channel,
+            amount,
+            nonce,
+        }
+    }
+
+    pub fn with_program_id(mut self, program_id: Pubkey) -> Self {
+        self.program_id = program_id;
+        self
+    }
+
+    pub fn message(&self) -> [u8; VOUCHER_MESSAGE_LEN] {
+        let mut message = [0u8; VOUCHER_MESSAGE_LEN];
+        message[..VOUCHER_DOMAIN.len()].copy_from_slice(VOUCHER_DOMAIN);
+        let body = &mut message[VOUCHER_DOMAIN.len()..];
+        body[..32].copy_from_slice(self.program_id.as_ref());
+        body[32..64].copy_from_slice(self.channel.as_ref());
+        body[64..72].copy_from_slice(&self.amount.to_le_bytes());
+        body[72..].copy_from_slice(&self.nonce.to_le_bytes());
+        message
+    }
+
+    pub fn sign(&self, payer: &Keypair) -> SignedVoucher {
+        SignedVoucher {
+            voucher: *self,
+            payer: payer.pubkey(),
+            signature: payer.sign_message(&self.message()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedVoucher {
+    pub voucher: Voucher,
+    pub payer: Pubkey,
+    pub signature: Signature,
+}
+
+impl SignedVoucher {
+    pub fn verify(&self) -> bool {
+        self.signature.verify(self.payer.as_ref(), &self.voucher.message())
+    }
+
+    pub fn verify_against(&self, channel_address: &Pubkey, channel: &PaymentChannel) -> Result<()> {
+        if self.voucher.channel != *channel_address {
+            return Err(X402Error::Signature("Voucher is for a different channel".to_string()));
+        }
+        if self.payer != channel.payer {
+            return Err(X402Error::Signature("Voucher is not signed by the channel payer".to_string()));
+        }
+        if !self.verify() {
+            return Err(X402Error::Signature("Invalid voucher signature".to_string()));
+        }
+        if channel.status == ChannelStatus::Closed {
+            return Err(X402Error::InvalidConfig("Channel is closed".to_string()));
+        }
+        if self.voucher.nonce <= channel.last_nonce || self.voucher.amount <= channel.redeemed {
+            return Err(X402Error::Signature("Voucher is older than the last redemption".to_string()));
+        }
+        if self.voucher.amount > channel.deposit {
+            return Err(X402Error::InsufficientBalance(format!(
+                "Voucher amount {} exceeds channel deposit {}",
+                self.voucher.amount, channel.deposit
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn encode(&self) -> String {
+        let mut bytes = Vec::with_capacity(SIGNED_VOUCHER_LEN);
+        bytes.extend_from_slice(&self.voucher.message());
+        bytes.extend_from_slice(self.payer.as_ref());
+        bytes.extend_from_slice(self.signature.as_ref());
+        general_purpose::STANDARD.encode(bytes)
+    }
+
+    pub fn decode(encoded: &str) -> Result<Self> {
+        let bytes = general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| X402Error::Signature(format!("Invalid voucher encoding: {}", e)))?;
+
+        if bytes.len() != SIGNED_VOUCHER_LEN {
+            return Err(X402Error::Signature("Invalid voucher length".to_string()));
+        }
+        if !bytes.starts_with(VOUCHER_DOMAIN) {
+            return Err(X402Error::Signature("Invalid voucher domain".to_string()));
+        }
+
+        let body = &bytes[VOUCHER_DOMAIN.len()..VOUCHER_MESSAGE_LEN];
+        let read_u64 = |offset: usize| {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&body[offset..offset + 8]);
+            u64::from_le_bytes(buf)
+        };
+
+        Ok(Self {
+            voucher: Voucher {
+                program_id: Pubkey::try_from(&body[..32]).expect("32-byte slice"),
+                channel: Pubkey::try_from(&body[32..64]).expect("32-byte slice"),
+                amount: read_u64(64),
+                nonce: read_u64(72),
+            },
+            payer: Pubkey::try_from(&bytes[VOUCHER_MESSAGE_LEN..VOUCHER_MESSAGE_LEN + 32]).expect("32-byte slice"),
+            signature: Signature::try_from(&bytes[VOUCHER_MESSAGE_LEN + 32..]).expect("64-byte slice"),
+        })
+    }
+
+    pub fn verify_instruction(&self) -> Instruction {
+        ed25519_verify_instruction(&self.payer, &self.signature, &self.voucher.message())
+    }
+}
+
+pub fn ed25519_verify_instruction(signer: &Pubkey, signature: &Signature, message: &[u8]) -> Instruction {
+    let public_key_offset = ED25519_OFFSETS_START + ED25519_OFFSETS_SIZE;
+    let signature_offset = public_key_offset + 32;
+    let message_data_offset = signature_offset + 64;
+
+    let mut data = Vec::with_capacity(message_data_offset as usize + message.len());
+    data.push(1);
+    data.push(0);
+    for value in [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_data_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature.as_ref());
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: ed25519_program::id(),
+        accounts: vec![],
+        data,
+    }
+}
@@ -0,0 +1,749 @@
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_transaction_status::UiTransactionEncoding;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use std::sync::Arc;
+
+use super::accounts::{
+    Agent, CreditLine, Dispute, GovernanceAction, HealthReport, PaymentChannel, PaymentEscrow, PaymentEscrowStatus,
+    Proposal, RegistryConfig, Service, ServiceDirectory, ServiceDirectoryPage, ServiceHealth, ServiceInteraction,
+    ServiceRating, StakeMint, TransactionReceipt,
+};
+use super::channel::SignedVoucher;
+use super::events::RegistryEvent;
+use super::receipt::{CosignedReceipt, Receipt};
+use super::{
+    find_agent_address, find_channel_address, find_config_address, find_credit_line_address, find_dispute_address,
+    find_interaction_address, find_payment_escrow_address, find_proposal_address, find_rating_address,
+    find_receipt_address, find_service_address, find_service_directory_address, find_service_health_address,
+    find_service_page_address, find_stake_mint_address, instructions, REGISTRY_PROGRAM_ID,
+};
+use crate::error::{Result, X402Error};
+
+pub struct RegistryClient {
+    rpc_client: Arc<RpcClient>,
+    program_id: Pubkey,
+}
+
+impl RegistryClient {
+    pub fn new(rpc_url: &str) -> Self {
+        let rpc_client = Arc::new(RpcClient::new_with_commitment(
+            rpc_url.to_string(),
+            CommitmentConfig::confirmed(),
+        ));
+
+        Self {
+            rpc_client,
+            program_id: *REGISTRY_PROGRAM_ID,
+        }
+    }
+
+    pub fn with_rpc_client(rpc_client: Arc<RpcClient>, program_id: Pubkey) -> Self {
+        Self { rpc_client, program_id }
+    }
+
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    pub fn fetch_config(&self) -> Result<RegistryConfig> {
+        let (address, _) = find_config_address(&self.program_id);
+        RegistryConfig::try_from_account_data(&self.fetch_account_data(&address)?)
+    }
+
+    pub fn fetch_agent(&self, wallet: &Pubkey) -> Result<Agent> {
+        let (address, _) = find_agent_address(wallet, &self.program_id);
+        Agent::try_from_account_data(&self.fetch_account_data(&address)?)
+    }
+
+    pub fn fetch_stake_mint(&self, mint: &Pubkey) -> Result<StakeMint> {
+        let (address, _) = find_stake_mint_address(mint, &self.program_id);
+        StakeMint::try_from_account_data(&self.fetch_account_data(&address)?)
+    }
+
+    pub fn fetch_service(&self, address: &Pubkey) -> Result<Service> {
+        Service::try_from_account_data(&self.fetch_account_data(address)?)
+    }
+
+    pub fn fetch_service_directory(&self, owner: &Pubkey) -> Result<ServiceDirectory> {
+        let (address, _) = find_service_directory_address(owner, &self.program_id);
+        ServiceDirectory::try_from_account_data(&self.fetch_account_data(&address)?)
+    }
+
+    pub fn fetch_service_page(&self, owner: &Pubkey, page: u32) -> Result<ServiceDirectoryPage> {
+        let (address, _) = find_service_page_address(owner, page, &self.program_id);
+        ServiceDirectoryPage::try_from_account_data(&self.fetch_account_data(&address)?)
+    }
+
+    /// Fetches one page of an owner's services. Pages run from 0 to `ServiceDirectory::page_count`.
+    pub fn list_services(&self, owner: &Pubkey, page: u32) -> Result<Vec<(Pubkey, Service)>> {
+        let listed = self.fetch_service_page(owner, page)?.services;
+        let accounts = self.rpc_client.get_multiple_accounts(&listed)?;
+
+        listed
+            .into_iter()
+            .zip(accounts)
+            .filter_map(|(address, account)| account.map(|account| (address, account)))
+            .map(|(address, account)| Ok((address, Service::try_from_account_data(&account.data)?)))
+            .collect()
+    }
+
+    pub fn fetch_service_rating(&self, service: &Pubkey, rater: &Pubkey) -> Result<ServiceRating> {
+        let (address, _) = find_rating_address(service, rater, &self.program_id);
+        ServiceRating::try_from_account_data(&self.fetch_account_data(&address)?)
+    }
+
+    pub fn fetch_service_interaction(&self, service: &Pubkey, wallet: &Pubkey) -> Result<ServiceInteraction> {
+        let (agent, _) = find_agent_address(wallet, &self.program_id);
+        let (address, _) = find_interaction_address(service, &agent, &self.program_id);
+        ServiceInteraction::try_from_account_data(&self.fetch_account_data(&address)?)
+    }
+
+    pub fn fetch_receipt(&self, service: &Pubkey, payer: &Pubkey, nonce: u64) -> Result<TransactionReceipt> {
+        let (address, _) = find_receipt_address(service, payer, nonce, &self.program_id);
+        TransactionReceipt::try_from_account_data(&self.fetch_account_data(&address)?)
+    }
+
+    pub fn fetch_proposal(&self, id: u64) -> Result<Proposal> {
+        let (address, _) = find_proposal_address(id, &self.program_id);
+        Proposal::try_from_account_data(&self.fetch_account_data(&address)?)
+    }
+
+    pub fn fetch_dispute(&self, address: &Pubkey) -> Result<Dispute> {
+        Dispute::try_from_account_data(&self.fetch_account_data(address)?)
+    }
+
+    pub fn fetch_credit_line(&self, wallet: &Pubkey) -> Result<CreditLine> {
+        let (address, _) = find_credit_line_address(wallet, &self.program_id);
+        CreditLine::try_from_account_data(&self.fetch_account_data(&address)?)
+    }
+
+    pub fn fetch_payment_escrow(&self, address: &Pubkey) -> Result<PaymentEscrow> {
+        PaymentEscrow::try_from_account_data(&self.fetch_account_data(address)?)
+    }
+
+    pub fn fetch_channel(&self, address: &Pubkey) -> Result<PaymentChannel> {
+        PaymentChannel::try_from_account_data(&self.fetch_account_data(address)?)
+    }
+
+    pub fn fetch_service_health(&self, service: &Pubkey) -> Result<ServiceHealth> {
+        let (address, _) = find_service_health_address(service, &self.program_id);
+        ServiceHealth::try_from_account_data(&self.fetch_account_data(&address)?)
+    }
+
+    pub fn fetch_transaction_events(&self, signature: &Signature) -> Result<Vec<RegistryEvent>> {
+        let transaction = self.rpc_client.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Json),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+
+        let meta = transaction
+            .transaction
+            .meta
+            .ok_or_else(|| X402Error::Transaction(format!("No metadata for transaction {}", signature)))?;
+        if let Some(err) = meta.err {
+            return Err(X402Error::Transaction(format!("Transaction {} failed: {:?}", signature, err)));
+        }
+
+        let logs: Option<Vec<String>> = meta.log_messages.into();
+        Ok(RegistryEvent::parse_logs(&logs.unwrap_or_default(), &self.program_id))
+    }
+
+    pub fn verify_payment_escrow_event(&self, signature: &Signature, payment_escrow: &Pubkey) -> Result<RegistryEvent> {
+        self.fetch_transaction_events(signature)?
+            .into_iter()
+            .find(|event| event.payment_escrow() == Some(*payment_escrow))
+            .ok_or_else(|| {
+                X402Error::Transaction(format!(
+                    "Transaction {} has no event for payment escrow {}",
+                    signature, payment_escrow
+                ))
+            })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn register_agent(
+        &self,
+        wallet: &Keypair,
+        stake_mint: &Pubkey,
+        did: &str,
+        visa_tap_cert: &str,
+        stake_amount: u64,
+        metadata_uri: &str,
+    ) -> Result<Signature> {
+        let (staker_token_account, token_program) = self.stake_token_account(&wallet.pubkey(), stake_mint)?;
+
+        let instruction = instructions::register_agent(
+            &self.program_id,
+            &wallet.pubkey(),
+            stake_mint,
+            &staker_token_account,
+            &token_program,
+            did.to_string(),
+            visa_tap_cert.to_string(),
+            stake_amount,
+            metadata_uri.to_string(),
+        );
+
+        self.send(&[instruction], wallet, &[])
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn register_service(
+        &self,
+        owner: &Keypair,
+        url: &str,
+        name: &str,
+        description: &str,
+        category: &str,
+        price_per_call: u64,
+        accepted_tokens: Vec<Pubkey>,
+    ) -> Result<(Pubkey, Signature)> {
+        let index = self.next_service_index(&owner.pubkey())?;
+        let (service, _) = find_service_address(&owner.pubkey(), index, &self.program_id);
+        let instruction = instructions::register_service(
+            &self.program_id,
+            &owner.pubkey(),
+            index,
+            url.to_string(),
+            name.to_string(),
+            description.to_string(),
+            category.to_string(),
+            price_per_call,
+            accepted_tokens,
+        );
+
+        Ok((service, self.send(&[instruction], owner, &[])?))
+    }
+
+    pub fn migrate_service(&self, payer: &Keypair, service: &Pubkey) -> Result<Signature> {
+        let owner = Service::try_from_legacy_account_data(&self.fetch_account_data(service)?)?.owner;
+        let instruction = instructions::migrate_service(
+            &self.program_id,
+            service,
+            &owner,
+            self.next_service_index(&owner)?,
+            &payer.pubkey(),
+        );
+
+        self.send(&[instruction], payer, &[])
+    }
+
+    pub fn close_service(&self, owner: &Keypair, service: &Pubkey) -> Result<Signature> {
+        let directory_index = self
+            .fetch_service(service)?
+            .directory_index
+            .ok_or_else(|| X402Error::Solana(format!("{} must be migrated before it can be closed", service)))?;
+        let instruction = instructions::close_service(&self.program_id, service, &owner.pubkey(), directory_index);
+
+        self.send(&[instruction], owner, &[])
+    }
+
+    pub fn update_service(
+        &self,
+        owner: &Keypair,
+        service: &Pubkey,
+        new_price_per_call: Option<u64>,
+        new_url: Option<String>,
+        new_description: Option<String>,
+    ) -> Result<Signature> {
+        let instruction = instructions::update_service(
+            &self.program_id,
+            service,
+            &owner.pubkey(),
+            new_price_per_call,
+            new_url,
+            new_description,
+        );
+
+        self.send(&[instruction], owner, &[])
+    }
+
+    /// Rates `service`, or updates the rater's existing rating, migrating it first if it predates rating weights.
+    pub fn rate_service(&self, rater: &Keypair, service: &Pubkey, rating: u16) -> Result<Signature> {
+        let stake_mint = self.fetch_agent(&rater.pubkey())?.stake_mint;
+        let (address, _) = find_rating_address(service, &rater.pubkey(), &self.program_id);
+
+        let wallet = rater.pubkey();
+        let mut batch = Vec::new();
+        match self.rpc_client.get_account_with_commitment(&address, self.rpc_client.commitment())?.value {
+            Some(account) => {
+                if ServiceRating::try_from_account_data(&account.data).is_err() {
+                    batch.push(instructions::migrate_rating(&self.program_id, service, &wallet, &wallet));
+                }
+                batch.push(instructions::update_rating(&self.program_id, service, &wallet, &stake_mint, rating));
+            }
+            None => batch.push(instructions::rate_service(&self.program_id, service, &wallet, &stake_mint, rating)),
+        }
+
+        self.send(&batch, rater, &[])
+    }
+
+    pub fn migrate_rating(&self, payer: &Keypair, service: &Pubkey, rater: &Pubkey) -> Result<Signature> {
+        let instruction = instructions::migrate_rating(&self.program_id, service, rater, &payer.pubkey());
+        self.send(&[instruction], payer, &[])
+    }
+
+    pub fn prune_rating(&self, payer: &Keypair, service: &Pubkey, rater: &Pubkey) -> Result<Signature> {
+        let instruction = instructions::prune_rating(&self.program_id, service, rater);
+        self.send(&[instruction], payer, &[])
+    }
+
+    pub fn stake(&self, wallet: &Keypair, stake_mint: &Pubkey, stake_amount: u64) -> Result<Signature> {
+        let (staker_token_account, token_program) = self.stake_token_account(&wallet.pubkey(), stake_mint)?;
+
+        let instruction = instructions::restake_agent(
+            &self.program_id,
+            &wallet.pubkey(),
+            stake_mint,
+            &staker_token_account,
+            &token_program,
+            stake_amount,
+        );
+
+        self.send(&[instruction], wallet, &[])
+    }
+
+    pub fn unstake(&self, wallet: &Keypair) -> Result<Signature> {
+        let stake_mint = self.fetch_agent(&wallet.pubkey())?.stake_mint;
+        let (recipient_token_account, token_program) = self.stake_token_account(&wallet.pubkey(), &stake_mint)?;
+
+        let instruction = instructions::unstake_agent(
+            &self.program_id,
+            &wallet.pubkey(),
+            &stake_mint,
+            &recipient_token_account,
+            &token_program,
+        );
+
+        self.send(&[instruction], wallet, &[])
+    }
+
+    /// Moves a pre-multi-mint agent's stake from the legacy global escrow into the config stake mint's escrow.
+    pub fn migrate_legacy_stake(&self, payer: &Keypair, wallet: &Pubkey) -> Result<Signature> {
+        let mint = self.fetch_config()?.stake_token_mint;
+        let token_program = self.fetch_stake_mint(&mint)?.token_program;
+        let (agent, _) = find_agent_address(wallet, &self.program_id);
+
+        let instruction =
+            instructions::migrate_legacy_stake(&self.program_id, &agent, &mint, &payer.pubkey(), &token_program);
+
+        self.send(&[instruction], payer, &[])
+    }
+
+    pub fn open_dispute(
+        &self,
+        payer: &Keypair,
+        service: &Pubkey,
+        transaction_signature: &Signature,
+        evidence_uri: &str,
+    ) -> Result<(Pubkey, Signature)> {
+        let mut signature_bytes = [0u8; 64];
+        signature_bytes.copy_from_slice(transaction_signature.as_ref());
+        let (dispute, _) = find_dispute_address(service, &signature_bytes, &self.program_id);
+
+        let instruction = instructions::open_dispute(
+            &self.program_id,
+            service,
+            &payer.pubkey(),
+            signature_bytes,
+            evidence_uri.to_string(),
+        );
+
+        let signature = self.send(&[instruction], payer, &[])?;
+        Ok((dispute, signature))
+    }
+
+    pub fn respond_to_dispute(&self, provider: &Keypair, dispute: &Pubkey, response_uri: &str) -> Result<Signature> {
+        let instruction =
+            instructions::respond_to_dispute(&self.program_id, dispute, &provider.pubkey(), response_uri.to_string());
+
+        self.send(&[instruction], provider, &[])
+    }
+
+    pub fn resolve_expired_dispute(&self, payer: &Keypair, dispute: &Pubkey) -> Result<Signature> {
+        let account = self.fetch_dispute(dispute)?;
+        let instruction =
+            instructions::resolve_expired_dispute(&self.program_id, dispute, &account.payer, &account.provider);
+
+        self.send(&[instruction], payer, &[])
+    }
+
+    pub fn draw_credit(&self, wallet: &Keypair, service: &Pubkey, amount: u64) -> Result<Signature> {
+        let mint = self.fetch_config()?.stake_token_mint;
+        let owner = self.fetch_service(service)?.owner;
+        let (service_token_account, token_program) = self.stake_token_account(&owner, &mint)?;
+
+        let mut batch = Vec::new();
+        if self.fetch_credit_line(&wallet.pubkey()).is_err() {
+            batch.push(instructions::open_credit_line(&self.program_id, &wallet.pubkey()));
+        }
+        batch.push(instructions::draw_credit(
+            &self.program_id,
+            &wallet.pubkey(),
+            service,
+            &mint,
+            &service_token_account,
+            &token_program,
+            amount,
+        ));
+
+        self.send(&batch, wallet, &[])
+    }
+
+    pub fn repay_credit(&self, wallet: &Keypair, amount: u64) -> Result<Signature> {
+        let mint = self.fetch_config()?.stake_token_mint;
+        let (payer_token_account, token_program) = self.stake_token_account(&wallet.pubkey(), &mint)?;
+
+        let instruction = instructions::repay_credit(
+            &self.program_id,
+            &wallet.pubkey(),
+            &mint,
+            &payer_token_account,
+            &token_program,
+            amount,
+        );
+
+        self.send(&[instruction], wallet, &[])
+    }
+
+    pub fn settle_credit_default(&self, payer: &Keypair, wallet: &Pubkey) -> Result<Signature> {
+        let mint = self.fetch_config()?.stake_token_mint;
+        let token_program = self.fetch_stake_mint(&mint)?.token_program;
+
+        let instruction = instructions::settle_credit_default(&self.program_id, wallet, &mint, &token_program);
+        self.send(&[instruction], payer, &[])
+    }
+
+    pub fn create_payment_escrow(
+        &self,
+        payer: &Keypair,
+        service: &Pubkey,
+        mint: &Pubkey,
+        nonce: u64,
+        amount: u64,
+        deadline: i64,
+    ) -> Result<(Pubkey, Signature)> {
+        let (payment_escrow, _) = find_payment_escrow_address(&payer.pubkey(), service, nonce, &self.program_id);
+        let (payer_token_account, token_program) = self.token_account(&payer.pubkey(), mint)?;
+
+        let instruction = instructions::create_payment_escrow(
+            &self.program_id,
+            &payer.pubkey(),
+            service,
+            mint,
+            &payer_token_account,
+            &token_program,
+            nonce,
+            amount,
+            deadline,
+        );
+
+        let signature = self.send(&[instruction], payer, &[])?;
+        Ok((payment_escrow, signature))
+    }
+
+    pub fn release_payment_escrow(&self, authority: &Keypair, payment_escrow: &Pubkey) -> Result<Signature> {
+        let escrow = self.fetch_payment_escrow(payment_escrow)?;
+        let (payee_token_account, token_program) = self.token_account(&escrow.payee, &escrow.mint)?;
+
+        let instruction = instructions::release_payment_escrow(
+            &self.program_id,
+            payment_escrow,
+            &escrow.mint,
+            &escrow.payer,
+            &payee_token_account,
+            &authority.pubkey(),
+            &token_program,
+        );
+
+        self.send(&[instruction], authority, &[])
+    }
+
+    pub fn refund_payment_escrow(&self, payer: &Keypair, payment_escrow: &Pubkey) -> Result<Signature> {
+        let escrow = self.fetch_payment_escrow(payment_escrow)?;
+        let (payer_token_account, token_program) = self.token_account(&payer.pubkey(), &escrow.mint)?;
+
+        let instruction = instructions::refund_payment_escrow(
+            &self.program_id,
+            payment_escrow,
+            &escrow.mint,
+            &payer.pubkey(),
+            &payer_token_account,
+            &token_program,
+        );
+
+        self.send(&[instruction], payer, &[])
+    }
+
+    pub fn open_channel(
+        &self,
+        payer: &Keypair,
+        service: &Pubkey,
+        mint: &Pubkey,
+        channel_id: u64,
+        deposit: u64,
+    ) -> Result<(Pubkey, Signature)> {
+        let (channel, _) = find_channel_address(&payer.pubkey(), service, channel_id, &self.program_id);
+        let (payer_token_account, token_program) = self.token_account(&payer.pubkey(), mint)?;
+
+        let instruction = instructions::open_channel(
+            &self.program_id,
+            &payer.pubkey(),
+            service,
+            mint,
+            &payer_token_account,
+            &token_program,
+            channel_id,
+            deposit,
+        );
+
+        let signature = self.send(&[instruction], payer, &[])?;
+        Ok((channel, signature))
+    }
+
+    pub fn top_up_channel(&self, payer: &Keypair, channel: &Pubkey, amount: u64) -> Result<Signature> {
+        let state = self.fetch_channel(channel)?;
+        let (payer_token_account, token_program) = self.token_account(&payer.pubkey(), &state.mint)?;
+
+        let instruction = instructions::top_up_channel(
+            &self.program_id,
+            channel,
+            &state.mint,
+            &payer.pubkey(),
+            &payer_token_account,
+            &token_program,
+            amount,
+        );
+
+        self.send(&[instruction], payer, &[])
+    }
+
+    /// Records a co-signed receipt, crediting spend from the matching payment escrow once it has been released.
+    pub fn record_cosigned_transaction(&self, payer: &Keypair, receipt: &CosignedReceipt) -> Result<Signature> {
+        receipt.verify()?;
+        let Receipt {
+            program_id,
+            service,
+            payer: wallet,
+            nonce,
+            ..
+        } = receipt.receipt;
+        if program_id != self.program_id {
+            return Err(X402Error::Signature("Receipt was signed for a different registry program".to_string()));
+        }
+        if self.fetch_service(&service)?.owner != receipt.owner {
+            return Err(X402Error::Signature("Receipt is not co-signed by the service owner".to_string()));
+        }
+
+        let (escrow, _) = find_payment_escrow_address(&wallet, &service, nonce, &self.program_id);
+        let status = match self.rpc_client.get_account_with_commitment(&escrow, self.rpc_client.commitment())?.value {
+            Some(account) => Some(PaymentEscrow::try_from_account_data(&account.data)?.status),
+            None => None,
+        };
+
+        let payment_escrow = (status == Some(PaymentEscrowStatus::Released)).then_some(&escrow);
+        let batch =
+            instructions::record_cosigned_transaction(&self.program_id, &payer.pubkey(), receipt, payment_escrow);
+        self.send(&batch, payer, &[])
+    }
+
+    pub fn redeem_voucher(&self, payee: &Keypair, voucher: &SignedVoucher) -> Result<Signature> {
+        if voucher.voucher.program_id != self.program_id {
+            return Err(X402Error::Signature("Voucher was signed for a different registry program".to_string()));
+        }
+        let channel = voucher.voucher.channel;
+        let state = self.fetch_channel(&channel)?;
+        voucher.verify_against(&channel, &state)?;
+
+        let (payee_token_account, token_program) = self.token_account(&payee.pubkey(), &state.mint)?;
+        let batch = instructions::redeem_voucher(
+            &self.program_id,
+            &state.mint,
+            &payee.pubkey(),
+            &payee_token_account,
+            &token_program,
+            voucher,
+        );
+
+        self.send(&batch, payee, &[])
+    }
+
+    pub fn request_channel_close(&self, payer: &Keypair, channel: &Pubkey) -> Result<Signature> {
+        let instruction = instructions::request_channel_close(&self.program_id, channel, &payer.pubkey());
+        self.send(&[instruction], payer, &[])
+    }
+
+    pub fn finalize_channel_close(&self, authority: &Keypair, channel: &Pubkey) -> Result<Signature> {
+        let state = self.fetch_channel(channel)?;
+        let (payer_token_account, token_program) = self.token_account(&state.payer, &state.mint)?;
+
+        let instruction = instructions::finalize_channel_close(
+            &self.program_id,
+            channel,
+            &state.mint,
+            &state.payer,
+            &payer_token_account,
+            &authority.pubkey(),
+            &token_program,
+        );
+
+        self.send(&[instruction], authority, &[])
+    }
+
+    pub fn report_health(&self, operator: &Keypair, service: &Pubkey, report: HealthReport) -> Result<Signature> {
+        let mut batch = Vec::new();
+        if self.fetch_service_health(service).is_err() {
+            batch.push(instructions::open_service_health(&self.program_id, service, &operator.pubkey()));
+        }
+        batch.push(instructions::report_health(&self.program_id, &operator.pubkey(), service, report));
+
+        self.send(&batch, operator, &[])
+    }
+
+    pub fn create_proposal(
+        &self,
+        proposer: &Keypair,
+        action: GovernanceAction,
+        emergency: bool,
+    ) -> Result<(u64, Signature)> {
+        let id = self.fetch_config()?.proposal_count;
+        let instruction = instructions::create_proposal(&self.program_id, &proposer.pubkey(), id, action, emergency);
+
+        Ok((id, self.send(&[instruction], proposer, &[])?))
+    }
+
+    pub fn approve_proposal(&self, approver: &Keypair, id: u64) -> Result<Signature> {
+        let instruction = instructions::approve_proposal(&self.program_id, id, &approver.pubkey());
+        self.send(&[instruction], approver, &[])
+    }
+
+    pub fn cancel_proposal(&self, proposer: &Keypair, id: u64) -> Result<Signature> {
+        let instruction = instructions::cancel_proposal(&self.program_id, id, &proposer.pubkey());
+        self.send(&[instruction], proposer, &[])
+    }
+
+    /// Sends the instruction for an approved proposal's action once its timelock has passed.
+    pub fn execute_proposal(&self, executor: &Keypair, id: u64) -> Result<Signature> {
+        let proposal = self.fetch_proposal(id)?;
+        let instruction = match &proposal.action {
+            GovernanceAction::SuspendAgent { agent } => {
+                instructions::suspend_agent(&self.program_id, agent, id, &executor.pubkey())
+            }
+            GovernanceAction::BanAgent { agent } => {
+                instructions::ban_agent(&self.program_id, agent, id, &executor.pubkey())
+            }
+            GovernanceAction::SlashForFraud { agent, .. } => {
+                let mint = Agent::try_from_account_data(&self.fetch_account_data(agent)?)?.stake_mint;
+                let token_program = self.fetch_stake_mint(&mint)?.token_program;
+                let dao_treasury = get_associated_token_address_with_program_id(
+                    &self.fetch_config()?.dao_treasury,
+                    &mint,
+                    &token_program,
+                );
+                instructions::slash_for_fraud(
+                    &self.program_id,
+                    agent,
+                    &mint,
+                    &dao_treasury,
+                    id,
+                    &executor.pubkey(),
+                    &token_program,
+                )
+            }
+            GovernanceAction::SuspendService { service } => {
+                instructions::suspend_service(&self.program_id, service, id, &executor.pubkey())
+            }
+            GovernanceAction::UnsuspendAgent { agent } => {
+                instructions::unsuspend_agent(&self.program_id, agent, id, &executor.pubkey())
+            }
+            GovernanceAction::UnsuspendService { service } => {
+                instructions::unsuspend_service(&self.program_id, service, id, &executor.pubkey())
+            }
+            GovernanceAction::UpdateCouncil { .. } => {
+                instructions::update_council(&self.program_id, id, &executor.pubkey())
+            }
+            GovernanceAction::UpdateAuthorities { .. } => {
+                instructions::update_authorities(&self.program_id, id, &executor.pubkey())
+            }
+            GovernanceAction::CloseSuspendedService { service } => {
+                let account = self.fetch_service(service)?;
+                let directory_index = account
+                    .directory_index
+                    .ok_or_else(|| X402Error::Solana(format!("{} must be migrated before it can be closed", service)))?;
+                instructions::close_suspended_service(
+                    &self.program_id,
+                    service,
+                    &account.owner,
+                    directory_index,
+                    &self.fetch_config()?.dao_treasury,
+                    id,
+                    &executor.pubkey(),
+                )
+            }
+        };
+
+        self.send(&[instruction], executor, &[])
+    }
+
+    pub fn send(&self, instructions: &[Instruction], payer: &Keypair, signers: &[&Keypair]) -> Result<Signature> {
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+
+        let mut all_signers: Vec<&Keypair> = vec![payer];
+        all_signers.extend_from_slice(signers);
+
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &all_signers,
+            recent_blockhash,
+        );
+
+        self.rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .map_err(|e| X402Error::Transaction(e.to_string()))
+    }
+
+    fn stake_token_account(&self, wallet: &Pubkey, mint: &Pubkey) -> Result<(Pubkey, Pubkey)> {
+        let token_program = self.fetch_stake_mint(mint)?.token_program;
+        Ok((
+            get_associated_token_address_with_program_id(wallet, mint, &token_program),
+            token_program,
+        ))
+    }
+
+    /// The wallet's associated token account for any mint, under the token program that owns the mint.
+    fn token_account(&self, wallet: &Pubkey, mint: &Pubkey) -> Result<(Pubkey, Pubkey)> {
+        let token_program = self.rpc_client.get_account(mint)?.owner;
+        Ok((
+            get_associated_token_address_with_program_id(wallet, mint, &token_program),
+            token_program,
+        ))
+    }
+
+    fn next_service_index(&self, owner: &Pubkey) -> Result<u32> {
+        let (address, _) = find_service_directory_address(owner, &self.program_id);
+        match self.rpc_client.get_account_with_commitment(&address, self.rpc_client.commitment())?.value {
+            Some(account) => Ok(ServiceDirectory::try_from_account_data(&account.data)?.next_index),
+            None => Ok(0),
+        }
+    }
+
+    fn fetch_account_data(&self, address: &Pubkey) -> Result<Vec<u8>> {
+        let account = self.rpc_client.get_account(address)?;
+        if account.owner != self.program_id {
+            return Err(X402Error::Solana(format!("{} is not owned by the registry program", address)));
+        }
+
+        Ok(account.data)
+    }
+}
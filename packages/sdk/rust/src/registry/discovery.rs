@@ -0,0 +1,312 @@
+use async_trait::async_trait;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use super::accounts::{account_discriminator, Service, ServiceDirectory, ServiceDirectoryPage, ServiceStatus};
+use super::{find_service_directory_address, find_service_page_address, REGISTRY_PROGRAM_ID};
+use crate::client::{CASH_DECIMALS, CASH_MINT};
+use crate::discovery::{DiscoveryBackend, DiscoveryQuery, SortBy, SortOrder};
+use crate::error::{Result, X402Error};
+use crate::query::Page;
+use crate::types::{ServicePricing, X402ServiceInfo};
+
+#[derive(Debug, Clone)]
+pub struct OnChainDiscoveryConfig {
+    pub rpc_url: String,
+    pub program_id: Pubkey,
+    pub network: String,
+    pub default_page_size: usize,
+    /// How long a full `Service` scan is reused across `discover_page` calls,
+    /// so paging through results does not rescan the program each time.
+    pub scan_ttl: u64,
+}
+
+impl Default for OnChainDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            rpc_url: "https://api.devnet.solana.com".to_string(),
+            program_id: *REGISTRY_PROGRAM_ID,
+            network: "solana-devnet".to_string(),
+            default_page_size: 50,
+            scan_ttl: 30,
+        }
+    }
+}
+
+type ServiceScan = Arc<Vec<(Pubkey, Service)>>;
+
+pub struct OnChainDiscovery {
+    config: OnChainDiscoveryConfig,
+    rpc_client: Arc<RpcClient>,
+    last_scan: Mutex<Option<(Instant, ServiceScan)>>,
+}
+
+impl OnChainDiscovery {
+    pub fn new(config: OnChainDiscoveryConfig) -> Self {
+        let rpc_client = Arc::new(RpcClient::new_with_commitment(
+            config.rpc_url.clone(),
+            CommitmentConfig::confirmed(),
+        ));
+
+        Self::with_rpc_client(config, rpc_client)
+    }
+
+    pub fn with_rpc_client(config: OnChainDiscoveryConfig, rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            config,
+            rpc_client,
+            last_scan: Mutex::new(None),
+        }
+    }
+
+    /// Reads every `Service` account. Only the discriminator can be filtered
+    /// with memcmp: `owner`, `category` and `status` all follow the
+    /// variable-length `url`, `name` and `description` strings, so they have no
+    /// fixed offset and are filtered after decoding.
+    pub async fn fetch_services(&self) -> Result<Vec<(Pubkey, Service)>> {
+        let discriminator = account_discriminator(Service::DISCRIMINATOR_NAME);
+
+        let accounts = self.rpc_client.get_program_accounts_with_config(
+            &self.config.program_id,
+            RpcProgramAccountsConfig {
+                filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                    0,
+                    discriminator.to_vec(),
+                ))]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ).await?;
+
+        Ok(accounts
+            .into_iter()
+            .filter_map(|(address, account)| {
+                Service::try_from_account_data(&account.data)
+                    .ok()
+                    .map(|service| (address, service))
+            })
+            .collect())
+    }
+
+    /// Reads one owner's services through their `ServiceDirectory` pages
+    /// instead of scanning the program.
+    pub async fn fetch_owner_services(&self, owner: &Pubkey) -> Result<Vec<(Pubkey, Service)>> {
+        let (directory_address, _) = find_service_directory_address(owner, &self.config.program_id);
+        let directory = match self.fetch_owned_accounts(&[directory_address]).await?.pop().flatten() {
+            Some(data) => ServiceDirectory::try_from_account_data(&data)?,
+            None => return Ok(Vec::new()),
+        };
+
+        let page_addresses: Vec<Pubkey> = (0..directory.page_count())
+            .map(|page| find_service_page_address(owner, page, &self.config.program_id).0)
+            .collect();
+        let mut listed = Vec::new();
+        for data in self.fetch_owned_accounts(&page_addresses).await?.into_iter().flatten() {
+            listed.extend(ServiceDirectoryPage::try_from_account_data(&data)?.services);
+        }
+
+        let accounts = self.fetch_owned_accounts(&listed).await?;
+        listed
+            .into_iter()
+            .zip(accounts)
+            .filter_map(|(address, data)| data.map(|data| (address, data)))
+            .map(|(address, data)| Ok((address, Service::try_from_account_data(&data)?)))
+            .collect()
+    }
+
+    async fn fetch_owned_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Vec<u8>>>> {
+        let mut data = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(100) {
+            let accounts = self.rpc_client.get_multiple_accounts(chunk).await?;
+            data.extend(accounts.into_iter().map(|account| {
+                account
+                    .filter(|account| account.owner == self.config.program_id)
+                    .map(|account| account.data)
+            }));
+        }
+        Ok(data)
+    }
+
+    async fn services_for(&self, query: &DiscoveryQuery) -> Result<ServiceScan> {
+        if let Some(owner) = &query.owner {
+            let owner = Pubkey::from_str(owner).map_err(|e| X402Error::InvalidConfig(e.to_string()))?;
+            return Ok(Arc::new(self.fetch_owner_services(&owner).await?));
+        }
+
+        let mut last_scan = self.last_scan.lock().await;
+        if let Some((scanned_at, services)) = last_scan.as_ref() {
+            if scanned_at.elapsed() < Duration::from_secs(self.config.scan_ttl) {
+                return Ok(services.clone());
+            }
+        }
+
+        let services = Arc::new(self.fetch_services().await?);
+        *last_scan = Some((Instant::now(), services.clone()));
+        Ok(services)
+    }
+
+    pub fn to_service_info(&self, address: &Pubkey, service: &Service) -> X402ServiceInfo {
+        let asset = match service.accepted_tokens.first() {
+            Some(mint) if *mint == *CASH_MINT => "CASH".to_string(),
+            Some(mint) => mint.to_string(),
+            None => "SOL".to_string(),
+        };
+
+        X402ServiceInfo {
+            id: address.to_string(),
+            name: service.name.clone(),
+            description: service.description.clone(),
+            resource: service.url.clone(),
+            method: "GET".to_string(),
+            pricing: Some(ServicePricing {
+                amount: (service.price_per_call as f64 / 10_f64.powi(CASH_DECIMALS as i32)).to_string(),
+                asset,
+                network: self.config.network.clone(),
+            }),
+            category: Some(service.category.clone()),
+            reputation: Some(service.reputation_score as f64 / 1000.0),
+            uptime: Some(service.uptime_percent as f64 / 100.0),
+            verified: Some(service.verified),
+            capabilities: Vec::new(),
+            tags: Vec::new(),
+            owner: Some(service.owner.to_string()),
+            created_at: Some(service.created_at),
+        }
+    }
+
+    fn matches(&self, query: &DiscoveryQuery, service: &Service, info: &X402ServiceInfo) -> bool {
+        if service.status != ServiceStatus::Active {
+            return false;
+        }
+        if let Some(category) = &query.category {
+            if !service.category.eq_ignore_ascii_case(category) {
+                return false;
+            }
+        }
+        if let Some(owner) = &query.owner {
+            if service.owner.to_string() != *owner {
+                return false;
+            }
+        }
+        if query.filters.verified_only && !service.verified {
+            return false;
+        }
+        if let Some(text) = &query.query {
+            let text = text.to_lowercase();
+            if !service.name.to_lowercase().contains(&text)
+                && !service.description.to_lowercase().contains(&text)
+            {
+                return false;
+            }
+        }
+        if let Some(asset) = &query.asset {
+            let matches_asset = service.accepted_tokens.iter().any(|mint| {
+                mint.to_string() == *asset || (asset == "CASH" && *mint == *CASH_MINT)
+            });
+            if !matches_asset {
+                return false;
+            }
+        }
+        if let Some(network) = &query.network {
+            if *network != self.config.network {
+                return false;
+            }
+        }
+        if let (Some(max_price), Some(pricing)) = (query.max_price, &info.pricing) {
+            if pricing.amount.parse::<f64>().unwrap_or(f64::INFINITY) > max_price {
+                return false;
+            }
+        }
+        if let Some(min_reputation) = query.filters.min_reputation {
+            if info.reputation.unwrap_or(0.0) < min_reputation {
+                return false;
+            }
+        }
+        if let Some(min_uptime) = query.min_uptime {
+            if info.uptime.unwrap_or(0.0) < min_uptime {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[async_trait]
+impl DiscoveryBackend for OnChainDiscovery {
+    async fn discover_page(&self, query: &DiscoveryQuery) -> Result<Page<X402ServiceInfo>> {
+        let services = self.services_for(query).await?;
+
+        let mut matched: Vec<(Service, X402ServiceInfo)> = services
+            .iter()
+            .map(|(address, service)| (service.clone(), self.to_service_info(address, service)))
+            .filter(|(service, info)| self.matches(query, service, info))
+            .collect();
+
+        if let Some((sort, order)) = query.sort {
+            matched.sort_by(|(a_service, a), (b_service, b)| {
+                let ordering = match sort {
+                    SortBy::Price => a_service.price_per_call.cmp(&b_service.price_per_call),
+                    SortBy::Reputation => a_service.reputation_score.cmp(&b_service.reputation_score),
+                    SortBy::Uptime => a_service.uptime_percent.cmp(&b_service.uptime_percent),
+                    SortBy::Newest => a_service.created_at.cmp(&b_service.created_at),
+                    SortBy::Relevance => a.id.cmp(&b.id),
+                };
+                match order {
+                    SortOrder::Asc => ordering,
+                    SortOrder::Desc => ordering.reverse(),
+                }
+            });
+        } else {
+            matched.sort_by(|(_, a), (_, b)| a.id.cmp(&b.id));
+        }
+
+        let offset = query
+            .filters
+            .cursor
+            .as_deref()
+            .map(|c| c.parse::<usize>().map_err(|e| X402Error::InvalidConfig(e.to_string())))
+            .transpose()?
+            .unwrap_or(0);
+        let limit = query.filters.limit.unwrap_or(self.config.default_page_size).max(1);
+
+        let items: Vec<X402ServiceInfo> = matched
+            .into_iter()
+            .skip(offset)
+            .take(limit + 1)
+            .map(|(_, info)| info)
+            .collect();
+
+        let next_cursor = (items.len() > limit).then(|| (offset + limit).to_string());
+
+        Ok(Page {
+            items: items.into_iter().take(limit).collect(),
+            next_cursor,
+        })
+    }
+
+    async fn get_service(&self, service_id: &str) -> Result<X402ServiceInfo> {
+        let address = Pubkey::from_str(service_id)
+            .map_err(|e| X402Error::InvalidConfig(e.to_string()))?;
+
+        let account = self.rpc_client.get_account(&address).await?;
+        if account.owner != self.config.program_id {
+            return Err(X402Error::Solana(format!("{} is not owned by the registry program", address)));
+        }
+
+        let service = Service::try_from_account_data(&account.data)?;
+        Ok(self.to_service_info(&address, &service))
+    }
+}
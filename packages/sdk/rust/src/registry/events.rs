@@ -0,0 +1,935 @@
+use base64::{engine::general_purpose, Engine as _};
+use borsh::BorshDeserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+
+use super::accounts::{AgentStatus, GovernanceAction, ServiceStatus};
+use crate::error::X402Error;
+
+pub fn event_discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("event:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash[..8]);
+    discriminator
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct AgentRegisteredEvent {
+    pub wallet: Pubkey,
+    pub reputation_score: u64,
+    pub stake_amount: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ServiceRegisteredEvent {
+    pub service_key: Pubkey,
+    pub owner: Pubkey,
+    pub price_per_call: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct TransactionRecordedEvent {
+    pub agent: Pubkey,
+    pub service: Pubkey,
+    pub amount: u64,
+    pub success: bool,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ServiceRatedEvent {
+    pub service: Pubkey,
+    pub rating: u16,
+    pub new_average: u16,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct AgentSlashedEvent {
+    pub wallet: Pubkey,
+    pub slash_amount: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ServiceVerifiedEvent {
+    pub service: Pubkey,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct AgentStatusChangedEvent {
+    pub wallet: Pubkey,
+    pub new_status: AgentStatus,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ServiceStatusChangedEvent {
+    pub service: Pubkey,
+    pub new_status: ServiceStatus,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct DisputeOpenedEvent {
+    pub dispute: Pubkey,
+    pub payer: Pubkey,
+    pub provider: Pubkey,
+    pub service: Pubkey,
+    pub bond_lamports: u64,
+    pub response_deadline: i64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct DisputeRespondedEvent {
+    pub dispute: Pubkey,
+    pub provider: Pubkey,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct DisputeResolvedEvent {
+    pub dispute: Pubkey,
+    pub payer: Pubkey,
+    pub provider: Pubkey,
+    pub payer_wins: bool,
+    pub refund_amount: u64,
+    pub slash_amount: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct CreditDrawnEvent {
+    pub wallet: Pubkey,
+    pub service: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub credit_used: u64,
+    pub due_at: i64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct CreditRepaidEvent {
+    pub wallet: Pubkey,
+    pub amount: u64,
+    pub credit_used: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct CreditDefaultedEvent {
+    pub wallet: Pubkey,
+    pub seized: u64,
+    pub written_off: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct PaymentEscrowFundedEvent {
+    pub escrow: Pubkey,
+    pub payer: Pubkey,
+    pub payee: Pubkey,
+    pub service: Pubkey,
+    pub mint: Pubkey,
+    pub nonce: u64,
+    pub amount: u64,
+    pub deadline: i64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct PaymentEscrowReleasedEvent {
+    pub escrow: Pubkey,
+    pub payee: Pubkey,
+    pub amount: u64,
+    pub released_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct PaymentEscrowRefundedEvent {
+    pub escrow: Pubkey,
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ChannelOpenedEvent {
+    pub channel: Pubkey,
+    pub payer: Pubkey,
+    pub payee: Pubkey,
+    pub service: Pubkey,
+    pub mint: Pubkey,
+    pub deposit: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ChannelToppedUpEvent {
+    pub channel: Pubkey,
+    pub amount: u64,
+    pub deposit: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct VoucherRedeemedEvent {
+    pub channel: Pubkey,
+    pub payee: Pubkey,
+    pub amount: u64,
+    pub nonce: u64,
+    pub payout: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ChannelCloseRequestedEvent {
+    pub channel: Pubkey,
+    pub payer: Pubkey,
+    pub closes_at: i64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ChannelClosedEvent {
+    pub channel: Pubkey,
+    pub redeemed: u64,
+    pub refunded: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct StakeMintUpdatedEvent {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub reputation_weight_bps: u32,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct LegacyStakeMigratedEvent {
+    pub agent: Pubkey,
+    pub wallet: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ProbeOperatorUpdatedEvent {
+    pub operator: Pubkey,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ServiceHealthReportedEvent {
+    pub service: Pubkey,
+    pub operator: Pubkey,
+    pub window_start: i64,
+    pub window_end: i64,
+    pub probes: u32,
+    pub successes: u32,
+    pub window_uptime_bps: u16,
+    pub uptime_bps: u16,
+    pub latency_ms: u32,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct RatingPrunedEvent {
+    pub service: Pubkey,
+    pub agent: Pubkey,
+    pub rating: u16,
+    pub weight: u64,
+    pub new_average: u16,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ServiceMigratedEvent {
+    pub service: Pubkey,
+    pub owner: Pubkey,
+    pub index: u32,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct CouncilUpdatedEvent {
+    pub council: Vec<Pubkey>,
+    pub threshold: u8,
+    pub timelock_seconds: i64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ProposalCreatedEvent {
+    pub proposal: Pubkey,
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: GovernanceAction,
+    pub emergency: bool,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ProposalApprovedEvent {
+    pub proposal: Pubkey,
+    pub approver: Pubkey,
+    pub approvals: u8,
+    pub executable_at: i64,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ProposalExecutedEvent {
+    pub proposal: Pubkey,
+    pub id: u64,
+    pub executor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, BorshDeserialize)]
+pub struct ProposalCancelledEvent {
+    pub proposal: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone)]
+pub enum RegistryEvent {
+    AgentRegistered(AgentRegisteredEvent),
+    ServiceRegistered(ServiceRegisteredEvent),
+    TransactionRecorded(TransactionRecordedEvent),
+    ServiceRated(ServiceRatedEvent),
+    AgentSlashed(AgentSlashedEvent),
+    ServiceVerified(ServiceVerifiedEvent),
+    AgentStatusChanged(AgentStatusChangedEvent),
+    ServiceStatusChanged(ServiceStatusChangedEvent),
+    DisputeOpened(DisputeOpenedEvent),
+    DisputeResponded(DisputeRespondedEvent),
+    DisputeResolved(DisputeResolvedEvent),
+    CreditDrawn(CreditDrawnEvent),
+    CreditRepaid(CreditRepaidEvent),
+    CreditDefaulted(CreditDefaultedEvent),
+    PaymentEscrowFunded(PaymentEscrowFundedEvent),
+    PaymentEscrowReleased(PaymentEscrowReleasedEvent),
+    PaymentEscrowRefunded(PaymentEscrowRefundedEvent),
+    ChannelOpened(ChannelOpenedEvent),
+    ChannelToppedUp(ChannelToppedUpEvent),
+    VoucherRedeemed(VoucherRedeemedEvent),
+    ChannelCloseRequested(ChannelCloseRequestedEvent),
+    ChannelClosed(ChannelClosedEvent),
+    StakeMintUpdated(StakeMintUpdatedEvent),
+    LegacyStakeMigrated(LegacyStakeMigratedEvent),
+    ProbeOperatorUpdated(ProbeOperatorUpdatedEvent),
+    ServiceHealthReported(ServiceHealthReportedEvent),
+    RatingPruned(RatingPrunedEvent),
+    ServiceMigrated(ServiceMigratedEvent),
+    CouncilUpdated(CouncilUpdatedEvent),
+    ProposalCreated(ProposalCreatedEvent),
+    ProposalApproved(ProposalApprovedEvent),
+    ProposalExecuted(ProposalExecutedEvent),
+    ProposalCancelled(ProposalCancelledEvent),
+}
+
+impl RegistryEvent {
+    pub const NAMES: [&'static str; 33] = [
+        "AgentRegisteredEvent",
+        "ServiceRegisteredEvent",
+        "TransactionRecordedEvent",
+        "ServiceRatedEvent",
+        "AgentSlashedEvent",
+        "ServiceVerifiedEvent",
+        "AgentStatusChangedEvent",
+        "ServiceStatusChangedEvent",
+        "DisputeOpenedEvent",
+        "DisputeRespondedEvent",
+        "DisputeResolvedEvent",
+        "CreditDrawnEvent",
+        "CreditRepaidEvent",
+        "CreditDefaultedEvent",
+        "PaymentEscrowFundedEvent",
+        "PaymentEscrowReleasedEvent",
+        "PaymentEscrowRefundedEvent",
+        "ChannelOpenedEvent",
+        "ChannelToppedUpEvent",
+        "VoucherRedeemedEvent",
+        "ChannelCloseRequestedEvent",
+        "ChannelClosedEvent",
+        "StakeMintUpdatedEvent",
+        "LegacyStakeMigratedEvent",
+        "ProbeOperatorUpdatedEvent",
+        "ServiceHealthReportedEvent",
+        "RatingPrunedEvent",
+        "ServiceMigratedEvent",
+        "CouncilUpdatedEvent",
+        "ProposalCreatedEvent",
+        "ProposalApprovedEvent",
+        "ProposalExecutedEvent",
+        "ProposalCancelledEvent",
+    ];
+
+    pub fn decode(data: &[u8]) -> Result<Option<Self>, X402Error> {
+        if data.len() < 8 {
+            return Ok(None);
+        }
+
+        let (discriminator, mut body) = data.split_at(8);
+        let name = match Self::NAMES
+            .iter()
+            .find(|name| event_discriminator(name) == discriminator)
+        {
+            Some(name) => *name,
+            None => return Ok(None),
+        };
+
+        let decode_error = |e: std::io::Error| X402Error::Solana(format!("Failed to decode {}: {}", name, e));
+
+        let event = match name {
+            "AgentRegisteredEvent" => {
+                Self::AgentRegistered(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ServiceRegisteredEvent" => {
+                Self::ServiceRegistered(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "TransactionRecordedEvent" => {
+                Self::TransactionRecorded(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ServiceRatedEvent" => {
+                Self::ServiceRated(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "AgentSlashedEvent" => {
+                Self::AgentSlashed(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ServiceVerifiedEvent" => {
+                Self::ServiceVerified(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "AgentStatusChangedEvent" => {
+                Self::AgentStatusChanged(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ServiceStatusChangedEvent" => {
+                Self::ServiceStatusChanged(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "DisputeOpenedEvent" => {
+                Self::DisputeOpened(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "DisputeRespondedEvent" => {
+                Self::DisputeResponded(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "DisputeResolvedEvent" => {
+                Self::DisputeResolved(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "CreditDrawnEvent" => Self::CreditDrawn(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?),
+            "CreditRepaidEvent" => Self::CreditRepaid(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?),
+            "CreditDefaultedEvent" => {
+                Self::CreditDefaulted(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "PaymentEscrowFundedEvent" => {
+                Self::PaymentEscrowFunded(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "PaymentEscrowReleasedEvent" => {
+                Self::PaymentEscrowReleased(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "PaymentEscrowRefundedEvent" => {
+                Self::PaymentEscrowRefunded(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ChannelOpenedEvent" => {
+                Self::ChannelOpened(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ChannelToppedUpEvent" => {
+                Self::ChannelToppedUp(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "VoucherRedeemedEvent" => {
+                Self::VoucherRedeemed(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ChannelCloseRequestedEvent" => {
+                Self::ChannelCloseRequested(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ChannelClosedEvent" => {
+                Self::ChannelClosed(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "StakeMintUpdatedEvent" => {
+                Self::StakeMintUpdated(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "LegacyStakeMigratedEvent" => {
+                Self::LegacyStakeMigrated(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ProbeOperatorUpdatedEvent" => {
+                Self::ProbeOperatorUpdated(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ServiceHealthReportedEvent" => {
+                Self::ServiceHealthReported(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "RatingPrunedEvent" => {
+                Self::RatingPruned(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ServiceMigratedEvent" => {
+                Self::ServiceMigrated(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "CouncilUpdatedEvent" => {
+                Self::CouncilUpdated(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ProposalCreatedEvent" => {
+                Self::ProposalCreated(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ProposalApprovedEvent" => {
+                Self::ProposalApproved(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            "ProposalExecutedEvent" => {
+                Self::ProposalExecuted(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?)
+            }
+            _ => Self::ProposalCancelled(BorshDeserialize::deserialize(&mut body).map_err(decode_error)?),
+        };
+
+        Ok(Some(event))
+    }
+
+    pub fn parse_logs(logs: &[String], program_id: &Pubkey) -> Vec<Self> {
+        let program = program_id.to_string();
+        let mut stack: Vec<String> = Vec::new();
+        let mut events = Vec::new();
+
+        for log in logs {
+            if let Some(rest) = log.strip_prefix("Program ") {
+                let mut parts = rest.split_whitespace();
+                let id = parts.next().unwrap_or_default();
+                match parts.next() {
+                    Some("invoke") => {
+                        stack.push(id.to_string());
+                        continue;
+                    }
+                    Some("success") | Some("failed:") => {
+                        stack.pop();
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+
+            if stack.last() != Some(&program) {
+                continue;
+            }
+
+            if let Some(encoded) = log.strip_prefix("Program data: ") {
+                if let Ok(data) = general_purpose::STANDARD.decode(encoded.trim()) {
+                    if let Ok(Some(event)) = Self::decode(&data) {
+                        events.push(event);
+                    }
+                }
+            }
+        }
+
+        events
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AgentRegistered(_) => "AgentRegisteredEvent",
+            Self::ServiceRegistered(_) => "ServiceRegisteredEvent",
+            Self::TransactionRecorded(_) => "TransactionRecordedEvent",
+            Self::ServiceRated(_) => "ServiceRatedEvent",
+            Self::AgentSlashed(_) => "AgentSlashedEvent",
+            Self::ServiceVerified(_) => "ServiceVerifiedEvent",
+            Self::AgentStatusChanged(_) => "AgentStatusChangedEvent",
+            Self::ServiceStatusChanged(_) => "ServiceStatusChangedEvent",
+            Self::DisputeOpened(_) => "DisputeOpenedEvent",
+            Self::DisputeResponded(_) => "DisputeRespondedEvent",
+            Self::DisputeResolved(_) => "DisputeResolvedEvent",
+            Self::CreditDrawn(_) => "CreditDrawnEvent",
+            Self::CreditRepaid(_) => "CreditRepaidEvent",
+            Self::CreditDefaulted(_) => "CreditDefaultedEvent",
+            Self::PaymentEscrowFunded(_) => "PaymentEscrowFundedEvent",
+            Self::PaymentEscrowReleased(_) => "PaymentEscrowReleasedEvent",
+            Self::PaymentEscrowRefunded(_) => "PaymentEscrowRefundedEvent",
+            Self::ChannelOpened(_) => "ChannelOpenedEvent",
+            Self::ChannelToppedUp(_) => "ChannelToppedUpEvent",
+            Self::VoucherRedeemed(_) => "VoucherRedeemedEvent",
+            Self::ChannelCloseRequested(_) => "ChannelCloseRequestedEvent",
+            Self::ChannelClosed(_) => "ChannelClosedEvent",
+            Self::StakeMintUpdated(_) => "StakeMintUpdatedEvent",
+            Self::LegacyStakeMigrated(_) => "LegacyStakeMigratedEvent",
+            Self::ProbeOperatorUpdated(_) => "ProbeOperatorUpdatedEvent",
+            Self::ServiceHealthReported(_) => "ServiceHealthReportedEvent",
+            Self::RatingPruned(_) => "RatingPrunedEvent",
+            Self::ServiceMigrated(_) => "ServiceMigratedEvent",
+            Self::CouncilUpdated(_) => "CouncilUpdatedEvent",
+            Self::ProposalCreated(_) => "ProposalCreatedEvent",
+            Self::ProposalApproved(_) => "ProposalApprovedEvent",
+            Self::ProposalExecuted(_) => "ProposalExecutedEvent",
+            Self::ProposalCancelled(_) => "ProposalCancelledEvent",
+        }
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        match self {
+            Self::AgentRegistered(e) => e.timestamp,
+            Self::ServiceRegistered(e) => e.timestamp,
+            Self::TransactionRecorded(e) => e.timestamp,
+            Self::ServiceRated(e) => e.timestamp,
+            Self::AgentSlashed(e) => e.timestamp,
+            Self::ServiceVerified(e) => e.timestamp,
+            Self::AgentStatusChanged(e) => e.timestamp,
+            Self::ServiceStatusChanged(e) => e.timestamp,
+            Self::DisputeOpened(e) => e.timestamp,
+            Self::DisputeResponded(e) => e.timestamp,
+            Self::DisputeResolved(e) => e.timestamp,
+            Self::CreditDrawn(e) => e.timestamp,
+            Self::CreditRepaid(e) => e.timestamp,
+            Self::CreditDefaulted(e) => e.timestamp,
+            Self::PaymentEscrowFunded(e) => e.timestamp,
+            Self::PaymentEscrowReleased(e) => e.timestamp,
+            Self::PaymentEscrowRefunded(e) => e.timestamp,
+            Self::ChannelOpened(e) => e.timestamp,
+            Self::ChannelToppedUp(e) => e.timestamp,
+            Self::VoucherRedeemed(e) => e.timestamp,
+            Self::ChannelCloseRequested(e) => e.timestamp,
+            Self::ChannelClosed(e) => e.timestamp,
+            Self::StakeMintUpdated(e) => e.timestamp,
+            Self::LegacyStakeMigrated(e) => e.timestamp,
+            Self::ProbeOperatorUpdated(e) => e.timestamp,
+            Self::ServiceHealthReported(e) => e.timestamp,
+            Self::RatingPruned(e) => e.timestamp,
+            Self::ServiceMigrated(e) => e.timestamp,
+            Self::CouncilUpdated(e) => e.timestamp,
+            Self::ProposalCreated(e) => e.timestamp,
+            Self::ProposalApproved(e) => e.timestamp,
+            Self::ProposalExecuted(e) => e.timestamp,
+            Self::ProposalCancelled(e) => e.timestamp,
+        }
+    }
+
+    pub fn agent(&self) -> Option<Pubkey> {
+        match self {
+            Self::AgentRegistered(e) => Some(e.wallet),
+            Self::ServiceRegistered(e) => Some(e.owner),
+            Self::TransactionRecorded(e) => Some(e.agent),
+            Self::AgentSlashed(e) => Some(e.wallet),
+            Self::AgentStatusChanged(e) => Some(e.wallet),
+            Self::DisputeOpened(e) => Some(e.payer),
+            Self::DisputeResponded(e) => Some(e.provider),
+            Self::DisputeResolved(e) => Some(e.payer),
+            Self::CreditDrawn(e) => Some(e.wallet),
+            Self::CreditRepaid(e) => Some(e.wallet),
+            Self::CreditDefaulted(e) => Some(e.wallet),
+            Self::PaymentEscrowFunded(e) => Some(e.payer),
+            Self::PaymentEscrowReleased(e) => Some(e.payee),
+            Self::PaymentEscrowRefunded(e) => Some(e.payer),
+            Self::ChannelOpened(e) => Some(e.payer),
+            Self::VoucherRedeemed(e) => Some(e.payee),
+            Self::ChannelCloseRequested(e) => Some(e.payer),
+            Self::RatingPruned(e) => Some(e.agent),
+            _ => None,
+        }
+    }
+
+    pub fn service(&self) -> Option<Pubkey> {
+        match self {
+            Self::ServiceRegistered(e) => Some(e.service_key),
+            Self::TransactionRecorded(e) => Some(e.service),
+            Self::ServiceRated(e) => Some(e.service),
+            Self::ServiceVerified(e) => Some(e.service),
+            Self::ServiceStatusChanged(e) => Some(e.service),
+            Self::DisputeOpened(e) => Some(e.service),
+            Self::CreditDrawn(e) => Some(e.service),
+            Self::PaymentEscrowFunded(e) => Some(e.service),
+            Self::ChannelOpened(e) => Some(e.service),
+            Self::ServiceHealthReported(e) => Some(e.service),
+            Self::RatingPruned(e) => Some(e.service),
+            Self::ServiceMigrated(e) => Some(e.service),
+            _ => None,
+        }
+    }
+
+    pub fn payment_escrow(&self) -> Option<Pubkey> {
+        match self {
+            Self::PaymentEscrowFunded(e) => Some(e.escrow),
+            Self::PaymentEscrowReleased(e) => Some(e.escrow),
+            Self::PaymentEscrowRefunded(e) => Some(e.escrow),
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            Self::AgentRegistered(e) => json!({
+                "wallet": e.wallet.to_string(),
+                "reputationScore": e.reputation_score,
+                "stakeAmount": e.stake_amount,
+                "timestamp": e.timestamp,
+            }),
+            Self::ServiceRegistered(e) => json!({
+                "serviceKey": e.service_key.to_string(),
+                "owner": e.owner.to_string(),
+                "pricePerCall": e.price_per_call,
+                "timestamp": e.timestamp,
+            }),
+            Self::TransactionRecorded(e) => json!({
+                "agent": e.agent.to_string(),
+                "service": e.service.to_string(),
+                "amount": e.amount,
+                "success": e.success,
+                "timestamp": e.timestamp,
+            }),
+            Self::ServiceRated(e) => json!({
+                "service": e.service.to_string(),
+                "rating": e.rating,
+                "newAverage": e.new_average,
+                "timestamp": e.timestamp,
+            }),
+            Self::AgentSlashed(e) => json!({
+                "wallet": e.wallet.to_string(),
+                "slashAmount": e.slash_amount,
+                "timestamp": e.timestamp,
+            }),
+            Self::CreditDrawn(e) => json!({
+                "wallet": e.wallet.to_string(),
+                "service": e.service.to_string(),
+                "amount": e.amount,
+                "fee": e.fee,
+                "creditUsed": e.credit_used,
+                "dueAt": e.due_at,
+                "timestamp": e.timestamp,
+            }),
+            Self::CreditRepaid(e) => json!({
+                "wallet": e.wallet.to_string(),
+                "amount": e.amount,
+                "creditUsed": e.credit_used,
+                "timestamp": e.timestamp,
+            }),
+            Self::CreditDefaulted(e) => json!({
+                "wallet": e.wallet.to_string(),
+                "seized": e.seized,
+                "writtenOff": e.written_off,
+                "timestamp": e.timestamp,
+            }),
+            Self::PaymentEscrowFunded(e) => json!({
+                "escrow": e.escrow.to_string(),
+                "payer": e.payer.to_string(),
+                "payee": e.payee.to_string(),
+                "service": e.service.to_string(),
+                "mint": e.mint.to_string(),
+                "nonce": e.nonce,
+                "amount": e.amount,
+                "deadline": e.deadline,
+                "timestamp": e.timestamp,
+            }),
+            Self::PaymentEscrowReleased(e) => json!({
+                "escrow": e.escrow.to_string(),
+                "payee": e.payee.to_string(),
+                "amount": e.amount,
+                "releasedBy": e.released_by.to_string(),
+                "timestamp": e.timestamp,
+            }),
+            Self::PaymentEscrowRefunded(e) => json!({
+                "escrow": e.escrow.to_string(),
+                "payer": e.payer.to_string(),
+                "amount": e.amount,
+                "timestamp": e.timestamp,
+            }),
+            Self::ServiceVerified(e) => json!({
+                "service": e.service.to_string(),
+                "timestamp": e.timestamp,
+            }),
+            Self::AgentStatusChanged(e) => json!({
+                "wallet": e.wallet.to_string(),
+                "newStatus": e.new_status.as_str(),
+                "timestamp": e.timestamp,
+            }),
+            Self::ServiceStatusChanged(e) => json!({
+                "service": e.service.to_string(),
+                "newStatus": e.new_status.as_str(),
+                "timestamp": e.timestamp,
+            }),
+            Self::DisputeOpened(e) => json!({
+                "dispute": e.dispute.to_string(),
+                "payer": e.payer.to_string(),
+                "provider": e.provider.to_string(),
+                "service": e.service.to_string(),
+                "bondLamports": e.bond_lamports,
+                "responseDeadline": e.response_deadline,
+                "timestamp": e.timestamp,
+            }),
+            Self::DisputeResponded(e) => json!({
+                "dispute": e.dispute.to_string(),
+                "provider": e.provider.to_string(),
+                "timestamp": e.timestamp,
+            }),
+            Self::DisputeResolved(e) => json!({
+                "dispute": e.dispute.to_string(),
+                "payer": e.payer.to_string(),
+                "provider": e.provider.to_string(),
+                "payerWins": e.payer_wins,
+                "refundAmount": e.refund_amount,
+                "slashAmount": e.slash_amount,
+                "timestamp": e.timestamp,
+            }),
+            Self::CreditDrawn(e) => json!({
+                "wallet": e.wallet.to_string(),
+                "service": e.service.to_string(),
+                "amount": e.amount,
+                "fee": e.fee,
+                "creditUsed": e.credit_used,
+                "dueAt": e.due_at,
+                "timestamp": e.timestamp,
+            }),
+            Self::CreditRepaid(e) => json!({
+                "wallet": e.wallet.to_string(),
+                "amount": e.amount,
+                "creditUsed": e.credit_used,
+                "timestamp": e.timestamp,
+            }),
+            Self::CreditDefaulted(e) => json!({
+                "wallet": e.wallet.to_string(),
+                "seized": e.seized,
+                "writtenOff": e.written_off,
+                "timestamp": e.timestamp,
+            }),
+            Self::PaymentEscrowFunded(e) => json!({
+                "escrow": e.escrow.to_string(),
+                "payer": e.payer.to_string(),
+                "payee": e.payee.to_string(),
+                "service": e.service.to_string(),
+                "mint": e.mint.to_string(),
+                "nonce": e.nonce,
+                "amount": e.amount,
+                "deadline": e.deadline,
+                "timestamp": e.timestamp,
+            }),
+            Self::PaymentEscrowReleased(e) => json!({
+                "escrow": e.escrow.to_string(),
+                "payee": e.payee.to_string(),
+                "amount": e.amount,
+                "releasedBy": e.released_by.to_string(),
+                "timestamp": e.timestamp,
+            }),
+            Self::PaymentEscrowRefunded(e) => json!({
+                "escrow": e.escrow.to_string(),
+                "payer": e.payer.to_string(),
+                "amount": e.amount,
+                "timestamp": e.timestamp,
+            }),
+            Self::ChannelOpened(e) => json!({
+                "channel": e.channel.to_string(),
+                "payer": e.payer.to_string(),
+                "payee": e.payee.to_string(),
+                "service": e.service.to_string(),
+                "mint": e.mint.to_string(),
+                "deposit": e.deposit,
+                "timestamp": e.timestamp,
+            }),
+            Self::ChannelToppedUp(e) => json!({
+                "channel": e.channel.to_string(),
+                "amount": e.amount,
+                "deposit": e.deposit,
+                "timestamp": e.timestamp,
+            }),
+            Self::VoucherRedeemed(e) => json!({
+                "channel": e.channel.to_string(),
+                "payee": e.payee.to_string(),
+                "amount": e.amount,
+                "nonce": e.nonce,
+                "payout": e.payout,
+                "timestamp": e.timestamp,
+            }),
+            Self::ChannelCloseRequested(e) => json!({
+                "channel": e.channel.to_string(),
+                "payer": e.payer.to_string(),
+                "closesAt": e.closes_at,
+                "timestamp": e.timestamp,
+            }),
+            Self::ChannelClosed(e) => json!({
+                "channel": e.channel.to_string(),
+                "redeemed": e.redeemed,
+                "refunded": e.refunded,
+                "timestamp": e.timestamp,
+            }),
+            Self::StakeMintUpdated(e) => json!({
+                "mint": e.mint.to_string(),
+                "tokenProgram": e.token_program.to_string(),
+                "reputationWeightBps": e.reputation_weight_bps,
+                "enabled": e.enabled,
+                "timestamp": e.timestamp,
+            }),
+            Self::LegacyStakeMigrated(e) => json!({
+                "agent": e.agent.to_string(),
+                "wallet": e.wallet.to_string(),
+                "mint": e.mint.to_string(),
+                "amount": e.amount,
+                "timestamp": e.timestamp,
+            }),
+            Self::ProbeOperatorUpdated(e) => json!({
+                "operator": e.operator.to_string(),
+                "enabled": e.enabled,
+                "timestamp": e.timestamp,
+            }),
+            Self::ServiceHealthReported(e) => json!({
+                "service": e.service.to_string(),
+                "operator": e.operator.to_string(),
+                "windowStart": e.window_start,
+                "windowEnd": e.window_end,
+                "probes": e.probes,
+                "successes": e.successes,
+                "windowUptimeBps": e.window_uptime_bps,
+                "uptimeBps": e.uptime_bps,
+                "latencyMs": e.latency_ms,
+                "timestamp": e.timestamp,
+            }),
+            Self::RatingPruned(e) => json!({
+                "service": e.service.to_string(),
+                "agent": e.agent.to_string(),
+                "rating": e.rating,
+                "weight": e.weight,
+                "newAverage": e.new_average,
+                "timestamp": e.timestamp,
+            }),
+            Self::ServiceMigrated(e) => json!({
+                "service": e.service.to_string(),
+                "owner": e.owner.to_string(),
+                "index": e.index,
+                "timestamp": e.timestamp,
+            }),
+            Self::CouncilUpdated(e) => json!({
+                "council": e.council.iter().map(|member| member.to_string()).collect::<Vec<_>>(),
+                "threshold": e.threshold,
+                "timelockSeconds": e.timelock_seconds,
+                "timestamp": e.timestamp,
+            }),
+            Self::ProposalCreated(e) => json!({
+                "proposal": e.proposal.to_string(),
+                "id": e.id,
+                "proposer": e.proposer.to_string(),
+                "action": e.action.as_str(),
+                "target": e.action.target().map(|target| target.to_string()),
+                "emergency": e.emergency,
+                "timestamp": e.timestamp,
+            }),
+            Self::ProposalApproved(e) => json!({
+                "proposal": e.proposal.to_string(),
+                "approver": e.approver.to_string(),
+                "approvals": e.approvals,
+                "executableAt": e.executable_at,
+                "timestamp": e.timestamp,
+            }),
+            Self::ProposalExecuted(e) => json!({
+                "proposal": e.proposal.to_string(),
+                "id": e.id,
+                "executor": e.executor.to_string(),
+                "timestamp": e.timestamp,
+            }),
+            Self::ProposalCancelled(e) => json!({
+                "proposal": e.proposal.to_string(),
+                "cancelledBy": e.cancelled_by.to_string(),
+                "timestamp": e.timestamp,
+            }),
+        }
+    }
+}
@@ -0,0 +1,1134 @@
+use borsh::BorshSerialize;
+use sha2::{Digest, Sha256};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{bpf_loader_upgradeable, system_program, sysvar};
+
+use super::accounts::{ConfigParams, ConfigUpdate, DisputeRuling, GovernanceAction, HealthReport, StakeMintUpdate};
+use super::channel::SignedVoucher;
+use super::receipt::{CosignedReceipt, Receipt};
+use super::{
+    find_agent_address, find_channel_address, find_channel_vault_address, find_config_address,
+    find_credit_line_address, find_credit_vault_address, find_dispute_address, find_escrow_address,
+    find_interaction_address, find_legacy_escrow_address, find_payment_escrow_address, find_payment_vault_address,
+    find_probe_operator_address, find_proposal_address, find_rating_address, find_receipt_address,
+    find_service_address, find_service_directory_address, find_service_health_address, find_service_page_address,
+    find_stake_mint_address, SERVICE_PAGE_SIZE,
+};
+
+pub fn instruction_discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("global:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash[..8]);
+    discriminator
+}
+
+fn build(program_id: &Pubkey, name: &str, args: impl BorshSerialize, accounts: Vec<AccountMeta>) -> Instruction {
+    let mut data = instruction_discriminator(name).to_vec();
+    args.serialize(&mut data).expect("instruction args serialize into a Vec");
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn initialize_config(program_id: &Pubkey, authority: &Pubkey, params: ConfigParams) -> Instruction {
+    let (program_data, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+
+    build(
+        program_id,
+        "initialize_config",
+        params,
+        vec![
+            AccountMeta::new(find_config_address(program_id).0, false),
+            AccountMeta::new_readonly(*program_id, false),
+            AccountMeta::new_readonly(program_data, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn update_config(program_id: &Pubkey, authority: &Pubkey, update: ConfigUpdate) -> Instruction {
+    build(program_id, "update_config", update, config_authority_accounts(program_id, authority))
+}
+
+pub fn transfer_config_authority(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    new_authority: Option<Pubkey>,
+) -> Instruction {
+    build(
+        program_id,
+        "transfer_config_authority",
+        new_authority,
+        config_authority_accounts(program_id, authority),
+    )
+}
+
+pub fn accept_config_authority(program_id: &Pubkey, new_authority: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "accept_config_authority",
+        (),
+        config_authority_accounts(program_id, new_authority),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn register_agent(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    mint: &Pubkey,
+    staker_token_account: &Pubkey,
+    token_program: &Pubkey,
+    did: String,
+    visa_tap_cert: String,
+    stake_amount: u64,
+    metadata_uri: String,
+) -> Instruction {
+    build(
+        program_id,
+        "register_agent",
+        (did, visa_tap_cert, stake_amount, metadata_uri),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_agent_address(signer, program_id).0, false),
+            AccountMeta::new(*signer, true),
+            AccountMeta::new_readonly(find_stake_mint_address(mint, program_id).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*staker_token_account, false),
+            AccountMeta::new(find_escrow_address(mint, program_id).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+/// `index` is the owner's `ServiceDirectory::next_index`, or 0 before their first service.
+pub fn register_service(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    index: u32,
+    url: String,
+    name: String,
+    description: String,
+    category: String,
+    price_per_call: u64,
+    accepted_tokens: Vec<Pubkey>,
+) -> Instruction {
+    build(
+        program_id,
+        "register_service",
+        (url, name, description, category, price_per_call, accepted_tokens),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_service_directory_address(owner, program_id).0, false),
+            AccountMeta::new(find_service_page_address(owner, index / SERVICE_PAGE_SIZE, program_id).0, false),
+            AccountMeta::new(find_service_address(owner, index, program_id).0, false),
+            AccountMeta::new(find_agent_address(owner, program_id).0, false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn record_transaction(
+    program_id: &Pubkey,
+    agent: &Pubkey,
+    service: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    success: bool,
+    response_time_ms: u32,
+) -> Instruction {
+    build(
+        program_id,
+        "record_transaction",
+        (amount, success, response_time_ms),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(*agent, false),
+            AccountMeta::new(*service, false),
+            AccountMeta::new(find_interaction_address(service, agent, program_id).0, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Pass the receipt's released payment escrow, if any; only the amount it settled counts toward the payer's spend.
+pub fn record_cosigned_transaction(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    receipt: &CosignedReceipt,
+    payment_escrow: Option<&Pubkey>,
+) -> Vec<Instruction> {
+    let Receipt {
+        service,
+        payer: wallet,
+        amount,
+        success,
+        response_time_ms,
+        nonce,
+        ..
+    } = receipt.receipt;
+    let agent = find_agent_address(&wallet, program_id).0;
+    let [payer_verification, owner_verification] = receipt.verify_instructions();
+
+    vec![
+        payer_verification,
+        owner_verification,
+        build(
+            program_id,
+            "record_cosigned_transaction",
+            (amount, success, response_time_ms, nonce),
+            vec![
+                config_meta(program_id),
+                AccountMeta::new(agent, false),
+                AccountMeta::new(service, false),
+                AccountMeta::new(find_interaction_address(&service, &agent, program_id).0, false),
+                AccountMeta::new(find_receipt_address(&service, &wallet, nonce, program_id).0, false),
+                AccountMeta::new_readonly(*payment_escrow.unwrap_or(program_id), false),
+                AccountMeta::new(*payer, true),
+                AccountMeta::new_readonly(sysvar::instructions::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        ),
+    ]
+}
+
+pub fn rate_service(
+    program_id: &Pubkey,
+    service: &Pubkey,
+    rater: &Pubkey,
+    stake_mint: &Pubkey,
+    rating: u16,
+) -> Instruction {
+    let agent = find_agent_address(rater, program_id).0;
+
+    build(
+        program_id,
+        "rate_service",
+        rating,
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(*service, false),
+            AccountMeta::new_readonly(agent, false),
+            AccountMeta::new_readonly(find_stake_mint_address(stake_mint, program_id).0, false),
+            AccountMeta::new_readonly(find_interaction_address(service, &agent, program_id).0, false),
+            AccountMeta::new(find_rating_address(service, rater, program_id).0, false),
+            AccountMeta::new(*rater, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Executes an approved `GovernanceAction::SlashForFraud` proposal.
+pub fn slash_for_fraud(
+    program_id: &Pubkey,
+    agent: &Pubkey,
+    mint: &Pubkey,
+    dao_treasury: &Pubkey,
+    proposal_id: u64,
+    executor: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    build(
+        program_id,
+        "slash_for_fraud",
+        (),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(*agent, false),
+            AccountMeta::new_readonly(find_stake_mint_address(mint, program_id).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(find_escrow_address(mint, program_id).0, false),
+            AccountMeta::new(*dao_treasury, false),
+            AccountMeta::new(find_proposal_address(proposal_id, program_id).0, false),
+            AccountMeta::new_readonly(*executor, true),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+pub fn open_dispute(
+    program_id: &Pubkey,
+    service: &Pubkey,
+    payer: &Pubkey,
+    transaction_signature: [u8; 64],
+    evidence_uri: String,
+) -> Instruction {
+    build(
+        program_id,
+        "open_dispute",
+        (transaction_signature, evidence_uri),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_dispute_address(service, &transaction_signature, program_id).0, false),
+            AccountMeta::new_readonly(*service, false),
+            AccountMeta::new_readonly(find_agent_address(payer, program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn respond_to_dispute(
+    program_id: &Pubkey,
+    dispute: &Pubkey,
+    provider: &Pubkey,
+    response_uri: String,
+) -> Instruction {
+    build(
+        program_id,
+        "respond_to_dispute",
+        response_uri,
+        vec![
+            AccountMeta::new(*dispute, false),
+            AccountMeta::new(*provider, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn resolve_dispute(
+    program_id: &Pubkey,
+    dispute: &Pubkey,
+    payer: &Pubkey,
+    provider: &Pubkey,
+    mint: &Pubkey,
+    payer_token_account: &Pubkey,
+    dao_treasury: &Pubkey,
+    arbiter: &Pubkey,
+    token_program: &Pubkey,
+    ruling: DisputeRuling,
+) -> Instruction {
+    build(
+        program_id,
+        "resolve_dispute",
+        ruling,
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(*dispute, false),
+            AccountMeta::new(find_agent_address(payer, program_id).0, false),
+            AccountMeta::new(find_agent_address(provider, program_id).0, false),
+            AccountMeta::new(*payer, false),
+            AccountMeta::new(*provider, false),
+            AccountMeta::new_readonly(find_stake_mint_address(mint, program_id).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(find_escrow_address(mint, program_id).0, false),
+            AccountMeta::new(*payer_token_account, false),
+            AccountMeta::new(*dao_treasury, false),
+            AccountMeta::new_readonly(*arbiter, true),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+pub fn resolve_expired_dispute(program_id: &Pubkey, dispute: &Pubkey, payer: &Pubkey, provider: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "resolve_expired_dispute",
+        (),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(*dispute, false),
+            AccountMeta::new(find_agent_address(payer, program_id).0, false),
+            AccountMeta::new(find_agent_address(provider, program_id).0, false),
+            AccountMeta::new(*payer, false),
+        ],
+    )
+}
+
+pub fn verify_service(program_id: &Pubkey, service: &Pubkey, verifier: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "verify_service",
+        (),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(*service, false),
+            AccountMeta::new_readonly(*verifier, true),
+        ],
+    )
+}
+
+pub fn set_visa_tap_verified(
+    program_id: &Pubkey,
+    service: &Pubkey,
+    authority: &Pubkey,
+    verified: bool,
+) -> Instruction {
+    build(
+        program_id,
+        "set_visa_tap_verified",
+        verified,
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(*service, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+pub fn update_rating(
+    program_id: &Pubkey,
+    service: &Pubkey,
+    rater: &Pubkey,
+    stake_mint: &Pubkey,
+    new_rating: u16,
+) -> Instruction {
+    build(
+        program_id,
+        "update_rating",
+        new_rating,
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(*service, false),
+            AccountMeta::new_readonly(find_agent_address(rater, program_id).0, false),
+            AccountMeta::new_readonly(find_stake_mint_address(stake_mint, program_id).0, false),
+            AccountMeta::new(find_rating_address(service, rater, program_id).0, false),
+            AccountMeta::new_readonly(*rater, true),
+        ],
+    )
+}
+
+pub fn prune_rating(program_id: &Pubkey, service: &Pubkey, rater: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "prune_rating",
+        (),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(*service, false),
+            AccountMeta::new(find_rating_address(service, rater, program_id).0, false),
+            AccountMeta::new(*rater, false),
+        ],
+    )
+}
+
+pub fn migrate_rating(program_id: &Pubkey, service: &Pubkey, rater: &Pubkey, payer: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "migrate_rating",
+        (),
+        vec![
+            AccountMeta::new(*service, false),
+            AccountMeta::new(find_rating_address(service, rater, program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn update_service(
+    program_id: &Pubkey,
+    service: &Pubkey,
+    owner: &Pubkey,
+    new_price_per_call: Option<u64>,
+    new_url: Option<String>,
+    new_description: Option<String>,
+) -> Instruction {
+    build(
+        program_id,
+        "update_service",
+        (new_price_per_call, new_url, new_description),
+        service_owner_accounts(service, owner),
+    )
+}
+
+pub fn unstake_agent(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    mint: &Pubkey,
+    recipient_token_account: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    build(
+        program_id,
+        "unstake_agent",
+        (),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_agent_address(signer, program_id).0, false),
+            AccountMeta::new_readonly(find_stake_mint_address(mint, program_id).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(find_escrow_address(mint, program_id).0, false),
+            AccountMeta::new(*recipient_token_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+pub fn restake_agent(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    mint: &Pubkey,
+    staker_token_account: &Pubkey,
+    token_program: &Pubkey,
+    stake_amount: u64,
+) -> Instruction {
+    build(
+        program_id,
+        "restake_agent",
+        stake_amount,
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_agent_address(signer, program_id).0, false),
+            AccountMeta::new(*signer, true),
+            AccountMeta::new_readonly(find_stake_mint_address(mint, program_id).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*staker_token_account, false),
+            AccountMeta::new(find_escrow_address(mint, program_id).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+pub fn suspend_agent(program_id: &Pubkey, agent: &Pubkey, proposal_id: u64, executor: &Pubkey) -> Instruction {
+    build(program_id, "suspend_agent", (), governed_accounts(program_id, agent, proposal_id, executor))
+}
+
+pub fn unsuspend_agent(program_id: &Pubkey, agent: &Pubkey, proposal_id: u64, executor: &Pubkey) -> Instruction {
+    build(program_id, "unsuspend_agent", (), governed_accounts(program_id, agent, proposal_id, executor))
+}
+
+pub fn ban_agent(program_id: &Pubkey, agent: &Pubkey, proposal_id: u64, executor: &Pubkey) -> Instruction {
+    build(program_id, "ban_agent", (), governed_accounts(program_id, agent, proposal_id, executor))
+}
+
+pub fn pause_service(program_id: &Pubkey, service: &Pubkey, owner: &Pubkey) -> Instruction {
+    build(program_id, "pause_service", (), service_owner_accounts(service, owner))
+}
+
+pub fn unpause_service(program_id: &Pubkey, service: &Pubkey, owner: &Pubkey) -> Instruction {
+    build(program_id, "unpause_service", (), service_owner_accounts(service, owner))
+}
+
+pub fn deprecate_service(program_id: &Pubkey, service: &Pubkey, owner: &Pubkey) -> Instruction {
+    build(program_id, "deprecate_service", (), service_owner_accounts(service, owner))
+}
+
+pub fn suspend_service(program_id: &Pubkey, service: &Pubkey, proposal_id: u64, executor: &Pubkey) -> Instruction {
+    build(program_id, "suspend_service", (), governed_accounts(program_id, service, proposal_id, executor))
+}
+
+pub fn unsuspend_service(program_id: &Pubkey, service: &Pubkey, proposal_id: u64, executor: &Pubkey) -> Instruction {
+    build(program_id, "unsuspend_service", (), governed_accounts(program_id, service, proposal_id, executor))
+}
+
+/// Executes an approved `GovernanceAction::UpdateCouncil` proposal.
+pub fn update_council(program_id: &Pubkey, proposal_id: u64, executor: &Pubkey) -> Instruction {
+    build(program_id, "update_council", (), governed_config_accounts(program_id, proposal_id, executor))
+}
+
+/// Executes an approved `GovernanceAction::UpdateAuthorities` proposal.
+pub fn update_authorities(program_id: &Pubkey, proposal_id: u64, executor: &Pubkey) -> Instruction {
+    build(program_id, "update_authorities", (), governed_config_accounts(program_id, proposal_id, executor))
+}
+
+pub fn close_agent(program_id: &Pubkey, wallet: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "close_agent",
+        (),
+        vec![
+            AccountMeta::new(find_agent_address(wallet, program_id).0, false),
+            AccountMeta::new(*wallet, true),
+        ],
+    )
+}
+
+/// `directory_index` is the service's `Service::directory_index`.
+pub fn close_service(program_id: &Pubkey, service: &Pubkey, owner: &Pubkey, directory_index: u32) -> Instruction {
+    let page = directory_index / SERVICE_PAGE_SIZE;
+
+    build(
+        program_id,
+        "close_service",
+        (),
+        vec![
+            AccountMeta::new(*service, false),
+            AccountMeta::new(find_agent_address(owner, program_id).0, false),
+            AccountMeta::new(find_service_directory_address(owner, program_id).0, false),
+            AccountMeta::new(find_service_page_address(owner, page, program_id).0, false),
+            AccountMeta::new(*owner, true),
+        ],
+    )
+}
+
+pub fn close_suspended_service(
+    program_id: &Pubkey,
+    service: &Pubkey,
+    owner: &Pubkey,
+    directory_index: u32,
+    dao_treasury: &Pubkey,
+    proposal_id: u64,
+    executor: &Pubkey,
+) -> Instruction {
+    let page = directory_index / SERVICE_PAGE_SIZE;
+
+    build(
+        program_id,
+        "close_suspended_service",
+        (),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(*service, false),
+            AccountMeta::new(find_agent_address(owner, program_id).0, false),
+            AccountMeta::new(find_service_directory_address(owner, program_id).0, false),
+            AccountMeta::new(find_service_page_address(owner, page, program_id).0, false),
+            AccountMeta::new(find_proposal_address(proposal_id, program_id).0, false),
+            AccountMeta::new(*dao_treasury, false),
+            AccountMeta::new_readonly(*executor, true),
+        ],
+    )
+}
+
+/// Lists a service created before services were PDAs in its owner's directory. `next_index` is the owner's
+/// `ServiceDirectory::next_index`, or 0 if they have no directory yet.
+pub fn migrate_service(
+    program_id: &Pubkey,
+    service: &Pubkey,
+    owner: &Pubkey,
+    next_index: u32,
+    payer: &Pubkey,
+) -> Instruction {
+    build(
+        program_id,
+        "migrate_service",
+        *owner,
+        vec![
+            AccountMeta::new(*service, false),
+            AccountMeta::new(find_service_directory_address(owner, program_id).0, false),
+            AccountMeta::new(find_service_page_address(owner, next_index / SERVICE_PAGE_SIZE, program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// `id` is the config's `proposal_count`.
+pub fn create_proposal(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    id: u64,
+    action: GovernanceAction,
+    emergency: bool,
+) -> Instruction {
+    build(
+        program_id,
+        "create_proposal",
+        (action, emergency),
+        vec![
+            AccountMeta::new(find_config_address(program_id).0, false),
+            AccountMeta::new(find_proposal_address(id, program_id).0, false),
+            AccountMeta::new(*proposer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn approve_proposal(program_id: &Pubkey, id: u64, approver: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "approve_proposal",
+        (),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_proposal_address(id, program_id).0, false),
+            AccountMeta::new_readonly(*approver, true),
+        ],
+    )
+}
+
+pub fn cancel_proposal(program_id: &Pubkey, id: u64, proposer: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "cancel_proposal",
+        (),
+        vec![
+            AccountMeta::new(find_proposal_address(id, program_id).0, false),
+            AccountMeta::new_readonly(*proposer, true),
+        ],
+    )
+}
+
+pub fn initialize_credit_vault(
+    program_id: &Pubkey,
+    stake_token_mint: &Pubkey,
+    payer: &Pubkey,
+    authority: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    build(
+        program_id,
+        "initialize_credit_vault",
+        (),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_credit_vault_address(program_id).0, false),
+            AccountMeta::new_readonly(*stake_token_mint, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+    )
+}
+
+pub fn open_credit_line(program_id: &Pubkey, signer: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "open_credit_line",
+        (),
+        vec![
+            AccountMeta::new(find_credit_line_address(signer, program_id).0, false),
+            AccountMeta::new_readonly(find_agent_address(signer, program_id).0, false),
+            AccountMeta::new(*signer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn draw_credit(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    service: &Pubkey,
+    mint: &Pubkey,
+    service_token_account: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        program_id,
+        "draw_credit",
+        amount,
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_agent_address(signer, program_id).0, false),
+            AccountMeta::new(find_credit_line_address(signer, program_id).0, false),
+            AccountMeta::new_readonly(*service, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(find_credit_vault_address(program_id).0, false),
+            AccountMeta::new(*service_token_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+pub fn repay_credit(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    mint: &Pubkey,
+    payer_token_account: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        program_id,
+        "repay_credit",
+        amount,
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_agent_address(signer, program_id).0, false),
+            AccountMeta::new(find_credit_line_address(signer, program_id).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(find_credit_vault_address(program_id).0, false),
+            AccountMeta::new(*payer_token_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+pub fn settle_credit_default(program_id: &Pubkey, wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "settle_credit_default",
+        (),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_agent_address(wallet, program_id).0, false),
+            AccountMeta::new(find_credit_line_address(wallet, program_id).0, false),
+            AccountMeta::new_readonly(find_stake_mint_address(mint, program_id).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(find_escrow_address(mint, program_id).0, false),
+            AccountMeta::new(find_credit_vault_address(program_id).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_payment_escrow(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    service: &Pubkey,
+    mint: &Pubkey,
+    payer_token_account: &Pubkey,
+    token_program: &Pubkey,
+    nonce: u64,
+    amount: u64,
+    deadline: i64,
+) -> Instruction {
+    let (payment_escrow, _) = find_payment_escrow_address(payer, service, nonce, program_id);
+
+    build(
+        program_id,
+        "create_payment_escrow",
+        (nonce, amount, deadline),
+        vec![
+            AccountMeta::new(payment_escrow, false),
+            AccountMeta::new(find_payment_vault_address(&payment_escrow, program_id).0, false),
+            AccountMeta::new_readonly(*service, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*payer_token_account, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn release_payment_escrow(
+    program_id: &Pubkey,
+    payment_escrow: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    payee_token_account: &Pubkey,
+    authority: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    build(
+        program_id,
+        "release_payment_escrow",
+        (),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(*payment_escrow, false),
+            AccountMeta::new(find_payment_vault_address(payment_escrow, program_id).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*payee_token_account, false),
+            AccountMeta::new(*payer, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+pub fn refund_payment_escrow(
+    program_id: &Pubkey,
+    payment_escrow: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    payer_token_account: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    build(
+        program_id,
+        "refund_payment_escrow",
+        (),
+        vec![
+            AccountMeta::new(*payment_escrow, false),
+            AccountMeta::new(find_payment_vault_address(payment_escrow, program_id).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*payer_token_account, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn open_channel(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    service: &Pubkey,
+    mint: &Pubkey,
+    payer_token_account: &Pubkey,
+    token_program: &Pubkey,
+    channel_id: u64,
+    deposit: u64,
+) -> Instruction {
+    let (channel, _) = find_channel_address(payer, service, channel_id, program_id);
+
+    build(
+        program_id,
+        "open_channel",
+        (channel_id, deposit),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(channel, false),
+            AccountMeta::new(find_channel_vault_address(&channel, program_id).0, false),
+            AccountMeta::new_readonly(*service, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*payer_token_account, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+    )
+}
+
+pub fn top_up_channel(
+    program_id: &Pubkey,
+    channel: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    payer_token_account: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        program_id,
+        "top_up_channel",
+        amount,
+        vec![
+            AccountMeta::new(*channel, false),
+            AccountMeta::new(find_channel_vault_address(channel, program_id).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*payer_token_account, false),
+            AccountMeta::new_readonly(*payer, true),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+pub fn redeem_voucher(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    payee: &Pubkey,
+    payee_token_account: &Pubkey,
+    token_program: &Pubkey,
+    voucher: &SignedVoucher,
+) -> Vec<Instruction> {
+    let channel = voucher.voucher.channel;
+
+    vec![
+        voucher.verify_instruction(),
+        build(
+            program_id,
+            "redeem_voucher",
+            (voucher.voucher.amount, voucher.voucher.nonce),
+            vec![
+                AccountMeta::new(channel, false),
+                AccountMeta::new(find_channel_vault_address(&channel, program_id).0, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(*payee_token_account, false),
+                AccountMeta::new_readonly(*payee, true),
+                AccountMeta::new_readonly(sysvar::instructions::id(), false),
+                AccountMeta::new_readonly(*token_program, false),
+            ],
+        ),
+    ]
+}
+
+pub fn request_channel_close(program_id: &Pubkey, channel: &Pubkey, payer: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "request_channel_close",
+        (),
+        vec![
+            AccountMeta::new(*channel, false),
+            AccountMeta::new_readonly(*payer, true),
+        ],
+    )
+}
+
+pub fn finalize_channel_close(
+    program_id: &Pubkey,
+    channel: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    payer_token_account: &Pubkey,
+    authority: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    build(
+        program_id,
+        "finalize_channel_close",
+        (),
+        vec![
+            AccountMeta::new(*channel, false),
+            AccountMeta::new(find_channel_vault_address(channel, program_id).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*payer_token_account, false),
+            AccountMeta::new(*payer, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+pub fn add_stake_mint(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    authority: &Pubkey,
+    token_program: &Pubkey,
+    reputation_weight_bps: u32,
+) -> Instruction {
+    build(
+        program_id,
+        "add_stake_mint",
+        reputation_weight_bps,
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_stake_mint_address(mint, program_id).0, false),
+            AccountMeta::new(find_escrow_address(mint, program_id).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+    )
+}
+
+pub fn update_stake_mint(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    update: StakeMintUpdate,
+) -> Instruction {
+    build(
+        program_id,
+        "update_stake_mint",
+        update,
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_stake_mint_address(mint, program_id).0, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+    )
+}
+
+pub fn migrate_legacy_stake(
+    program_id: &Pubkey,
+    agent: &Pubkey,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    build(
+        program_id,
+        "migrate_legacy_stake",
+        (),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(*agent, false),
+            AccountMeta::new(find_legacy_escrow_address(program_id).0, false),
+            AccountMeta::new_readonly(find_stake_mint_address(mint, program_id).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(find_escrow_address(mint, program_id).0, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+    )
+}
+
+pub fn add_probe_operator(program_id: &Pubkey, authority: &Pubkey, operator: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "add_probe_operator",
+        *operator,
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_probe_operator_address(operator, program_id).0, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn remove_probe_operator(program_id: &Pubkey, authority: &Pubkey, operator: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "remove_probe_operator",
+        (),
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_probe_operator_address(operator, program_id).0, false),
+            AccountMeta::new(*authority, true),
+        ],
+    )
+}
+
+pub fn open_service_health(program_id: &Pubkey, service: &Pubkey, payer: &Pubkey) -> Instruction {
+    build(
+        program_id,
+        "open_service_health",
+        (),
+        vec![
+            AccountMeta::new(find_service_health_address(service, program_id).0, false),
+            AccountMeta::new_readonly(*service, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub fn report_health(program_id: &Pubkey, operator: &Pubkey, service: &Pubkey, report: HealthReport) -> Instruction {
+    build(
+        program_id,
+        "report_health",
+        report,
+        vec![
+            config_meta(program_id),
+            AccountMeta::new(find_probe_operator_address(operator, program_id).0, false),
+            AccountMeta::new(find_service_health_address(service, program_id).0, false),
+            AccountMeta::new(*service, false),
+            AccountMeta::new_readonly(*operator, true),
+        ],
+    )
+}
+
+fn config_meta(program_id: &Pubkey) -> AccountMeta {
+    AccountMeta::new_readonly(find_config_address(program_id).0, false)
+}
+
+fn config_authority_accounts(program_id: &Pubkey, authority: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(find_config_address(program_id).0, false),
+        AccountMeta::new_readonly(*authority, true),
+    ]
+}
+
+fn governed_config_accounts(program_id: &Pubkey, proposal_id: u64, executor: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(find_config_address(program_id).0, false),
+        AccountMeta::new(find_proposal_address(proposal_id, program_id).0, false),
+        AccountMeta::new_readonly(*executor, true),
+    ]
+}
+
+fn governed_accounts(program_id: &Pubkey, target: &Pubkey, proposal_id: u64, executor: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        config_meta(program_id),
+        AccountMeta::new(find_proposal_address(proposal_id, program_id).0, false),
+        AccountMeta::new(*target, false),
+        AccountMeta::new_readonly(*executor, true),
+    ]
+}
+
+fn service_owner_accounts(service: &Pubkey, owner: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(*service, false),
+        AccountMeta::new_readonly(*owner, true),
+    ]
+}
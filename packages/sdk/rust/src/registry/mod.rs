@@ -0,0 +1,130 @@
+pub mod accounts;
+pub mod channel;
+pub mod client;
+pub mod discovery;
+pub mod events;
+pub mod instructions;
+pub mod receipt;
+
+pub use accounts::{
+    Agent, AgentStatus, ChannelStatus, ConfigParams, ConfigUpdate, CreditLine, Dispute, DisputeRuling, DisputeStatus,
+    GovernanceAction, HealthReport, PaymentChannel, PaymentEscrow, PaymentEscrowStatus, ProbeOperator, Proposal,
+    ProposalStatus, RegistryConfig, Service, ServiceDirectory, ServiceDirectoryPage, ServiceHealth, ServiceInteraction,
+    ServiceRating, ServiceStatus, StakeMint, StakeMintUpdate, TransactionReceipt,
+};
+pub use channel::{SignedVoucher, Voucher};
+pub use client::RegistryClient;
+pub use discovery::{OnChainDiscovery, OnChainDiscoveryConfig};
+pub use events::RegistryEvent;
+pub use receipt::{CosignedReceipt, Receipt};
+
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+lazy_static::lazy_static! {
+    pub static ref REGISTRY_PROGRAM_ID: Pubkey = Pubkey::from_str("85GHuKTjE4RXR2d4tCMKLXSbdwr2wkELVvUhNeyrwEfj").unwrap();
+}
+
+pub const SERVICE_PAGE_SIZE: u32 = 32;
+
+pub fn find_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"config"], program_id)
+}
+
+pub fn find_agent_address(wallet: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"agent", wallet.as_ref()], program_id)
+}
+
+pub fn find_stake_mint_address(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"stake_mint", mint.as_ref()], program_id)
+}
+
+pub fn find_escrow_address(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow", mint.as_ref()], program_id)
+}
+
+/// The single escrow every agent staked into before stakes were kept per mint.
+pub fn find_legacy_escrow_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow"], program_id)
+}
+
+pub fn find_credit_vault_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"credit_vault"], program_id)
+}
+
+pub fn find_credit_line_address(wallet: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"credit", wallet.as_ref()], program_id)
+}
+
+pub fn find_rating_address(service: &Pubkey, rater: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"rating", service.as_ref(), rater.as_ref()], program_id)
+}
+
+pub fn find_interaction_address(service: &Pubkey, agent: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"interaction", service.as_ref(), agent.as_ref()], program_id)
+}
+
+pub fn find_dispute_address(service: &Pubkey, transaction_signature: &[u8; 64], program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"dispute",
+            service.as_ref(),
+            &transaction_signature[..32],
+            &transaction_signature[32..],
+        ],
+        program_id,
+    )
+}
+
+pub fn find_receipt_address(service: &Pubkey, payer: &Pubkey, nonce: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"receipt", service.as_ref(), payer.as_ref(), &nonce.to_le_bytes()],
+        program_id,
+    )
+}
+
+pub fn find_payment_escrow_address(payer: &Pubkey, service: &Pubkey, nonce: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"payment_escrow", payer.as_ref(), service.as_ref(), &nonce.to_le_bytes()],
+        program_id,
+    )
+}
+
+pub fn find_payment_vault_address(payment_escrow: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"payment_vault", payment_escrow.as_ref()], program_id)
+}
+
+pub fn find_channel_address(payer: &Pubkey, service: &Pubkey, channel_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"channel", payer.as_ref(), service.as_ref(), &channel_id.to_le_bytes()],
+        program_id,
+    )
+}
+
+pub fn find_channel_vault_address(channel: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"channel_vault", channel.as_ref()], program_id)
+}
+
+pub fn find_probe_operator_address(operator: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"probe_operator", operator.as_ref()], program_id)
+}
+
+pub fn find_service_health_address(service: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"service_health", service.as_ref()], program_id)
+}
+
+pub fn find_service_address(owner: &Pubkey, index: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"service", owner.as_ref(), &index.to_le_bytes()], program_id)
+}
+
+pub fn find_service_directory_address(owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"service_directory", owner.as_ref()], program_id)
+}
+
+pub fn find_service_page_address(owner: &Pubkey, page: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"service_page", owner.as_ref(), &page.to_le_bytes()], program_id)
+}
+
+pub fn find_proposal_address(id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"proposal", &id.to_le_bytes()], program_id)
+}
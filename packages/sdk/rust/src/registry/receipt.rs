@@ -0,0 +1,129 @@
+use base64::{engine::general_purpose, Engine as _};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+
+use super::channel::ed25519_verify_instruction;
+use crate::error::{Result, X402Error};
+
+/// Prefixed, with the program ID, to every receipt message so the signatures cannot be replayed as anything else.
+const RECEIPT_DOMAIN: &[u8] = b"x402-registry:receipt";
+const RECEIPT_MESSAGE_LEN: usize = RECEIPT_DOMAIN.len() + 32 + 85;
+const COSIGNED_RECEIPT_LEN: usize = RECEIPT_MESSAGE_LEN + 64 + 32 + 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Receipt {
+    pub program_id: Pubkey,
+    pub service: Pubkey,
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub success: bool,
+    pub response_time_ms: u32,
+    pub nonce: u64,
+}
+
+impl Receipt {
+    pub fn message(&self) -> [u8; RECEIPT_MESSAGE_LEN] {
+        let mut message = [0u8; RECEIPT_MESSAGE_LEN];
+        message[..RECEIPT_DOMAIN.len()].copy_from_slice(RECEIPT_DOMAIN);
+        let body = &mut message[RECEIPT_DOMAIN.len()..];
+        body[..32].copy_from_slice(self.program_id.as_ref());
+        body[32..64].copy_from_slice(self.service.as_ref());
+        body[64..96].copy_from_slice(self.payer.as_ref());
+        body[96..104].copy_from_slice(&self.amount.to_le_bytes());
+        body[104] = self.success as u8;
+        body[105..109].copy_from_slice(&self.response_time_ms.to_le_bytes());
+        body[109..].copy_from_slice(&self.nonce.to_le_bytes());
+        message
+    }
+
+    pub fn sign(&self, signer: &Keypair) -> Signature {
+        signer.sign_message(&self.message())
+    }
+
+    pub fn cosign(&self, payer_signature: Signature, owner: &Keypair) -> CosignedReceipt {
+        CosignedReceipt {
+            receipt: *self,
+            payer_signature,
+            owner: owner.pubkey(),
+            owner_signature: self.sign(owner),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CosignedReceipt {
+    pub receipt: Receipt,
+    pub payer_signature: Signature,
+    pub owner: Pubkey,
+    pub owner_signature: Signature,
+}
+
+impl CosignedReceipt {
+    pub fn verify(&self) -> Result<()> {
+        let message = self.receipt.message();
+        if !self.payer_signature.verify(self.receipt.payer.as_ref(), &message) {
+            return Err(X402Error::Signature("Invalid payer signature on receipt".to_string()));
+        }
+        if !self.owner_signature.verify(self.owner.as_ref(), &message) {
+            return Err(X402Error::Signature("Invalid service owner signature on receipt".to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub fn encode(&self) -> String {
+        let mut bytes = Vec::with_capacity(COSIGNED_RECEIPT_LEN);
+        bytes.extend_from_slice(&self.receipt.message());
+        bytes.extend_from_slice(self.payer_signature.as_ref());
+        bytes.extend_from_slice(self.owner.as_ref());
+        bytes.extend_from_slice(self.owner_signature.as_ref());
+        general_purpose::STANDARD.encode(bytes)
+    }
+
+    pub fn decode(encoded: &str) -> Result<Self> {
+        let bytes = general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| X402Error::Signature(format!("Invalid receipt encoding: {}", e)))?;
+
+        if bytes.len() != COSIGNED_RECEIPT_LEN {
+            return Err(X402Error::Signature("Invalid receipt length".to_string()));
+        }
+        if !bytes.starts_with(RECEIPT_DOMAIN) {
+            return Err(X402Error::Signature("Invalid receipt domain".to_string()));
+        }
+
+        let body = &bytes[RECEIPT_DOMAIN.len()..RECEIPT_MESSAGE_LEN];
+        let mut amount = [0u8; 8];
+        amount.copy_from_slice(&body[96..104]);
+        let mut response_time_ms = [0u8; 4];
+        response_time_ms.copy_from_slice(&body[105..109]);
+        let mut nonce = [0u8; 8];
+        nonce.copy_from_slice(&body[109..117]);
+
+        let signatures = &bytes[RECEIPT_MESSAGE_LEN..];
+        Ok(Self {
+            receipt: Receipt {
+                program_id: Pubkey::try_from(&body[..32]).expect("32-byte slice"),
+                service: Pubkey::try_from(&body[32..64]).expect("32-byte slice"),
+                payer: Pubkey::try_from(&body[64..96]).expect("32-byte slice"),
+                amount: u64::from_le_bytes(amount),
+                success: body[104] != 0,
+                response_time_ms: u32::from_le_bytes(response_time_ms),
+                nonce: u64::from_le_bytes(nonce),
+            },
+            payer_signature: Signature::try_from(&signatures[..64]).expect("64-byte slice"),
+            owner: Pubkey::try_from(&signatures[64..96]).expect("32-byte slice"),
+            owner_signature: Signature::try_from(&signatures[96..]).expect("64-byte slice"),
+        })
+    }
+
+    /// The payer's verification must come first, directly followed by the owner's.
+    pub fn verify_instructions(&self) -> [Instruction; 2] {
+        let message = self.receipt.message();
+        [
+            ed25519_verify_instruction(&self.receipt.payer, &self.payer_signature, &message),
+            ed25519_verify_instruction(&self.owner, &self.owner_signature, &message),
+        ]
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::discovery::X402Service;
+use crate::health::HealthMonitor;
+use crate::types::ServiceStats;
+
+const UNOBSERVED_SCORE: f64 = 0.5;
+
+#[derive(Debug, Clone)]
+pub struct SelectionWeights {
+    pub price: f64,
+    pub reputation: f64,
+    pub uptime: f64,
+    pub latency: f64,
+    pub success_rate: f64,
+    pub health: f64,
+}
+
+impl Default for SelectionWeights {
+    fn default() -> Self {
+        Self {
+            price: 0.3,
+            reputation: 0.2,
+            uptime: 0.15,
+            latency: 0.15,
+            success_rate: 0.2,
+            health: 0.2,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SelectionConfig {
+    pub weights: SelectionWeights,
+    pub max_attempts: usize,
+}
+
+impl Default for SelectionConfig {
+    fn default() -> Self {
+        Self {
+            weights: SelectionWeights::default(),
+            max_attempts: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RankedService {
+    pub service: X402Service,
+    pub score: f64,
+}
+
+#[derive(Clone, Default)]
+pub struct ServiceSelector {
+    config: SelectionConfig,
+    health: Option<Arc<HealthMonitor>>,
+}
+
+struct Range {
+    min: f64,
+    max: f64,
+}
+
+impl Range {
+    fn of(values: impl Iterator<Item = f64>) -> Option<Self> {
+        values.fold(None, |range, value| match range {
+            None => Some(Range { min: value, max: value }),
+            Some(Range { min, max }) => Some(Range {
+                min: min.min(value),
+                max: max.max(value),
+            }),
+        })
+    }
+
+    fn higher_is_better(&self, value: f64) -> f64 {
+        if self.max > self.min {
+            (value - self.min) / (self.max - self.min)
+        } else {
+            1.0
+        }
+    }
+
+    fn lower_is_better(&self, value: f64) -> f64 {
+        if self.max > self.min {
+            (self.max - value) / (self.max - self.min)
+        } else {
+            1.0
+        }
+    }
+}
+
+impl ServiceSelector {
+    pub fn new(config: SelectionConfig) -> Self {
+        Self { config, health: None }
+    }
+
+    pub fn with_health_monitor(mut self, health: Arc<HealthMonitor>) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    pub fn config(&self) -> &SelectionConfig {
+        &self.config
+    }
+
+    pub fn rank(
+        &self,
+        candidates: &[X402Service],
+        stats: &HashMap<String, ServiceStats>,
+    ) -> Vec<RankedService> {
+        let observed = |service: &X402Service| stats.get(&service.resource);
+
+        let prices = Range::of(candidates.iter().filter_map(price));
+        let reputations = Range::of(candidates.iter().filter_map(|s| s.reputation));
+        let uptimes = Range::of(candidates.iter().filter_map(|s| s.uptime));
+        let latencies = Range::of(
+            candidates
+                .iter()
+                .filter_map(|s| observed(s).and_then(|o| o.average_latency_ms())),
+        );
+
+        let health = self.health.as_ref().map(|monitor| monitor.scores());
+
+        let weights = &self.config.weights;
+        let health_weight = if health.is_some() { weights.health } else { 0.0 };
+        let total_weight = weights.price
+            + weights.reputation
+            + weights.uptime
+            + weights.latency
+            + weights.success_rate
+            + health_weight;
+
+        let mut ranked: Vec<RankedService> = candidates
+            .iter()
+            .map(|service| {
+                let stats = observed(service);
+
+                let price_score = match (price(service), &prices) {
+                    (Some(p), Some(range)) => range.lower_is_better(p),
+                    _ => 0.0,
+                };
+                let reputation_score = match (service.reputation, &reputations) {
+                    (Some(r), Some(range)) => range.higher_is_better(r),
+                    _ => 0.0,
+                };
+                let uptime_score = match (service.uptime, &uptimes) {
+                    (Some(u), Some(range)) => range.higher_is_better(u),
+                    _ => 0.0,
+                };
+                let latency_score = match (stats.and_then(|s| s.average_latency_ms()), &latencies) {
+                    (Some(l), Some(range)) => range.lower_is_better(l),
+                    _ => UNOBSERVED_SCORE,
+                };
+                let success_score = stats
+                    .and_then(|s| s.success_rate())
+                    .unwrap_or(UNOBSERVED_SCORE);
+                let health_score = health
+                    .as_ref()
+                    .and_then(|scores| scores.get(&service.id))
+                    .map_or(UNOBSERVED_SCORE, |h| h.score);
+
+                let weighted = weights.price * price_score
+                    + weights.reputation * reputation_score
+                    + weights.uptime * uptime_score
+                    + weights.latency * latency_score
+                    + weights.success_rate * success_score
+                    + health_weight * health_score;
+
+                RankedService {
+                    service: service.clone(),
+                    score: if total_weight > 0.0 { weighted / total_weight } else { 0.0 },
+                }
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+        ranked
+    }
+
+    pub fn select(
+        &self,
+        candidates: &[X402Service],
+        stats: &HashMap<String, ServiceStats>,
+    ) -> Option<X402Service> {
+        self.rank(candidates, stats).into_iter().next().map(|r| r.service)
+    }
+}
+
+fn price(service: &X402Service) -> Option<f64> {
+    service.pricing.as_ref().and_then(|p| p.amount.parse::<f64>().ok())
+}
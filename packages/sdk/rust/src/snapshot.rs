@@ -0,0 +1,100 @@
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use futures::stream::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::discovery::{discover_stream, DiscoveryBackend, DiscoveryQuery};
+use crate::error::{Result, X402Error};
+use crate::types::X402ServiceInfo;
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotBody<'a> {
+    version: u32,
+    created_at: u64,
+    services: &'a [X402ServiceInfo],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverySnapshot {
+    pub version: u32,
+    pub created_at: u64,
+    pub services: Vec<X402ServiceInfo>,
+    pub signer: String,
+    pub signature: String,
+}
+
+impl DiscoverySnapshot {
+    pub fn new(services: Vec<X402ServiceInfo>, keypair: &Keypair) -> Result<Self> {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let mut snapshot = Self {
+            version: SNAPSHOT_VERSION,
+            created_at,
+            services,
+            signer: general_purpose::STANDARD.encode(keypair.public.as_bytes()),
+            signature: String::new(),
+        };
+
+        let signature = keypair.sign(&snapshot.signing_payload()?);
+        snapshot.signature = general_purpose::STANDARD.encode(signature.to_bytes());
+
+        Ok(snapshot)
+    }
+
+    pub async fn capture(
+        backend: &dyn DiscoveryBackend,
+        query: DiscoveryQuery,
+        keypair: &Keypair,
+    ) -> Result<Self> {
+        let services: Vec<X402ServiceInfo> = discover_stream(backend, query).try_collect().await?;
+        Self::new(services, keypair)
+    }
+
+    pub fn verify(&self, trusted_key: &PublicKey) -> Result<()> {
+        if self.version != SNAPSHOT_VERSION {
+            return Err(X402Error::Signature(format!(
+                "Unsupported snapshot version: {}",
+                self.version
+            )));
+        }
+
+        if general_purpose::STANDARD.encode(trusted_key.as_bytes()) != self.signer {
+            return Err(X402Error::Signature("Snapshot signed by an untrusted key".to_string()));
+        }
+
+        let signature_bytes = general_purpose::STANDARD.decode(&self.signature)?;
+        let signature = Signature::from_bytes(&signature_bytes)
+            .map_err(|e| X402Error::Signature(e.to_string()))?;
+
+        trusted_key
+            .verify(&self.signing_payload()?, &signature)
+            .map_err(|_| X402Error::Signature("Invalid snapshot signature".to_string()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn signing_payload(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(&SnapshotBody {
+            version: self.version,
+            created_at: self.created_at,
+            services: &self.services,
+        })?)
+    }
+}
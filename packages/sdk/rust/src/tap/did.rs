@@ -0,0 +1,365 @@
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::PublicKey;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use super::keys::Jwk;
+use crate::error::X402Error;
+use crate::registry::{find_agent_address, Agent, AgentStatus, REGISTRY_PROGRAM_ID};
+
+const ED25519_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationMethod {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub method_type: String,
+    pub controller: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key_multibase: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key_jwk: Option<Jwk>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key_base58: Option<String>,
+}
+
+impl VerificationMethod {
+    pub fn ed25519_public_key(&self) -> Result<PublicKey, X402Error> {
+        let bytes = if let Some(multibase) = &self.public_key_multibase {
+            DidResolver::decode_multibase_ed25519(multibase)?
+        } else if let Some(jwk) = &self.public_key_jwk {
+            if jwk.kty != "OKP" || jwk.crv != "Ed25519" {
+                return Err(X402Error::Did(format!("{} is not an Ed25519 key", self.id)));
+            }
+            general_purpose::URL_SAFE_NO_PAD.decode(&jwk.x)?
+        } else if let Some(base58) = &self.public_key_base58 {
+            bs58::decode(base58)
+                .into_vec()
+                .map_err(|e| X402Error::Did(e.to_string()))?
+        } else {
+            return Err(X402Error::Did(format!("{} has no public key material", self.id)));
+        };
+
+        PublicKey::from_bytes(&bytes).map_err(|e| X402Error::Did(e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidDocument {
+    pub id: String,
+    #[serde(default)]
+    pub also_known_as: Vec<String>,
+    #[serde(default)]
+    pub verification_method: Vec<VerificationMethod>,
+    #[serde(default)]
+    pub authentication: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub assertion_method: Vec<serde_json::Value>,
+}
+
+impl DidDocument {
+    pub fn verification_key(&self, key_ref: Option<&str>) -> Result<PublicKey, X402Error> {
+        let method = match key_ref {
+            Some(reference) => {
+                let fragment = reference.rsplit('#').next().unwrap_or(reference);
+                self.verification_method
+                    .iter()
+                    .find(|m| m.id == reference || m.id.ends_with(&format!("#{}", fragment)))
+            }
+            None => self
+                .authentication_method_ids()
+                .first()
+                .and_then(|id| self.verification_method.iter().find(|m| &m.id == id))
+                .or_else(|| self.verification_method.first()),
+        };
+
+        method
+            .ok_or_else(|| X402Error::Did(format!("No matching verification method in {}", self.id)))?
+            .ed25519_public_key()
+    }
+
+    fn authentication_method_ids(&self) -> Vec<String> {
+        self.authentication
+            .iter()
+            .filter_map(|entry| match entry {
+                serde_json::Value::String(id) => Some(id.clone()),
+                serde_json::Value::Object(map) => map.get("id").and_then(|v| v.as_str()).map(String::from),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn single_key(did: &str, public_key: &PublicKey) -> Self {
+        let key_id = format!("{}#{}", did, DidResolver::encode_multibase_ed25519(public_key));
+        Self {
+            id: did.to_string(),
+            also_known_as: Vec::new(),
+            verification_method: vec![VerificationMethod {
+                id: key_id.clone(),
+                method_type: "Ed25519VerificationKey2020".to_string(),
+                controller: did.to_string(),
+                public_key_multibase: Some(DidResolver::encode_multibase_ed25519(public_key)),
+                public_key_jwk: None,
+                public_key_base58: None,
+            }],
+            authentication: vec![serde_json::Value::String(key_id.clone())],
+            assertion_method: vec![serde_json::Value::String(key_id)],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DidResolverConfig {
+    pub rpc_url: Option<String>,
+    pub program_id: Pubkey,
+    pub timeout: u64,
+    pub cache_ttl: u64,
+}
+
+impl Default for DidResolverConfig {
+    fn default() -> Self {
+        Self {
+            rpc_url: None,
+            program_id: *REGISTRY_PROGRAM_ID,
+            timeout: 10,
+            cache_ttl: 300,
+        }
+    }
+}
+
+pub struct DidResolver {
+    config: DidResolverConfig,
+    http_client: Client,
+    rpc_client: Option<Arc<RpcClient>>,
+    cache: RwLock<HashMap<String, (Instant, DidDocument)>>,
+}
+
+impl DidResolver {
+    pub fn new(config: DidResolverConfig) -> Result<Self, X402Error> {
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout))
+            .build()?;
+
+        let rpc_client = config
+            .rpc_url
+            .clone()
+            .map(|url| Arc::new(RpcClient::new(url)));
+
+        Ok(Self {
+            config,
+            http_client,
+            rpc_client,
+            cache: RwLock::new(HashMap::new()),
+        })
+    }
+
+    pub fn with_rpc_client(mut self, rpc_client: Arc<RpcClient>) -> Self {
+        self.rpc_client = Some(rpc_client);
+        self
+    }
+
+    pub async fn resolve(&self, did: &str) -> Result<DidDocument, X402Error> {
+        let did = did.split('#').next().unwrap_or(did);
+
+        if let Some((fetched_at, document)) = self.cache.read().unwrap().get(did) {
+            if fetched_at.elapsed() < Duration::from_secs(self.config.cache_ttl) {
+                return Ok(document.clone());
+            }
+        }
+
+        let document = if let Some(rest) = did.strip_prefix("did:key:") {
+            Self::resolve_did_key(did, rest)?
+        } else if let Some(rest) = did.strip_prefix("did:web:") {
+            self.resolve_did_web(did, rest).await?
+        } else if let Some(rest) = did.strip_prefix("did:x402:") {
+            self.resolve_did_x402(did, rest).await?
+        } else {
+            return Err(X402Error::Did(format!("Unsupported DID method: {}", did)));
+        };
+
+        self.cache
+            .write()
+            .unwrap()
+            .insert(did.to_string(), (Instant::now(), document.clone()));
+
+        Ok(document)
+    }
+
+    pub async fn resolve_key(&self, did_url: &str) -> Result<PublicKey, X402Error> {
+        let document = self.resolve(did_url).await?;
+        let key_ref = did_url.contains('#').then_some(did_url);
+        document.verification_key(key_ref)
+    }
+
+    pub fn did_key(public_key: &PublicKey) -> String {
+        format!("did:key:{}", Self::encode_multibase_ed25519(public_key))
+    }
+
+    pub fn did_x402(wallet: &Pubkey) -> String {
+        format!("did:x402:{}", wallet)
+    }
+
+    pub fn encode_multibase_ed25519(public_key: &PublicKey) -> String {
+        let mut bytes = ED25519_MULTICODEC.to_vec();
+        bytes.extend_from_slice(public_key.as_bytes());
+        format!("z{}", bs58::encode(bytes).into_string())
+    }
+
+    pub fn decode_multibase_ed25519(multibase: &str) -> Result<Vec<u8>, X402Error> {
+        let encoded = multibase
+            .strip_prefix('z')
+            .ok_or_else(|| X402Error::Did("Only base58btc multibase keys are supported".to_string()))?;
+        let bytes = bs58::decode(encoded)
+            .into_vec()
+            .map_err(|e| X402Error::Did(e.to_string()))?;
+
+        if bytes.len() != 34 || bytes[..2] != ED25519_MULTICODEC {
+            return Err(X402Error::Did("Multibase key is not an Ed25519 public key".to_string()));
+        }
+
+        Ok(bytes[2..].to_vec())
+    }
+
+    fn resolve_did_key(did: &str, identifier: &str) -> Result<DidDocument, X402Error> {
+        let bytes = Self::decode_multibase_ed25519(identifier)?;
+        let public_key = PublicKey::from_bytes(&bytes).map_err(|e| X402Error::Did(e.to_string()))?;
+        Ok(DidDocument::single_key(did, &public_key))
+    }
+
+    async fn resolve_did_web(&self, did: &str, identifier: &str) -> Result<DidDocument, X402Error> {
+        let mut segments = identifier.split(':');
+        let domain = segments
+            .next()
+            .filter(|d| !d.is_empty())
+            .ok_or_else(|| X402Error::Did(format!("Invalid did:web identifier: {}", did)))?
+            .replace("%3A", ":");
+        let path: Vec<&str> = segments.collect();
+
+        let url = if path.is_empty() {
+            format!("https://{}/.well-known/did.json", domain)
+        } else {
+            format!("https://{}/{}/did.json", domain, path.join("/"))
+        };
+
+        let response = self.http_client.get(&url).send().await?;
+        response.error_for_status_ref()?;
+        let document: DidDocument = response.json().await?;
+
+        if document.id != did {
+            return Err(X402Error::Did(format!(
+                "DID document id {} does not match {}",
+                document.id, did
+            )));
+        }
+
+        Ok(document)
+    }
+
+    /// Resolves the agent's TAP key from its on-chain account rather than its
+    /// wallet, since TAP keys may be separate or derived and can be rotated.
+    /// `visa_tap_cert` is used when it holds a key (`did:key`, multibase or
+    /// base58); otherwise the key comes from the agent's registered `did`, and
+    /// the wallet key is used only when the agent publishes neither.
+    async fn resolve_did_x402(&self, did: &str, identifier: &str) -> Result<DidDocument, X402Error> {
+        let rpc_client = self
+            .rpc_client
+            .as_ref()
+            .ok_or_else(|| X402Error::InvalidConfig("RPC URL required to resolve did:x402".to_string()))?;
+
+        let wallet = Pubkey::from_str(identifier)
+            .map_err(|e| X402Error::Did(format!("did:x402 identifier is not a wallet address: {}", e)))?;
+        let (agent_address, _) = find_agent_address(&wallet, &self.config.program_id);
+
+        let account = rpc_client.get_account(&agent_address).await?;
+        if account.owner != self.config.program_id {
+            return Err(X402Error::Did(format!("{} is not owned by the registry program", agent_address)));
+        }
+
+        let agent = Agent::try_from_account_data(&account.data)?;
+        if agent.wallet != wallet {
+            return Err(X402Error::Did(format!("Agent account {} does not belong to {}", agent_address, wallet)));
+        }
+        if agent.status == AgentStatus::Banned {
+            return Err(X402Error::Did(format!("{} has been deactivated", did)));
+        }
+
+        let mut document = if let Some(public_key) = Self::tap_cert_key(&agent.visa_tap_cert)? {
+            DidDocument::single_key(did, &public_key)
+        } else if agent.did.starts_with("did:key:") || agent.did.starts_with("did:web:") {
+            let registered = agent.did.split('#').next().unwrap_or(&agent.did);
+            let public_key = match registered.strip_prefix("did:key:") {
+                Some(rest) => Self::resolve_did_key(registered, rest)?,
+                None => {
+                    let rest = registered.trim_start_matches("did:web:");
+                    self.resolve_did_web(registered, rest).await?
+                }
+            }
+            .verification_key(agent.did.contains('#').then_some(agent.did.as_str()))?;
+            DidDocument::single_key(did, &public_key)
+        } else {
+            let public_key = PublicKey::from_bytes(wallet.as_ref()).map_err(|e| X402Error::Did(e.to_string()))?;
+            DidDocument::single_key(did, &public_key)
+        };
+
+        if !agent.did.is_empty() && agent.did != did {
+            document.also_known_as.push(agent.did);
+        }
+
+        Ok(document)
+    }
+
+    fn tap_cert_key(cert: &str) -> Result<Option<PublicKey>, X402Error> {
+        let cert = cert.trim();
+        let bytes = if let Some(rest) = cert.strip_prefix("did:key:") {
+            Self::decode_multibase_ed25519(rest.split('#').next().unwrap_or(rest))?
+        } else {
+            match Self::decode_multibase_ed25519(cert) {
+                Ok(bytes) => bytes,
+                // A raw base58 key also starts with 'z' about one time in 58.
+                Err(error) => match bs58::decode(cert).into_vec() {
+                    Ok(bytes) if bytes.len() == 32 => bytes,
+                    _ if cert.starts_with('z') => return Err(error),
+                    _ => return Ok(None),
+                },
+            }
+        };
+
+        PublicKey::from_bytes(&bytes)
+            .map(Some)
+            .map_err(|e| X402Error::Did(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SecretKey;
+
+    #[test]
+    fn reads_raw_base58_tap_certs_that_start_with_z() {
+        let public_key = (0..=u16::MAX)
+            .map(|seed| {
+                let mut secret = [0u8; 32];
+                secret[..2].copy_from_slice(&seed.to_le_bytes());
+                PublicKey::from(&SecretKey::from_bytes(&secret).unwrap())
+            })
+            .find(|key| bs58::encode(key.as_bytes()).into_string().starts_with('z'))
+            .unwrap();
+        let cert = bs58::encode(public_key.as_bytes()).into_string();
+
+        assert_eq!(DidResolver::tap_cert_key(&cert).unwrap(), Some(public_key));
+
+        let mut multibase = ED25519_MULTICODEC.to_vec();
+        multibase.extend_from_slice(public_key.as_bytes());
+        let multibase = format!("z{}", bs58::encode(multibase).into_string());
+        assert_eq!(DidResolver::tap_cert_key(&multibase).unwrap(), Some(public_key));
+    }
+}
@@ -0,0 +1,265 @@
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::{Keypair, PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+use crate::error::X402Error;
+
+const PKCS8_ED25519_PREFIX: [u8; 16] = [
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+
+const SPKI_ED25519_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyFormat {
+    Pkcs8Pem,
+    Jwk,
+    SolanaJson,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    pub crv: String,
+    pub x: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub d: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+}
+
+pub struct TAPKeys;
+
+impl TAPKeys {
+    pub fn load(path: impl AsRef<Path>) -> Result<Keypair, X402Error> {
+        let contents = fs::read_to_string(path)?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Keypair, X402Error> {
+        match Self::detect_format(contents)? {
+            KeyFormat::Pkcs8Pem => Self::from_pkcs8_pem(contents),
+            KeyFormat::Jwk => Self::from_jwk(&serde_json::from_str(contents)?),
+            KeyFormat::SolanaJson => Self::from_solana_json(contents),
+        }
+    }
+
+    pub fn save(path: impl AsRef<Path>, keypair: &Keypair, format: KeyFormat) -> Result<(), X402Error> {
+        let contents = match format {
+            KeyFormat::Pkcs8Pem => Self::to_pkcs8_pem(keypair),
+            KeyFormat::Jwk => serde_json::to_string_pretty(&Self::to_jwk(keypair, true))?,
+            KeyFormat::SolanaJson => Self::to_solana_json(keypair)?,
+        };
+
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        #[cfg(unix)]
+        {
+            use std::io::Write;
+            use std::os::unix::fs::OpenOptionsExt;
+
+            let mut file = fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .mode(0o600)
+                .open(path)?;
+            file.write_all(contents.as_bytes())?;
+        }
+
+        #[cfg(not(unix))]
+        fs::write(path, contents)?;
+
+        Ok(())
+    }
+
+    pub fn load_or_generate(path: impl AsRef<Path>, format: KeyFormat) -> Result<Keypair, X402Error> {
+        let path = path.as_ref();
+        if path.exists() {
+            return Self::load(path);
+        }
+
+        let keypair = super::rfc9421::RFC9421Signature::generate_ed25519_keypair();
+        Self::save(path, &keypair, format)?;
+        Ok(keypair)
+    }
+
+    pub fn detect_format(contents: &str) -> Result<KeyFormat, X402Error> {
+        let trimmed = contents.trim_start();
+
+        if trimmed.starts_with("-----BEGIN") {
+            Ok(KeyFormat::Pkcs8Pem)
+        } else if trimmed.starts_with('{') {
+            Ok(KeyFormat::Jwk)
+        } else if trimmed.starts_with('[') {
+            Ok(KeyFormat::SolanaJson)
+        } else {
+            Err(X402Error::Key("Unrecognized key file format".to_string()))
+        }
+    }
+
+    pub fn to_pkcs8_pem(keypair: &Keypair) -> String {
+        let mut der = PKCS8_ED25519_PREFIX.to_vec();
+        der.extend_from_slice(keypair.secret.as_bytes());
+        Self::encode_pem("PRIVATE KEY", &der)
+    }
+
+    pub fn from_pkcs8_pem(pem: &str) -> Result<Keypair, X402Error> {
+        let der = Self::decode_pem("PRIVATE KEY", pem)?;
+
+        if der.len() != PKCS8_ED25519_PREFIX.len() + 32 || der[..16] != PKCS8_ED25519_PREFIX {
+            return Err(X402Error::Key("PEM is not an Ed25519 PKCS#8 private key".to_string()));
+        }
+
+        Self::from_seed(&der[16..])
+    }
+
+    pub fn public_key_to_spki_pem(public_key: &PublicKey) -> String {
+        let mut der = SPKI_ED25519_PREFIX.to_vec();
+        der.extend_from_slice(public_key.as_bytes());
+        Self::encode_pem("PUBLIC KEY", &der)
+    }
+
+    pub fn public_key_from_spki_pem(pem: &str) -> Result<PublicKey, X402Error> {
+        let der = Self::decode_pem("PUBLIC KEY", pem)?;
+
+        if der.len() != SPKI_ED25519_PREFIX.len() + 32 || der[..12] != SPKI_ED25519_PREFIX {
+            return Err(X402Error::Key("PEM is not an Ed25519 public key".to_string()));
+        }
+
+        PublicKey::from_bytes(&der[12..]).map_err(|e| X402Error::Key(e.to_string()))
+    }
+
+    pub fn to_jwk(keypair: &Keypair, include_private: bool) -> Jwk {
+        let mut jwk = Self::public_jwk(&keypair.public);
+        if include_private {
+            jwk.d = Some(general_purpose::URL_SAFE_NO_PAD.encode(keypair.secret.as_bytes()));
+        }
+        jwk
+    }
+
+    pub fn public_jwk(public_key: &PublicKey) -> Jwk {
+        Jwk {
+            kty: "OKP".to_string(),
+            crv: "Ed25519".to_string(),
+            x: general_purpose::URL_SAFE_NO_PAD.encode(public_key.as_bytes()),
+            d: None,
+            kid: Some(Self::thumbprint(public_key)),
+        }
+    }
+
+    pub fn from_jwk(jwk: &Jwk) -> Result<Keypair, X402Error> {
+        if jwk.kty != "OKP" || jwk.crv != "Ed25519" {
+            return Err(X402Error::Key(format!(
+                "Unsupported JWK key type: {}/{}",
+                jwk.kty, jwk.crv
+            )));
+        }
+
+        let d = jwk
+            .d
+            .as_ref()
+            .ok_or_else(|| X402Error::Key("JWK has no private key component".to_string()))?;
+        let keypair = Self::from_seed(&general_purpose::URL_SAFE_NO_PAD.decode(d)?)?;
+
+        let x = general_purpose::URL_SAFE_NO_PAD.decode(&jwk.x)?;
+        if x != keypair.public.as_bytes() {
+            return Err(X402Error::Key("JWK public key does not match private key".to_string()));
+        }
+
+        Ok(keypair)
+    }
+
+    pub fn to_solana_json(keypair: &Keypair) -> Result<String, X402Error> {
+        Ok(serde_json::to_string(&keypair.to_bytes().to_vec())?)
+    }
+
+    pub fn from_solana_json(json: &str) -> Result<Keypair, X402Error> {
+        let bytes: Vec<u8> = serde_json::from_str(json)?;
+        Self::from_keypair_bytes(&bytes)
+    }
+
+    pub fn from_solana_keypair(wallet: &solana_sdk::signature::Keypair) -> Result<Keypair, X402Error> {
+        Self::from_keypair_bytes(&wallet.to_bytes())
+    }
+
+    pub fn derive_from_solana_keypair(
+        wallet: &solana_sdk::signature::Keypair,
+        label: &str,
+    ) -> Result<Keypair, X402Error> {
+        let wallet_bytes = wallet.to_bytes();
+        let mut hasher = Sha256::new();
+        hasher.update(b"x402-tap-key:");
+        hasher.update(label.as_bytes());
+        hasher.update(&wallet_bytes[..32]);
+        Self::from_seed(&hasher.finalize())
+    }
+
+    pub fn thumbprint(public_key: &PublicKey) -> String {
+        let canonical = format!(
+            r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#,
+            general_purpose::URL_SAFE_NO_PAD.encode(public_key.as_bytes())
+        );
+        general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+    }
+
+    fn from_seed(seed: &[u8]) -> Result<Keypair, X402Error> {
+        let secret = SecretKey::from_bytes(seed).map_err(|e| X402Error::Key(e.to_string()))?;
+        let public: PublicKey = (&secret).into();
+        Ok(Keypair { secret, public })
+    }
+
+    fn from_keypair_bytes(bytes: &[u8]) -> Result<Keypair, X402Error> {
+        if bytes.len() != 64 {
+            return Err(X402Error::Key(format!(
+                "Expected 64-byte keypair, got {} bytes",
+                bytes.len()
+            )));
+        }
+
+        let keypair = Self::from_seed(&bytes[..32])?;
+        if keypair.public.as_bytes() != &bytes[32..] {
+            return Err(X402Error::Key("Keypair public key does not match secret key".to_string()));
+        }
+
+        Ok(keypair)
+    }
+
+    fn encode_pem(label: &str, der: &[u8]) -> String {
+        let body = general_purpose::STANDARD.encode(der);
+        let mut pem = format!("-----BEGIN {}-----\n", label);
+        for chunk in body.as_bytes().chunks(64) {
+            pem.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+            pem.push('\n');
+        }
+        pem.push_str(&format!("-----END {}-----\n", label));
+        pem
+    }
+
+    fn decode_pem(label: &str, pem: &str) -> Result<Vec<u8>, X402Error> {
+        let begin = format!("-----BEGIN {}-----", label);
+        let end = format!("-----END {}-----", label);
+
+        let start = pem
+            .find(&begin)
+            .ok_or_else(|| X402Error::Key(format!("Missing {} header", begin)))?
+            + begin.len();
+        let stop = pem[start..]
+            .find(&end)
+            .ok_or_else(|| X402Error::Key(format!("Missing {} footer", end)))?
+            + start;
+
+        let body: String = pem[start..stop].chars().filter(|c| !c.is_whitespace()).collect();
+        Ok(general_purpose::STANDARD.decode(body)?)
+    }
+}
@@ -1,5 +1,15 @@
+pub mod did;
+pub mod keys;
+pub mod nonce;
 pub mod rfc9421;
 pub mod tap_client;
+pub mod verifier;
 
+pub use did::{DidDocument, DidResolver, DidResolverConfig, VerificationMethod};
+pub use keys::{Jwk, KeyFormat, TAPKeys};
+#[cfg(feature = "redis")]
+pub use nonce::RedisNonceStore;
+pub use nonce::{InMemoryNonceStore, NonceStore};
 pub use rfc9421::{RFC9421Signature, SignatureAlgorithm, SignatureComponents, SignatureParams, SignatureResult};
-pub use tap_client::{TAPClient, TAPConfig, AgentIdentity};
+pub use tap_client::{TAPClient, TAPConfig, AgentIdentity, AgentQuery, KeyRotation};
+pub use verifier::RFC9421Verifier;
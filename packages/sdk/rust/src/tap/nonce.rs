@@ -0,0 +1,240 @@
+use async_trait::async_trait;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+use super::rfc9421::{RFC9421Signature, SignatureParams};
+use super::verifier::{DEFAULT_CLOCK_SKEW_SECONDS, DEFAULT_MAX_VALIDITY_SECONDS};
+use crate::error::X402Error;
+
+/// The longest a store holds a nonce by default: the verifier's default maximum validity plus its clock skew, the
+/// furthest in the future a signature it accepts can expire.
+pub const DEFAULT_MAX_NONCE_TTL_SECONDS: u64 = DEFAULT_MAX_VALIDITY_SECONDS + DEFAULT_CLOCK_SKEW_SECONDS;
+
+#[async_trait]
+pub trait NonceStore: Send + Sync {
+    /// Records `(key_id, nonce)` until `expires_at` (unix seconds), capped at
+    /// the store's maximum TTL. Returns `false` if the pair was already
+    /// recorded and has not yet expired.
+    async fn check_and_insert(&self, key_id: &str, nonce: &str, expires_at: u64) -> Result<bool, X402Error>;
+
+    async fn check_signature(&self, params: &SignatureParams) -> Result<(), X402Error> {
+        if params.nonce.is_empty() {
+            return Err(X402Error::Signature("Missing nonce".to_string()));
+        }
+
+        if self.check_and_insert(&params.key_id, &params.nonce, params.expires).await? {
+            Ok(())
+        } else {
+            Err(X402Error::Signature(format!(
+                "Nonce already used for keyid {} (replay)",
+                params.key_id
+            )))
+        }
+    }
+}
+
+#[derive(Default)]
+struct NonceEntries {
+    expiry: HashMap<(String, String), u64>,
+    by_expiry: BTreeSet<(u64, (String, String))>,
+}
+
+/// Holds each `(keyid, nonce)` pair until its signature expires, for at most
+/// `max_ttl_seconds`. Expired pairs are evicted first; when the store is full
+/// of live pairs, new pairs are rejected rather than evicting a pair that
+/// could then be replayed.
+pub struct InMemoryNonceStore {
+    capacity: usize,
+    max_ttl_seconds: u64,
+    entries: Mutex<NonceEntries>,
+}
+
+impl InMemoryNonceStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            max_ttl_seconds: DEFAULT_MAX_NONCE_TTL_SECONDS,
+            entries: Mutex::new(NonceEntries::default()),
+        }
+    }
+
+    pub fn with_max_ttl(mut self, seconds: u64) -> Self {
+        self.max_ttl_seconds = seconds;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().expiry.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn evict_expired(entries: &mut NonceEntries, now: u64) {
+        while let Some((expires_at, key)) = entries.by_expiry.first().cloned() {
+            if expires_at > now {
+                break;
+            }
+            entries.by_expiry.pop_first();
+            entries.expiry.remove(&key);
+        }
+    }
+
+    fn check_and_insert_at(&self, key_id: &str, nonce: &str, expires_at: u64, now: u64) -> Result<bool, X402Error> {
+        if expires_at <= now {
+            return Ok(false);
+        }
+        let expires_at = expires_at.min(now.saturating_add(self.max_ttl_seconds));
+
+        let mut entries = self.entries.lock().unwrap();
+        Self::evict_expired(&mut entries, now);
+
+        let key = (key_id.to_string(), nonce.to_string());
+        if entries.expiry.contains_key(&key) {
+            return Ok(false);
+        }
+
+        if entries.expiry.len() >= self.capacity {
+            return Err(X402Error::Storage(format!(
+                "Nonce store is full ({} unexpired nonces)",
+                self.capacity
+            )));
+        }
+
+        entries.expiry.insert(key.clone(), expires_at);
+        entries.by_expiry.insert((expires_at, key));
+
+        Ok(true)
+    }
+}
+
+impl Default for InMemoryNonceStore {
+    fn default() -> Self {
+        Self::new(100_000)
+    }
+}
+
+#[async_trait]
+impl NonceStore for InMemoryNonceStore {
+    async fn check_and_insert(&self, key_id: &str, nonce: &str, expires_at: u64) -> Result<bool, X402Error> {
+        self.check_and_insert_at(key_id, nonce, expires_at, RFC9421Signature::get_current_timestamp())
+    }
+}
+
+#[cfg(feature = "redis")]
+pub struct RedisNonceStore {
+    connection: redis::aio::ConnectionManager,
+    prefix: String,
+    max_ttl_seconds: u64,
+}
+
+#[cfg(feature = "redis")]
+impl RedisNonceStore {
+    pub async fn connect(redis_url: &str) -> Result<Self, X402Error> {
+        Self::connect_with_prefix(redis_url, "tap:nonces:").await
+    }
+
+    pub async fn connect_with_prefix(redis_url: &str, prefix: &str) -> Result<Self, X402Error> {
+        let client = redis::Client::open(redis_url).map_err(|e| X402Error::Storage(e.to_string()))?;
+        let connection = redis::aio::ConnectionManager::new(client)
+            .await
+            .map_err(|e| X402Error::Storage(e.to_string()))?;
+
+        Ok(Self {
+            connection,
+            prefix: prefix.to_string(),
+            max_ttl_seconds: DEFAULT_MAX_NONCE_TTL_SECONDS,
+        })
+    }
+
+    pub fn with_max_ttl(mut self, seconds: u64) -> Self {
+        self.max_ttl_seconds = seconds;
+        self
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl NonceStore for RedisNonceStore {
+    async fn check_and_insert(&self, key_id: &str, nonce: &str, expires_at: u64) -> Result<bool, X402Error> {
+        let now = RFC9421Signature::get_current_timestamp();
+        if expires_at <= now {
+            return Ok(false);
+        }
+
+        let key = format!("{}{}:{}", self.prefix, key_id, nonce);
+        let mut connection = self.connection.clone();
+
+        let result: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg((expires_at - now).min(self.max_ttl_seconds))
+            .query_async(&mut connection)
+            .await
+            .map_err(|e| X402Error::Storage(e.to_string()))?;
+
+        Ok(result.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn rejects_a_replayed_nonce() {
+        let store = InMemoryNonceStore::new(10);
+
+        assert!(store.check_and_insert_at("key", "n1", NOW + 60, NOW).unwrap());
+        assert!(!store.check_and_insert_at("key", "n1", NOW + 60, NOW + 1).unwrap());
+        assert!(store.check_and_insert_at("other", "n1", NOW + 60, NOW + 1).unwrap());
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn forgets_nonces_once_they_expire() {
+        let store = InMemoryNonceStore::new(10);
+
+        assert!(!store.check_and_insert_at("key", "n1", NOW, NOW).unwrap());
+        assert!(store.check_and_insert_at("key", "n1", NOW + 60, NOW).unwrap());
+        assert!(!store.check_and_insert_at("key", "n1", NOW + 120, NOW + 59).unwrap());
+        assert!(store.check_and_insert_at("key", "n1", NOW + 120, NOW + 60).unwrap());
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn rejects_new_nonces_when_full_of_live_ones() {
+        let store = InMemoryNonceStore::new(2);
+        assert!(store.check_and_insert_at("key", "n1", NOW + 30, NOW).unwrap());
+        assert!(store.check_and_insert_at("key", "n2", NOW + 60, NOW).unwrap());
+
+        assert!(matches!(
+            store.check_and_insert_at("key", "n3", NOW + 60, NOW),
+            Err(X402Error::Storage(_))
+        ));
+        assert!(!store.check_and_insert_at("key", "n1", NOW + 30, NOW + 1).unwrap());
+        assert!(!store.check_and_insert_at("key", "n2", NOW + 60, NOW + 1).unwrap());
+
+        assert!(store.check_and_insert_at("key", "n3", NOW + 60, NOW + 30).unwrap());
+        assert!(!store.check_and_insert_at("key", "n2", NOW + 60, NOW + 30).unwrap());
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn holds_far_future_nonces_only_for_the_max_ttl() {
+        let store = InMemoryNonceStore::new(1).with_max_ttl(60);
+        assert!(store.check_and_insert_at("key", "n1", u64::MAX, NOW).unwrap());
+
+        assert!(matches!(
+            store.check_and_insert_at("key", "n2", NOW + 60, NOW + 59),
+            Err(X402Error::Storage(_))
+        ));
+        assert!(store.check_and_insert_at("key", "n2", NOW + 60, NOW + 60).unwrap());
+        assert_eq!(store.len(), 1);
+    }
+}
@@ -1,4 +1,4 @@
-use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signature, Signer};
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signature, Signer, Verifier};
 use rand::rngs::OsRng;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -15,6 +15,14 @@ impl SignatureAlgorithm {
             SignatureAlgorithm::RsaPssSha256 => "rsa-pss-sha256",
         }
     }
+
+    pub fn parse(alg: &str) -> Option<Self> {
+        match alg {
+            "ed25519" => Some(SignatureAlgorithm::Ed25519),
+            "rsa-pss-sha256" => Some(SignatureAlgorithm::RsaPssSha256),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -31,6 +39,44 @@ pub struct SignatureParams {
 pub struct SignatureComponents {
     pub authority: String,
     pub path: String,
+    pub method: Option<String>,
+    pub headers: Vec<(String, String)>,
+}
+
+impl SignatureComponents {
+    pub fn new(authority: impl Into<String>, path: impl Into<String>) -> Self {
+        Self {
+            authority: authority.into(),
+            path: path.into(),
+            method: None,
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn with_method(mut self, method: impl Into<String>) -> Self {
+        self.method = Some(method.into().to_uppercase());
+        self
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into().to_lowercase(), value.into().trim().to_string()));
+        self
+    }
+
+    pub fn covered_components(&self) -> String {
+        let mut names = Vec::new();
+
+        if self.method.is_some() {
+            names.push(r#""@method""#.to_string());
+        }
+        names.push(r#""@authority""#.to_string());
+        names.push(r#""@path""#.to_string());
+        for (name, _) in &self.headers {
+            names.push(format!(r#""{}""#, name));
+        }
+
+        format!("({})", names.join(" "))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -48,20 +94,19 @@ impl RFC9421Signature {
     ) -> String {
         let mut lines = Vec::new();
 
+        if let Some(method) = &components.method {
+            lines.push(format!(r#""@method": {}"#, method));
+        }
         lines.push(format!(r#""@authority": {}"#, components.authority));
         lines.push(format!(r#""@path": {}"#, components.path));
+        for (name, value) in &components.headers {
+            lines.push(format!(r#""{}": {}"#, name, value));
+        }
 
-        let signature_params_value = format!(
-            r#"("@authority" "@path"); created={}; expires={}; keyid="{}"; alg="{}"; nonce="{}"; tag="{}""#,
-            params.created,
-            params.expires,
-            params.key_id,
-            params.alg.as_str(),
-            params.nonce,
-            params.tag
-        );
-
-        lines.push(format!(r#""@signature-params": {}"#, signature_params_value));
+        lines.push(format!(
+            r#""@signature-params": {}"#,
+            Self::signature_params_value(components, params)
+        ));
 
         lines.join("\n")
     }
@@ -77,20 +122,103 @@ impl RFC9421Signature {
         let signature: Signature = keypair.sign(message);
         let signature_b64 = base64::encode(signature.to_bytes());
 
-        let signature_input = format!(
-            r#"sig2=("@authority" "@path"); created={}; expires={}; keyid="{}"; alg="{}"; nonce="{}"; tag="{}""#,
+        SignatureResult {
+            signature_input: format!("sig2={}", Self::signature_params_value(components, params)),
+            signature: format!("sig2=:{}:", signature_b64),
+        }
+    }
+
+    pub fn content_digest(body: &[u8]) -> String {
+        use sha2::{Digest, Sha256};
+        format!("sha-256=:{}:", base64::encode(Sha256::digest(body)))
+    }
+
+    fn signature_params_value(components: &SignatureComponents, params: &SignatureParams) -> String {
+        format!(
+            r#"{}; created={}; expires={}; keyid="{}"; alg="{}"; nonce="{}"; tag="{}""#,
+            components.covered_components(),
             params.created,
             params.expires,
             params.key_id,
             params.alg.as_str(),
             params.nonce,
             params.tag
-        );
+        )
+    }
 
-        SignatureResult {
-            signature_input,
-            signature: format!("sig2=:{}:", signature_b64),
+    pub fn parse_signature_input(header: &str) -> Option<SignatureParams> {
+        let (_, value) = header.split_once('=')?;
+        let (_, params_str) = value.split_once(')')?;
+
+        let mut created = None;
+        let mut expires = None;
+        let mut key_id = None;
+        let mut alg = None;
+        let mut nonce = String::new();
+        let mut tag = String::new();
+
+        for part in params_str.split(';') {
+            let Some((key, raw)) = part.trim().split_once('=') else {
+                continue;
+            };
+            let raw = raw.trim().trim_matches('"');
+
+            match key.trim() {
+                "created" => created = raw.parse().ok(),
+                "expires" => expires = raw.parse().ok(),
+                "keyid" => key_id = Some(raw.to_string()),
+                "alg" => alg = SignatureAlgorithm::parse(raw),
+                "nonce" => nonce = raw.to_string(),
+                "tag" => tag = raw.to_string(),
+                _ => {}
+            }
         }
+
+        Some(SignatureParams {
+            created: created?,
+            expires: expires?,
+            key_id: key_id?,
+            alg: alg?,
+            nonce,
+            tag,
+        })
+    }
+
+    pub fn parse_covered_components(header: &str) -> Vec<String> {
+        header
+            .split_once('(')
+            .and_then(|(_, rest)| rest.split_once(')'))
+            .map(|(inner, _)| {
+                inner
+                    .split_whitespace()
+                    .map(|name| name.trim_matches('"').to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn verify_ed25519(
+        components: &SignatureComponents,
+        params: &SignatureParams,
+        signature_header: &str,
+        public_key: &PublicKey,
+    ) -> bool {
+        let encoded = signature_header
+            .split_once('=')
+            .map(|(_, v)| v)
+            .unwrap_or(signature_header)
+            .trim_matches(':');
+
+        let signature = match base64::decode(encoded)
+            .ok()
+            .and_then(|bytes| Signature::from_bytes(&bytes).ok())
+        {
+            Some(signature) => signature,
+            None => return false,
+        };
+
+        let signature_base = Self::create_signature_base(components, params);
+        public_key.verify(signature_base.as_bytes(), &signature).is_ok()
     }
 
     pub fn generate_nonce() -> String {
@@ -1,11 +1,16 @@
-use ed25519_dalek::Keypair;
+use ed25519_dalek::{Keypair, Signer};
 use reqwest::{Client, Method, Response};
+use futures::stream::Stream;
 use serde_json::Value;
 use std::collections::HashMap;
 use url::Url;
 
+use super::did::DidResolver;
+use super::keys::TAPKeys;
 use super::rfc9421::{RFC9421Signature, SignatureAlgorithm, SignatureComponents, SignatureParams};
 use crate::error::X402Error;
+use crate::query::{build_url, paginate, Page, QueryFilters};
+use crate::registry::AgentStatus;
 
 #[derive(Debug, Clone)]
 pub struct TAPConfig {
@@ -25,10 +30,119 @@ pub struct AgentIdentity {
     pub reputation_score: Option<u32>,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct AgentQuery {
+    pub filters: QueryFilters,
+    pub did_method: Option<String>,
+    pub wallet: Option<String>,
+    pub status: Option<AgentStatus>,
+}
+
+impl AgentQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn min_reputation(mut self, score: f64) -> Self {
+        self.filters.min_reputation = Some(score);
+        self
+    }
+
+    pub fn verified_only(mut self) -> Self {
+        self.filters.verified_only = true;
+        self
+    }
+
+    pub fn did_method(mut self, method: &str) -> Self {
+        self.did_method = Some(method.trim_start_matches("did:").to_string());
+        self
+    }
+
+    pub fn wallet(mut self, wallet: &str) -> Self {
+        self.wallet = Some(wallet.to_string());
+        self
+    }
+
+    pub fn status(mut self, status: AgentStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn page_size(mut self, limit: usize) -> Self {
+        self.filters.limit = Some(limit);
+        self
+    }
+
+    pub fn cursor(mut self, cursor: &str) -> Self {
+        self.filters.cursor = Some(cursor.to_string());
+        self
+    }
+
+    pub fn to_query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = Vec::new();
+        self.filters.append_to(&mut pairs);
+
+        if let Some(method) = &self.did_method {
+            pairs.push(("didMethod", method.clone()));
+        }
+        if let Some(wallet) = &self.wallet {
+            pairs.push(("wallet", wallet.clone()));
+        }
+        if let Some(status) = &self.status {
+            pairs.push(("status", status.as_str().to_string()));
+        }
+
+        pairs
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyRotation {
+    pub old_key_id: String,
+    pub new_key_id: String,
+    pub new_public_key: String,
+    pub algorithm: String,
+    pub rotated_at: u64,
+    pub old_key_expires_at: u64,
+    pub signature: String,
+}
+
+impl KeyRotation {
+    pub fn signing_payload(&self) -> String {
+        format!(
+            "x402-tap-key-rotation\nold-keyid={}\nnew-keyid={}\nnew-key={}\nalg={}\nrotated={}\nold-expires={}",
+            self.old_key_id,
+            self.new_key_id,
+            self.new_public_key,
+            self.algorithm,
+            self.rotated_at,
+            self.old_key_expires_at
+        )
+    }
+
+    pub fn verify(&self, old_public_key: &ed25519_dalek::PublicKey) -> bool {
+        use ed25519_dalek::Verifier;
+
+        let signature = match base64::decode(&self.signature)
+            .ok()
+            .and_then(|bytes| ed25519_dalek::Signature::from_bytes(&bytes).ok())
+        {
+            Some(signature) => signature,
+            None => return false,
+        };
+
+        old_public_key
+            .verify(self.signing_payload().as_bytes(), &signature)
+            .is_ok()
+    }
+}
+
 pub struct TAPClient {
     config: TAPConfig,
     agent_identity: Option<AgentIdentity>,
     http_client: Client,
+    signed_headers: Vec<String>,
 }
 
 impl TAPClient {
@@ -37,76 +151,157 @@ impl TAPClient {
             config,
             agent_identity,
             http_client: Client::new(),
+            signed_headers: vec!["content-type".to_string(), "x-payment".to_string()],
         }
     }
 
-    pub fn sign_request(&self, url: &str, method: &str) -> Result<HashMap<String, String>, X402Error> {
-        let parsed = Url::parse(url).map_err(|e| X402Error::Network(e.to_string()))?;
+    pub fn with_http_client(mut self, http_client: Client) -> Self {
+        self.http_client = http_client;
+        self
+    }
 
-        let components = SignatureComponents {
-            authority: parsed.host_str().unwrap_or("").to_string(),
-            path: format!("{}{}", parsed.path(), parsed.query().map(|q| format!("?{}", q)).unwrap_or_default()),
-        };
+    pub fn with_signed_headers(mut self, headers: Vec<String>) -> Self {
+        self.signed_headers = headers.into_iter().map(|h| h.to_lowercase()).collect();
+        self
+    }
 
-        let now = RFC9421Signature::get_current_timestamp();
-        let params = SignatureParams {
-            created: now,
-            expires: now + 300,
-            key_id: self.config.key_id.clone(),
-            alg: self.config.algorithm.clone(),
-            nonce: RFC9421Signature::generate_nonce(),
-            tag: "agent-payer-auth".to_string(),
-        };
+    pub fn sign_request(&self, url: &str, _method: &str) -> Result<HashMap<String, String>, X402Error> {
+        let parsed = Url::parse(url).map_err(|e| X402Error::InvalidConfig(e.to_string()))?;
 
-        let result = RFC9421Signature::sign_ed25519(&components, &params, &self.config.keypair);
+        let components = SignatureComponents::new(
+            parsed.host_str().unwrap_or(""),
+            Self::request_path(&parsed),
+        );
 
         let mut headers = HashMap::new();
-        headers.insert("Signature-Input".to_string(), result.signature_input);
-        headers.insert("Signature".to_string(), result.signature);
-
-        if let Some(ref identity) = self.agent_identity {
-            headers.insert("X-Agent-DID".to_string(), identity.did.clone());
-            headers.insert("X-Agent-Cert".to_string(), identity.visa_tap_cert.clone());
-            headers.insert("X-Agent-Wallet".to_string(), identity.wallet_address.clone());
+        for (name, value) in self.signature_headers(&components) {
+            headers.insert(name.to_string(), value);
         }
 
         Ok(headers)
     }
 
+    pub fn sign_reqwest(&self, request: &mut reqwest::Request) -> Result<(), X402Error> {
+        let url = request.url().clone();
+        let method = request.method().as_str().to_string();
+        let body = request.body().and_then(|b| b.as_bytes()).map(|b| b.to_vec());
+
+        self.sign_parts(&method, &url, request.headers_mut(), body.as_deref())
+    }
+
+    pub fn sign_http<B: AsRef<[u8]>>(&self, request: &mut http::Request<B>) -> Result<(), X402Error> {
+        let url = Url::parse(&request.uri().to_string())
+            .map_err(|e| X402Error::InvalidConfig(e.to_string()))?;
+        let method = request.method().as_str().to_string();
+        let body = request.body().as_ref().to_vec();
+        let body = if body.is_empty() { None } else { Some(body) };
+
+        self.sign_parts(&method, &url, request.headers_mut(), body.as_deref())
+    }
+
+    pub async fn send(&self, mut request: reqwest::Request) -> Result<Response, X402Error> {
+        self.sign_reqwest(&mut request)?;
+        Ok(self.http_client.execute(request).await?)
+    }
+
     pub async fn request(
         &self,
         method: Method,
         url: &str,
         data: Option<Value>,
     ) -> Result<Value, X402Error> {
-        let headers = self.sign_request(url, method.as_str())?;
-
         let mut request = self.http_client.request(method, url);
 
-        for (key, value) in headers {
-            request = request.header(key, value);
-        }
-
         if let Some(body) = data {
             request = request.json(&body);
         }
 
-        let response = request
-            .send()
-            .await
-            .map_err(|e| X402Error::Network(e.to_string()))?;
+        let response = self.send(request.build()?).await?;
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            return Err(X402Error::HttpStatus {
+                status: status.as_u16(),
+                body,
+            });
+        }
+
+        if body.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    fn sign_parts(
+        &self,
+        method: &str,
+        url: &Url,
+        headers: &mut http::HeaderMap,
+        body: Option<&[u8]>,
+    ) -> Result<(), X402Error> {
+        if let Some(body) = body {
+            headers.insert(
+                "content-digest",
+                http::HeaderValue::from_str(&RFC9421Signature::content_digest(body))
+                    .map_err(|e| X402Error::InvalidConfig(e.to_string()))?,
+            );
+        }
+
+        let mut components = SignatureComponents::new(url.host_str().unwrap_or(""), Self::request_path(url))
+            .with_method(method);
+
+        for name in &self.signed_headers {
+            if let Some(value) = headers.get(name.as_str()).and_then(|v| v.to_str().ok()) {
+                components = components.with_header(name.as_str(), value);
+            }
+        }
+        if let Some(digest) = headers.get("content-digest").and_then(|v| v.to_str().ok()) {
+            if !self.signed_headers.iter().any(|h| h == "content-digest") {
+                components = components.with_header("content-digest", digest);
+            }
+        }
+
+        for (name, value) in self.signature_headers(&components) {
+            headers.insert(
+                http::HeaderName::from_bytes(name.as_bytes()).map_err(|e| X402Error::InvalidConfig(e.to_string()))?,
+                http::HeaderValue::from_str(&value).map_err(|e| X402Error::InvalidConfig(e.to_string()))?,
+            );
+        }
+
+        Ok(())
+    }
+
+    fn signature_headers(&self, components: &SignatureComponents) -> Vec<(&'static str, String)> {
+        let now = RFC9421Signature::get_current_timestamp();
+        let params = SignatureParams {
+            created: now,
+            expires: now + 300,
+            key_id: self.config.key_id.clone(),
+            alg: self.config.algorithm.clone(),
+            nonce: RFC9421Signature::generate_nonce(),
+            tag: "agent-payer-auth".to_string(),
+        };
+
+        let result = RFC9421Signature::sign_ed25519(components, &params, &self.config.keypair);
+
+        let mut headers = vec![
+            ("Signature-Input", result.signature_input),
+            ("Signature", result.signature),
+        ];
 
-        if !response.status().is_success() {
-            return Err(X402Error::Network(format!(
-                "Request failed with status: {}",
-                response.status()
-            )));
+        if let Some(ref identity) = self.agent_identity {
+            headers.push(("X-Agent-DID", identity.did.clone()));
+            headers.push(("X-Agent-Cert", identity.visa_tap_cert.clone()));
+            headers.push(("X-Agent-Wallet", identity.wallet_address.clone()));
         }
 
-        response
-            .json()
-            .await
-            .map_err(|e| X402Error::Network(e.to_string()))
+        headers
+    }
+
+    fn request_path(url: &Url) -> String {
+        format!("{}{}", url.path(), url.query().map(|q| format!("?{}", q)).unwrap_or_default())
     }
 
     pub async fn register_agent(
@@ -118,13 +313,13 @@ impl TAPClient {
             .config
             .registry_url
             .as_ref()
-            .ok_or_else(|| X402Error::Network("Registry URL required for agent registration".to_string()))?;
+            .ok_or_else(|| X402Error::InvalidConfig("Registry URL required for agent registration".to_string()))?;
 
         let public_key_bytes = self.config.keypair.public.to_bytes();
         let public_key_b64 = base64::encode(public_key_bytes);
 
         let registration_data = serde_json::json!({
-            "did": self.config.did.as_ref().unwrap_or(&format!("did:x402:{}", self.config.key_id)),
+            "did": self.config.did.clone().unwrap_or_else(|| DidResolver::did_key(&self.config.keypair.public)),
             "walletAddress": wallet_address,
             "visaTapCert": self.config.visa_tap_cert.as_ref().unwrap_or(&self.config.key_id),
             "publicKey": public_key_b64,
@@ -135,39 +330,72 @@ impl TAPClient {
         let url = format!("{}/agents/register", registry_url);
         let response = self.request(Method::POST, &url, Some(registration_data)).await?;
 
-        let agent: AgentIdentity = serde_json::from_value(response["agent"].clone())
-            .map_err(|e| X402Error::Network(e.to_string()))?;
+        let agent: AgentIdentity = serde_json::from_value(response["agent"].clone())?;
 
         self.agent_identity = Some(agent.clone());
 
         Ok(agent)
     }
 
-    pub async fn discover_agents(
-        &self,
-        filters: Option<HashMap<String, String>>,
-    ) -> Result<Vec<AgentIdentity>, X402Error> {
+    pub async fn discover_agents(&self, query: &AgentQuery) -> Result<Page<AgentIdentity>, X402Error> {
         let registry_url = self
             .config
             .registry_url
             .as_ref()
-            .ok_or_else(|| X402Error::Network("Registry URL required for agent discovery".to_string()))?;
+            .ok_or_else(|| X402Error::InvalidConfig("Registry URL required for agent discovery".to_string()))?;
+
+        let url = build_url(&format!("{}/agents/discover", registry_url), &query.to_query_pairs())?;
+        let response = self.request(Method::GET, &url, None).await?;
 
-        let mut url = format!("{}/agents/discover", registry_url);
+        Page::from_value(response)
+    }
 
-        if let Some(params) = filters {
-            let query: Vec<String> = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
-            if !query.is_empty() {
-                url = format!("{}?{}", url, query.join("&"));
+    pub fn discover_agents_stream<'a>(
+        &'a self,
+        query: AgentQuery,
+    ) -> impl Stream<Item = Result<AgentIdentity, X402Error>> + 'a {
+        paginate(move |cursor| {
+            let mut page_query = query.clone();
+            if cursor.is_some() {
+                page_query.filters.cursor = cursor;
             }
-        }
+            async move { self.discover_agents(&page_query).await }
+        })
+    }
 
-        let response = self.request(Method::GET, &url, None).await?;
+    pub async fn rotate_key(
+        &mut self,
+        new_keypair: Keypair,
+        new_key_id: Option<String>,
+        overlap_seconds: u64,
+    ) -> Result<KeyRotation, X402Error> {
+        let registry_url = self
+            .config
+            .registry_url
+            .clone()
+            .ok_or_else(|| X402Error::InvalidConfig("Registry URL required for key rotation".to_string()))?;
+
+        let now = RFC9421Signature::get_current_timestamp();
+        let mut rotation = KeyRotation {
+            old_key_id: self.config.key_id.clone(),
+            new_key_id: new_key_id.unwrap_or_else(|| TAPKeys::thumbprint(&new_keypair.public)),
+            new_public_key: base64::encode(new_keypair.public.to_bytes()),
+            algorithm: self.config.algorithm.as_str().to_string(),
+            rotated_at: now,
+            old_key_expires_at: now + overlap_seconds,
+            signature: String::new(),
+        };
+
+        let signature = self.config.keypair.sign(rotation.signing_payload().as_bytes());
+        rotation.signature = base64::encode(signature.to_bytes());
+
+        let url = format!("{}/agents/keys/rotate", registry_url);
+        self.request(Method::POST, &url, Some(serde_json::to_value(&rotation)?)).await?;
 
-        let agents: Vec<AgentIdentity> = serde_json::from_value(response["agents"].clone())
-            .map_err(|e| X402Error::Network(e.to_string()))?;
+        self.config.keypair = new_keypair;
+        self.config.key_id = rotation.new_key_id.clone();
 
-        Ok(agents)
+        Ok(rotation)
     }
 
     pub fn get_agent_identity(&self) -> Option<&AgentIdentity> {
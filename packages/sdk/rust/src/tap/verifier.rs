@@ -0,0 +1,124 @@
+use ed25519_dalek::PublicKey;
+use std::sync::Arc;
+
+use super::nonce::{InMemoryNonceStore, NonceStore};
+use super::rfc9421::{RFC9421Signature, SignatureAlgorithm, SignatureComponents, SignatureParams};
+use crate::error::X402Error;
+
+pub const DEFAULT_CLOCK_SKEW_SECONDS: u64 = 30;
+/// Matches the validity `TAPClient` signs with.
+pub const DEFAULT_MAX_VALIDITY_SECONDS: u64 = 300;
+
+pub struct RFC9421Verifier {
+    nonce_store: Arc<dyn NonceStore>,
+    clock_skew_seconds: u64,
+    max_validity_seconds: u64,
+}
+
+impl RFC9421Verifier {
+    pub fn new(nonce_store: Arc<dyn NonceStore>) -> Self {
+        Self {
+            nonce_store,
+            clock_skew_seconds: DEFAULT_CLOCK_SKEW_SECONDS,
+            max_validity_seconds: DEFAULT_MAX_VALIDITY_SECONDS,
+        }
+    }
+
+    pub fn with_clock_skew(mut self, seconds: u64) -> Self {
+        self.clock_skew_seconds = seconds;
+        self
+    }
+
+    /// Rejects signatures whose `expires - created` exceeds `seconds`, which bounds how long the nonce store has to
+    /// hold each nonce. Keep the store's maximum TTL at least this plus the clock skew.
+    pub fn with_max_validity(mut self, seconds: u64) -> Self {
+        self.max_validity_seconds = seconds;
+        self
+    }
+
+    pub fn nonce_store(&self) -> Arc<dyn NonceStore> {
+        self.nonce_store.clone()
+    }
+
+    pub async fn verify(
+        &self,
+        components: &SignatureComponents,
+        signature_input: &str,
+        signature: &str,
+        public_key: &PublicKey,
+    ) -> Result<SignatureParams, X402Error> {
+        let params = RFC9421Signature::parse_signature_input(signature_input)
+            .ok_or_else(|| X402Error::Signature("Malformed Signature-Input".to_string()))?;
+
+        if !matches!(params.alg, SignatureAlgorithm::Ed25519) {
+            return Err(X402Error::Signature(format!(
+                "Unsupported algorithm: {}",
+                params.alg.as_str()
+            )));
+        }
+
+        let now = RFC9421Signature::get_current_timestamp();
+        if params.created > now + self.clock_skew_seconds {
+            return Err(X402Error::Signature("Signature created in the future".to_string()));
+        }
+        if params.expires < now {
+            return Err(X402Error::Signature("Signature expired".to_string()));
+        }
+        if params.expires.saturating_sub(params.created) > self.max_validity_seconds {
+            return Err(X402Error::Signature(format!(
+                "Signature valid for longer than {} seconds",
+                self.max_validity_seconds
+            )));
+        }
+
+        if !RFC9421Signature::verify_ed25519(components, &params, signature, public_key) {
+            return Err(X402Error::Signature("Invalid signature".to_string()));
+        }
+
+        self.nonce_store.check_signature(&params).await?;
+
+        Ok(params)
+    }
+}
+
+impl Default for RFC9421Verifier {
+    fn default() -> Self {
+        Self::new(Arc::new(InMemoryNonceStore::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn verify_for(verifier: &RFC9421Verifier, validity_seconds: u64) -> Result<SignatureParams, X402Error> {
+        let keypair = RFC9421Signature::generate_ed25519_keypair();
+        let components = SignatureComponents::new("api.example.com", "/data").with_method("GET");
+        let created = RFC9421Signature::get_current_timestamp();
+        let params = SignatureParams {
+            created,
+            expires: created + validity_seconds,
+            key_id: "key".to_string(),
+            alg: SignatureAlgorithm::Ed25519,
+            nonce: RFC9421Signature::generate_nonce(),
+            tag: "agent-payer-auth".to_string(),
+        };
+        let signed = RFC9421Signature::sign_ed25519(&components, &params, &keypair);
+
+        verifier
+            .verify(&components, &signed.signature_input, &signed.signature, &keypair.public)
+            .await
+    }
+
+    #[tokio::test]
+    async fn rejects_signatures_valid_for_longer_than_the_max() {
+        let verifier = RFC9421Verifier::default();
+
+        assert!(verify_for(&verifier, DEFAULT_MAX_VALIDITY_SECONDS).await.is_ok());
+        assert!(matches!(
+            verify_for(&verifier, DEFAULT_MAX_VALIDITY_SECONDS + 1).await,
+            Err(X402Error::Signature(_))
+        ));
+        assert!(verify_for(&verifier.with_max_validity(3_600), 3_600).await.is_ok());
+    }
+}
@@ -46,6 +46,16 @@ pub struct X402ServiceInfo {
     pub category: Option<String>,
     pub reputation: Option<f64>,
     pub uptime: Option<f64>,
+    pub verified: Option<bool>,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, alias = "ownerWalletAddress", skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Unix seconds; only known for services read from the registry program.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,3 +90,22 @@ pub struct PaymentRecord {
     pub from_address: String,
     pub to_address: String,
 }
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServiceStats {
+    pub calls: u64,
+    pub successes: u64,
+    pub total_latency_ms: u64,
+    pub total_spent: f64,
+    pub last_called: u64,
+}
+
+impl ServiceStats {
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.calls > 0).then(|| self.successes as f64 / self.calls as f64)
+    }
+
+    pub fn average_latency_ms(&self) -> Option<f64> {
+        (self.calls > 0).then(|| self.total_latency_ms as f64 / self.calls as f64)
+    }
+}